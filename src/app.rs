@@ -1,14 +1,35 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+use ratatui::widgets::ListState;
+use std::collections::HashSet;
 use std::io;
 use std::path::Path;
-use crate::config::{Config, key_matches_any};
-use crate::note::{Note, NoteManager};
-use crate::encryption::MAX_PASSWORD_LENGTH;
+use crate::config::{finish_sequence, Behavior, Config, KeyBinding, key_matches_any};
+use crate::note::{find_matches, parse_tags, ImportReport, ExportFormat, MergePolicy, Note, NoteFilter, NoteManager, SortMode};
+use crate::encryption::{MAX_PASSWORD_LENGTH, MIN_PASSWORD_LENGTH};
+use crate::spellcheck::{self, SpellChecker};
+use crate::backup::{self, BackupInfo};
+use crate::markdown;
+use crate::theme;
+use crate::config::ColorTheme;
+use crate::cli;
+use crate::clipboard;
+use crate::credentials;
+use crate::external_editor;
+use crate::recovery::Recovery;
+use crate::change_journal;
+use crate::sync;
+use crate::vault_lock;
+use crate::vim::Vim;
+use crate::csv_notes::{self, CsvField, CsvMapping, CsvPreview};
+use std::time::{Duration, Instant, SystemTime};
 use tui_textarea::TextArea;
 use secrecy::{SecretString, ExposeSecret};
-use chrono::Utc;
+use zeroize::{Zeroize, Zeroizing};
+use chrono::{DateTime, Local, Utc};
+use chrono::format::{Item, StrftimeItems};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AppMode {
     PasswordPrompt,
     PasswordSetup,
@@ -21,50 +42,485 @@ pub enum AppMode {
     ConfirmingUnsavedExit,
     ConfirmingExport,
     ReauthenticatingForExport,
+    EnteringExportPassword,
     SelectingExportLocation,
     EncryptedFileWarning,
+    VaultInUse,
+    ConfirmingRecovery,
+    About,
+    EnteringPath,
+    EnteringText,
+    ChoosingImportSource,
+    ChoosingImportPolicy,
+    ChoosingSaveAsMode,
+    ResolvingConflict,
+    Statistics,
+    ConfirmingEmptyTrash,
+    NotebookList,
+    TrashView,
+    ConfirmingPurge,
+    ViewingHistory,
+    ViewingJournal,
+    BackupList,
+    ConfirmingRestoreBackup,
+    Importing,
+    MappingCsvColumns,
+    ChoosingTheme,
+    QuickSwitch,
+    ChangingPassword,
+    NotePassword,
+    PickingVault,
+    Settings,
 }
 
-#[derive(Debug, PartialEq)]
+// the settings screen's rows, top to bottom
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Setting {
+    AutoSave,
+    ConfirmDelete,
+    Encryption,
+    Theme,
+    SortOrder,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 5] = [Setting::AutoSave, Setting::ConfirmDelete, Setting::Encryption, Setting::Theme, Setting::SortOrder];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Setting::AutoSave => "Auto-save",
+            Setting::ConfirmDelete => "Confirm before deleting",
+            Setting::Encryption => "Encrypt the vault",
+            Setting::Theme => "Theme",
+            Setting::SortOrder => "Sort order",
+        }
+    }
+}
+
+// what the password asked for an encrypted note is for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotePasswordPurpose {
+    Open,
+    Encrypt,
+    Decrypt,
+}
+
+// which password the change password dialog is asking for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PasswordChangeStage {
+    Current,
+    New,
+    Confirm,
+}
+
+// what the vault password dialog does once it has the passwords it needs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VaultPasswordAction {
+    Change,
+    Encrypt,
+    Decrypt,
+}
+
+// what the generic path prompt is asking for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathPrompt {
+    ImportJson,
+    ImportFolder,
+    ImportJoplin,
+    ImportSimplenote,
+    ImportCsv,
+    SaveAs,
+    AttachFile,
+}
+
+impl PathPrompt {
+    pub fn title(&self) -> &'static str {
+        match self {
+            PathPrompt::ImportJson => "Import Notes (JSON)",
+            PathPrompt::ImportFolder => "Import Notes (Markdown/Text Folder)",
+            PathPrompt::ImportJoplin => "Import Notes (Joplin)",
+            PathPrompt::ImportSimplenote => "Import Notes (Simplenote)",
+            PathPrompt::ImportCsv => "Import Notes (CSV)",
+            PathPrompt::SaveAs => "Save As",
+            PathPrompt::AttachFile => "Attach File",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            PathPrompt::ImportJson => "Path of a plaintext JSON export to import:",
+            PathPrompt::ImportFolder => "Folder of Markdown/text files to import:",
+            PathPrompt::ImportJoplin => "Path of a Joplin export (.jex) to import:",
+            PathPrompt::ImportSimplenote => "Path of the notes.json from a Simplenote export:",
+            PathPrompt::ImportCsv => "Path of a CSV file with a header row:",
+            PathPrompt::SaveAs => "Write the notes to a new file:",
+            PathPrompt::AttachFile => "Path of the file to attach:",
+        }
+    }
+}
+
+// what the single line text prompt is asking for, plain text where the path prompt takes paths
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextPrompt {
+    QuickCapture,
+    MoveToNotebook,
+    AddTags,
+}
+
+impl TextPrompt {
+    pub fn title(&self) -> &'static str {
+        match self {
+            TextPrompt::QuickCapture => "Quick Capture",
+            TextPrompt::MoveToNotebook => "Move to Notebook",
+            TextPrompt::AddTags => "Add Tags",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            TextPrompt::QuickCapture => "Add a line to today's note:",
+            TextPrompt::MoveToNotebook => "Notebook name (leave empty to unfile the note):",
+            TextPrompt::AddTags => "Tags to add (comma separated):",
+        }
+    }
+}
+
+// a csv file waiting for the user to say which column is what
+#[derive(Debug, Clone)]
+pub struct CsvImport {
+    pub path: std::path::PathBuf,
+    pub preview: CsvPreview,
+    pub mapping: CsvMapping,
+    // index into CsvField::ALL
+    pub selected: usize,
+}
+
+// find/replace bar over the editor's content
+#[derive(Debug, Clone, Default)]
+pub struct FindBar {
+    pub query: String,
+    pub replacement: String,
+    // typing goes into the replacement instead of the query
+    pub in_replacement: bool,
+}
+
+// suggestions for the misspelled word under the editor's cursor
+#[derive(Debug, Clone)]
+pub struct SpellingPopup {
+    pub word: String,
+    pub row: usize,
+    // char columns of the word in its line
+    pub start: usize,
+    pub end: usize,
+    pub suggestions: Vec<String>,
+    pub selected: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompletionKind {
+    Tag,
+    Wikilink,
+}
+
+// completion popup for what's being typed after a # or [[ in the editor
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub kind: CompletionKind,
+    pub row: usize,
+    // char column right after the # or [[
+    pub start: usize,
+    pub candidates: Vec<String>,
+    pub selected: usize,
+}
+
+const MAX_COMPLETIONS: usize = 8;
+
+// the list's share of the split view can be resized within these bounds, in steps
+const MIN_SPLIT_RATIO: u16 = 20;
+const MAX_SPLIT_RATIO: u16 = 80;
+const SPLIT_STEP: u16 = 5;
+
+// lines one notch of the scroll wheel moves
+const MOUSE_SCROLL_LINES: usize = 3;
+// how many recently opened notes the quick switcher remembers
+const MAX_RECENT_NOTES: usize = 50;
+// how often unsaved editor text is written to the recovery journal
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(2);
+
+// "/" search inside the viewed note, matches are worked out from the current text when needed
+#[derive(Debug, Clone, Default)]
+pub struct ViewerSearch {
+    pub query: String,
+    // still typing the query, keys go to it instead of the viewer
+    pub typing: bool,
+    // index into the matches of the current text
+    pub current: usize,
+}
+
+// an open note, the one on screen lives in the App's own fields and its slot here is
+// only filled in while it's in the background
+pub struct Tab {
+    pub mode: AppMode,
+    pub note_id: Option<String>,
+    pub label: String,
+    // edits that haven't been saved yet, kept with the tab until it's closed or saved
+    pub dirty: bool,
+    edit_mode: EditMode,
+    title_textarea: TextArea<'static>,
+    content_textarea: TextArea<'static>,
+    tags_textarea: TextArea<'static>,
+    original_title: String,
+    original_content: String,
+    original_tags: String,
+    scroll_offset: usize,
+}
+
+impl Tab {
+    fn empty(mode: AppMode, note_id: Option<String>, label: String) -> Self {
+        Tab {
+            mode,
+            note_id,
+            label,
+            dirty: false,
+            edit_mode: EditMode::Title,
+            title_textarea: TextArea::default(),
+            content_textarea: TextArea::default(),
+            tags_textarea: TextArea::default(),
+            original_title: String::new(),
+            original_content: String::new(),
+            original_tags: String::new(),
+            scroll_offset: 0,
+        }
+    }
+}
+
+const LOCKED_MESSAGE: &str = "This note is locked, unlock it to make changes";
+const READ_ONLY_MESSAGE: &str = "The vault is open read-only (--read-only)";
+
+// notes as they were before an action, None for notes that didn't exist yet
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    pub description: String,
+    notes: Vec<(String, Option<Note>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum EditMode {
     Title,
     Content,
+    Tags,
 }
 
 pub struct App {
     pub mode: AppMode,
     pub edit_mode: EditMode,
     pub note_manager: NoteManager,
+    // the [vaults] entry that's open, None for the notes file from [behavior]
+    pub vault_name: Option<String>,
+    pub vault_choices: Vec<String>,
+    pub vault_index: usize,
+    // where Esc goes back to, None while picking the first vault at startup (Esc quits then)
+    pub vault_picker_return: Option<AppMode>,
+    // why the vault couldn't be opened, shown on the VaultInUse screen
+    pub vault_in_use: Option<String>,
     pub selected_note_index: usize,
     pub title_textarea: TextArea<'static>,
+    pub tags_textarea: TextArea<'static>,
     pub content_textarea: TextArea<'static>,
     pub current_note_id: Option<String>,
     pub viewing_note: Option<Note>,
     pub search_query: String,
+    // starts out as search_case_sensitive in the config, flipped from the search
+    pub search_case_sensitive: bool,
+    // the notebook/favorites filter of the list the search started from, until widened
+    pub search_scope: Option<NoteFilter>,
     pub search_cursor_position: usize,
     pub search_results: Vec<String>,
+    // where the query matched in each result's content, for the snippets
+    pub search_snippets: Vec<Option<(usize, usize)>>,
+    pub search_return_query: Option<String>,
     pub delete_note_title: String,
     pub scroll_offset: usize,
     pub should_quit: bool,
     pub highlighting_enabled: bool,
+    pub line_numbers_enabled: bool,
+    pub wrap_enabled: bool,
+    // first wrapped row shown in the editor, kept so the cursor stays in view
+    pub editor_wrap_top: usize,
+    // first column shown when the editor draws unwrapped lines itself
+    pub editor_wrap_left: usize,
+    // where the editor drew the cursor, for anchoring the completion popup
+    pub editor_cursor_screen: Option<(u16, u16)>,
+    // screen rows each visible note list entry covers and where the editor fields are,
+    // set while drawing so mouse clicks can be mapped back
+    pub list_item_rows: Vec<(u16, u16)>,
+    pub editor_field_areas: Vec<(EditMode, Rect)>,
+    // scroll position of the note list and search results, kept between frames
+    pub list_state: ListState,
+    pub split_view: bool,
+    pub split_ratio: u16,
+    pub relative_timestamps: bool,
+    pub tabs: Vec<Tab>,
+    // slot in tabs of the note on screen, None while on the list
+    pub active_tab: Option<usize>,
+    // quitting with unsaved tabs needs a second press
+    quit_armed: bool,
+    // digits typed in the list, Enter jumps to that note
+    pub jump_input: String,
+    // first g of gg
+    pending_g: bool,
+    // the start of a key sequence binding, waiting for the rest until pending_since + the timeout
+    pub pending_keys: Vec<KeyEvent>,
+    pending_since: Option<Instant>,
+    // outcome of the last write for the status bar
+    pub last_saved_at: Option<DateTime<Local>>,
+    pub last_save_failed: bool,
+    // settings to swap in for the running config, main picks them up on the next frame
+    pub config_update: Option<Config>,
+    pub theme_choices: Vec<String>,
+    pub theme_index: usize,
+    // the colors to go back to when the theme picker is cancelled
+    theme_before: Option<ColorTheme>,
+    pub settings_index: usize,
+    // the binding on the settings screen waiting for its new key
+    pub rebinding: Option<&'static str>,
+    // ids of the notes opened lately, most recent first
+    pub recent_notes: Vec<String>,
+    // quick switcher: what's typed, the notes it matches, the highlighted one and where Esc goes back to
+    pub switcher_query: String,
+    pub switcher_matches: Vec<String>,
+    pub switcher_index: usize,
+    pub switcher_return: AppMode,
+    // set while drawing the viewer: furthest it can scroll and how many rows fit
+    pub viewer_max_scroll: usize,
+    pub viewer_page_height: usize,
     pub help_visible: bool,
+    pub list_filter: NoteFilter,
     pub original_title: String,
     pub original_content: String,
+    pub original_tags: String,
     pub password_input: SecretString,
     pub password_error: Option<String>,
     pub password_limit_reached: bool,
+    // first entry of a new vault's password while it's typed again to confirm
+    pub password_setup_first: Option<SecretString>,
     pub export_file_input: String,
     pub export_cursor_position: usize,
+    pub spell_checker: Option<SpellChecker>,
+    pub status_message: Option<String>,
+    // bumped on every animation tick, drives spinners and the like
+    pub tick_count: u64,
+    pub path_prompt: Option<PathPrompt>,
+    pub path_input: String,
+    pub path_cursor_position: usize,
+    pub text_prompt: Option<TextPrompt>,
+    pub text_input: String,
+    // a byte offset into text_input, always on a char boundary
+    pub text_cursor_position: usize,
+    pub pending_import_path: Option<String>,
+    pub pending_save_as_path: Option<String>,
+    pub notebook_list_index: usize,
+    pub trash_index: usize,
+    // index into the viewed note's revisions, newest first
+    pub history_index: usize,
+    // the viewed note's entries in the change journal, oldest first, and the one picked (newest first)
+    pub journal_entries: Vec<change_journal::Entry>,
+    pub journal_index: usize,
+    // selected attachment of the viewed note
+    pub attachment_index: usize,
+    // set while a single note from the viewer goes through the export flow
+    pub export_note_id: Option<String>,
+    pub export_format: ExportFormat,
+    // password for an encrypted export, None to use the vault's key
+    pub export_password: Option<SecretString>,
+    // first entry of the export password while it's typed again to confirm
+    pub export_password_first: Option<SecretString>,
+    pub password_change_stage: PasswordChangeStage,
+    pub vault_password_action: VaultPasswordAction,
+    // the checked current password and the first entry of the new one, while the rest is typed
+    password_change_current: Option<SecretString>,
+    password_change_new: Option<SecretString>,
+    // the note the note password prompt is for
+    pub note_password: Option<(String, NotePasswordPurpose)>,
+    // first entry of a new note password while it's typed again to confirm
+    pub note_password_first: Option<SecretString>,
+    pub note_password_return: AppMode,
+    // outcome of the last folder import, shown until dismissed
+    pub import_report: Option<ImportReport>,
+    pub csv_import: Option<CsvImport>,
+    pub backups: Vec<BackupInfo>,
+    pub backup_index: usize,
+    last_backup_at: Option<Instant>,
+    last_backup_mtime: Option<SystemTime>,
+    // when the note being edited should be auto-saved, pushed back by every keystroke
+    autosave_due: Option<Instant>,
+    // the pending auto-save waits out auto_save_interval_secs, typing doesn't push it back
+    autosave_timed: bool,
+    // what's in the recovery journal, Some while there's one of ours on disk
+    recovery_written: Option<Recovery>,
+    recovery_checked: Option<Instant>,
+    // a journal left behind by a crash, waiting for restore or discard
+    pub recovered: Option<Recovery>,
+    pub undo_stack: Vec<UndoEntry>,
+    pub redo_stack: Vec<UndoEntry>,
+    undo_depth: usize,
+    editor_undo_depth: usize,
+    pub move_note_id: Option<String>,
+    // notes marked in the list, bulk actions apply to these instead of the highlighted note
+    pub marked_notes: HashSet<String>,
+    pub conflict_return_mode: Option<AppMode>,
+    // set when the user asks for the external editor, main suspends the terminal and runs it
+    pub external_edit_requested: bool,
+    // modal editing state for the content editor, None unless vim_mode is on
+    pub vim: Option<Vim>,
+    // open while searching inside the note being edited
+    pub find_bar: Option<FindBar>,
+    pub viewer_search: Option<ViewerSearch>,
+    pub spelling_popup: Option<SpellingPopup>,
+    pub completion: Option<Completion>,
 }
 
 impl App {
     pub fn new(config: &Config) -> io::Result<Self> {
-        let note_manager_result = NoteManager::new(&config.behavior.default_notes_file, config.behavior.encryption_enabled);
+        // --notes-file skips the picker, there's only the one file
+        if config.vaults.is_empty() || config.session.notes_file.is_some() {
+            return match Self::open_vault(config, None) {
+                // another instance has the notes file, say so instead of failing to start
+                Err(e) if vault_lock::is_in_use(&e) => {
+                    let mut app = Self::with_notes(config, NoteManager::new("/dev/null", false)?, AppMode::VaultInUse, None);
+                    app.vault_in_use = Some(e.to_string());
+                    Ok(app)
+                }
+                result => result,
+            };
+        }
+        // nothing is opened until a vault is picked
+        let mut app = Self::with_notes(config, NoteManager::new("/dev/null", false)?, AppMode::PickingVault, None);
+        app.start_vault_picker(config);
+        Ok(app)
+    }
+
+    // the named vault (None for the notes file in [behavior]), unlocked or asking for its password
+    pub fn open_vault(config: &Config, name: Option<&str>) -> io::Result<Self> {
+        let vault = config.vault(name)?;
+        let read_only = config.session.read_only;
+        log::info!("opening {}{}", vault.notes_file, if read_only { " read-only" } else { "" });
+        // the password prompt or setup below needs to know what's on the server
+        if vault.remote {
+            sync::fetch_remote_vault(Path::new(&vault.notes_file), &vault.sync_remote).map_err(|e| {
+                io::Error::new(e.kind(), format!("there's no local copy of the vault yet and fetching it failed: {}", e))
+            })?;
+        }
+        let note_manager_result = NoteManager::new(&vault.notes_file, vault.encryption_enabled).and_then(|mut manager| {
+            manager.set_read_only(read_only);
+            // only looking doesn't keep anyone else out
+            if read_only { Ok(manager) } else { manager.acquire_lock().map(|()| manager) }
+        });
         
-        let (note_manager, mode) = match note_manager_result {
+        let (mut note_manager, mode) = match note_manager_result {
             Ok(manager) => {
-                let mode = if config.behavior.encryption_enabled {
-                    let notes_path = Path::new(&config.behavior.default_notes_file);
+                let mode = if vault.encryption_enabled {
+                    let notes_path = Path::new(&vault.notes_file);
                     if notes_path.exists() {
                         // check if existing file is encrypted
                         match std::fs::read_to_string(notes_path) {
@@ -97,43 +553,458 @@ impl App {
                 }
             }
         };
-        
-        Ok(App {
+        note_manager.set_history_limit(config.behavior.history_revisions);
+        note_manager.set_change_journal(config.behavior.change_journal);
+        note_manager.set_keyfile(vault.keyfile_path());
+        note_manager.set_sort_mode(config.behavior.sort_mode);
+
+        let mut app = Self::with_notes(config, note_manager, mode, name.map(str::to_string));
+        if app.mode == AppMode::PasswordPrompt && config.behavior.remember_password {
+            app.unlock_from_keyring();
+        }
+        if app.mode == AppMode::PasswordSetup && (read_only || config.session.no_encryption_prompt) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "encryption isn't set up for this vault yet, start without --read-only or --no-encryption-prompt to set a password",
+            ));
+        }
+        if app.mode == AppMode::PasswordPrompt && config.session.no_encryption_prompt {
+            app.unlock_from_environment()?;
+        }
+        if app.mode == AppMode::NoteList {
+            app.on_vault_ready(config);
+        }
+        Ok(app)
+    }
+
+    fn with_notes(config: &Config, note_manager: NoteManager, mode: AppMode, vault_name: Option<String>) -> Self {
+        let spell_checker = load_spell_checker(&config.behavior);
+
+        App {
             mode,
             edit_mode: EditMode::Title,
             note_manager,
+            vault_name,
+            vault_choices: Vec::new(),
+            vault_index: 0,
+            vault_picker_return: None,
+            vault_in_use: None,
             selected_note_index: 0,
             title_textarea: TextArea::default(),
+            tags_textarea: TextArea::default(),
             content_textarea: TextArea::default(),
             current_note_id: None,
             viewing_note: None,
             search_query: String::new(),
+            search_case_sensitive: config.behavior.search_case_sensitive,
+            search_scope: None,
             search_cursor_position: 0,
             search_results: Vec::new(),
+            search_snippets: Vec::new(),
+            search_return_query: None,
             delete_note_title: String::new(),
             scroll_offset: 0,
             should_quit: false,
             highlighting_enabled: config.behavior.highlighting_enabled,
+            line_numbers_enabled: config.behavior.show_line_numbers,
+            wrap_enabled: config.behavior.soft_wrap,
+            editor_wrap_top: 0,
+            editor_wrap_left: 0,
+            editor_cursor_screen: None,
+            list_item_rows: Vec::new(),
+            editor_field_areas: Vec::new(),
+            list_state: ListState::default(),
+            split_view: config.behavior.split_view,
+            relative_timestamps: config.behavior.relative_timestamps,
+            split_ratio: config.behavior.split_ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO),
+            tabs: Vec::new(),
+            active_tab: None,
+            quit_armed: false,
+            jump_input: String::new(),
+            pending_g: false,
+            pending_keys: Vec::new(),
+            pending_since: None,
+            last_saved_at: None,
+            last_save_failed: false,
+            config_update: None,
+            theme_choices: Vec::new(),
+            theme_index: 0,
+            theme_before: None,
+            settings_index: 0,
+            rebinding: None,
+            recent_notes: Vec::new(),
+            switcher_query: String::new(),
+            switcher_matches: Vec::new(),
+            switcher_index: 0,
+            switcher_return: AppMode::NoteList,
+            viewer_max_scroll: usize::MAX,
+            viewer_page_height: 10,
             help_visible: true,
+            list_filter: NoteFilter::default(),
             original_title: String::new(),
             original_content: String::new(),
+            original_tags: String::new(),
             password_input: SecretString::new("".into()),
             password_error: None,
             password_limit_reached: false,
+            password_setup_first: None,
             export_file_input: String::new(),
             export_cursor_position: 0,
-        })
+            spell_checker,
+            status_message: None,
+            tick_count: 0,
+            path_prompt: None,
+            path_input: String::new(),
+            path_cursor_position: 0,
+            text_prompt: None,
+            text_input: String::new(),
+            text_cursor_position: 0,
+            pending_import_path: None,
+            pending_save_as_path: None,
+            notebook_list_index: 0,
+            trash_index: 0,
+            history_index: 0,
+            journal_entries: Vec::new(),
+            journal_index: 0,
+            backups: Vec::new(),
+            backup_index: 0,
+            last_backup_at: None,
+            last_backup_mtime: None,
+            autosave_due: None,
+            autosave_timed: false,
+            recovery_written: None,
+            recovery_checked: None,
+            recovered: None,
+            marked_notes: HashSet::new(),
+            attachment_index: 0,
+            export_note_id: None,
+            export_format: ExportFormat::Json,
+            export_password: None,
+            export_password_first: None,
+            password_change_stage: PasswordChangeStage::Current,
+            vault_password_action: VaultPasswordAction::Change,
+            password_change_current: None,
+            password_change_new: None,
+            note_password: None,
+            note_password_first: None,
+            note_password_return: AppMode::NoteList,
+            import_report: None,
+            csv_import: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            undo_depth: config.behavior.undo_depth,
+            editor_undo_depth: config.behavior.editor_undo_depth,
+            move_note_id: None,
+            conflict_return_mode: None,
+            external_edit_requested: false,
+            vim: config.modal_editing().then(Vim::default),
+            find_bar: None,
+            viewer_search: None,
+            spelling_popup: None,
+            completion: None,
+        }
+    }
+
+    // a remembered password that stopped working (changed on another machine) falls back to the prompt
+    fn unlock_from_keyring(&mut self) {
+        match credentials::load(self.note_manager.notes_file()) {
+            Ok(Some(password)) => match self.note_manager.unlock_encryption(password.expose_secret()) {
+                Ok(()) => self.mode = AppMode::NoteList,
+                Err(e) => self.show_error(format!("The remembered password didn't unlock the vault: {}", e)),
+            },
+            Ok(None) => {}
+            Err(e) => self.show_error(format!("Couldn't read the keyring: {}", e)),
+        }
+    }
+
+    // --no-encryption-prompt, the password the keyring didn't have has to be in the environment
+    fn unlock_from_environment(&mut self) -> io::Result<()> {
+        let password = std::env::var(cli::PASSWORD_ENV).map(Zeroizing::new).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("the vault is encrypted and there's no prompt, set {} or remember the password", cli::PASSWORD_ENV),
+            )
+        })?;
+        self.note_manager.unlock_encryption(&password)?;
+        self.mode = AppMode::NoteList;
+        Ok(())
+    }
+
+    fn remember_password(&mut self, password: &SecretString, config: &Config) {
+        if !config.behavior.remember_password {
+            return;
+        }
+        if let Err(e) = credentials::save(self.note_manager.notes_file(), password) {
+            self.show_error(format!("Couldn't save the password to the keyring: {}", e));
+        }
+    }
+
+    // housekeeping once the notes are loaded (and unlocked)
+    fn on_vault_ready(&mut self, config: &Config) {
+        // poll_loading calls this again when the notes are in
+        if self.note_manager.is_loading() {
+            return;
+        }
+        // both would write to the vault
+        if self.note_manager.is_read_only() {
+            return;
+        }
+        if let Some(remote) = self.remote_of_open_vault(config) {
+            match self.note_manager.sync(&remote) {
+                Ok(summary) => {
+                    log::info!("synced with {}: {}", remote, summary);
+                    self.refresh_after_reload();
+                    self.status_message = Some(format!("Synced with the server: {}", summary));
+                }
+                Err(e) => {
+                    log::warn!("syncing with {} failed: {}", remote, e);
+                    self.status_message = Some(format!("Working on the local copy, syncing with the server failed: {}", e));
+                }
+            }
+        }
+        self.offer_recovery();
+
+        let retention_days = config.behavior.trash_retention_days;
+        if retention_days == 0 {
+            return;
+        }
+
+        let purged = self.note_manager.purge_trash(chrono::Duration::days(retention_days as i64));
+        if purged > 0 {
+            self.status_message = Some(match self.note_manager.save_notes() {
+                Ok(()) => format!("Purged {} note(s) from the trash older than {} days", purged, retention_days),
+                Err(e) => format!("Purged {} old trashed note(s) but saving failed: {}", purged, e),
+            });
+        }
+    }
+
+    pub fn handle_mouse(&mut self, mouse: MouseEvent) -> io::Result<()> {
+        if self.note_manager.is_loading() {
+            return Ok(());
+        }
+        let editing = matches!(self.mode, AppMode::EditingNote | AppMode::CreatingNote);
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                let down = mouse.kind == MouseEventKind::ScrollDown;
+                match self.mode {
+                    AppMode::NoteList if down => self.move_selection_down(),
+                    AppMode::NoteList => self.move_selection_up(),
+                    AppMode::Searching if down => self.move_selection_down_filtered(),
+                    AppMode::Searching => self.move_selection_up_filtered(),
+                    AppMode::ViewingNote => {
+                        for _ in 0..MOUSE_SCROLL_LINES {
+                            if down { self.scroll_down() } else { self.scroll_up() }
+                        }
+                    }
+                    _ if editing && self.edit_mode == EditMode::Content => {
+                        // moving the cursor scrolls both the textarea and the wrapped view
+                        let direction = if down { tui_textarea::CursorMove::Down } else { tui_textarea::CursorMove::Up };
+                        for _ in 0..MOUSE_SCROLL_LINES {
+                            self.content_textarea.move_cursor(direction);
+                        }
+                        self.completion = None;
+                    }
+                    _ => {}
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let position = Position::new(mouse.column, mouse.row);
+                match self.mode {
+                    AppMode::NoteList | AppMode::Searching => {
+                        let clicked = self.list_item_rows
+                            .iter()
+                            .position(|&(top, bottom)| (top..bottom).contains(&mouse.row));
+                        // the rows start at the first note scrolled into view
+                        let Some(index) = clicked.map(|row| row + self.list_state.offset()) else { return Ok(()) };
+                        // the first click selects, clicking the selected note opens it
+                        if index != self.selected_note_index {
+                            self.selected_note_index = index;
+                        } else if self.mode == AppMode::Searching {
+                            self.start_viewing_filtered_note();
+                        } else {
+                            self.start_viewing_selected_note();
+                        }
+                    }
+                    _ if editing && self.find_bar.is_none() => {
+                        let clicked = self.editor_field_areas
+                            .iter()
+                            .find(|(_, area)| area.contains(position))
+                            .map(|(field, _)| *field);
+                        if let Some(field) = clicked {
+                            self.edit_mode = field;
+                            self.spelling_popup = None;
+                            self.completion = None;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn handle_paste(&mut self, text: &str, config: &Config) -> io::Result<()> {
+        self.status_message = None;
+        if self.note_manager.is_loading() {
+            return Ok(());
+        }
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+        let editing = matches!(self.mode, AppMode::EditingNote | AppMode::CreatingNote);
+        if editing && self.find_bar.is_none() {
+            self.spelling_popup = None;
+            self.completion = None;
+            // one insert is one undo step and one autosave
+            match self.edit_mode {
+                EditMode::Title => self.title_textarea.insert_str(text.replace('\n', " ")),
+                EditMode::Content => self.content_textarea.insert_str(&text),
+                EditMode::Tags => self.tags_textarea.insert_str(text.replace('\n', " ")),
+            };
+            self.schedule_autosave(config);
+            return Ok(());
+        }
+
+        // single line inputs take the paste as typed characters, anywhere else it's ignored
+        // so pasted text can't fire off list commands
+        let typing = editing
+            || self.viewer_search.as_ref().is_some_and(|search| search.typing)
+            || matches!(self.mode,
+                AppMode::PasswordPrompt
+                | AppMode::PasswordSetup
+                | AppMode::Searching
+                | AppMode::EnteringPath
+                | AppMode::EnteringText
+                | AppMode::EnteringExportPassword
+                | AppMode::ReauthenticatingForExport
+                | AppMode::ChangingPassword
+                | AppMode::NotePassword);
+        if typing {
+            for c in text.chars().filter(|c| !c.is_control()) {
+                self.handle_input(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE), config)?;
+            }
+        }
+        Ok(())
     }
 
+    // keys that start one of the sequence bindings ("g g") are held back until the sequence is
+    // finished or broken off, the rest go straight to handle_key
     pub fn handle_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        // where keys are typed text only a sequence starting with ctrl or alt can't be mistaken for it
+        let commands = self.takes_sequences();
+        let sequences: Vec<&KeyBinding> = config.keybindings.sequences()
+            .into_iter()
+            .filter(|sequence| commands || sequence.steps().first().is_some_and(KeyBinding::is_chord))
+            .collect();
+        if sequences.is_empty() || self.note_manager.is_loading() || self.rebinding.is_some() {
+            self.pending_keys.clear();
+            self.pending_since = None;
+            return self.handle_key(key, config);
+        }
+        // Esc drops a half typed sequence
+        if key.code == KeyCode::Esc && !self.pending_keys.is_empty() {
+            self.pending_keys.clear();
+            self.pending_since = None;
+            return Ok(());
+        }
+
+        self.pending_keys.push(key);
+        let typed = self.pending_keys.as_slice();
+        let goes_on = sequences.iter().any(|sequence| {
+            let steps = sequence.steps();
+            steps.len() > typed.len() && sequence_starts_with(&steps, typed)
+        });
+        if goes_on {
+            self.pending_since = Some(Instant::now());
+            return Ok(());
+        }
+        if let Some(sequence) = finished_sequence(&sequences, typed) {
+            self.pending_keys.clear();
+            self.pending_since = None;
+            return self.run_sequence(sequence, config);
+        }
+
+        // the key breaks off what was typed before, that counts first and then the key on its own
+        self.pending_keys.pop();
+        if self.pending_keys.is_empty() {
+            self.pending_since = None;
+            return self.handle_key(key, config);
+        }
+        self.flush_pending_keys(config)?;
+        self.handle_input(key, config)
+    }
+
+    // the keys typed so far as the sequence they make up, or one by one when they make none
+    fn flush_pending_keys(&mut self, config: &Config) -> io::Result<()> {
+        let keys = std::mem::take(&mut self.pending_keys);
+        self.pending_since = None;
+        match finished_sequence(&config.keybindings.sequences(), &keys) {
+            Some(sequence) => self.run_sequence(sequence, config),
+            None => {
+                for key in keys {
+                    self.handle_key(key, config)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn run_sequence(&mut self, sequence: &KeyBinding, config: &Config) -> io::Result<()> {
+        finish_sequence(sequence, || self.handle_key(KeyEvent::new(KeyCode::Null, KeyModifiers::NONE), config))
+    }
+
+    // called every loop iteration, a sequence left unfinished for too long counts as typed
+    pub fn run_sequence_timeout(&mut self, config: &Config) -> io::Result<()> {
+        let timeout = Duration::from_millis(config.behavior.sequence_timeout_ms);
+        if self.pending_since.is_some_and(|since| since.elapsed() >= timeout) {
+            self.flush_pending_keys(config)?;
+        }
+        Ok(())
+    }
+
+    // any sequence works where keys are commands, anywhere else they're typed text
+    fn takes_sequences(&self) -> bool {
+        self.jump_input.is_empty()
+            && !self.viewer_search.as_ref().is_some_and(|search| search.typing)
+            && matches!(self.mode,
+                AppMode::NoteList
+                | AppMode::ViewingNote
+                | AppMode::NotebookList
+                | AppMode::TrashView
+                | AppMode::ViewingHistory
+                | AppMode::ViewingJournal
+                | AppMode::BackupList
+                | AppMode::Statistics
+                | AppMode::Settings)
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        // status messages only stick around until the next keypress
+        self.status_message = None;
+
+        // nothing to act on until the notes are in
+        if self.note_manager.is_loading() {
+            if config.keybindings.quit.matches(key.code, key.modifiers) {
+                self.should_quit = true;
+            }
+            return Ok(());
+        }
+
         if config.keybindings.toggle_help.matches(key.code, key.modifiers) {
             self.help_visible = !self.help_visible;
             return Ok(());
         }
+
+        if self.note_manager.is_read_only() && self.changes_notes(key, config) {
+            self.status_message = Some(READ_ONLY_MESSAGE.to_string());
+            return Ok(());
+        }
         
         if config.keybindings.manual_save.matches(key.code, key.modifiers) {
             match self.mode {
                 AppMode::EditingNote => {
+                    self.autosave_due = None;
                     self.save_current_note()?;
                     return Ok(());
                 }
@@ -159,35 +1030,106 @@ impl App {
             }
         }
 
+        if !config.keybindings.quit.matches(key.code, key.modifiers) {
+            self.quit_armed = false;
+        }
+
+        if matches!(self.mode, AppMode::NoteList | AppMode::ViewingNote | AppMode::EditingNote | AppMode::CreatingNote) {
+            let kb = &config.keybindings;
+            if kb.next_tab.matches(key.code, key.modifiers) || kb.previous_tab.matches(key.code, key.modifiers) {
+                self.cycle_tab(kb.next_tab.matches(key.code, key.modifiers), config);
+                return Ok(());
+            } else if kb.close_tab.matches(key.code, key.modifiers) {
+                return self.close_tab(config);
+            } else if kb.new_tab.matches(key.code, key.modifiers) && self.active_tab.is_some() {
+                self.park_tab(config);
+                self.return_to_list();
+                return Ok(());
+            }
+        }
+
         match self.mode {
             AppMode::PasswordPrompt => self.handle_password_input(key, config),
             AppMode::PasswordSetup => self.handle_password_setup_input(key, config),
             AppMode::NoteList => self.handle_list_input(key, config),
             AppMode::Searching => self.handle_search_input(key, config),
             AppMode::ViewingNote => self.handle_viewing_input(key, config),
+            AppMode::ViewingHistory => self.handle_history_input(key, config),
+            AppMode::ViewingJournal => self.handle_journal_input(key, config),
+            AppMode::BackupList => self.handle_backup_list_input(key, config),
+            AppMode::ConfirmingRestoreBackup => self.handle_restore_backup_input(key, config),
             AppMode::EditingNote | AppMode::CreatingNote => self.handle_editor_input(key, config),
             AppMode::ConfirmingDelete => self.handle_delete_confirmation_input(key, config),
             AppMode::ConfirmingUnsavedExit => self.handle_unsaved_exit_confirmation_input(key, config),
             AppMode::ConfirmingExport => self.handle_export_confirmation_input(key, config),
             AppMode::ReauthenticatingForExport => self.handle_reauthentication_input(key, config),
+            AppMode::EnteringExportPassword => self.handle_export_password_input(key, config),
             AppMode::SelectingExportLocation => self.handle_export_location_input(key, config),
-            AppMode::EncryptedFileWarning => self.handle_encrypted_file_warning_input(key, config),
+            AppMode::EncryptedFileWarning | AppMode::VaultInUse => self.handle_startup_error_input(key, config),
+            AppMode::ConfirmingRecovery => self.handle_recovery_input(key, config),
+            AppMode::About => self.handle_about_input(key, config),
+            AppMode::EnteringPath => self.handle_path_input(key, config),
+            AppMode::EnteringText => self.handle_text_input(key, config),
+            AppMode::ChoosingImportSource => self.handle_import_source_input(key, config),
+            AppMode::ChoosingImportPolicy => self.handle_import_policy_input(key, config),
+            AppMode::ChoosingSaveAsMode => self.handle_save_as_mode_input(key, config),
+            AppMode::ResolvingConflict => self.handle_conflict_input(key, config),
+            AppMode::Statistics => self.handle_statistics_input(key, config),
+            AppMode::Importing => self.handle_import_report_input(key, config),
+            AppMode::MappingCsvColumns => self.handle_csv_mapping_input(key, config),
+            AppMode::ChoosingTheme => self.handle_theme_input(key, config),
+            AppMode::QuickSwitch => self.handle_quick_switch_input(key, config),
+            AppMode::ChangingPassword => self.handle_change_password_input(key, config),
+            AppMode::NotePassword => self.handle_note_password_input(key, config),
+            AppMode::PickingVault => self.handle_vault_picker_input(key, config),
+            AppMode::Settings => self.handle_settings_input(key, config),
+            AppMode::ConfirmingEmptyTrash => self.handle_empty_trash_confirmation_input(key, config),
+            AppMode::NotebookList => self.handle_notebook_list_input(key, config),
+            AppMode::TrashView => self.handle_trash_input(key, config),
+            AppMode::ConfirmingPurge => self.handle_purge_confirmation_input(key, config),
         }
     }
 
-    fn handle_password_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+    // keys whose action writes to the vault, turned away while it's open read-only. editing
+    // is refused where it starts, see edit_refusal
+    fn changes_notes(&self, key: KeyEvent, config: &Config) -> bool {
+        let kb = &config.keybindings;
+        let writing: Vec<&KeyBinding> = match self.mode {
+            AppMode::NoteList => vec![
+                &kb.create_note, &kb.quick_capture, &kb.delete_note, &kb.empty_trash, &kb.toggle_pin,
+                &kb.toggle_favorite, &kb.toggle_archive, &kb.toggle_lock, &kb.add_tags, &kb.move_to_notebook,
+                &kb.move_note_up, &kb.move_note_down, &kb.undo, &kb.redo, &kb.import_json, &kb.save_as,
+                &kb.encrypt_note, &kb.change_password, &kb.toggle_vault_encryption, &kb.sync_vault,
+            ],
+            AppMode::ViewingNote => vec![
+                &kb.toggle_todo, &kb.add_attachment, &kb.remove_attachment, &kb.toggle_lock,
+                &kb.encrypt_note, &kb.external_editor,
+            ],
+            AppMode::TrashView => vec![&kb.restore_note, &kb.delete_note],
+            AppMode::ViewingHistory | AppMode::BackupList => vec![&kb.restore_note],
+            _ => return false,
+        };
+        writing.iter().any(|binding| binding.matches(key.code, key.modifiers))
+    }
+
+    fn handle_password_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
         use crossterm::event::KeyCode;
         
         match key.code {
+            // the prompt counts down until another password may be tried, keep what's typed until then
+            KeyCode::Enter if self.note_manager.unlock_wait().is_some() => {}
             KeyCode::Enter => {
                 if !self.password_input.expose_secret().is_empty() {
                     match self.note_manager.unlock_encryption(self.password_input.expose_secret()) {
                         Ok(()) => {
                             self.mode = AppMode::NoteList;
-                            self.password_input = SecretString::new("".into());
+                            let password = std::mem::replace(&mut self.password_input, SecretString::new("".into()));
+                            self.remember_password(&password, config);
                             self.password_error = None;
+                            self.on_vault_ready(config);
                         }
                         Err(e) => {
+                            log::warn!("unlocking {} failed: {}", self.note_manager.notes_file().display(), e);
                             self.password_error = Some(e.to_string());
                             self.password_input = SecretString::new("".into());
                         }
@@ -198,20 +1140,15 @@ impl App {
                 self.should_quit = true;
             }
             KeyCode::Backspace => {
-                if !self.password_input.expose_secret().is_empty() {
-                    let secret_chars: Vec<char> = self.password_input.expose_secret().chars().collect();
-                    let char_count = secret_chars.len();
-                    let new_chars: Vec<char> = secret_chars.into_iter().take(char_count - 1).collect();
-                    self.password_input = SecretString::new(new_chars.into_iter().collect());
-                }
+                edit_password(&mut self.password_input, |text| {
+                    text.pop();
+                });
                 self.password_error = None;
                 self.password_limit_reached = false;
             }
             KeyCode::Char(c) => {
                 if self.password_input.expose_secret().len() < MAX_PASSWORD_LENGTH {
-                    let mut new_secret_str = self.password_input.expose_secret().to_string();
-                    new_secret_str.push(c);
-                    self.password_input = SecretString::new(new_secret_str.into());
+                    edit_password(&mut self.password_input, |text| text.push(c));
                     self.password_limit_reached = self.password_input.expose_secret().len() >= MAX_PASSWORD_LENGTH;
                 } else {
                     self.password_limit_reached = true;
@@ -223,43 +1160,61 @@ impl App {
         Ok(())
     }
 
-    fn handle_password_setup_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+    // the new vault's password is typed twice, a typo in a single entry would lock the vault for good
+    fn handle_password_setup_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
         use crossterm::event::KeyCode;
         
         match key.code {
             KeyCode::Enter => {
-                if !self.password_input.expose_secret().is_empty() {
-                    match self.note_manager.unlock_encryption(self.password_input.expose_secret()) {
-                        Ok(()) => {
-                            self.mode = AppMode::NoteList;
-                            self.password_input = SecretString::new("".into());
-                            self.password_error = None;
-                        }
-                        Err(e) => {
-                            self.password_error = Some(e.to_string());
-                            self.password_input = SecretString::new("".into());
+                let entered = std::mem::replace(&mut self.password_input, SecretString::new("".into()));
+                self.password_limit_reached = false;
+                match self.password_setup_first.take() {
+                    None if entered.expose_secret().len() < MIN_PASSWORD_LENGTH => {
+                        self.password_error = Some(format!("Password must be at least {} characters", MIN_PASSWORD_LENGTH));
+                    }
+                    None => {
+                        self.password_error = None;
+                        self.password_setup_first = Some(entered);
+                    }
+                    Some(first) if first.expose_secret() == entered.expose_secret() => {
+                        match self.note_manager.unlock_encryption(first.expose_secret()) {
+                            Ok(()) => {
+                                self.mode = AppMode::NoteList;
+                                self.password_error = None;
+                                self.remember_password(&first, config);
+                                self.on_vault_ready(config);
+                            }
+                            Err(e) => {
+                                log::error!("setting up encryption for {} failed: {}", self.note_manager.notes_file().display(), e);
+                                self.password_error = Some(e.to_string());
+                            }
                         }
                     }
+                    Some(_) => {
+                        self.password_error = Some("Passwords don't match, choose one again".to_string());
+                    }
                 }
             }
+            KeyCode::Esc if self.password_setup_first.is_some() => {
+                // back to picking the password
+                self.password_setup_first = None;
+                self.password_input = SecretString::new("".into());
+                self.password_error = None;
+                self.password_limit_reached = false;
+            }
             KeyCode::Esc => {
                 self.should_quit = true;
             }
             KeyCode::Backspace => {
-                if !self.password_input.expose_secret().is_empty() {
-                    let secret_chars: Vec<char> = self.password_input.expose_secret().chars().collect();
-                    let char_count = secret_chars.len();
-                    let new_chars: Vec<char> = secret_chars.into_iter().take(char_count - 1).collect();
-                    self.password_input = SecretString::new(new_chars.into_iter().collect());
-                }
+                edit_password(&mut self.password_input, |text| {
+                    text.pop();
+                });
                 self.password_error = None;
                 self.password_limit_reached = false;
             }
             KeyCode::Char(c) => {
                 if self.password_input.expose_secret().len() < MAX_PASSWORD_LENGTH {
-                    let mut new_secret_str = self.password_input.expose_secret().to_string();
-                    new_secret_str.push(c);
-                    self.password_input = SecretString::new(new_secret_str.into());
+                    edit_password(&mut self.password_input, |text| text.push(c));
                     self.password_limit_reached = self.password_input.expose_secret().len() >= MAX_PASSWORD_LENGTH;
                 } else {
                     self.password_limit_reached = true;
@@ -273,9 +1228,63 @@ impl App {
 
     fn handle_list_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
         let kb = &config.keybindings;
-        
+
+        let digit = match key.code {
+            KeyCode::Char(c) if c.is_ascii_digit() && key.modifiers.is_empty() => Some(c),
+            _ => None,
+        };
+        if let Some(digit) = digit {
+            self.jump_input.push(digit);
+            return Ok(());
+        }
+        if !self.jump_input.is_empty() {
+            match key.code {
+                KeyCode::Enter => {
+                    let count = self.note_manager.get_filtered_notes(&self.list_filter).len();
+                    let number = std::mem::take(&mut self.jump_input).parse::<usize>().unwrap_or(0);
+                    if number > 0 {
+                        self.selected_note_index = (number - 1).min(count.saturating_sub(1));
+                    }
+                    return Ok(());
+                }
+                KeyCode::Backspace => {
+                    self.jump_input.pop();
+                    return Ok(());
+                }
+                KeyCode::Esc => {
+                    self.jump_input.clear();
+                    return Ok(());
+                }
+                _ => self.jump_input.clear(),
+            }
+        }
+        // gg goes to the top like in vim
+        let g = key.code == KeyCode::Char('g') && key.modifiers.is_empty();
+        if std::mem::take(&mut self.pending_g) && g {
+            self.selected_note_index = 0;
+            return Ok(());
+        } else if g {
+            self.pending_g = true;
+            return Ok(());
+        }
+
         if kb.quit.matches(key.code, key.modifiers) {
-            self.should_quit = true;
+            self.request_quit();
+        } else if kb.return_to_list.matches(key.code, key.modifiers) && !self.marked_notes.is_empty() {
+            self.marked_notes.clear();
+        } else if kb.toggle_mark.matches(key.code, key.modifiers) {
+            let notes = self.note_manager.get_filtered_notes(&self.list_filter);
+            if let Some(note) = notes.get(self.selected_note_index) {
+                let id = note.id.clone();
+                if !self.marked_notes.remove(&id) {
+                    self.marked_notes.insert(id);
+                }
+                self.move_selection_down();
+            }
+        } else if kb.add_tags.matches(key.code, key.modifiers) {
+            if !self.target_note_ids().is_empty() {
+                self.start_text_prompt(TextPrompt::AddTags, String::new());
+            }
         } else if kb.create_note.matches(key.code, key.modifiers) {
             self.start_creating_note();
         } else if kb.view_note.matches(key.code, key.modifiers) {
@@ -292,8 +1301,108 @@ impl App {
             self.move_selection_up();
         } else if kb.move_down.matches(key.code, key.modifiers) {
             self.move_selection_down();
+        } else if kb.page_up.matches(key.code, key.modifiers) || kb.page_down.matches(key.code, key.modifiers) {
+            let count = self.note_manager.get_filtered_notes(&self.list_filter).len();
+            self.page_selection(kb.page_down.matches(key.code, key.modifiers), count);
+        } else if key_matches_any(&kb.jump_to_top, key.code, key.modifiers) {
+            self.selected_note_index = 0;
+        } else if key_matches_any(&kb.jump_to_bottom, key.code, key.modifiers) {
+            let count = self.note_manager.get_filtered_notes(&self.list_filter).len();
+            self.selected_note_index = count.saturating_sub(1);
+        } else if kb.toggle_relative_time.matches(key.code, key.modifiers) {
+            self.relative_timestamps = !self.relative_timestamps;
+        } else if kb.choose_theme.matches(key.code, key.modifiers) {
+            self.start_choosing_theme(config);
+        } else if kb.show_settings.matches(key.code, key.modifiers) {
+            self.settings_index = 0;
+            self.rebinding = None;
+            self.mode = AppMode::Settings;
+        } else if kb.quick_switch.matches(key.code, key.modifiers) {
+            self.start_quick_switch();
+        } else if kb.switch_vault.matches(key.code, key.modifiers) {
+            self.start_vault_picker(config);
+        } else if kb.sync_vault.matches(key.code, key.modifiers) {
+            self.sync_vault(config);
+        } else if kb.change_password.matches(key.code, key.modifiers) {
+            self.start_changing_password();
+        } else if kb.toggle_vault_encryption.matches(key.code, key.modifiers) {
+            self.start_toggling_vault_encryption();
+        } else if kb.toggle_split_view.matches(key.code, key.modifiers) {
+            self.split_view = !self.split_view;
+        } else if kb.shrink_list.matches(key.code, key.modifiers) && self.split_view {
+            self.split_ratio = self.split_ratio.saturating_sub(SPLIT_STEP).max(MIN_SPLIT_RATIO);
+        } else if kb.grow_list.matches(key.code, key.modifiers) && self.split_view {
+            self.split_ratio = (self.split_ratio + SPLIT_STEP).min(MAX_SPLIT_RATIO);
+        } else if kb.cycle_sort.matches(key.code, key.modifiers) {
+            self.cycle_sort_mode(config);
+        } else if kb.move_note_up.matches(key.code, key.modifiers) {
+            self.move_selected_note(true)?;
+        } else if kb.move_note_down.matches(key.code, key.modifiers) {
+            self.move_selected_note(false)?;
         } else if kb.toggle_pin.matches(key.code, key.modifiers) {
             self.toggle_pin_selected_note()?;
+        } else if kb.toggle_favorite.matches(key.code, key.modifiers) {
+            self.toggle_favorite_selected_note()?;
+        } else if kb.toggle_archive.matches(key.code, key.modifiers) {
+            self.toggle_archive_selected_note()?;
+        } else if kb.toggle_lock.matches(key.code, key.modifiers) {
+            let notes = self.note_manager.get_filtered_notes(&self.list_filter);
+            if let Some(id) = notes.get(self.selected_note_index).map(|note| note.id.clone()) {
+                self.toggle_lock(&id)?;
+            }
+        } else if kb.encrypt_note.matches(key.code, key.modifiers) {
+            let notes = self.note_manager.get_filtered_notes(&self.list_filter);
+            if let Some(id) = notes.get(self.selected_note_index).map(|note| note.id.clone()) {
+                self.toggle_note_encryption(&id)?;
+            }
+        } else if kb.show_archive.matches(key.code, key.modifiers) {
+            self.list_filter.archived = !self.list_filter.archived;
+            self.selected_note_index = 0;
+        } else if kb.toggle_favorites_filter.matches(key.code, key.modifiers) {
+            self.list_filter.favorites_only = !self.list_filter.favorites_only;
+            self.selected_note_index = 0;
+        } else if kb.show_about.matches(key.code, key.modifiers) {
+            self.mode = AppMode::About;
+        } else if kb.import_json.matches(key.code, key.modifiers) {
+            self.mode = AppMode::ChoosingImportSource;
+        } else if kb.undo.matches(key.code, key.modifiers) {
+            self.undo()?;
+        } else if kb.redo.matches(key.code, key.modifiers) {
+            self.redo()?;
+        } else if kb.show_backups.matches(key.code, key.modifiers) {
+            self.open_backup_list(config);
+        } else if kb.show_trash.matches(key.code, key.modifiers) {
+            self.trash_index = 0;
+            self.mode = AppMode::TrashView;
+        } else if kb.show_notebooks.matches(key.code, key.modifiers) {
+            // start on whichever notebook is currently shown
+            let notebooks = self.note_manager.notebooks();
+            self.notebook_list_index = match &self.list_filter.notebook {
+                Some(current) => notebooks.iter().position(|n| &n.name == current).map_or(0, |i| i + 1),
+                None => 0,
+            };
+            self.mode = AppMode::NotebookList;
+        } else if kb.move_to_notebook.matches(key.code, key.modifiers) {
+            let notes = self.note_manager.get_filtered_notes(&self.list_filter);
+            if let Some(note) = notes.get(self.selected_note_index) {
+                let (id, notebook) = (note.id.clone(), note.notebook.clone());
+                self.move_note_id = Some(id);
+                self.start_text_prompt(TextPrompt::MoveToNotebook, notebook);
+            }
+        } else if kb.quick_capture.matches(key.code, key.modifiers) {
+            self.start_text_prompt(TextPrompt::QuickCapture, String::new());
+        } else if kb.save_as.matches(key.code, key.modifiers) {
+            let current = self.note_manager.notes_file().to_string_lossy().to_string();
+            self.start_path_prompt(PathPrompt::SaveAs, current);
+        } else if kb.show_statistics.matches(key.code, key.modifiers) {
+            self.mode = AppMode::Statistics;
+            self.scroll_offset = 0;
+        } else if kb.empty_trash.matches(key.code, key.modifiers) {
+            if self.note_manager.trash_count() == 0 {
+                self.status_message = Some("Trash is already empty".to_string());
+            } else {
+                self.mode = AppMode::ConfirmingEmptyTrash;
+            }
         }
         
         Ok(())
@@ -312,6 +1421,18 @@ impl App {
             if !self.search_results.is_empty() {
                 self.start_viewing_filtered_note();
             }
+        } else if kb.search_edit.matches(key.code, key.modifiers) {
+            if !self.search_results.is_empty() {
+                self.start_editing_filtered_note();
+            }
+        } else if kb.toggle_search_case.matches(key.code, key.modifiers) {
+            self.search_case_sensitive = !self.search_case_sensitive;
+            self.update_search_filter();
+        } else if kb.widen_search.matches(key.code, key.modifiers) {
+            if self.search_scope.take().is_some() {
+                self.selected_note_index = 0;
+                self.update_search_filter();
+            }
         } else {
             match key.code {
                 KeyCode::Backspace => {
@@ -351,8 +1472,10 @@ impl App {
                         self.move_selection_down_filtered();
                     }
                 }
-                KeyCode::PageUp => self.page_up(),
-                KeyCode::PageDown => self.page_down(),
+                KeyCode::PageUp => self.page_selection(false, self.search_results.len()),
+                KeyCode::PageDown => self.page_selection(true, self.search_results.len()),
+                KeyCode::Home => self.selected_note_index = 0,
+                KeyCode::End => self.selected_note_index = self.search_results.len().saturating_sub(1),
                 KeyCode::Char(c) => {
                     self.search_query.insert(self.search_cursor_position, c);
                     self.search_cursor_position += 1;
@@ -366,13 +1489,81 @@ impl App {
 
     fn handle_viewing_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
         let kb = &config.keybindings;
+
+        if self.viewer_search.as_ref().is_some_and(|search| search.typing) {
+            return self.handle_viewer_search_input(key, config);
+        }
         
-        if kb.return_to_list.matches(key.code, key.modifiers) {
+        if kb.quick_switch.matches(key.code, key.modifiers) {
+            self.start_quick_switch();
+        } else if kb.search_in_note.matches(key.code, key.modifiers) {
+            self.viewer_search = Some(ViewerSearch { typing: true, ..ViewerSearch::default() });
+        } else if self.viewer_search.is_some() && key.code == KeyCode::Esc {
+            // first Esc only drops the search
+            self.viewer_search = None;
+        } else if self.viewer_search.is_some() && kb.next_match.matches(key.code, key.modifiers) {
+            self.jump_to_viewer_match(config, true);
+        } else if self.viewer_search.is_some() && kb.previous_match.matches(key.code, key.modifiers) {
+            self.jump_to_viewer_match(config, false);
+        } else if kb.return_to_list.matches(key.code, key.modifiers) {
             self.return_to_list();
         } else if kb.edit_from_view.matches(key.code, key.modifiers) {
             self.start_editing_from_viewing();
+        } else if kb.show_history.matches(key.code, key.modifiers) {
+            if self.viewing_note.as_ref().is_some_and(|note| !note.revisions.is_empty()) {
+                self.history_index = 0;
+                self.scroll_offset = 0;
+                self.mode = AppMode::ViewingHistory;
+            } else {
+                self.status_message = Some("No earlier versions of this note".to_string());
+            }
+        } else if kb.show_journal.matches(key.code, key.modifiers) {
+            self.show_journal(config);
+        } else if kb.toggle_lock.matches(key.code, key.modifiers) {
+            if let Some(id) = self.current_note_id.clone() {
+                self.toggle_lock(&id)?;
+                self.viewing_note = self.note_manager.get_note(&id).cloned();
+            }
+        } else if kb.encrypt_note.matches(key.code, key.modifiers) {
+            if let Some(id) = self.current_note_id.clone() {
+                self.toggle_note_encryption(&id)?;
+            }
+        } else if self.viewing_note.as_ref().is_some_and(|note| note.locked)
+            && [&kb.toggle_todo, &kb.add_attachment, &kb.remove_attachment].iter().any(|kb| kb.matches(key.code, key.modifiers))
+        {
+            self.status_message = Some(LOCKED_MESSAGE.to_string());
+        } else if kb.toggle_todo.matches(key.code, key.modifiers) {
+            self.toggle_todo_at_cursor()?;
+        } else if kb.add_attachment.matches(key.code, key.modifiers) {
+            self.start_path_prompt(PathPrompt::AttachFile, String::new());
+        } else if kb.copy_to_clipboard.matches(key.code, key.modifiers) {
+            if let Some(content) = self.viewing_note.as_ref().map(|note| note.content.clone()) {
+                self.copy_to_clipboard(&content);
+            }
+        } else if kb.external_editor.matches(key.code, key.modifiers) {
+            if self.viewing_note.as_ref().is_some_and(|note| note.locked) {
+                self.status_message = Some(LOCKED_MESSAGE.to_string());
+            } else {
+                self.external_edit_requested = true;
+            }
+        } else if kb.export_note.matches(key.code, key.modifiers) {
+            self.export_note_id = self.current_note_id.clone();
+            self.mode = AppMode::ConfirmingExport;
+        } else if kb.switch_field.matches(key.code, key.modifiers) {
+            let count = self.viewing_note.as_ref().map_or(0, |note| note.attachments.len());
+            if count > 0 {
+                self.attachment_index = (self.attachment_index + 1) % count;
+            }
+        } else if kb.open_attachment.matches(key.code, key.modifiers) {
+            self.open_attachment();
+        } else if kb.remove_attachment.matches(key.code, key.modifiers) {
+            self.remove_attachment()?;
+        } else if kb.toggle_wrap.matches(key.code, key.modifiers) {
+            self.wrap_enabled = !self.wrap_enabled;
+        } else if kb.toggle_line_numbers.matches(key.code, key.modifiers) {
+            self.line_numbers_enabled = !self.line_numbers_enabled;
         } else if kb.quit.matches(key.code, key.modifiers) {
-            self.should_quit = true;
+            self.request_quit();
         } else if kb.move_up.matches(key.code, key.modifiers) {
             self.scroll_up();
         } else if kb.move_down.matches(key.code, key.modifiers) {
@@ -385,56 +1576,1379 @@ impl App {
         Ok(())
     }
 
-    fn handle_delete_confirmation_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
-        let kb = &config.keybindings;
-        
-        if key_matches_any(&kb.confirm_delete, key.code, key.modifiers) {
-            self.confirm_and_delete_note()?;
+    fn handle_viewer_search_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let Some(search) = self.viewer_search.as_mut() else { return Ok(()) };
+        match key.code {
+            KeyCode::Esc => self.viewer_search = None,
+            KeyCode::Enter => {
+                search.typing = false;
+                if search.query.is_empty() {
+                    self.viewer_search = None;
+                } else if self.viewer_matches(config).is_empty() {
+                    self.status_message = Some("No matches".to_string());
+                }
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                search.current = 0;
+                self.scroll_to_viewer_match(config);
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                search.query.push(c);
+                search.current = 0;
+                self.scroll_to_viewer_match(config);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // line and byte range of every match of the viewer search in the viewed note
+    pub fn viewer_matches(&self, config: &Config) -> Vec<(usize, usize, usize)> {
+        let (Some(search), Some(note)) = (&self.viewer_search, &self.viewing_note) else { return Vec::new() };
+        note.content
+            .lines()
+            .enumerate()
+            .flat_map(|(line, text)| {
+                find_matches(text, &search.query, config.behavior.search_case_sensitive)
+                    .into_iter()
+                    .map(move |(start, end)| (line, start, end))
+            })
+            .collect()
+    }
+
+    fn jump_to_viewer_match(&mut self, config: &Config, forward: bool) {
+        let count = self.viewer_matches(config).len();
+        let Some(search) = self.viewer_search.as_mut() else { return };
+        if count == 0 {
+            self.status_message = Some("No matches".to_string());
+            return;
+        }
+        search.current = if forward {
+            (search.current + 1) % count
+        } else {
+            (search.current + count - 1) % count
+        };
+        self.scroll_to_viewer_match(config);
+    }
+
+    // bring the current match to the top of the viewer
+    fn scroll_to_viewer_match(&mut self, config: &Config) {
+        let matches = self.viewer_matches(config);
+        let current = self.viewer_search.as_ref().map_or(0, |search| search.current);
+        if let Some((line, _, _)) = matches.get(current) {
+            self.scroll_offset = *line;
+            self.clamp_viewer_scroll();
+        }
+    }
+
+    // the viewer's cursor is the top visible line
+    pub fn view_cursor_line(&self) -> usize {
+        let line_count = self.viewing_note.as_ref().map_or(0, |note| note.content.lines().count());
+        self.scroll_offset.min(line_count.saturating_sub(1))
+    }
+
+    fn toggle_lock(&mut self, id: &str) -> io::Result<()> {
+        self.record_undo("lock", &[id.to_string()]);
+        let mut locked = false;
+        if let Some(note) = self.note_manager.get_note_mut(id) {
+            note.toggle_locked();
+            locked = note.locked;
+        }
+        self.note_manager.save_notes()?;
+        self.status_message = Some(if locked { "Note locked" } else { "Note unlocked" }.to_string());
+        Ok(())
+    }
+
+    fn attach_file(&mut self, path: &str) -> io::Result<()> {
+        let Some(id) = self.current_note_id.clone() else { return Ok(()) };
+        match self.note_manager.attach_file(&id, &expand_home(path)) {
+            Ok(attachment) => {
+                self.note_manager.save_notes()?;
+                self.viewing_note = self.note_manager.get_note(&id).cloned();
+                self.attachment_index = self.viewing_note.as_ref().map_or(0, |note| note.attachments.len().saturating_sub(1));
+                self.status_message = Some(format!("Attached {}", attachment.name));
+            }
+            Err(e) => self.show_error(format!("Couldn't attach file: {}", e)),
+        }
+        Ok(())
+    }
+
+    fn open_attachment(&mut self) {
+        let Some(id) = self.current_note_id.clone() else { return };
+        if self.viewing_note.as_ref().is_none_or(|note| note.attachments.is_empty()) {
+            self.status_message = Some("This note has no attachments".to_string());
+            return;
+        }
+        self.status_message = Some(match self.note_manager.open_attachment(&id, self.attachment_index) {
+            Ok(path) => format!("Opened {}", path.display()),
+            Err(e) => format!("Couldn't open attachment: {}", e),
+        });
+    }
+
+    fn remove_attachment(&mut self) -> io::Result<()> {
+        let Some(id) = self.current_note_id.clone() else { return Ok(()) };
+        match self.note_manager.remove_attachment(&id, self.attachment_index) {
+            Ok(Some(removed)) => {
+                self.note_manager.save_notes()?;
+                self.status_message = Some(format!("Removed {}", removed.name));
+            }
+            Ok(None) => self.status_message = Some("This note has no attachments".to_string()),
+            Err(e) => self.show_error(format!("Attachment removed, but its file couldn't be deleted: {}", e)),
+        }
+        self.viewing_note = self.note_manager.get_note(&id).cloned();
+        let count = self.viewing_note.as_ref().map_or(0, |note| note.attachments.len());
+        self.attachment_index = self.attachment_index.min(count.saturating_sub(1));
+        Ok(())
+    }
+
+    fn toggle_todo_at_cursor(&mut self) -> io::Result<()> {
+        let Some(id) = self.current_note_id.clone() else { return Ok(()) };
+        let line = self.view_cursor_line();
+
+        let toggled = self.note_manager.get_note_mut(&id).is_some_and(|note| note.toggle_todo(line));
+        if !toggled {
+            self.status_message = Some("No todo item on this line".to_string());
+            return Ok(());
+        }
+
+        self.note_manager.save_notes()?;
+        self.viewing_note = self.note_manager.get_all_notes().into_iter().find(|n| n.id == id).cloned();
+        Ok(())
+    }
+
+    fn handle_history_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+        let revision_count = self.viewing_note.as_ref().map_or(0, |note| note.revisions.len());
+
+        if kb.return_to_list.matches(key.code, key.modifiers) {
+            self.scroll_offset = 0;
+            self.mode = AppMode::ViewingNote;
+        } else if kb.quit.matches(key.code, key.modifiers) {
+            self.should_quit = true;
+        } else if kb.move_up.matches(key.code, key.modifiers) {
+            self.history_index = self.history_index.saturating_sub(1);
+            self.scroll_offset = 0;
+        } else if kb.move_down.matches(key.code, key.modifiers) {
+            if self.history_index + 1 < revision_count {
+                self.history_index += 1;
+                self.scroll_offset = 0;
+            }
+        } else if kb.page_up.matches(key.code, key.modifiers) {
+            self.page_up();
+        } else if kb.page_down.matches(key.code, key.modifiers) {
+            self.page_down();
+        } else if kb.restore_note.matches(key.code, key.modifiers) && self.viewing_note.as_ref().is_some_and(|note| note.locked) {
+            self.status_message = Some(LOCKED_MESSAGE.to_string());
+        } else if kb.restore_note.matches(key.code, key.modifiers) && revision_count > 0 {
+            let Some(id) = self.current_note_id.clone() else { return Ok(()) };
+            // revisions are stored oldest first but listed newest first
+            let index = revision_count - 1 - self.history_index.min(revision_count - 1);
+
+            self.record_undo("restore version", std::slice::from_ref(&id));
+            if self.note_manager.restore_revision(&id, index) {
+                self.note_manager.save_notes()?;
+                self.viewing_note = self.note_manager.get_all_notes().into_iter().find(|n| n.id == id).cloned();
+                self.status_message = Some("Restored an earlier version".to_string());
+            }
+            self.scroll_offset = 0;
+            self.mode = AppMode::ViewingNote;
+        }
+        Ok(())
+    }
+
+    // every saved change to the viewed note, from the change journal
+    fn show_journal(&mut self, config: &Config) {
+        let Some(id) = self.current_note_id.clone() else { return };
+        if !config.behavior.change_journal {
+            self.status_message = Some("The change journal is off, set change_journal = true under [behavior]".to_string());
+            return;
+        }
+        match self.note_manager.journal_entries(&id) {
+            Ok((entries, _)) if entries.is_empty() => {
+                self.status_message = Some("No changes to this note in the journal yet".to_string());
+            }
+            Ok((entries, unreadable)) => {
+                if unreadable > 0 {
+                    self.status_message = Some(format!("{} line(s) of the change journal couldn't be read", unreadable));
+                }
+                self.journal_entries = entries;
+                self.journal_index = 0;
+                self.scroll_offset = 0;
+                self.mode = AppMode::ViewingJournal;
+            }
+            Err(e) => self.show_error(format!("Couldn't read the change journal: {}", e)),
+        }
+    }
+
+    fn handle_journal_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+
+        if kb.return_to_list.matches(key.code, key.modifiers) {
+            self.journal_entries.clear();
+            self.scroll_offset = 0;
+            self.mode = AppMode::ViewingNote;
+        } else if kb.quit.matches(key.code, key.modifiers) {
+            self.should_quit = true;
+        } else if kb.move_up.matches(key.code, key.modifiers) {
+            self.journal_index = self.journal_index.saturating_sub(1);
+            self.scroll_offset = 0;
+        } else if kb.move_down.matches(key.code, key.modifiers) {
+            if self.journal_index + 1 < self.journal_entries.len() {
+                self.journal_index += 1;
+                self.scroll_offset = 0;
+            }
+        } else if kb.page_up.matches(key.code, key.modifiers) {
+            self.page_up();
+        } else if kb.page_down.matches(key.code, key.modifiers) {
+            self.page_down();
+        }
+        Ok(())
+    }
+
+    fn handle_delete_confirmation_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+        
+        if key_matches_any(&kb.confirm_delete, key.code, key.modifiers) {
+            self.confirm_and_delete_note()?;
         } else if key_matches_any(&kb.cancel_delete, key.code, key.modifiers) {
             self.cancel_delete_confirmation();
         }
-        Ok(())
+        Ok(())
+    }
+
+    fn handle_empty_trash_confirmation_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+
+        if key_matches_any(&kb.confirm_delete, key.code, key.modifiers) {
+            let trashed: Vec<String> = self.note_manager.trashed_notes().iter().map(|note| note.id.clone()).collect();
+            self.record_undo("empty trash", &trashed);
+            let purged = self.note_manager.empty_trash();
+            self.note_manager.save_notes()?;
+            self.status_message = Some(format!("Permanently deleted {} note(s) from the trash", purged));
+            self.mode = AppMode::NoteList;
+        } else if key_matches_any(&kb.cancel_delete, key.code, key.modifiers) {
+            self.mode = AppMode::NoteList;
+        }
+        Ok(())
+    }
+
+    fn handle_unsaved_exit_confirmation_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+        
+        if key_matches_any(&kb.save_and_exit_unsaved, key.code, key.modifiers) {
+            self.save_current_note()?;
+            self.return_to_list();
+        } else if key_matches_any(&kb.discard_and_exit, key.code, key.modifiers) {
+            self.return_to_list();
+        } else if key_matches_any(&kb.cancel_exit, key.code, key.modifiers) {
+            self.mode = AppMode::EditingNote;
+        }
+        Ok(())
+    }
+
+    fn handle_startup_error_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+        // only allow quitting from this screen
+        if key.code == KeyCode::Esc || key.code == KeyCode::Char('q') {
+            self.should_quit = true;
+        }
+        Ok(())
+    }
+
+    fn handle_recovery_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('r') => self.restore_recovery(),
+            KeyCode::Char('d') => {
+                self.recovered = None;
+                self.mode = AppMode::NoteList;
+                if let Err(e) = self.note_manager.clear_recovery() {
+                    self.show_error(format!("Couldn't remove the recovery journal: {}", e));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_about_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        if config.keybindings.return_to_list.matches(key.code, key.modifiers) {
+            self.mode = AppMode::NoteList;
+        } else if config.keybindings.quit.matches(key.code, key.modifiers) {
+            self.should_quit = true;
+        }
+        Ok(())
+    }
+
+    // called every loop iteration, copies the notes file into the backups folder every so often
+    pub fn run_scheduled_backup(&mut self, config: &Config) {
+        let interval = config.behavior.backup_interval_minutes;
+        if interval == 0 || !self.note_manager.is_ready() || self.note_manager.is_saving() {
+            return;
+        }
+        if self.last_backup_at.is_some_and(|at| at.elapsed() < Duration::from_secs(interval * 60)) {
+            return;
+        }
+        self.last_backup_at = Some(Instant::now());
+
+        // nothing new to back up
+        let notes_file = self.note_manager.notes_file().to_path_buf();
+        let mtime = std::fs::metadata(&notes_file).and_then(|m| m.modified()).ok();
+        if mtime.is_none() || mtime == self.last_backup_mtime {
+            return;
+        }
+
+        let dir = backup::backup_dir(&notes_file, &config.behavior.backup_dir);
+        match backup::create_backup(&notes_file, &dir).and_then(|_| backup::rotate(&notes_file, &dir, config.behavior.backup_keep.max(1))) {
+            Ok(_) => self.last_backup_mtime = mtime,
+            Err(e) => self.show_error(format!("Backup failed: {}", e)),
+        }
+    }
+
+    fn open_backup_list(&mut self, config: &Config) {
+        let notes_file = self.note_manager.notes_file().to_path_buf();
+        let dir = backup::backup_dir(&notes_file, &config.behavior.backup_dir);
+        match backup::list_backups(&notes_file, &dir) {
+            Ok(backups) => {
+                self.backups = backups;
+                self.backup_index = 0;
+                self.mode = AppMode::BackupList;
+            }
+            Err(e) => self.show_error(format!("Could not read backups: {}", e)),
+        }
+    }
+
+    fn handle_backup_list_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+
+        if kb.return_to_list.matches(key.code, key.modifiers) {
+            self.backups.clear();
+            self.mode = AppMode::NoteList;
+        } else if kb.quit.matches(key.code, key.modifiers) {
+            self.should_quit = true;
+        } else if kb.move_up.matches(key.code, key.modifiers) {
+            self.backup_index = self.backup_index.saturating_sub(1);
+        } else if kb.move_down.matches(key.code, key.modifiers) {
+            if self.backup_index + 1 < self.backups.len() {
+                self.backup_index += 1;
+            }
+        } else if kb.restore_note.matches(key.code, key.modifiers) && !self.backups.is_empty() {
+            self.mode = AppMode::ConfirmingRestoreBackup;
+        }
+        Ok(())
+    }
+
+    fn handle_restore_backup_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+
+        if key_matches_any(&kb.confirm_delete, key.code, key.modifiers) {
+            if let Some(selected) = self.backups.get(self.backup_index).cloned() {
+                self.status_message = Some(match self.restore_backup(&selected, config) {
+                    Ok(()) => format!("Restored backup {}", selected.file_name()),
+                    Err(e) => format!("Could not restore backup: {}", e),
+                });
+            }
+            self.backups.clear();
+            self.mode = AppMode::NoteList;
+        } else if key_matches_any(&kb.cancel_delete, key.code, key.modifiers) {
+            self.mode = AppMode::BackupList;
+        }
+        Ok(())
+    }
+
+    fn restore_backup(&mut self, selected: &BackupInfo, config: &Config) -> io::Result<()> {
+        let notes_file = self.note_manager.notes_file().to_path_buf();
+        let dir = backup::backup_dir(&notes_file, &config.behavior.backup_dir);
+
+        // whatever is current becomes a backup too, in case this was the wrong one
+        self.note_manager.flush_saves()?;
+        let safety = if notes_file.exists() { Some(backup::create_backup(&notes_file, &dir)?) } else { None };
+
+        backup::restore_backup(&selected.path, &notes_file)?;
+        if let Err(e) = self.note_manager.reload_from_disk() {
+            // e.g. encrypted with another password, put the old file back
+            if let Some(safety) = safety {
+                backup::restore_backup(&safety, &notes_file)?;
+                let _ = self.note_manager.reload_from_disk();
+            }
+            return Err(e);
+        }
+
+        // undo entries point at notes that may not exist anymore
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.refresh_after_reload();
+        self.selected_note_index = 0;
+        Ok(())
+    }
+
+    fn handle_trash_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+        let selected = self.note_manager
+            .trashed_notes()
+            .get(self.trash_index)
+            .map(|note| (note.id.clone(), note.title.clone()));
+
+        if kb.return_to_list.matches(key.code, key.modifiers) {
+            self.mode = AppMode::NoteList;
+        } else if kb.quit.matches(key.code, key.modifiers) {
+            self.should_quit = true;
+        } else if kb.move_up.matches(key.code, key.modifiers) {
+            self.trash_index = self.trash_index.saturating_sub(1);
+        } else if kb.move_down.matches(key.code, key.modifiers) {
+            if self.trash_index + 1 < self.note_manager.trash_count() {
+                self.trash_index += 1;
+            }
+        } else if kb.restore_note.matches(key.code, key.modifiers) {
+            if let Some((id, title)) = selected {
+                self.record_undo("restore", std::slice::from_ref(&id));
+                self.note_manager.restore_note(&id);
+                self.note_manager.save_notes()?;
+                self.status_message = Some(format!("Restored '{}'", title));
+                self.clamp_trash_selection();
+            }
+        } else if kb.delete_note.matches(key.code, key.modifiers)
+            && let Some((_, title)) = selected
+        {
+            self.delete_note_title = title;
+            self.mode = AppMode::ConfirmingPurge;
+        }
+        Ok(())
+    }
+
+    fn handle_purge_confirmation_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+
+        if key_matches_any(&kb.confirm_delete, key.code, key.modifiers) {
+            let selected = self.note_manager.trashed_notes().get(self.trash_index).map(|note| note.id.clone());
+            if let Some(id) = selected {
+                self.record_undo("permanent delete", std::slice::from_ref(&id));
+                self.note_manager.purge_note(&id);
+                self.note_manager.save_notes()?;
+                self.status_message = Some("Note permanently deleted".to_string());
+                self.clamp_trash_selection();
+            }
+            self.delete_note_title.clear();
+            self.mode = AppMode::TrashView;
+        } else if key_matches_any(&kb.cancel_delete, key.code, key.modifiers) {
+            self.delete_note_title.clear();
+            self.mode = AppMode::TrashView;
+        }
+        Ok(())
+    }
+
+    fn clamp_trash_selection(&mut self) {
+        let count = self.note_manager.trash_count();
+        if self.trash_index >= count {
+            self.trash_index = count.saturating_sub(1);
+        }
+    }
+
+    fn start_vault_picker(&mut self, config: &Config) {
+        if config.session.notes_file.is_some() {
+            self.status_message = Some("Opened with --notes-file, there are no other vaults to switch to".to_string());
+            return;
+        }
+        if config.vaults.is_empty() {
+            self.status_message = Some("No vaults set up, add [vaults.<name>] sections to the config".to_string());
+            return;
+        }
+        self.vault_choices = config.vaults.keys().cloned().collect();
+        // start on the open vault, or the default one
+        let current = self.vault_name.as_deref().unwrap_or(&config.behavior.default_vault);
+        self.vault_index = self.vault_choices.iter().position(|name| name == current).unwrap_or(0);
+        self.vault_picker_return = (self.mode != AppMode::PickingVault).then_some(self.mode);
+        self.mode = AppMode::PickingVault;
+    }
+
+    fn handle_vault_picker_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+        let count = self.vault_choices.len();
+
+        if kb.move_up.matches(key.code, key.modifiers) && count > 0 {
+            self.vault_index = (self.vault_index + count - 1) % count;
+        } else if kb.move_down.matches(key.code, key.modifiers) && count > 0 {
+            self.vault_index = (self.vault_index + 1) % count;
+        } else if key.code == KeyCode::Enter {
+            let Some(name) = self.vault_choices.get(self.vault_index).cloned() else { return Ok(()) };
+            match self.vault_picker_return {
+                Some(previous) if self.vault_name.as_deref() == Some(name.as_str()) => self.mode = previous,
+                _ => self.switch_vault(&name, config),
+            }
+        } else if key.code == KeyCode::Esc {
+            match self.vault_picker_return {
+                Some(previous) => self.mode = previous,
+                None => self.should_quit = true,
+            }
+        } else if kb.quit.matches(key.code, key.modifiers) {
+            self.should_quit = true;
+        }
+        Ok(())
+    }
+
+    // merge with the vault's sync_remote, the ui waits until it's done
+    fn sync_vault(&mut self, config: &Config) {
+        let remote = match config.vault(self.vault_name.as_deref()) {
+            Ok(vault) => vault.sync_remote,
+            Err(e) => {
+                self.show_error(format!("Not syncing: {}", e));
+                return;
+            }
+        };
+        if remote.trim().is_empty() {
+            self.status_message = Some("Nothing to sync with, set sync_remote for this vault in the config".to_string());
+            return;
+        }
+        if self.note_manager.has_blocked_save() {
+            self.status_message = Some("Not syncing, the notes file changed on disk and has to be dealt with first".to_string());
+            return;
+        }
+        match self.note_manager.sync(&remote) {
+            Ok(summary) => {
+                log::info!("synced with {}: {}", remote, summary);
+                self.refresh_after_reload();
+                self.status_message = Some(format!("Synced: {}", summary));
+            }
+            Err(e) => self.show_error(format!("Sync failed: {}", e)),
+        }
+    }
+
+    // the sync_remote of the open vault if its notes file is on a server, that one is synced
+    // without being asked to
+    fn remote_of_open_vault(&self, config: &Config) -> Option<String> {
+        let vault = config.vault(self.vault_name.as_deref()).ok()?;
+        let open = vault.remote
+            && self.note_manager.is_ready()
+            && !self.note_manager.is_read_only()
+            && self.note_manager.notes_file() == Path::new(&vault.notes_file);
+        open.then_some(vault.sync_remote)
+    }
+
+    // what changed in a vault on a server goes back to it before the vault is closed, once
+    // everything is saved
+    pub fn sync_before_exit(&mut self, config: &Config) -> io::Result<()> {
+        let Some(remote) = self.remote_of_open_vault(config) else { return Ok(()) };
+        if self.note_manager.has_blocked_save() {
+            return Err(io::Error::other("the notes file changed on disk, the local copy wasn't synced"));
+        }
+        let summary = self.note_manager.sync(&remote)?;
+        log::info!("synced with {}: {}", remote, summary);
+        Ok(())
+    }
+
+    // the open vault is saved and locked before the other one shows up, it asks for its own
+    // password if it's encrypted
+    fn switch_vault(&mut self, name: &str, config: &Config) {
+        let back = self.vault_picker_return.unwrap_or(AppMode::PickingVault);
+        if let Err(e) = self.note_manager.flush_saves() {
+            self.show_error(format!("Not switching, saving this vault failed: {}", e));
+            self.mode = back;
+            return;
+        }
+        if self.note_manager.has_blocked_save() {
+            self.status_message = Some("Not switching, the notes file changed on disk and has to be dealt with first".to_string());
+            self.mode = back;
+            return;
+        }
+        // the changes stay in the local copy and go to the server the next time it's opened
+        if let Err(e) = self.sync_before_exit(config) {
+            log::warn!("syncing before switching vaults failed: {}", e);
+        }
+        let mut app = match App::open_vault(config, Some(name)) {
+            Ok(app) => app,
+            Err(e) => {
+                self.show_error(format!("Couldn't open the {} vault: {}", name, e));
+                self.mode = back;
+                return;
+            }
+        };
+        self.sync_recovery();
+        self.wipe();
+
+        // view settings changed while running carry over
+        app.help_visible = self.help_visible;
+        app.split_view = self.split_view;
+        app.split_ratio = self.split_ratio;
+        app.relative_timestamps = self.relative_timestamps;
+        app.highlighting_enabled = self.highlighting_enabled;
+        app.line_numbers_enabled = self.line_numbers_enabled;
+        app.wrap_enabled = self.wrap_enabled;
+        app.status_message.get_or_insert_with(|| format!("Vault: {}", name));
+        *self = app;
+    }
+
+    fn start_choosing_theme(&mut self, config: &Config) {
+        self.theme_choices = theme::available();
+        let current = if config.colors.theme.is_empty() { "dark" } else { config.colors.theme.as_str() };
+        self.theme_index = self.theme_choices.iter().position(|name| name == current).unwrap_or(0);
+        self.theme_before = Some(config.colors.clone());
+        self.mode = AppMode::ChoosingTheme;
+    }
+
+    // colors follow the highlighted theme right away, Enter keeps them and Esc puts the old ones back
+    fn handle_theme_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+        let count = self.theme_choices.len();
+
+        if kb.move_up.matches(key.code, key.modifiers) && count > 0 {
+            self.theme_index = (self.theme_index + count - 1) % count;
+            self.preview_theme(config);
+        } else if kb.move_down.matches(key.code, key.modifiers) && count > 0 {
+            self.theme_index = (self.theme_index + 1) % count;
+            self.preview_theme(config);
+        } else if key.code == KeyCode::Enter {
+            let Some(name) = self.theme_choices.get(self.theme_index).cloned() else { return Ok(()) };
+            match theme::load(&name) {
+                Ok(colors) => {
+                    let mut updated = config.clone();
+                    // picking a theme here starts over from it, without the single colors set before
+                    updated.colors = ColorTheme { theme: name.clone(), ..colors };
+                    self.status_message = Some(match updated.save() {
+                        Ok(()) => format!("Theme: {}", name),
+                        Err(e) => format!("Theme: {} (couldn't save config: {})", name, e),
+                    });
+                    self.config_update = Some(updated);
+                    self.theme_before = None;
+                    self.mode = AppMode::NoteList;
+                }
+                Err(e) => self.status_message = Some(e.to_string()),
+            }
+        } else if key.code == KeyCode::Esc {
+            if let Some(colors) = self.theme_before.take() {
+                let mut updated = config.clone();
+                updated.colors = colors;
+                self.config_update = Some(updated);
+            }
+            self.mode = AppMode::NoteList;
+        }
+        Ok(())
+    }
+
+    fn preview_theme(&mut self, config: &Config) {
+        let Some(name) = self.theme_choices.get(self.theme_index) else { return };
+        match theme::load(name) {
+            Ok(colors) => {
+                let mut updated = config.clone();
+                updated.colors = ColorTheme { theme: name.clone(), ..colors };
+                self.config_update = Some(updated);
+            }
+            Err(e) => self.status_message = Some(e.to_string()),
+        }
+    }
+
+    // the settings come first, then one row per key binding
+    fn handle_settings_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        if let Some(name) = self.rebinding.take() {
+            if key.code == KeyCode::Esc {
+                self.status_message = Some(format!("{} left as it was", name));
+            } else {
+                self.rebind(name, key, config);
+            }
+            return Ok(());
+        }
+
+        let kb = &config.keybindings;
+        let names = kb.names();
+        let count = Setting::ALL.len() + names.len();
+
+        if kb.move_up.matches(key.code, key.modifiers) {
+            self.settings_index = (self.settings_index + count - 1) % count;
+        } else if kb.move_down.matches(key.code, key.modifiers) {
+            self.settings_index = (self.settings_index + 1) % count;
+        } else if key.code == KeyCode::Enter || key.code == KeyCode::Char(' ') {
+            match Setting::ALL.get(self.settings_index) {
+                Some(setting) => self.change_setting(*setting, config),
+                None => self.rebinding = names.get(self.settings_index - Setting::ALL.len()).copied(),
+            }
+        } else if kb.return_to_list.matches(key.code, key.modifiers) || key.code == KeyCode::Esc {
+            self.mode = AppMode::NoteList;
+        } else if kb.quit.matches(key.code, key.modifiers) {
+            self.should_quit = true;
+        }
+        Ok(())
+    }
+
+    // flip or step the setting and save the config right away
+    fn change_setting(&mut self, setting: Setting, config: &Config) {
+        let mut updated = config.clone();
+        match setting {
+            Setting::AutoSave => updated.behavior.auto_save = !updated.behavior.auto_save,
+            Setting::ConfirmDelete => updated.behavior.confirm_delete = !updated.behavior.confirm_delete,
+            // the vault is rewritten for this, it goes through the password dialog like K does
+            Setting::Encryption => {
+                if self.note_manager.is_read_only() {
+                    self.status_message = Some(READ_ONLY_MESSAGE.to_string());
+                } else {
+                    self.start_toggling_vault_encryption();
+                }
+                return;
+            }
+            Setting::Theme => {
+                let themes = theme::available();
+                let current = if config.colors.theme.is_empty() { "dark" } else { config.colors.theme.as_str() };
+                let next = themes.iter().position(|name| name == current).map_or(0, |i| (i + 1) % themes.len());
+                let Some(name) = themes.get(next).cloned() else { return };
+                match theme::load(&name) {
+                    Ok(colors) => updated.colors = ColorTheme { theme: name, ..colors },
+                    Err(e) => {
+                        self.show_error(e.to_string());
+                        return;
+                    }
+                }
+            }
+            Setting::SortOrder => {
+                let mode = self.note_manager.sort_mode().next();
+                self.note_manager.set_sort_mode(mode);
+                self.selected_note_index = 0;
+                updated.behavior.sort_mode = mode;
+            }
+        }
+        let value = self.setting_value(setting, &updated);
+        self.status_message = Some(match updated.save() {
+            Ok(()) => format!("{}: {}", setting.label(), value),
+            Err(e) => format!("{}: {} (couldn't save config: {})", setting.label(), value, e),
+        });
+        self.config_update = Some(updated);
+    }
+
+    // the key just pressed replaces the binding, written to the config the way it would be typed
+    fn rebind(&mut self, name: &'static str, key: KeyEvent, config: &Config) {
+        let Some(binding) = KeyBinding::from_key(&key) else {
+            self.status_message = Some("That key can't be bound, pick another one".to_string());
+            self.rebinding = Some(name);
+            return;
+        };
+        let mut updated = config.clone();
+        if let Err(e) = updated.keybindings.rebind(name, &binding.key) {
+            self.show_error(e.to_string());
+            return;
+        }
+        // bindings for other screens may share a key, it's only pointed out
+        let mut shared: Vec<&str> = config.keybindings
+            .entries()
+            .into_iter()
+            .filter(|(other, existing)| *other != name && existing.matches(key.code, key.modifiers))
+            .map(|(other, _)| other)
+            .collect();
+        shared.dedup();
+        let mut message = format!("{}: {}", name, binding.key);
+        if !shared.is_empty() {
+            message.push_str(&format!(" (also {})", shared.join(", ")));
+        }
+        if let Err(e) = updated.save() {
+            message.push_str(&format!(" (couldn't save config: {})", e));
+        }
+        log::info!("{} rebound to {}", name, binding.key);
+        self.status_message = Some(message);
+        self.config_update = Some(updated);
+    }
+
+    // what the settings screen shows next to the setting
+    pub fn setting_value(&self, setting: Setting, config: &Config) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+        match setting {
+            Setting::AutoSave if config.behavior.auto_save && config.behavior.auto_save_interval_secs > 0 => {
+                format!("every {}s", config.behavior.auto_save_interval_secs)
+            }
+            Setting::AutoSave => on_off(config.behavior.auto_save),
+            Setting::ConfirmDelete => on_off(config.behavior.confirm_delete),
+            // the open vault as it is, not what the config says for the next start
+            Setting::Encryption => on_off(self.note_manager.is_encrypted()),
+            Setting::Theme if config.colors.theme.is_empty() => "dark".to_string(),
+            Setting::Theme => config.colors.theme.clone(),
+            Setting::SortOrder => self.note_manager.sort_mode().label().to_string(),
+        }
+    }
+
+    // config.toml was edited while running. keybindings and colors simply come from the new config,
+    // settings the app keeps its own copy of are only taken over when they changed in the file, so
+    // toggling something in the app isn't undone by an unrelated edit
+    pub fn reload_config(&mut self, config: &Config) {
+        let mut updated = match Config::reload() {
+            Ok(updated) => updated,
+            Err(e) => {
+                self.show_error(format!("Config not reloaded: {}", e));
+                return;
+            }
+        };
+        let (old, new) = (&config.behavior, &updated.behavior);
+        if new.search_case_sensitive != old.search_case_sensitive {
+            self.search_case_sensitive = new.search_case_sensitive;
+        }
+        if new.highlighting_enabled != old.highlighting_enabled {
+            self.highlighting_enabled = new.highlighting_enabled;
+        }
+        if new.show_line_numbers != old.show_line_numbers {
+            self.line_numbers_enabled = new.show_line_numbers;
+        }
+        if new.soft_wrap != old.soft_wrap {
+            self.wrap_enabled = new.soft_wrap;
+        }
+        if new.split_view != old.split_view {
+            self.split_view = new.split_view;
+        }
+        if new.split_ratio != old.split_ratio {
+            self.split_ratio = new.split_ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO);
+        }
+        if new.relative_timestamps != old.relative_timestamps {
+            self.relative_timestamps = new.relative_timestamps;
+        }
+        if updated.modal_editing() != config.modal_editing() {
+            self.vim = updated.modal_editing().then(Vim::default);
+        }
+        if (&new.spellcheck, &new.spellcheck_language, &new.spellcheck_dictionaries, &new.spellcheck_dictionary)
+            != (&old.spellcheck, &old.spellcheck_language, &old.spellcheck_dictionaries, &old.spellcheck_dictionary)
+        {
+            self.spell_checker = load_spell_checker(new);
+        }
+        self.undo_depth = new.undo_depth;
+        self.editor_undo_depth = new.editor_undo_depth;
+        self.note_manager.set_history_limit(new.history_revisions);
+        self.note_manager.set_change_journal(new.change_journal);
+        if new.sort_mode != old.sort_mode {
+            self.note_manager.set_sort_mode(new.sort_mode);
+        }
+
+        // the vault that's open stays open, its settings apply the next time it's opened
+        let vault_changed = old.default_notes_file != new.default_notes_file
+            || old.encryption_enabled != new.encryption_enabled
+            || old.keyfile != new.keyfile
+            || config.vaults != updated.vaults;
+        // the command line still wins over the file
+        updated.session = config.session.clone();
+        log::info!("config reloaded");
+        self.status_message = Some(if vault_changed {
+            "Config reloaded, vault settings apply when a vault is opened next".to_string()
+        } else {
+            "Config reloaded".to_string()
+        });
+        self.config_update = Some(updated);
+    }
+
+    fn start_quick_switch(&mut self) {
+        self.switcher_return = self.mode;
+        self.switcher_query.clear();
+        self.mode = AppMode::QuickSwitch;
+        self.update_switcher();
+    }
+
+    // titles matching the typed letters in order, best matches first and recently opened notes
+    // ahead of the rest, so with nothing typed it's just the recent notes
+    fn update_switcher(&mut self) {
+        let query = self.switcher_query.to_lowercase();
+        let recent = &self.recent_notes;
+        let mut found: Vec<(i64, usize, String)> = self.note_manager
+            .get_all_notes()
+            .into_iter()
+            .filter_map(|note| {
+                let score = fuzzy_score(&note.title.to_lowercase(), &query)?;
+                let rank = recent.iter().position(|id| id == &note.id).unwrap_or(usize::MAX);
+                Some((score, rank, note.id.clone()))
+            })
+            .collect();
+        found.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        self.switcher_matches = found.into_iter().map(|(_, _, id)| id).collect();
+        self.switcher_index = 0;
+    }
+
+    fn handle_quick_switch_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let count = self.switcher_matches.len();
+        match key.code {
+            KeyCode::Esc => self.mode = self.switcher_return,
+            KeyCode::Up if count > 0 => self.switcher_index = (self.switcher_index + count - 1) % count,
+            KeyCode::Down if count > 0 => self.switcher_index = (self.switcher_index + 1) % count,
+            KeyCode::Enter => {
+                let Some(id) = self.switcher_matches.get(self.switcher_index).cloned() else { return Ok(()) };
+                // the note on screen keeps its tab, the picked one opens in its own
+                self.mode = self.switcher_return;
+                self.park_tab(config);
+                self.start_viewing_note(&id);
+            }
+            KeyCode::Backspace => {
+                self.switcher_query.pop();
+                self.update_switcher();
+            }
+            KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                self.switcher_query.push(c);
+                self.update_switcher();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_notebook_list_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+        // entry 0 is "all notebooks", the rest follow notebooks()
+        let notebooks = self.note_manager.notebooks();
+
+        if kb.return_to_list.matches(key.code, key.modifiers) {
+            self.mode = AppMode::NoteList;
+        } else if kb.quit.matches(key.code, key.modifiers) {
+            self.should_quit = true;
+        } else if kb.move_up.matches(key.code, key.modifiers) {
+            self.notebook_list_index = self.notebook_list_index.saturating_sub(1);
+        } else if kb.move_down.matches(key.code, key.modifiers) {
+            if self.notebook_list_index < notebooks.len() {
+                self.notebook_list_index += 1;
+            }
+        } else if kb.edit_note.matches(key.code, key.modifiers) {
+            self.list_filter.notebook = match self.notebook_list_index {
+                0 => None,
+                i => notebooks.get(i - 1).map(|n| n.name.clone()),
+            };
+            self.selected_note_index = 0;
+            self.mode = AppMode::NoteList;
+        }
+        Ok(())
+    }
+
+    fn handle_statistics_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+
+        if kb.return_to_list.matches(key.code, key.modifiers) {
+            self.scroll_offset = 0;
+            self.mode = AppMode::NoteList;
+        } else if kb.quit.matches(key.code, key.modifiers) {
+            self.should_quit = true;
+        } else if kb.move_up.matches(key.code, key.modifiers) {
+            self.scroll_up();
+        } else if kb.move_down.matches(key.code, key.modifiers) {
+            self.scroll_down();
+        } else if kb.page_up.matches(key.code, key.modifiers) {
+            self.page_up();
+        } else if kb.page_down.matches(key.code, key.modifiers) {
+            self.page_down();
+        }
+        Ok(())
+    }
+
+    fn handle_import_report_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+
+        if kb.return_to_list.matches(key.code, key.modifiers) || key.code == KeyCode::Enter {
+            self.scroll_offset = 0;
+            self.import_report = None;
+            self.mode = AppMode::NoteList;
+        } else if kb.quit.matches(key.code, key.modifiers) {
+            self.should_quit = true;
+        } else if kb.move_up.matches(key.code, key.modifiers) {
+            self.scroll_up();
+        } else if kb.move_down.matches(key.code, key.modifiers) {
+            self.scroll_down();
+        } else if kb.page_up.matches(key.code, key.modifiers) {
+            self.page_up();
+        } else if kb.page_down.matches(key.code, key.modifiers) {
+            self.page_down();
+        }
+        Ok(())
+    }
+
+    fn handle_import_source_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+        let prompt = match key.code {
+            KeyCode::Char('j') | KeyCode::Char('J') => PathPrompt::ImportJson,
+            KeyCode::Char('m') | KeyCode::Char('M') => PathPrompt::ImportFolder,
+            KeyCode::Char('x') | KeyCode::Char('X') => PathPrompt::ImportJoplin,
+            KeyCode::Char('s') | KeyCode::Char('S') => PathPrompt::ImportSimplenote,
+            KeyCode::Char('c') | KeyCode::Char('C') => PathPrompt::ImportCsv,
+            KeyCode::Esc => {
+                self.mode = AppMode::NoteList;
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+        self.start_path_prompt(prompt, String::new());
+        Ok(())
+    }
+
+    fn handle_csv_mapping_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let kb = &config.keybindings;
+        let Some(csv) = self.csv_import.as_mut() else {
+            self.mode = AppMode::NoteList;
+            return Ok(());
+        };
+        let field = CsvField::ALL[csv.selected];
+        let columns = csv.preview.headers.len();
+
+        if key.code == KeyCode::Esc {
+            self.csv_import = None;
+            self.mode = AppMode::NoteList;
+        } else if key.code == KeyCode::Enter {
+            if !csv.mapping.is_usable() {
+                self.status_message = Some("Map a column to the title or the content first".to_string());
+                return Ok(());
+            }
+            let Some(csv) = self.csv_import.take() else { return Ok(()) };
+            let result = self.note_manager.import_csv(&csv.path, &csv.mapping);
+            self.show_import_report(result);
+        } else if kb.move_up.matches(key.code, key.modifiers) {
+            csv.selected = csv.selected.saturating_sub(1);
+        } else if kb.move_down.matches(key.code, key.modifiers) {
+            csv.selected = (csv.selected + 1).min(CsvField::ALL.len() - 1);
+        } else if key.code == KeyCode::Left {
+            csv.mapping.cycle(field, columns, false);
+        } else if key.code == KeyCode::Right || key.code == KeyCode::Char(' ') {
+            csv.mapping.cycle(field, columns, true);
+        } else if key.code == KeyCode::Backspace || key.code == KeyCode::Delete {
+            csv.mapping.clear(field);
+        }
+        Ok(())
+    }
+
+    // save what a folder/archive import brought in, then show what happened to each file
+    fn show_import_report(&mut self, result: io::Result<ImportReport>) {
+        match result {
+            Ok(report) => {
+                if !report.imported.is_empty()
+                    && let Err(e) = self.note_manager.save_notes()
+                {
+                    self.show_error(format!("Imported notes could not be saved: {}", e));
+                }
+                self.import_report = Some(report);
+                self.scroll_offset = 0;
+                self.selected_note_index = 0;
+                self.mode = AppMode::Importing;
+            }
+            Err(e) => {
+                self.show_error(format!("Import failed: {}", e));
+                self.mode = AppMode::NoteList;
+            }
+        }
+    }
+
+    fn handle_export_confirmation_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        self.export_format = match key.code {
+            KeyCode::Char('m') | KeyCode::Char('M') if self.export_note_id.is_none() => ExportFormat::Markdown,
+            KeyCode::Char('s') | KeyCode::Char('S') if self.export_note_id.is_none() => ExportFormat::Simplenote,
+            KeyCode::Char('c') | KeyCode::Char('C') if self.export_note_id.is_none() => ExportFormat::Csv,
+            KeyCode::Char('e') | KeyCode::Char('E') if self.export_note_id.is_none() => ExportFormat::Encrypted,
+            KeyCode::Char('y') | KeyCode::Char('Y') => ExportFormat::Json,
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.leave_export();
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+
+        if self.note_manager.is_encrypted() {
+            // require re-authentication before proceeding with export
+            self.mode = AppMode::ReauthenticatingForExport;
+            self.password_input = SecretString::new("".into());
+            self.password_error = None;
+            self.password_limit_reached = false;
+        } else {
+            // nothing to re-authenticate against
+            self.after_export_auth(config);
+        }
+        Ok(())
+    }
+
+    // encrypted exports ask for their password before the location
+    fn after_export_auth(&mut self, config: &Config) {
+        if self.export_format == ExportFormat::Encrypted {
+            self.export_password = None;
+            self.export_password_first = None;
+            self.password_input = SecretString::new("".into());
+            self.password_error = None;
+            self.password_limit_reached = false;
+            self.mode = AppMode::EnteringExportPassword;
+        } else {
+            self.choose_export_location(config);
+        }
     }
 
-    fn handle_unsaved_exit_confirmation_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
-        let kb = &config.keybindings;
-        
-        if key_matches_any(&kb.save_and_exit_unsaved, key.code, key.modifiers) {
-            self.save_current_note()?;
-            self.return_to_list();
-        } else if key_matches_any(&kb.discard_and_exit, key.code, key.modifiers) {
-            self.return_to_list();
-        } else if key_matches_any(&kb.cancel_exit, key.code, key.modifiers) {
-            self.mode = AppMode::EditingNote;
+    fn handle_export_password_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        match key.code {
+            KeyCode::Enter => {
+                let entered = std::mem::replace(&mut self.password_input, SecretString::new("".into()));
+                match self.export_password_first.take() {
+                    None if entered.expose_secret().is_empty() => {
+                        // empty means the vault's own password
+                        if self.note_manager.is_encrypted() {
+                            self.password_error = None;
+                            self.choose_export_location(config);
+                        } else {
+                            self.password_error = Some("The vault isn't encrypted, the export needs a password".to_string());
+                        }
+                    }
+                    None if entered.expose_secret().len() < MIN_PASSWORD_LENGTH => {
+                        self.password_error = Some(format!("Password must be at least {} characters", MIN_PASSWORD_LENGTH));
+                    }
+                    None => {
+                        self.password_error = None;
+                        self.export_password_first = Some(entered);
+                    }
+                    Some(first) if first.expose_secret() == entered.expose_secret() => {
+                        self.password_error = None;
+                        self.export_password = Some(first);
+                        self.choose_export_location(config);
+                    }
+                    Some(_) => {
+                        self.password_error = Some("Passwords don't match, enter it again".to_string());
+                    }
+                }
+                self.password_limit_reached = false;
+            }
+            KeyCode::Esc => {
+                self.password_input = SecretString::new("".into());
+                self.password_error = None;
+                self.export_password_first = None;
+                self.leave_export();
+            }
+            KeyCode::Backspace => {
+                edit_password(&mut self.password_input, |text| {
+                    text.pop();
+                });
+                self.password_error = None;
+                self.password_limit_reached = false;
+            }
+            KeyCode::Char(c) => {
+                if self.password_input.expose_secret().len() < MAX_PASSWORD_LENGTH {
+                    edit_password(&mut self.password_input, |text| text.push(c));
+                    self.password_limit_reached = self.password_input.expose_secret().len() >= MAX_PASSWORD_LENGTH;
+                } else {
+                    self.password_limit_reached = true;
+                }
+                self.password_error = None;
+            }
+            _ => {}
         }
         Ok(())
     }
 
-    fn handle_encrypted_file_warning_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
-        // only allow quitting from this screen
-        if key.code == KeyCode::Esc || key.code == KeyCode::Char('q') {
-            self.should_quit = true;
+    fn start_changing_password(&mut self) {
+        if !self.note_manager.is_encrypted() {
+            self.status_message = Some("The vault isn't encrypted, there's no password to change".to_string());
+            return;
         }
-        Ok(())
+        self.start_vault_password(VaultPasswordAction::Change, PasswordChangeStage::Current);
+    }
+
+    // encrypting asks for a new password twice, removing the encryption for the current one
+    fn start_toggling_vault_encryption(&mut self) {
+        if !self.note_manager.is_ready() {
+            self.status_message = Some("The vault isn't open yet".to_string());
+            return;
+        }
+        if self.note_manager.is_encrypted() {
+            self.start_vault_password(VaultPasswordAction::Decrypt, PasswordChangeStage::Current);
+        } else {
+            self.start_vault_password(VaultPasswordAction::Encrypt, PasswordChangeStage::New);
+        }
+    }
+
+    fn start_vault_password(&mut self, action: VaultPasswordAction, stage: PasswordChangeStage) {
+        self.vault_password_action = action;
+        self.password_change_stage = stage;
+        self.password_change_current = None;
+        self.password_change_new = None;
+        self.password_input = SecretString::new("".into());
+        self.password_error = None;
+        self.password_limit_reached = false;
+        self.mode = AppMode::ChangingPassword;
     }
 
-    fn handle_export_confirmation_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+    // current password, then the new one twice. the vault is only rewritten after the second entry matches
+    fn handle_change_password_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
         match key.code {
-            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                // require re-authentication before proceeding with export
-                self.mode = AppMode::ReauthenticatingForExport;
+            KeyCode::Enter => {
+                let entered = std::mem::replace(&mut self.password_input, SecretString::new("".into()));
+                self.password_limit_reached = false;
+                match self.password_change_stage {
+                    // the password is checked while the encryption comes off, the dialog stays up if it's wrong
+                    PasswordChangeStage::Current if self.vault_password_action == VaultPasswordAction::Decrypt => {
+                        match self.note_manager.disable_encryption(entered.expose_secret()) {
+                            Ok(()) => {
+                                let saved = self.set_vault_encryption(false, config);
+                                // nothing left for the keyring to unlock
+                                if config.behavior.remember_password {
+                                    let _ = credentials::forget(self.note_manager.notes_file());
+                                }
+                                self.status_message = Some(format!("The vault is stored as plain text now{}", saved));
+                                self.password_error = None;
+                                self.mode = AppMode::NoteList;
+                            }
+                            Err(e) => self.password_error = Some(format!("Encryption not removed: {}", e)),
+                        }
+                    }
+                    PasswordChangeStage::Current => {
+                        if self.note_manager.verify_password(entered.expose_secret()).is_err() {
+                            self.password_error = Some("Wrong password".to_string());
+                        } else {
+                            self.password_error = None;
+                            self.password_change_current = Some(entered);
+                            self.password_change_stage = PasswordChangeStage::New;
+                        }
+                    }
+                    PasswordChangeStage::New => {
+                        if entered.expose_secret().len() < MIN_PASSWORD_LENGTH {
+                            self.password_error = Some(format!("Password must be at least {} characters", MIN_PASSWORD_LENGTH));
+                        } else {
+                            self.password_error = None;
+                            self.password_change_new = Some(entered);
+                            self.password_change_stage = PasswordChangeStage::Confirm;
+                        }
+                    }
+                    PasswordChangeStage::Confirm => {
+                        let matches = self.password_change_new.as_ref().is_some_and(|new| new.expose_secret() == entered.expose_secret());
+                        if !matches {
+                            // start the new password over, a typo could be in either entry
+                            self.password_error = Some("Passwords don't match, enter the new one again".to_string());
+                            self.password_change_new = None;
+                            self.password_change_stage = PasswordChangeStage::New;
+                            return Ok(());
+                        }
+                        self.password_change_new = None;
+                        if self.vault_password_action == VaultPasswordAction::Encrypt {
+                            match self.note_manager.enable_encryption(entered.expose_secret()) {
+                                Ok(()) => {
+                                    let saved = self.set_vault_encryption(true, config);
+                                    self.status_message = Some(format!("The vault is encrypted now{}", saved));
+                                    self.remember_password(&entered, config);
+                                }
+                                Err(e) => self.show_error(format!("Vault not encrypted: {}", e)),
+                            }
+                            self.password_error = None;
+                            self.mode = AppMode::NoteList;
+                            return Ok(());
+                        }
+                        let current = self.password_change_current.take().unwrap_or_else(|| SecretString::new("".into()));
+                        match self.note_manager.change_password(current.expose_secret(), entered.expose_secret()) {
+                            Ok(()) => {
+                                self.status_message = Some("Password changed, the vault is encrypted with the new one".to_string());
+                                self.remember_password(&entered, config);
+                            }
+                            Err(e) => self.show_error(format!("Password not changed: {}", e)),
+                        }
+                        self.password_error = None;
+                        self.mode = AppMode::NoteList;
+                    }
+                }
+            }
+            KeyCode::Esc => {
                 self.password_input = SecretString::new("".into());
                 self.password_error = None;
                 self.password_limit_reached = false;
-            }
-            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.password_change_current = None;
+                self.password_change_new = None;
                 self.mode = AppMode::NoteList;
             }
+            KeyCode::Backspace => {
+                edit_password(&mut self.password_input, |text| {
+                    text.pop();
+                });
+                self.password_error = None;
+                self.password_limit_reached = false;
+            }
+            KeyCode::Char(c) => {
+                if self.password_input.expose_secret().len() < MAX_PASSWORD_LENGTH {
+                    edit_password(&mut self.password_input, |text| text.push(c));
+                    self.password_limit_reached = self.password_input.expose_secret().len() >= MAX_PASSWORD_LENGTH;
+                } else {
+                    self.password_limit_reached = true;
+                }
+                self.password_error = None;
+            }
             _ => {}
         }
         Ok(())
     }
 
+    // encryption_enabled for the open vault in the config, so the next start opens it the new way.
+    // returns what to add to the status message when the config couldn't be written
+    fn set_vault_encryption(&mut self, enabled: bool, config: &Config) -> String {
+        // a --notes-file vault isn't in the config, the next start goes by the file itself
+        if config.session.notes_file.is_some() {
+            return String::new();
+        }
+        let mut updated = config.clone();
+        match &self.vault_name {
+            Some(name) => {
+                if let Some(vault) = updated.vaults.get_mut(name) {
+                    vault.encryption_enabled = enabled;
+                }
+            }
+            None => updated.behavior.encryption_enabled = enabled,
+        }
+        let result = updated.save();
+        self.config_update = Some(updated);
+        match result {
+            Ok(()) => String::new(),
+            Err(e) => format!(", but the config couldn't be saved ({}), set encryption_enabled = {} in it", e, enabled),
+        }
+    }
+
+    // native file dialog if there is one, the terminal prompt otherwise
+    fn choose_export_location(&mut self, #[cfg_attr(not(feature = "native-dialogs"), allow(unused_variables))] config: &Config) {
+        // generate default filename with timestamp
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+        let default_filename = match self.export_note_id.as_ref().and_then(|id| self.note_manager.get_note(id)) {
+            Some(note) => format!("{}.md", markdown::file_stem(&note.title)),
+            // markdown goes into a new folder with this name
+            None if self.export_format == ExportFormat::Markdown => format!("notes_markdown_{}", timestamp),
+            None if self.export_format == ExportFormat::Simplenote => format!("notes_simplenote_{}.json", timestamp),
+            None if self.export_format == ExportFormat::Csv => format!("notes_{}.csv", timestamp),
+            None if self.export_format == ExportFormat::Encrypted => format!("notes_encrypted_{}.json", timestamp),
+            None => format!("notes_backup_{}.json", timestamp),
+        };
+
+        #[cfg(feature = "native-dialogs")]
+        if config.behavior.use_native_dialog {
+            let single_note = self.export_note_id.is_some();
+            let format = self.export_format;
+            // try to use native file dialog first
+            match std::panic::catch_unwind(|| {
+                let dialog = rfd::FileDialog::new()
+                    .set_title(if single_note { "Export Note" } else { "Export Notes Backup" })
+                    .set_file_name(&default_filename);
+                let dialog = if single_note {
+                    dialog.add_filter("Markdown files", &["md"]).add_filter("Text files", &["txt"])
+                } else if format == ExportFormat::Csv {
+                    dialog.add_filter("CSV files", &["csv"])
+                } else if format != ExportFormat::Markdown {
+                    dialog.add_filter("JSON files", &["json"])
+                } else {
+                    dialog
+                };
+                dialog.add_filter("All files", &["*"]).save_file()
+            }) {
+                Ok(Some(file_path)) => {
+                    // native dialog succeeded and user selected a path
+                    if let Err(e) = self.export_notes(&file_path) {
+                        self.show_error(format!("Export failed: {}", e));
+                    }
+                    self.leave_export();
+                    return;
+                }
+                Ok(None) => {
+                    // native dialog succeeded but user cancelled
+                    self.leave_export();
+                    return;
+                }
+                Err(_) => {
+                    // native dialog failed (e.g., no GUI, missing dependencies)
+                    // fall back to terminal input with home directory as default
+                }
+            }
+        }
+
+        self.mode = AppMode::SelectingExportLocation;
+        let home_dir = dirs::home_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."));
+        let default_path = home_dir.join(&default_filename);
+        self.export_file_input = default_path.to_string_lossy().to_string();
+        self.export_cursor_position = self.export_file_input.len();
+    }
+
+    // back to the viewer when a single note was being exported, the list otherwise
+    fn leave_export(&mut self) {
+        self.export_password = None;
+        self.mode = if self.export_note_id.take().is_some() {
+            AppMode::ViewingNote
+        } else {
+            AppMode::NoteList
+        };
+    }
+
     fn handle_reauthentication_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
         use crossterm::event::KeyCode;
         
@@ -448,67 +2962,7 @@ impl App {
                             self.password_input = SecretString::new("".into());
                             self.password_error = None;
                             
-                            // generate default filename with timestamp
-                            let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
-                            let default_filename = format!("notes_backup_{}.json", timestamp);
-                            
-                            #[cfg(feature = "native-dialogs")]
-                            if config.behavior.use_native_dialog {
-                                // try to use native file dialog first
-                                match std::panic::catch_unwind(|| {
-                                    rfd::FileDialog::new()
-                                        .set_title("Export Notes Backup")
-                                        .set_file_name(&default_filename)
-                                        .add_filter("JSON files", &["json"])
-                                        .add_filter("All files", &["*"])
-                                        .save_file()
-                                }) {
-                                    Ok(Some(file_path)) => {
-                                        // native dialog succeeded and user selected a path
-                                        if let Err(e) = self.note_manager.export_plaintext(&file_path) {
-                                            // TODO: show error message in UI
-                                            eprintln!("Export failed: {}", e);
-                                        }
-                                        self.mode = AppMode::NoteList;
-                                    }
-                                    Ok(None) => {
-                                        // native dialog succeeded but user cancelled
-                                        self.mode = AppMode::NoteList;
-                                    }
-                                    Err(_) => {
-                                        // native dialog failed (e.g., no GUI, missing dependencies)
-                                        // fall back to terminal input with home directory as default
-                                        self.mode = AppMode::SelectingExportLocation;
-                                        
-                                        let home_dir = dirs::home_dir()
-                                            .unwrap_or_else(|| std::path::PathBuf::from("."));
-                                        let default_path = home_dir.join(&default_filename);
-                                        self.export_file_input = default_path.to_string_lossy().to_string();
-                                        self.export_cursor_position = self.export_file_input.len();
-                                    }
-                                }
-                            } else {
-                                // user prefers terminal dialog - go directly to terminal input
-                                self.mode = AppMode::SelectingExportLocation;
-                                
-                                let home_dir = dirs::home_dir()
-                                    .unwrap_or_else(|| std::path::PathBuf::from("."));
-                                let default_path = home_dir.join(&default_filename);
-                                self.export_file_input = default_path.to_string_lossy().to_string();
-                                self.export_cursor_position = self.export_file_input.len();
-                            }
-                            
-                            #[cfg(not(feature = "native-dialogs"))]
-                            {
-                                // native dialogs not compiled in - always use terminal input
-                                self.mode = AppMode::SelectingExportLocation;
-                                
-                                let home_dir = dirs::home_dir()
-                                    .unwrap_or_else(|| std::path::PathBuf::from("."));
-                                let default_path = home_dir.join(&default_filename);
-                                self.export_file_input = default_path.to_string_lossy().to_string();
-                                self.export_cursor_position = self.export_file_input.len();
-                            }
+                            self.after_export_auth(config);
                         }
                         Err(_) => {
                             self.password_error = Some("Invalid password or corrupted data".to_string());
@@ -518,25 +2972,20 @@ impl App {
                 }
             }
             KeyCode::Esc => {
-                self.mode = AppMode::NoteList;
+                self.leave_export();
                 self.password_input = SecretString::new("".into());
                 self.password_error = None;
             }
             KeyCode::Backspace => {
-                if !self.password_input.expose_secret().is_empty() {
-                    let secret_chars: Vec<char> = self.password_input.expose_secret().chars().collect();
-                    let char_count = secret_chars.len();
-                    let new_chars: Vec<char> = secret_chars.into_iter().take(char_count - 1).collect();
-                    self.password_input = SecretString::new(new_chars.into_iter().collect());
-                }
+                edit_password(&mut self.password_input, |text| {
+                    text.pop();
+                });
                 self.password_error = None;
                 self.password_limit_reached = false;
             }
             KeyCode::Char(c) => {
                 if self.password_input.expose_secret().len() < MAX_PASSWORD_LENGTH {
-                    let mut new_secret_str = self.password_input.expose_secret().to_string();
-                    new_secret_str.push(c);
-                    self.password_input = SecretString::new(new_secret_str.into());
+                    edit_password(&mut self.password_input, |text| text.push(c));
                     self.password_limit_reached = self.password_input.expose_secret().len() >= MAX_PASSWORD_LENGTH;
                 } else {
                     self.password_limit_reached = true;
@@ -552,19 +3001,19 @@ impl App {
         match key.code {
             KeyCode::Enter => {
                 if !self.export_file_input.trim().is_empty() {
-                    if let Err(e) = self.note_manager.export_plaintext(&self.export_file_input) {
-                        // TODO: show error message in UI
-                        eprintln!("Export failed: {}", e);
+                    let path = self.export_file_input.clone();
+                    if let Err(e) = self.export_notes(&path) {
+                        self.show_error(format!("Export failed: {}", e));
                     }
                     self.export_file_input.clear();
                     self.export_cursor_position = 0;
-                    self.mode = AppMode::NoteList;
+                    self.leave_export();
                 }
             }
             KeyCode::Esc => {
                 self.export_file_input.clear();
                 self.export_cursor_position = 0;
-                self.mode = AppMode::NoteList;
+                self.leave_export();
             }
             KeyCode::Backspace => {
                 if self.export_cursor_position > 0 {
@@ -602,39 +3051,375 @@ impl App {
         Ok(())
     }
 
-    fn handle_editor_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
-        let kb = &config.keybindings;
-        
-        if kb.save_and_exit.matches(key.code, key.modifiers) {
-            match self.mode {
-                AppMode::EditingNote => {
-                    if !config.behavior.auto_save && self.has_unsaved_changes() {
-                        self.mode = AppMode::ConfirmingUnsavedExit;
-                    } else {
-                        if !config.behavior.auto_save {
-                            self.save_current_note()?;
+    fn start_path_prompt(&mut self, prompt: PathPrompt, initial: String) {
+        self.path_prompt = Some(prompt);
+        self.path_cursor_position = initial.len();
+        self.path_input = initial;
+        self.mode = AppMode::EnteringPath;
+    }
+
+    fn handle_path_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+        match key.code {
+            KeyCode::Enter if !self.path_input.trim().is_empty() => {
+                let path = self.path_input.trim().to_string();
+                self.path_input.clear();
+                self.path_cursor_position = 0;
+                match self.path_prompt.take() {
+                    Some(PathPrompt::ImportFolder) => {
+                        let result = self.note_manager.import_dir(expand_home(&path));
+                        self.show_import_report(result);
+                    }
+                    Some(PathPrompt::ImportJoplin) => {
+                        let result = self.note_manager.import_jex(expand_home(&path));
+                        self.show_import_report(result);
+                    }
+                    Some(PathPrompt::ImportSimplenote) => {
+                        let result = self.note_manager.import_simplenote(expand_home(&path));
+                        self.show_import_report(result);
+                    }
+                    Some(PathPrompt::ImportCsv) => {
+                        let path = expand_home(&path);
+                        match csv_notes::preview(&path) {
+                            Ok(preview) => {
+                                let mapping = CsvMapping::guess(&preview.headers);
+                                self.csv_import = Some(CsvImport { path, preview, mapping, selected: 0 });
+                                self.mode = AppMode::MappingCsvColumns;
+                            }
+                            Err(e) => {
+                                self.show_error(format!("Import failed: {}", e));
+                                self.mode = AppMode::NoteList;
+                            }
                         }
-                        self.return_to_list();
                     }
+                    Some(PathPrompt::ImportJson) => {
+                        self.pending_import_path = Some(path);
+                        self.mode = AppMode::ChoosingImportPolicy;
+                    }
+                    Some(PathPrompt::SaveAs) => {
+                        self.pending_save_as_path = Some(path);
+                        self.mode = AppMode::ChoosingSaveAsMode;
+                    }
+                    Some(PathPrompt::AttachFile) => {
+                        self.attach_file(&path)?;
+                        self.mode = AppMode::ViewingNote;
+                    }
+                    None => self.mode = AppMode::NoteList,
                 }
-                AppMode::CreatingNote => {
-                    if !self.title_textarea.lines().join("").trim().is_empty() || 
-                       !self.content_textarea.lines().join("").trim().is_empty() {
-                        self.save_new_note()?;
+            }
+            KeyCode::Esc => {
+                self.path_input.clear();
+                self.path_cursor_position = 0;
+                self.mode = if self.path_prompt.take() == Some(PathPrompt::AttachFile) {
+                    AppMode::ViewingNote
+                } else {
+                    AppMode::NoteList
+                };
+            }
+            // the cursor is a byte offset, it moves a whole character at a time
+            KeyCode::Backspace => {
+                if let Some(c) = self.path_input[..self.path_cursor_position].chars().next_back() {
+                    self.path_cursor_position -= c.len_utf8();
+                    self.path_input.remove(self.path_cursor_position);
+                }
+            }
+            KeyCode::Delete if self.path_cursor_position < self.path_input.len() => {
+                self.path_input.remove(self.path_cursor_position);
+            }
+            KeyCode::Left => {
+                if let Some(c) = self.path_input[..self.path_cursor_position].chars().next_back() {
+                    self.path_cursor_position -= c.len_utf8();
+                }
+            }
+            KeyCode::Right => {
+                if let Some(c) = self.path_input[self.path_cursor_position..].chars().next() {
+                    self.path_cursor_position += c.len_utf8();
+                }
+            }
+            KeyCode::Home => {
+                self.path_cursor_position = 0;
+            }
+            KeyCode::End => {
+                self.path_cursor_position = self.path_input.len();
+            }
+            KeyCode::Char(c) => {
+                self.path_input.insert(self.path_cursor_position, c);
+                self.path_cursor_position += c.len_utf8();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn start_text_prompt(&mut self, prompt: TextPrompt, initial: String) {
+        self.text_prompt = Some(prompt);
+        self.text_cursor_position = initial.len();
+        self.text_input = initial;
+        self.mode = AppMode::EnteringText;
+    }
+
+    fn handle_text_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let position = self.text_cursor_position;
+        match key.code {
+            KeyCode::Enter => {
+                // an empty notebook name is how you unfile a note
+                if self.text_input.trim().is_empty() && self.text_prompt != Some(TextPrompt::MoveToNotebook) {
+                    return Ok(());
+                }
+                let text = self.text_input.trim().to_string();
+                self.text_input.clear();
+                self.text_cursor_position = 0;
+                match self.text_prompt.take() {
+                    Some(TextPrompt::QuickCapture) => self.quick_capture(&text, config)?,
+                    Some(TextPrompt::MoveToNotebook) => self.move_to_notebook(&text)?,
+                    Some(TextPrompt::AddTags) => self.add_tags_to_targets(&text)?,
+                    None => {}
+                }
+                self.mode = AppMode::NoteList;
+            }
+            KeyCode::Esc => {
+                self.text_input.clear();
+                self.text_cursor_position = 0;
+                self.text_prompt = None;
+                self.move_note_id = None;
+                self.mode = AppMode::NoteList;
+            }
+            KeyCode::Backspace => {
+                if let Some(c) = self.text_input[..position].chars().next_back() {
+                    self.text_cursor_position -= c.len_utf8();
+                    self.text_input.remove(self.text_cursor_position);
+                }
+            }
+            KeyCode::Delete if position < self.text_input.len() => {
+                self.text_input.remove(position);
+            }
+            KeyCode::Left => {
+                if let Some(c) = self.text_input[..position].chars().next_back() {
+                    self.text_cursor_position -= c.len_utf8();
+                }
+            }
+            KeyCode::Right => {
+                if let Some(c) = self.text_input[position..].chars().next() {
+                    self.text_cursor_position += c.len_utf8();
+                }
+            }
+            KeyCode::Home => {
+                self.text_cursor_position = 0;
+            }
+            KeyCode::End => {
+                self.text_cursor_position = self.text_input.len();
+            }
+            KeyCode::Char(c) => {
+                self.text_input.insert(position, c);
+                self.text_cursor_position += c.len_utf8();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_import_policy_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+        let policy = match key.code {
+            KeyCode::Char('n') | KeyCode::Char('N') => MergePolicy::KeepNewer,
+            KeyCode::Char('b') | KeyCode::Char('B') => MergePolicy::KeepBoth,
+            KeyCode::Char('s') | KeyCode::Char('S') => MergePolicy::SkipExisting,
+            KeyCode::Esc => {
+                self.pending_import_path = None;
+                self.mode = AppMode::NoteList;
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+
+        if let Some(path) = self.pending_import_path.take() {
+            let result = self.note_manager
+                .import_json(expand_home(&path), policy)
+                .and_then(|summary| self.note_manager.save_notes().map(|_| summary));
+            self.status_message = Some(match result {
+                Ok(summary) => format!("Import finished: {}", summary),
+                Err(e) => format!("Import failed: {}", e),
+            });
+        }
+        self.selected_note_index = 0;
+        self.mode = AppMode::NoteList;
+        Ok(())
+    }
+
+    // journal style capture: timestamped line appended to the note for today
+    fn move_to_notebook(&mut self, notebook: &str) -> io::Result<()> {
+        let Some(id) = self.move_note_id.take() else { return Ok(()) };
+        let before = self.note_manager.snapshot_notes(std::slice::from_ref(&id));
+        if self.note_manager.move_to_notebook(&id, notebook) {
+            self.push_undo("move to notebook", before);
+            self.note_manager.save_notes()?;
+            self.status_message = Some(if notebook.is_empty() {
+                "Note removed from its notebook".to_string()
+            } else {
+                format!("Moved to notebook '{}'", notebook)
+            });
+            self.clamp_list_selection();
+        }
+        Ok(())
+    }
+
+    fn quick_capture(&mut self, text: &str, config: &Config) -> io::Result<()> {
+        let now = Local::now();
+        let title = format_or_default(&now, &config.behavior.daily_note_title_format, "%Y-%m-%d");
+        let line = format!("{} {}", format_or_default(&now, &config.behavior.quick_capture_timestamp_format, "%H:%M"), text);
+
+        self.note_manager.append_to_note_titled(&title, &line);
+        self.note_manager.save_notes()?;
+        self.status_message = Some(format!("Added to '{}'", title));
+        Ok(())
+    }
+
+    fn handle_save_as_mode_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+        let switch = match key.code {
+            KeyCode::Char('s') | KeyCode::Char('S') => true,
+            KeyCode::Char('c') | KeyCode::Char('C') => false,
+            KeyCode::Esc => {
+                self.pending_save_as_path = None;
+                self.mode = AppMode::NoteList;
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+
+        if let Some(path) = self.pending_save_as_path.take() {
+            let path = expand_home(&path);
+            let result = if switch {
+                self.note_manager.save_as(path.clone())
+            } else {
+                self.note_manager.save_copy(&path)
+            };
+            self.status_message = Some(match result {
+                Ok(()) if switch => format!("Now using {}", path.display()),
+                Ok(()) => format!("Copy written to {}", path.display()),
+                Err(e) => format!("Save as failed: {}", e),
+            });
+        }
+        self.mode = AppMode::NoteList;
+        Ok(())
+    }
+
+    fn handle_editor_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        if self.find_bar.is_some() {
+            return self.handle_find_input(key, config);
+        }
+        if self.spelling_popup.is_some() {
+            return self.handle_spelling_input(key, config);
+        }
+        if config.keybindings.spelling_suggestions.matches(key.code, key.modifiers) {
+            self.open_spelling_popup();
+            return Ok(());
+        }
+        if config.keybindings.find_in_note.matches(key.code, key.modifiers) {
+            self.find_bar = Some(FindBar::default());
+            self.edit_mode = EditMode::Content;
+            return Ok(());
+        }
+
+        if self.completion.is_some() {
+            match key.code {
+                KeyCode::Up => {
+                    if let Some(completion) = &mut self.completion {
+                        completion.selected = completion.selected.saturating_sub(1);
                     }
-                    self.return_to_list();
+                    return Ok(());
+                }
+                KeyCode::Down => {
+                    if let Some(completion) = &mut self.completion {
+                        completion.selected = (completion.selected + 1).min(completion.candidates.len().saturating_sub(1));
+                    }
+                    return Ok(());
+                }
+                KeyCode::Enter | KeyCode::Tab => {
+                    self.accept_completion(config);
+                    return Ok(());
+                }
+                KeyCode::Esc => {
+                    self.completion = None;
+                    return Ok(());
                 }
                 _ => {}
             }
+        }
+
+        if self.edit_mode == EditMode::Content
+            && let Some(vim) = &mut self.vim
+        {
+            let before = self.content_textarea.lines().to_vec();
+            if vim.handle(key, &mut self.content_textarea) {
+                self.completion = None;
+                if self.content_textarea.lines() != before.as_slice() {
+                    self.schedule_autosave(config);
+                }
+                return Ok(());
+            }
+        }
+
+        let kb = &config.keybindings;
+        
+        if kb.save_and_exit.matches(key.code, key.modifiers) {
+            self.leave_editor(config)?;
         } else if kb.switch_field.matches(key.code, key.modifiers) {
             self.edit_mode = match self.edit_mode {
                 EditMode::Title => EditMode::Content,
-                EditMode::Content => EditMode::Title,
+                EditMode::Content => EditMode::Tags,
+                EditMode::Tags => EditMode::Title,
             };
         } else if kb.title_to_content.matches(key.code, key.modifiers) && self.edit_mode == EditMode::Title {
             self.edit_mode = EditMode::Content;
+        } else if key.code == KeyCode::Enter && self.edit_mode == EditMode::Tags {
+            // tags are a single line
+            self.edit_mode = EditMode::Content;
         } else if kb.toggle_highlighting.matches(key.code, key.modifiers) {
             self.highlighting_enabled = !self.highlighting_enabled;
+        } else if kb.toggle_line_numbers.matches(key.code, key.modifiers) {
+            self.line_numbers_enabled = !self.line_numbers_enabled;
+        } else if kb.toggle_wrap.matches(key.code, key.modifiers) {
+            self.wrap_enabled = !self.wrap_enabled;
+        } else if kb.external_editor.matches(key.code, key.modifiers) {
+            self.external_edit_requested = true;
+        } else if kb.copy_to_clipboard.matches(key.code, key.modifiers) {
+            let textarea = match self.edit_mode {
+                EditMode::Title => &mut self.title_textarea,
+                EditMode::Content => &mut self.content_textarea,
+                EditMode::Tags => &mut self.tags_textarea,
+            };
+            // the selection if there is one, otherwise everything in the field
+            let text = if textarea.selection_range().is_some() {
+                textarea.copy();
+                textarea.yank_text()
+            } else {
+                textarea.lines().join("\n")
+            };
+            self.copy_to_clipboard(&text);
+        } else if kb.paste_from_clipboard.matches(key.code, key.modifiers) {
+            match clipboard::paste() {
+                Ok(text) => {
+                    match self.edit_mode {
+                        // title and tags are single lines
+                        EditMode::Title => self.title_textarea.insert_str(text.replace('\n', " ")),
+                        EditMode::Content => self.content_textarea.insert_str(text),
+                        EditMode::Tags => self.tags_textarea.insert_str(text.replace('\n', " ")),
+                    };
+                    self.schedule_autosave(config);
+                }
+                Err(e) => self.show_error(format!("Couldn't paste: {}", e)),
+            }
+        } else if kb.editor_undo.matches(key.code, key.modifiers) || kb.editor_redo.matches(key.code, key.modifiers) {
+            let undo = kb.editor_undo.matches(key.code, key.modifiers);
+            let textarea = match self.edit_mode {
+                EditMode::Title => &mut self.title_textarea,
+                EditMode::Content => &mut self.content_textarea,
+                EditMode::Tags => &mut self.tags_textarea,
+            };
+            let changed = if undo { textarea.undo() } else { textarea.redo() };
+            if changed {
+                self.schedule_autosave(config);
+            } else {
+                self.status_message = Some(if undo { "Nothing to undo" } else { "Nothing to redo" }.to_string());
+            }
         } else {
             let text_changed = match self.edit_mode {
                 EditMode::Title => {
@@ -649,29 +3434,485 @@ impl App {
                     let new_content = self.content_textarea.lines().join("\n");
                     old_content != new_content
                 }
+                EditMode::Tags => {
+                    let old_tags = self.tags_textarea.lines().join("");
+                    self.tags_textarea.input(key);
+                    let new_tags = self.tags_textarea.lines().join("");
+                    old_tags != new_tags
+                }
             };
             
-            if text_changed && config.behavior.auto_save && self.mode == AppMode::EditingNote && self.current_note_id.is_some() {
-                if let Err(_) = self.save_current_note() {
-                    // if saving fails just keep typing
+            if text_changed {
+                self.schedule_autosave(config);
+            }
+            if self.edit_mode == EditMode::Content && (text_changed || self.completion.is_some()) {
+                self.update_completion();
+            }
+        }
+        Ok(())
+    }
+
+    // open, narrow down or close the completion popup for the text before the cursor
+    fn update_completion(&mut self) {
+        self.completion = None;
+        let (row, col) = self.content_textarea.cursor();
+        let before: String = self.content_textarea.lines()[row].chars().take(col).collect();
+
+        // an unclosed [[ wins over a # inside the link text
+        let trigger = match before.rfind("[[") {
+            Some(open) if !before[open..].contains("]]") => Some((CompletionKind::Wikilink, open + 2)),
+            _ => before.rfind('#').and_then(|hash| {
+                // a tag starts a word and has no spaces, that also keeps "# headings" out
+                let starts_word = before[..hash].chars().last().is_none_or(char::is_whitespace);
+                let typed = &before[hash + 1..];
+                (starts_word && !typed.contains(char::is_whitespace)).then_some((CompletionKind::Tag, hash + 1))
+            }),
+        };
+        let Some((kind, start)) = trigger else { return };
+
+        let typed = before[start..].to_lowercase();
+        let pool = match kind {
+            CompletionKind::Tag => self.note_manager.all_tags(),
+            CompletionKind::Wikilink => self.note_manager.note_titles(),
+        };
+        // prefix matches first, then anything containing what was typed
+        let (mut candidates, others): (Vec<String>, Vec<String>) = pool
+            .into_iter()
+            .filter(|candidate| {
+                let lower = candidate.to_lowercase();
+                lower.contains(&typed) && lower != typed
+            })
+            .partition(|candidate| candidate.to_lowercase().starts_with(&typed));
+        candidates.extend(others);
+        candidates.truncate(MAX_COMPLETIONS);
+
+        if !candidates.is_empty() {
+            let start = before[..start].chars().count();
+            self.completion = Some(Completion { kind, row, start, candidates, selected: 0 });
+        }
+    }
+
+    // swap what was typed after the trigger for the picked candidate
+    fn accept_completion(&mut self, config: &Config) {
+        let Some(completion) = self.completion.take() else { return };
+        let choice = &completion.candidates[completion.selected];
+        let (row, col) = self.content_textarea.cursor();
+        let closed = self.content_textarea.lines()[row].chars().skip(col).collect::<String>().starts_with("]]");
+
+        self.content_textarea.move_cursor(tui_textarea::CursorMove::Jump(completion.row as u16, completion.start as u16));
+        self.content_textarea.start_selection();
+        self.content_textarea.move_cursor(tui_textarea::CursorMove::Jump(row as u16, col as u16));
+        match completion.kind {
+            CompletionKind::Tag => {
+                self.content_textarea.insert_str(choice);
+            }
+            CompletionKind::Wikilink if closed => {
+                self.content_textarea.insert_str(choice);
+                // step over the brackets that were already there
+                self.content_textarea.move_cursor(tui_textarea::CursorMove::Forward);
+                self.content_textarea.move_cursor(tui_textarea::CursorMove::Forward);
+            }
+            CompletionKind::Wikilink => {
+                self.content_textarea.insert_str(format!("{}]]", choice));
+            }
+        }
+        self.schedule_autosave(config);
+    }
+
+    fn handle_find_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let Some(bar) = self.find_bar.as_mut() else { return Ok(()) };
+
+        if config.keybindings.replace_all.matches(key.code, key.modifiers) {
+            self.replace_all_matches(config);
+            return Ok(());
+        }
+        match key.code {
+            KeyCode::Esc => {
+                self.find_bar = None;
+                // spellcheck puts its own pattern back on the next draw
+                let _ = self.content_textarea.set_search_pattern("");
+            }
+            KeyCode::Tab => bar.in_replacement = !bar.in_replacement,
+            KeyCode::Enter if bar.in_replacement => self.replace_current_match(config),
+            KeyCode::Enter | KeyCode::Down => {
+                let found = self.content_textarea.search_forward(false);
+                if !found {
+                    self.status_message = Some("No matches".to_string());
+                }
+            }
+            KeyCode::Up => {
+                let found = self.content_textarea.search_back(false);
+                if !found {
+                    self.status_message = Some("No matches".to_string());
+                }
+            }
+            KeyCode::Backspace => {
+                if bar.in_replacement {
+                    bar.replacement.pop();
+                } else {
+                    bar.query.pop();
+                    self.update_find_pattern(config);
+                }
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if bar.in_replacement {
+                    bar.replacement.push(c);
+                } else {
+                    bar.query.push(c);
+                    self.update_find_pattern(config);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn open_spelling_popup(&mut self) {
+        let Some(checker) = &self.spell_checker else {
+            self.status_message = Some("Spellcheck is off".to_string());
+            return;
+        };
+        if self.edit_mode != EditMode::Content {
+            return;
+        }
+        let (row, col) = self.content_textarea.cursor();
+        let chars: Vec<char> = self.content_textarea.lines()[row].chars().collect();
+        let is_word_char = |c: &char| c.is_alphabetic() || *c == '\'';
+        let start = (0..col.min(chars.len())).rev().take_while(|&i| is_word_char(&chars[i])).last().unwrap_or(col);
+        let end = (col..chars.len()).take_while(|&i| is_word_char(&chars[i])).last().map_or(col, |i| i + 1);
+        let word: String = chars[start..end].iter().collect::<String>().trim_matches('\'').to_string();
+
+        if word.is_empty() {
+            self.status_message = Some("No word under the cursor".to_string());
+        } else if checker.is_correct(&word) {
+            self.status_message = Some(format!("'{}' is spelled correctly", word));
+        } else {
+            let suggestions = checker.suggest(&word, 8);
+            if suggestions.is_empty() {
+                self.status_message = Some(format!("No suggestions for '{}'", word));
+                return;
+            }
+            // the trimmed quotes aren't part of what gets replaced
+            let leading = chars[start..end].iter().take_while(|c| **c == '\'').count();
+            let start = start + leading;
+            let end = start + word.chars().count();
+            self.spelling_popup = Some(SpellingPopup { word, row, start, end, suggestions, selected: 0 });
+        }
+    }
+
+    fn handle_spelling_input(&mut self, key: KeyEvent, config: &Config) -> io::Result<()> {
+        let Some(popup) = self.spelling_popup.as_mut() else { return Ok(()) };
+        match key.code {
+            KeyCode::Esc => self.spelling_popup = None,
+            KeyCode::Up => popup.selected = popup.selected.saturating_sub(1),
+            KeyCode::Down => popup.selected = (popup.selected + 1).min(popup.suggestions.len().saturating_sub(1)),
+            KeyCode::Enter => {
+                if let Some(popup) = self.spelling_popup.take() {
+                    let replacement = &popup.suggestions[popup.selected];
+                    self.content_textarea.move_cursor(tui_textarea::CursorMove::Jump(popup.row as u16, popup.start as u16));
+                    self.content_textarea.start_selection();
+                    self.content_textarea.move_cursor(tui_textarea::CursorMove::Jump(popup.row as u16, popup.end as u16));
+                    self.content_textarea.insert_str(replacement);
+                    self.schedule_autosave(config);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // highlight every match of the query and jump to the nearest one
+    fn update_find_pattern(&mut self, config: &Config) {
+        let query = self.find_bar.as_ref().map(|bar| bar.query.as_str()).unwrap_or("");
+        let pattern = if query.is_empty() {
+            String::new()
+        } else if config.behavior.search_case_sensitive {
+            escape_regex(query)
+        } else {
+            format!("(?i){}", escape_regex(query))
+        };
+        if self.content_textarea.set_search_pattern(&pattern).is_ok() && !pattern.is_empty() {
+            self.content_textarea.search_forward(true);
+        }
+    }
+
+    pub fn find_match_count(&self) -> usize {
+        match self.content_textarea.search_pattern() {
+            Some(pattern) if self.find_bar.is_some() => {
+                self.content_textarea.lines().iter().map(|line| pattern.find_iter(line).count()).sum()
+            }
+            _ => 0,
+        }
+    }
+
+    // replace the match under the cursor, or move onto the next one first
+    fn replace_current_match(&mut self, config: &Config) {
+        let Some(pattern) = self.content_textarea.search_pattern().cloned() else { return };
+        let replacement = self.find_bar.as_ref().map(|bar| bar.replacement.clone()).unwrap_or_default();
+        let (row, col) = self.content_textarea.cursor();
+        let line = &self.content_textarea.lines()[row];
+        let start = line.char_indices().nth(col).map_or(line.len(), |(i, _)| i);
+
+        match pattern.find_at(line, start) {
+            Some(found) if found.start() == start && found.end() > start => {
+                let length = found.as_str().chars().count();
+                self.content_textarea.start_selection();
+                for _ in 0..length {
+                    self.content_textarea.move_cursor(tui_textarea::CursorMove::Forward);
+                }
+                self.content_textarea.insert_str(&replacement);
+                self.schedule_autosave(config);
+                self.content_textarea.search_forward(false);
+            }
+            _ => {
+                let found = self.content_textarea.search_forward(false);
+                if !found {
+                    self.status_message = Some("No matches".to_string());
+                }
+            }
+        }
+    }
+
+    fn replace_all_matches(&mut self, config: &Config) {
+        let Some(pattern) = self.content_textarea.search_pattern().cloned() else { return };
+        let replacement = self.find_bar.as_ref().map(|bar| bar.replacement.clone()).unwrap_or_default();
+        let text = self.content_textarea.lines().join("\n");
+
+        let mut replaced = String::with_capacity(text.len());
+        let mut last = 0;
+        let mut count = 0;
+        for found in pattern.find_iter(&text) {
+            replaced.push_str(&text[last..found.start()]);
+            replaced.push_str(&replacement);
+            last = found.end();
+            count += 1;
+        }
+        replaced.push_str(&text[last..]);
+
+        if count == 0 {
+            self.status_message = Some("No matches".to_string());
+            return;
+        }
+        // one edit, so a single undo brings everything back
+        let (row, col) = self.content_textarea.cursor();
+        self.content_textarea.select_all();
+        self.content_textarea.insert_str(&replaced);
+        self.content_textarea.move_cursor(tui_textarea::CursorMove::Jump(row as u16, col as u16));
+        self.schedule_autosave(config);
+        self.status_message = Some(format!("Replaced {} occurrence(s)", count));
+    }
+
+    // push the auto-save back while the user keeps typing
+    // save (or ask about unsaved changes) and go back to the list
+    fn leave_editor(&mut self, config: &Config) -> io::Result<()> {
+        match self.mode {
+            AppMode::EditingNote => {
+                if !config.behavior.auto_save && self.has_unsaved_changes() {
+                    self.mode = AppMode::ConfirmingUnsavedExit;
+                } else {
+                    if !config.behavior.auto_save {
+                        self.save_current_note()?;
+                    }
+                    self.flush_autosave();
+                    self.return_to_list();
+                }
+            }
+            AppMode::CreatingNote => {
+                if !self.title_textarea.lines().join("").trim().is_empty() || 
+                   !self.content_textarea.lines().join("").trim().is_empty() {
+                    self.save_new_note()?;
                 }
+                self.return_to_list();
             }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn request_quit(&mut self) {
+        let dirty = self.tabs.iter().filter(|tab| tab.dirty).count();
+        if dirty > 0 && !self.quit_armed {
+            self.quit_armed = true;
+            self.status_message = Some(format!("{} open tab(s) have unsaved changes, press quit again to discard them", dirty));
+            return;
+        }
+        self.should_quit = true;
+    }
+
+    // give the note on screen a tab, or take over the tab it already has
+    fn open_tab(&mut self) {
+        if let Some(id) = self.current_note_id.clone() {
+            self.recent_notes.retain(|recent| recent != &id);
+            self.recent_notes.insert(0, id);
+            self.recent_notes.truncate(MAX_RECENT_NOTES);
+        }
+        let label = self.tab_label();
+        if let Some(active) = self.active_tab {
+            let tab = &mut self.tabs[active];
+            tab.mode = self.mode;
+            tab.note_id = self.current_note_id.clone();
+            tab.label = label;
+            return;
+        }
+        // a note that's already open goes back to its tab, with whatever was left unsaved there
+        if let Some(existing) = self.current_note_id.as_ref().and_then(|id| {
+            self.tabs.iter().position(|tab| tab.note_id.as_ref() == Some(id))
+        }) {
+            self.restore_tab(existing);
+            return;
+        }
+        self.tabs.push(Tab::empty(self.mode, self.current_note_id.clone(), label));
+        self.active_tab = Some(self.tabs.len() - 1);
+    }
+
+    // with auto-save on an edited note is saved as it goes, new notes only save when they're left
+    pub fn unsaved_in_tab(&self, config: &Config) -> bool {
+        self.autosave_pending()
+            || (self.has_pending_edits() && !(config.behavior.auto_save && self.mode == AppMode::EditingNote))
+    }
+
+    fn tab_label(&self) -> String {
+        let title = match self.mode {
+            AppMode::ViewingNote => self.viewing_note.as_ref().map(|note| note.title.clone()).unwrap_or_default(),
+            _ => self.title_textarea.lines().join(""),
+        };
+        if title.trim().is_empty() { "Untitled".to_string() } else { title }
+    }
+
+    // move the note on screen into its tab slot and leave the editor fields empty
+    fn park_tab(&mut self, config: &Config) {
+        let Some(active) = self.active_tab.take() else { return };
+        self.flush_autosave();
+        let dirty = self.unsaved_in_tab(config);
+        let tab = Tab {
+            mode: self.mode,
+            note_id: self.current_note_id.clone(),
+            label: self.tab_label(),
+            dirty,
+            edit_mode: self.edit_mode,
+            title_textarea: std::mem::take(&mut self.title_textarea),
+            content_textarea: std::mem::take(&mut self.content_textarea),
+            tags_textarea: std::mem::take(&mut self.tags_textarea),
+            original_title: std::mem::take(&mut self.original_title),
+            original_content: std::mem::take(&mut self.original_content),
+            original_tags: std::mem::take(&mut self.original_tags),
+            scroll_offset: self.scroll_offset,
+        };
+        // fetched again when the tab comes back, the note may change in the meantime
+        self.viewing_note = None;
+        self.tabs[active] = tab;
+    }
+
+    fn restore_tab(&mut self, index: usize) {
+        let label = self.tabs[index].label.clone();
+        let tab = std::mem::replace(&mut self.tabs[index], Tab::empty(AppMode::NoteList, None, label));
+        self.mode = tab.mode;
+        self.edit_mode = tab.edit_mode;
+        self.current_note_id = tab.note_id;
+        self.title_textarea = tab.title_textarea;
+        self.content_textarea = tab.content_textarea;
+        self.tags_textarea = tab.tags_textarea;
+        self.original_title = tab.original_title;
+        self.original_content = tab.original_content;
+        self.original_tags = tab.original_tags;
+        self.scroll_offset = tab.scroll_offset;
+        // the note may have changed while it was in the background
+        self.viewing_note = match (&tab.mode, &self.current_note_id) {
+            (AppMode::ViewingNote, Some(id)) => self.note_manager.get_note(id).cloned(),
+            _ => None,
+        };
+        self.find_bar = None;
+        self.spelling_popup = None;
+        self.completion = None;
+        self.viewer_search = None;
+        self.editor_wrap_top = 0;
+        if let Some(vim) = &mut self.vim {
+            *vim = Vim::default();
+        }
+        self.search_return_query = None;
+        self.active_tab = Some(index);
+        self.open_tab();
+
+        if self.mode == AppMode::ViewingNote && self.viewing_note.is_none() {
+            // deleted in the meantime
+            self.return_to_list();
+            self.status_message = Some("That note no longer exists".to_string());
+        }
+    }
+
+    fn cycle_tab(&mut self, forward: bool, config: &Config) {
+        let count = self.tabs.len();
+        if count == 0 {
+            return;
+        }
+        let target = match self.active_tab {
+            Some(active) if forward => (active + 1) % count,
+            Some(active) => (active + count - 1) % count,
+            // from the list, jump into the first or last tab
+            None if forward => 0,
+            None => count - 1,
+        };
+        if self.active_tab == Some(target) {
+            return;
+        }
+        self.park_tab(config);
+        self.restore_tab(target);
+    }
+
+    // close the tab on screen like leaving it would, then show the one next to it
+    fn close_tab(&mut self, config: &Config) -> io::Result<()> {
+        let Some(active) = self.active_tab else { return Ok(()) };
+        if self.mode == AppMode::ViewingNote {
+            self.return_to_list();
+        } else {
+            self.leave_editor(config)?;
+        }
+        if self.mode == AppMode::NoteList && self.active_tab.is_none() && !self.tabs.is_empty() {
+            self.restore_tab(active.min(self.tabs.len() - 1));
         }
         Ok(())
     }
 
+    fn schedule_autosave(&mut self, config: &Config) {
+        if config.behavior.auto_save && self.mode == AppMode::EditingNote && self.current_note_id.is_some() {
+            let interval = config.behavior.auto_save_interval_secs;
+            self.autosave_timed = interval > 0;
+            if self.autosave_timed {
+                // the first change since the last save starts the clock, the rest ride along
+                self.autosave_due.get_or_insert(Instant::now() + Duration::from_secs(interval));
+                return;
+            }
+            let delay = Duration::from_millis(config.behavior.auto_save_delay_ms);
+            self.autosave_due = Some(Instant::now() + delay);
+            if delay.is_zero() {
+                self.flush_autosave();
+            }
+        }
+    }
+
     fn start_creating_note(&mut self) {
         self.mode = AppMode::CreatingNote;
         self.edit_mode = EditMode::Title;
         self.title_textarea = TextArea::default();
         self.content_textarea = TextArea::default();
+        self.tags_textarea = TextArea::default();
+        self.set_editor_history_limit();
+        self.find_bar = None;
+        self.spelling_popup = None;
+        self.completion = None;
         self.current_note_id = None;
         self.viewing_note = None;
         self.scroll_offset = 0;
+        if let Some(vim) = &mut self.vim {
+            *vim = Vim::default();
+        }
+        self.open_tab();
     }
 
     fn start_searching(&mut self) {
         self.mode = AppMode::Searching;
+        self.search_scope = Some(self.list_filter.clone()).filter(|filter| filter.is_scoped());
+        self.search_return_query = None;
         self.search_query.clear();
         self.search_cursor_position = 0;
         self.selected_note_index = 0;
@@ -683,12 +3924,14 @@ impl App {
         self.search_query.clear();
         self.search_cursor_position = 0;
         self.search_results.clear();
+        self.search_snippets.clear();
         self.selected_note_index = 0;
     }
 
     fn update_search_filter(&mut self) {
-        let search_notes = self.note_manager.search_notes(&self.search_query);
-        self.search_results = search_notes.iter().map(|note| note.id.clone()).collect();
+        let search_notes = self.note_manager.search_notes(&self.search_query, self.search_case_sensitive, self.search_scope.as_ref());
+        self.search_results = search_notes.iter().map(|(note, _)| note.id.clone()).collect();
+        self.search_snippets = search_notes.iter().map(|(_, found)| *found).collect();
         
         if self.selected_note_index >= self.search_results.len() && !self.search_results.is_empty() {
             self.selected_note_index = 0;
@@ -707,7 +3950,21 @@ impl App {
         }
     }
 
+    // jump a screenful of notes, the list's offset then follows the selection
+    fn page_selection(&mut self, down: bool, count: usize) {
+        let page = self.list_item_rows.len().max(1);
+        self.selected_note_index = if down {
+            (self.selected_note_index + page).min(count.saturating_sub(1))
+        } else {
+            self.selected_note_index.saturating_sub(page)
+        };
+    }
+
     fn start_viewing_filtered_note(&mut self) {
+        let id = self.search_results.get(self.selected_note_index).cloned();
+        if id.is_some_and(|id| self.ask_note_password(&id)) {
+            return;
+        }
         if let Some(note_id) = self.search_results.get(self.selected_note_index) {
             let all_notes = self.note_manager.get_all_notes();
             if let Some(note) = all_notes.iter().find(|n| &n.id == note_id) {
@@ -715,67 +3972,357 @@ impl App {
                 self.viewing_note = Some((*note).clone());
                 self.current_note_id = Some(note.id.clone());
                 self.scroll_offset = 0;
+                self.attachment_index = 0;
+                self.viewer_search = None;
+                self.open_tab();
+            }
+        }
+    }
+
+    fn start_editing_filtered_note(&mut self) {
+        let id = self.search_results.get(self.selected_note_index).cloned();
+        if id.is_some_and(|id| self.ask_note_password(&id)) {
+            return;
+        }
+        if let Some(note_id) = self.search_results.get(self.selected_note_index) {
+            let all_notes = self.note_manager.get_all_notes();
+            if let Some(note) = all_notes.iter().find(|n| &n.id == note_id).map(|n| (*n).clone()) {
+                if let Some(refusal) = self.edit_refusal(&note) {
+                    self.status_message = Some(refusal.to_string());
+                    return;
+                }
+                self.mode = AppMode::EditingNote;
+                self.edit_mode = EditMode::Title;
+                self.load_editor(&note);
+                self.current_note_id = Some(note.id.clone());
+                self.viewing_note = None;
+                self.scroll_offset = 0;
+                // remember the query so leaving the editor drops us back into the search
+                self.search_return_query = Some(self.search_query.clone());
+                self.open_tab();
             }
         }
     }
 
+    fn start_viewing_note(&mut self, id: &str) {
+        if self.ask_note_password(id) {
+            return;
+        }
+        let Some(note) = self.note_manager.get_note(id).cloned() else { return };
+        self.mode = AppMode::ViewingNote;
+        self.viewing_note = Some(note);
+        self.current_note_id = Some(id.to_string());
+        self.scroll_offset = 0;
+        self.attachment_index = 0;
+        self.viewer_search = None;
+        self.open_tab();
+    }
+
     fn start_viewing_selected_note(&mut self) {
-        let notes = self.note_manager.get_all_notes();
+        if self.selected_note_sealed() {
+            return;
+        }
+        let notes = self.note_manager.get_filtered_notes(&self.list_filter);
         if let Some(note) = notes.get(self.selected_note_index) {
             self.mode = AppMode::ViewingNote;
             self.viewing_note = Some((*note).clone());
             self.current_note_id = Some(note.id.clone());
             self.scroll_offset = 0;
+            self.attachment_index = 0;
+            self.viewer_search = None;
+            self.open_tab();
         }
     }
 
     fn start_editing_from_viewing(&mut self) {
-        if let Some(note) = &self.viewing_note {
+        if let Some(note) = self.viewing_note.clone() {
+            if let Some(refusal) = self.edit_refusal(&note) {
+                self.status_message = Some(refusal.to_string());
+                return;
+            }
             self.mode = AppMode::EditingNote;
             self.edit_mode = EditMode::Title;
-            self.title_textarea = TextArea::from(vec![note.title.clone()]);
-            self.content_textarea = TextArea::from(note.content.lines().map(|s| s.to_string()).collect::<Vec<_>>());
-            self.original_title = note.title.clone();
-            self.original_content = note.content.clone();
+            self.load_editor(&note);
+            self.open_tab();
         }
     }
 
     fn start_editing_selected_note(&mut self) {
-        let notes = self.note_manager.get_all_notes();
-        if let Some(note) = notes.get(self.selected_note_index) {
+        if self.selected_note_sealed() {
+            return;
+        }
+        let notes = self.note_manager.get_filtered_notes(&self.list_filter);
+        if let Some(note) = notes.get(self.selected_note_index).map(|n| (*n).clone()) {
+            // show locked notes read-only instead
+            if let Some(refusal) = self.edit_refusal(&note) {
+                self.start_viewing_selected_note();
+                self.status_message = Some(refusal.to_string());
+                return;
+            }
             self.mode = AppMode::EditingNote;
             self.edit_mode = EditMode::Title;
-            self.title_textarea = TextArea::from(vec![note.title.clone()]);
-            self.content_textarea = TextArea::from(note.content.lines().map(|s| s.to_string()).collect::<Vec<_>>());
+            self.load_editor(&note);
             self.current_note_id = Some(note.id.clone());
             self.viewing_note = None;
             self.scroll_offset = 0;
-            self.original_title = note.title.clone();
-            self.original_content = note.content.clone();
+            self.open_tab();
+        }
+    }
+
+    // why the note can't be edited, None when it can
+    fn edit_refusal(&self, note: &Note) -> Option<&'static str> {
+        if self.note_manager.is_read_only() {
+            Some(READ_ONLY_MESSAGE)
+        } else if note.locked {
+            Some(LOCKED_MESSAGE)
+        } else {
+            None
+        }
+    }
+
+    // true (and the password prompt up) when the highlighted note is encrypted and still sealed
+    fn selected_note_sealed(&mut self) -> bool {
+        let notes = self.note_manager.get_filtered_notes(&self.list_filter);
+        let id = notes.get(self.selected_note_index).map(|note| note.id.clone());
+        id.is_some_and(|id| self.ask_note_password(&id))
+    }
+
+    // encrypted notes ask for their password before they open, they open in the viewer after
+    fn ask_note_password(&mut self, id: &str) -> bool {
+        if !self.note_manager.is_note_sealed(id) {
+            return false;
+        }
+        self.start_note_password(id, NotePasswordPurpose::Open);
+        true
+    }
+
+    fn start_note_password(&mut self, id: &str, purpose: NotePasswordPurpose) {
+        self.note_password = Some((id.to_string(), purpose));
+        self.note_password_first = None;
+        self.note_password_return = self.mode;
+        self.password_input = SecretString::new("".into());
+        self.password_error = None;
+        self.password_limit_reached = false;
+        self.mode = AppMode::NotePassword;
+    }
+
+    fn close_note_password(&mut self) {
+        self.note_password = None;
+        self.note_password_first = None;
+        self.password_input = SecretString::new("".into());
+        self.password_error = None;
+        self.password_limit_reached = false;
+        self.mode = self.note_password_return;
+    }
+
+    fn toggle_note_encryption(&mut self, id: &str) -> io::Result<()> {
+        let Some(note) = self.note_manager.get_note(id) else { return Ok(()) };
+        if note.encrypted.is_none() {
+            self.start_note_password(id, NotePasswordPurpose::Encrypt);
+        } else if self.note_manager.is_note_sealed(id) {
+            self.start_note_password(id, NotePasswordPurpose::Decrypt);
+        } else {
+            self.note_manager.remove_note_encryption(id)?;
+            self.after_note_encryption(id, "is no longer encrypted")?;
+        }
+        Ok(())
+    }
+
+    fn after_note_encryption(&mut self, id: &str, what: &str) -> io::Result<()> {
+        self.note_manager.save_notes()?;
+        if self.viewing_note.as_ref().is_some_and(|note| note.id == id) {
+            self.viewing_note = self.note_manager.get_note(id).cloned();
+        }
+        let title = self.note_manager.get_note(id).map(|note| note.title.clone()).unwrap_or_default();
+        self.status_message = Some(format!("'{}' {}", title, what));
+        Ok(())
+    }
+
+    fn handle_note_password_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+        let Some((id, purpose)) = self.note_password.clone() else {
+            self.close_note_password();
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let entered = std::mem::replace(&mut self.password_input, SecretString::new("".into()));
+                self.password_limit_reached = false;
+                match purpose {
+                    NotePasswordPurpose::Open => match self.note_manager.unlock_note(&id, entered.expose_secret()) {
+                        Ok(()) => {
+                            self.close_note_password();
+                            self.start_viewing_note(&id);
+                        }
+                        Err(e) => {
+                            log::warn!("opening encrypted note {} failed: {}", id, e);
+                            self.password_error = Some(e.to_string());
+                        }
+                    },
+                    NotePasswordPurpose::Decrypt => {
+                        let result = self.note_manager.unlock_note(&id, entered.expose_secret())
+                            .and_then(|()| self.note_manager.remove_note_encryption(&id));
+                        match result {
+                            Ok(()) => {
+                                self.close_note_password();
+                                self.after_note_encryption(&id, "is no longer encrypted")?;
+                            }
+                            Err(e) => {
+                                log::warn!("decrypting note {} failed: {}", id, e);
+                                self.password_error = Some(e.to_string());
+                            }
+                        }
+                    }
+                    NotePasswordPurpose::Encrypt => match self.note_password_first.take() {
+                        None if entered.expose_secret().len() < MIN_PASSWORD_LENGTH => {
+                            self.password_error = Some(format!("Password must be at least {} characters", MIN_PASSWORD_LENGTH));
+                        }
+                        None => {
+                            self.password_error = None;
+                            self.note_password_first = Some(entered);
+                        }
+                        Some(first) if first.expose_secret() == entered.expose_secret() => {
+                            match self.note_manager.encrypt_note(&id, first.expose_secret()) {
+                                Ok(()) => {
+                                    self.close_note_password();
+                                    self.after_note_encryption(&id, "is encrypted, it asks for its password from the next start on")?;
+                                }
+                                Err(e) => self.password_error = Some(e.to_string()),
+                            }
+                        }
+                        Some(_) => {
+                            self.password_error = Some("Passwords don't match, enter it again".to_string());
+                        }
+                    },
+                }
+            }
+            KeyCode::Esc => self.close_note_password(),
+            KeyCode::Backspace => {
+                edit_password(&mut self.password_input, |text| {
+                    text.pop();
+                });
+                self.password_error = None;
+                self.password_limit_reached = false;
+            }
+            KeyCode::Char(c) => {
+                if self.password_input.expose_secret().len() < MAX_PASSWORD_LENGTH {
+                    edit_password(&mut self.password_input, |text| text.push(c));
+                    self.password_limit_reached = self.password_input.expose_secret().len() >= MAX_PASSWORD_LENGTH;
+                } else {
+                    self.password_limit_reached = true;
+                }
+                self.password_error = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // fill the editor from a note and remember what it looked like for unsaved-change checks
+    fn load_editor(&mut self, note: &Note) {
+        self.title_textarea = TextArea::from(vec![note.title.clone()]);
+        self.content_textarea = TextArea::from(note.content.lines().map(|s| s.to_string()).collect::<Vec<_>>());
+        self.tags_textarea = TextArea::from(vec![note.tags.join(", ")]);
+        self.original_title = note.title.clone();
+        self.original_content = note.content.clone();
+        self.original_tags = note.tags.join(", ");
+        self.set_editor_history_limit();
+        self.find_bar = None;
+        self.spelling_popup = None;
+        self.completion = None;
+        if let Some(vim) = &mut self.vim {
+            *vim = Vim::default();
+        }
+    }
+
+    // fresh textareas start with the library's default history size
+    fn set_editor_history_limit(&mut self) {
+        for textarea in [&mut self.title_textarea, &mut self.content_textarea, &mut self.tags_textarea] {
+            textarea.set_max_histories(self.editor_undo_depth);
+        }
+    }
+
+    fn confirm_delete_selected_note(&mut self) {
+        let ids = self.target_note_ids();
+        let title = match ids.as_slice() {
+            [] => return,
+            [id] if self.marked_notes.is_empty() => self.note_manager.get_note(id).map(|note| note.title.clone()).unwrap_or_default(),
+            _ => format!("{} marked note(s)", ids.len()),
+        };
+        self.delete_note_title = title;
+        self.mode = AppMode::ConfirmingDelete;
+    }
+
+    fn confirm_and_delete_note(&mut self) -> io::Result<()> {
+        let ids = self.target_note_ids();
+        if !ids.is_empty() {
+            self.record_undo("delete", &ids);
+            for id in &ids {
+                self.note_manager.delete_note(id);
+            }
+            self.note_manager.save_notes()?;
+            if ids.len() > 1 {
+                self.status_message = Some(format!("Moved {} notes to the trash", ids.len()));
+            }
+            self.marked_notes.clear();
+            self.clamp_list_selection();
         }
+        self.cancel_delete_confirmation();
+        Ok(())
     }
 
-    fn confirm_delete_selected_note(&mut self) {
-        let notes = self.note_manager.get_all_notes();
-        if let Some(note) = notes.get(self.selected_note_index) {
-            self.delete_note_title = note.title.clone();
-            self.mode = AppMode::ConfirmingDelete;
+    // the marked notes (in list order) if any are marked, otherwise the highlighted one
+    fn target_note_ids(&mut self) -> Vec<String> {
+        let notes = self.note_manager.get_filtered_notes(&self.list_filter);
+        if self.marked_notes.is_empty() {
+            notes.get(self.selected_note_index).map(|note| vec![note.id.clone()]).unwrap_or_default()
+        } else {
+            notes.iter().filter(|note| self.marked_notes.contains(&note.id)).map(|note| note.id.clone()).collect()
         }
     }
 
-    fn confirm_and_delete_note(&mut self) -> io::Result<()> {
-        let notes = self.note_manager.get_all_notes();
-        if let Some(note) = notes.get(self.selected_note_index) {
-            let id = note.id.clone();
-            self.note_manager.delete_note(&id);
-            self.note_manager.save_notes()?;
-            
-            let new_count = self.note_manager.get_all_notes().len();
-            if self.selected_note_index >= new_count && new_count > 0 {
-                self.selected_note_index = new_count - 1;
+    fn add_tags_to_targets(&mut self, input: &str) -> io::Result<()> {
+        let tags = parse_tags(input);
+        let ids = self.target_note_ids();
+        if tags.is_empty() || ids.is_empty() {
+            return Ok(());
+        }
+
+        self.record_undo("tag", &ids);
+        for id in &ids {
+            if let Some(note) = self.note_manager.get_note_mut(id) {
+                note.add_tags(&tags);
             }
         }
-        self.cancel_delete_confirmation();
+        self.note_manager.save_notes()?;
+        self.status_message = Some(format!("Tagged {} note(s)", ids.len()));
+        self.marked_notes.clear();
+        Ok(())
+    }
+
+    // plaintext export of the marked notes, or of everything when nothing is marked.
+    // a note exported from the viewer is written on its own as markdown
+    fn export_notes<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(id) = self.export_note_id.clone() {
+            self.note_manager.export_note(&id, &path)?;
+            self.status_message = Some(format!("Note exported to {}", path.display()));
+            return Ok(());
+        }
+        if self.export_format == ExportFormat::Encrypted {
+            let ids = (!self.marked_notes.is_empty()).then(|| self.target_note_ids());
+            let password = self.export_password.take();
+            self.note_manager.export_encrypted(ids.as_deref(), &path, password.as_ref().map(|p| p.expose_secret()))?;
+            self.status_message = Some(format!("Encrypted export written to {}", path.display()));
+            self.marked_notes.clear();
+            return Ok(());
+        }
+        if self.marked_notes.is_empty() {
+            return self.note_manager.export_plaintext(path, self.export_format);
+        }
+        let ids = self.target_note_ids();
+        self.note_manager.export_notes(&ids, path, self.export_format)?;
+        self.status_message = Some(format!("Exported {} note(s)", ids.len()));
+        self.marked_notes.clear();
         Ok(())
     }
 
@@ -784,6 +4331,13 @@ impl App {
         self.delete_note_title.clear();
     }
 
+    fn clamp_list_selection(&mut self) {
+        let count = self.note_manager.get_filtered_notes(&self.list_filter).len();
+        if self.selected_note_index >= count {
+            self.selected_note_index = count.saturating_sub(1);
+        }
+    }
+
     fn move_selection_up(&mut self) {
         if self.selected_note_index > 0 {
             self.selected_note_index -= 1;
@@ -791,25 +4345,37 @@ impl App {
     }
 
     fn move_selection_down(&mut self) {
-        let notes = self.note_manager.get_all_notes();
+        let notes = self.note_manager.get_filtered_notes(&self.list_filter);
         if self.selected_note_index < notes.len().saturating_sub(1) {
             self.selected_note_index += 1;
         }
     }
 
     fn save_current_note(&mut self) -> io::Result<()> {
+        self.apply_current_edits();
+        self.note_manager.save_notes()
+    }
+
+    // copy the editor buffers into the note without writing to disk
+    fn apply_current_edits(&mut self) {
         if let Some(id) = &self.current_note_id {
             if let Some(note) = self.note_manager.get_note_mut(id) {
                 let title = self.title_textarea.lines().join("");
                 let content = self.content_textarea.lines().join("\n");
+                let tags = parse_tags(&self.tags_textarea.lines().join(""));
                 note.update_title(title);
                 note.update_content(content);
+                note.set_tags(tags);
             }
         }
-        self.note_manager.save_notes()
     }
 
     fn save_new_note(&mut self) -> io::Result<()> {
+        self.apply_new_note();
+        self.note_manager.save_notes()
+    }
+
+    fn apply_new_note(&mut self) {
         let title_text = self.title_textarea.lines().join("");
         let content_text = self.content_textarea.lines().join("\n");
         
@@ -823,18 +4389,41 @@ impl App {
             title_text
         };
 
-        self.note_manager.add_note(title, content_text);
-        self.note_manager.save_notes()
+        let id = self.note_manager.add_note(title, content_text).id.clone();
+        let tags = parse_tags(&self.tags_textarea.lines().join(""));
+        // new notes land in the notebook being looked at
+        let notebook = self.list_filter.notebook.clone().unwrap_or_default();
+        if let Some(note) = self.note_manager.get_note_mut(&id) {
+            note.tags = tags;
+            note.notebook = notebook;
+        }
     }
 
     fn return_to_list(&mut self) {
+        self.flush_autosave();
+        if let Some(active) = self.active_tab.take() {
+            self.tabs.remove(active);
+        }
         self.mode = AppMode::NoteList;
         self.edit_mode = EditMode::Title;
         self.title_textarea = TextArea::default();
         self.content_textarea = TextArea::default();
+        self.tags_textarea = TextArea::default();
+        self.find_bar = None;
+        self.spelling_popup = None;
+        self.completion = None;
+        self.viewer_search = None;
         self.current_note_id = None;
         self.viewing_note = None;
         self.scroll_offset = 0;
+
+        // editing started from search results, go back to the same search
+        if let Some(query) = self.search_return_query.take() {
+            self.mode = AppMode::Searching;
+            self.search_cursor_position = query.len();
+            self.search_query = query;
+            self.update_search_filter();
+        }
     }
 
     fn scroll_up(&mut self) {
@@ -845,37 +4434,613 @@ impl App {
 
     fn scroll_down(&mut self) {
         self.scroll_offset += 1;
+        self.clamp_viewer_scroll();
     }
 
     fn page_up(&mut self) {
-        self.scroll_offset = self.scroll_offset.saturating_sub(10);
+        self.scroll_offset = self.scroll_offset.saturating_sub(self.page_size());
     }
 
     fn page_down(&mut self) {
-        self.scroll_offset += 10;
+        self.scroll_offset += self.page_size();
+        self.clamp_viewer_scroll();
+    }
+
+    // the viewer pages by what's on screen, everything else by 10 lines
+    fn page_size(&self) -> usize {
+        if self.mode == AppMode::ViewingNote { self.viewer_page_height.max(1) } else { 10 }
+    }
+
+    // don't let the viewer scroll into nothing, so scrolling back up reacts straight away
+    fn clamp_viewer_scroll(&mut self) {
+        if self.mode == AppMode::ViewingNote {
+            self.scroll_offset = self.scroll_offset.min(self.viewer_max_scroll);
+        }
+    }
+
+    // remember the notes an action is about to change so it can be undone
+    fn record_undo(&mut self, description: &str, ids: &[String]) {
+        let before = self.note_manager.snapshot_notes(ids);
+        self.push_undo(description, before);
+    }
+
+    fn push_undo(&mut self, description: &str, notes: Vec<(String, Option<Note>)>) {
+        if self.undo_depth == 0 {
+            return;
+        }
+        self.undo_stack.push(UndoEntry { description: description.to_string(), notes });
+        if self.undo_stack.len() > self.undo_depth {
+            self.undo_stack.remove(0);
+        }
+        // a new action makes the redo history meaningless
+        self.redo_stack.clear();
+    }
+
+    fn undo(&mut self) -> io::Result<()> {
+        let Some(entry) = self.undo_stack.pop() else {
+            self.status_message = Some("Nothing to undo".to_string());
+            return Ok(());
+        };
+        let replaced = self.note_manager.restore_notes(entry.notes);
+        self.note_manager.save_notes()?;
+        self.status_message = Some(format!("Undid {}", entry.description));
+        self.redo_stack.push(UndoEntry { description: entry.description, notes: replaced });
+        self.clamp_list_selection();
+        Ok(())
+    }
+
+    fn redo(&mut self) -> io::Result<()> {
+        let Some(entry) = self.redo_stack.pop() else {
+            self.status_message = Some("Nothing to redo".to_string());
+            return Ok(());
+        };
+        let replaced = self.note_manager.restore_notes(entry.notes);
+        self.note_manager.save_notes()?;
+        self.status_message = Some(format!("Redid {}", entry.description));
+        self.undo_stack.push(UndoEntry { description: entry.description, notes: replaced });
+        self.clamp_list_selection();
+        Ok(())
+    }
+
+    fn cycle_sort_mode(&mut self, config: &Config) {
+        let mode = self.note_manager.sort_mode().next();
+        self.note_manager.set_sort_mode(mode);
+        self.selected_note_index = 0;
+
+        // remember it for next time
+        let mut updated = config.clone();
+        updated.behavior.sort_mode = mode;
+        self.status_message = Some(match updated.save() {
+            Ok(()) => format!("Sorted by {}", mode.label()),
+            Err(e) => format!("Sorted by {} (couldn't save config: {})", mode.label(), e),
+        });
+    }
+
+    fn move_selected_note(&mut self, up: bool) -> io::Result<()> {
+        let notes = self.note_manager.get_filtered_notes(&self.list_filter);
+        let Some((id, pinned)) = notes.get(self.selected_note_index).map(|note| (note.id.clone(), note.pinned)) else { return Ok(()) };
+
+        // pinned notes can always be reordered, the rest only in manual order
+        if !pinned && self.note_manager.sort_mode() != SortMode::Manual {
+            self.status_message = Some("Switch to manual order to move unpinned notes around".to_string());
+            return Ok(());
+        }
+        if self.note_manager.move_note(&id, &self.list_filter, up) {
+            self.note_manager.save_notes()?;
+            // keep the cursor on the note that moved
+            if up {
+                self.selected_note_index -= 1;
+            } else {
+                self.selected_note_index += 1;
+            }
+        }
+        Ok(())
     }
 
     fn toggle_pin_selected_note(&mut self) -> io::Result<()> {
-        let notes = self.note_manager.get_all_notes();
+        let ids = self.target_note_ids();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        // with a mix of pinned and unpinned notes, pin them all
+        let pin = ids.iter().any(|id| self.note_manager.get_note(id).is_some_and(|note| !note.pinned));
+        self.record_undo("pin", &ids);
+        for id in &ids {
+            if let Some(note_mut) = self.note_manager.get_note_mut(id).filter(|note| note.pinned != pin) {
+                note_mut.toggle_pin();
+            }
+        }
+        self.note_manager.save_notes()?;
+        self.marked_notes.clear();
+        Ok(())
+    }
+
+    fn toggle_archive_selected_note(&mut self) -> io::Result<()> {
+        let ids = self.target_note_ids();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        // everything in one view is either archived or not, so this flips them all
+        let archive = !self.list_filter.archived;
+        self.record_undo("archive", &ids);
+        for id in &ids {
+            if let Some(note_mut) = self.note_manager.get_note_mut(id).filter(|note| note.archived != archive) {
+                note_mut.toggle_archived();
+            }
+        }
+        self.note_manager.save_notes()?;
+        self.status_message = Some(match (archive, ids.len()) {
+            (true, 1) => "Note archived".to_string(),
+            (false, 1) => "Note moved back to the list".to_string(),
+            (true, count) => format!("Archived {} notes", count),
+            (false, count) => format!("Moved {} notes back to the list", count),
+        });
+        self.marked_notes.clear();
+        // the notes always leave the current view
+        self.clamp_list_selection();
+        Ok(())
+    }
+
+    fn toggle_favorite_selected_note(&mut self) -> io::Result<()> {
+        let notes = self.note_manager.get_filtered_notes(&self.list_filter);
         if let Some(note) = notes.get(self.selected_note_index) {
             let id = note.id.clone();
+            self.record_undo("favorite", std::slice::from_ref(&id));
+            let mut favorite = false;
             if let Some(note_mut) = self.note_manager.get_note_mut(&id) {
-                note_mut.toggle_pin();
+                note_mut.toggle_favorite();
+                favorite = note_mut.favorite;
+            }
+            self.note_manager.save_notes()?;
+            self.status_message = Some(if favorite { "Added to favorites" } else { "Removed from favorites" }.to_string());
+
+            // unfavoriting in the favorites view drops the note from the list
+            let new_count = self.note_manager.get_filtered_notes(&self.list_filter).len();
+            if self.selected_note_index >= new_count && new_count > 0 {
+                self.selected_note_index = new_count - 1;
+            }
+        }
+        Ok(())
+    }
+
+    // called every loop iteration, reports on saves finished by the background worker
+    pub fn poll_saves(&mut self) {
+        match self.note_manager.poll_saves() {
+            Some(Ok(())) => {
+                self.last_saved_at = Some(Local::now());
+                self.last_save_failed = false;
+                // don't clobber a more interesting message
+                if self.status_message.is_none() {
+                    self.status_message = Some("Saved".to_string());
+                }
+            }
+            Some(Err(e)) => {
+                self.last_save_failed = true;
+                self.show_error(format!("Save failed: {}", e));
+            }
+            None => {}
+        }
+    }
+
+    // called every loop iteration, picks up writes to the notes file from other programs
+    pub fn check_external_changes(&mut self) {
+        match self.mode {
+            AppMode::PasswordPrompt
+            | AppMode::PasswordSetup
+            | AppMode::EncryptedFileWarning
+            | AppMode::VaultInUse
+            | AppMode::PickingVault
+            | AppMode::ResolvingConflict => return,
+            _ => {}
+        }
+        if !self.note_manager.is_ready() || !self.note_manager.disk_changed() {
+            return;
+        }
+
+        if self.has_pending_edits() || self.note_manager.has_blocked_save() {
+            // both sides changed, let the user decide
+            let previous = std::mem::replace(&mut self.mode, AppMode::ResolvingConflict);
+            self.conflict_return_mode = Some(previous);
+            return;
+        }
+
+        match self.note_manager.reload_from_disk() {
+            Ok(()) => {
+                self.refresh_after_reload();
+                self.status_message = Some("Notes file changed on disk, reloaded".to_string());
+            }
+            Err(e) => {
+                self.show_error(format!("Notes file changed on disk but could not be reloaded: {}", e));
+            }
+        }
+    }
+
+    // called every loop iteration, installs the notes once the background load finishes
+    pub fn poll_loading(&mut self, config: &Config) -> io::Result<()> {
+        if let Some(result) = self.note_manager.poll_loading() {
+            result?;
+            self.clamp_list_selection();
+            self.on_vault_ready(config);
+        }
+        Ok(())
+    }
+
+    // true while something on screen changes on its own and needs regular redraws
+    pub fn needs_tick(&self) -> bool {
+        self.note_manager.is_saving() || self.note_manager.is_loading() || self.autosave_imminent()
+            // a key sequence that times out
+            || !self.pending_keys.is_empty()
+            // the unlock countdown
+            || (self.mode == AppMode::PasswordPrompt && self.note_manager.unlock_wait().is_some())
+    }
+
+    // typed text that hasn't been handed to the save worker yet
+    pub fn autosave_pending(&self) -> bool {
+        self.autosave_due.is_some()
+    }
+
+    // pending and about to be written, rather than waiting for the next auto-save interval
+    pub fn autosave_imminent(&self) -> bool {
+        self.autosave_pending() && !self.autosave_timed
+    }
+
+    // Ctrl+C or a termination signal, there's no time to ask so the editor's changes are saved
+    pub fn save_before_exit(&mut self) -> io::Result<()> {
+        self.autosave_due = None;
+        match self.mode {
+            AppMode::EditingNote | AppMode::ConfirmingUnsavedExit if self.has_unsaved_changes() => self.save_current_note(),
+            AppMode::CreatingNote if self.has_pending_edits() => self.save_new_note(),
+            _ => Ok(()),
+        }
+    }
+
+    // a failure the user should know about, it's also logged since the status line is gone with
+    // the next keypress
+    fn show_error(&mut self, message: String) {
+        log::error!("{}", message);
+        self.status_message = Some(message);
+    }
+
+    // called every loop iteration, keeps the recovery journal in step with the editor
+    pub fn run_recovery(&mut self) {
+        if self.recovery_checked.is_some_and(|at| at.elapsed() < RECOVERY_INTERVAL) {
+            return;
+        }
+        self.recovery_checked = Some(Instant::now());
+        self.sync_recovery();
+    }
+
+    // write the editor's unsaved text to the journal, or remove the journal once there's none
+    pub fn sync_recovery(&mut self) {
+        let Some(entry) = self.recovery_entry() else {
+            if self.recovery_written.take().is_some() {
+                let _ = self.note_manager.clear_recovery();
+            }
+            return;
+        };
+        if self.recovery_written.as_ref().is_some_and(|written| written.same_text(&entry)) {
+            return;
+        }
+        match self.note_manager.write_recovery(&entry) {
+            Ok(()) => self.recovery_written = Some(entry),
+            Err(e) => self.show_error(format!("Couldn't write the recovery journal: {}", e)),
+        }
+    }
+
+    fn recovery_entry(&self) -> Option<Recovery> {
+        if !self.has_pending_edits() {
+            return None;
+        }
+        // notes with their own password stay out, the journal would only have them under the vault key
+        let note = self.current_note_id.as_ref().and_then(|id| self.note_manager.get_note(id));
+        if note.is_some_and(|note| note.encrypted.is_some()) {
+            return None;
+        }
+        Some(Recovery {
+            note_id: note.map(|note| note.id.clone()),
+            title: self.title_textarea.lines().join(""),
+            tags: self.tags_textarea.lines().join(""),
+            content: self.content_textarea.lines().join("\n"),
+            written_at: Utc::now(),
+        })
+    }
+
+    // ask about a journal the last run left behind, unless the note was saved after all
+    fn offer_recovery(&mut self) {
+        if self.mode != AppMode::NoteList {
+            return;
+        }
+        let entry = match self.note_manager.read_recovery() {
+            Ok(Some(entry)) => entry,
+            Ok(None) => return,
+            Err(e) => {
+                self.show_error(format!("Found a recovery journal but couldn't read it: {}", e));
+                return;
+            }
+        };
+        let note = entry.note_id.as_ref().and_then(|id| self.note_manager.get_note(id));
+        if note.is_some_and(|note| note.title == entry.title && note.content == entry.content && note.tags == parse_tags(&entry.tags)) {
+            let _ = self.note_manager.clear_recovery();
+            return;
+        }
+        self.recovered = Some(entry);
+        self.mode = AppMode::ConfirmingRecovery;
+    }
+
+    // open the recovered text in the editor, over the note it belonged to or as a new one
+    fn restore_recovery(&mut self) {
+        let Some(entry) = self.recovered.take() else {
+            self.mode = AppMode::NoteList;
+            return;
+        };
+        match entry.note_id.as_ref().and_then(|id| self.note_manager.get_note(id)).cloned() {
+            Some(note) => {
+                self.mode = AppMode::EditingNote;
+                self.load_editor(&note);
+                self.current_note_id = Some(note.id.clone());
+                self.viewing_note = None;
+                self.scroll_offset = 0;
+            }
+            // the note is gone, the text comes back as a new one
+            None => self.start_creating_note(),
+        }
+        self.edit_mode = EditMode::Content;
+        self.title_textarea = TextArea::from(vec![entry.title.clone()]);
+        self.tags_textarea = TextArea::from(vec![entry.tags.clone()]);
+        self.content_textarea = TextArea::from(entry.content.lines().map(|s| s.to_string()).collect::<Vec<_>>());
+        self.set_editor_history_limit();
+        self.open_tab();
+        let when = entry.written_at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+        self.status_message = Some(format!("Restored unsaved changes from {}, save to keep them", when));
+        // the journal stays until the text is saved or thrown away
+        self.recovery_written = Some(entry);
+    }
+
+    // lock the vault and overwrite the note text the app holds on to, once everything is saved
+    pub fn wipe(&mut self) {
+        if let Some(note) = self.viewing_note.as_mut() {
+            note.wipe();
+        }
+        self.viewing_note = None;
+        self.original_title.zeroize();
+        self.original_content.zeroize();
+        self.original_tags.zeroize();
+        self.recovered = None;
+        self.recovery_written = None;
+        self.journal_entries.clear();
+        for textarea in [&mut self.title_textarea, &mut self.tags_textarea, &mut self.content_textarea] {
+            for mut line in std::mem::take(textarea).into_lines() {
+                line.zeroize();
+            }
+        }
+        self.note_manager.lock();
+    }
+
+    // called every loop iteration, saves once the user has stopped typing for a moment
+    pub fn run_autosave(&mut self) {
+        if self.autosave_due.is_some_and(|due| Instant::now() >= due) {
+            self.flush_autosave();
+        }
+    }
+
+    // save a pending auto-save right now, e.g. when leaving the editor, quitting or the terminal
+    // losing focus
+    pub fn flush_autosave(&mut self) {
+        if self.autosave_due.take().is_none() || self.current_note_id.is_none() {
+            return;
+        }
+        if let Err(e) = self.save_current_note() {
+            // keep typing, the next change tries again
+            self.show_error(format!("Auto-save failed: {}", e));
+        }
+    }
+
+    // runs the external editor on the note's content, main has already handed over the terminal
+    fn copy_to_clipboard(&mut self, text: &str) {
+        self.status_message = Some(match clipboard::copy(text) {
+            Ok(via) => format!("Copied to the clipboard via {}", via),
+            Err(e) => format!("Couldn't copy: {}", e),
+        });
+    }
+
+    pub fn run_external_editor(&mut self, config: &Config) -> io::Result<()> {
+        self.external_edit_requested = false;
+        let editing = matches!(self.mode, AppMode::EditingNote | AppMode::CreatingNote);
+        let original = if editing {
+            self.content_textarea.lines().join("\n")
+        } else if let Some(note) = &self.viewing_note {
+            note.content.clone()
+        } else {
+            return Ok(());
+        };
+
+        let edited = match external_editor::edit(&config.behavior.external_editor, &original) {
+            Ok(edited) => edited,
+            Err(e) => {
+                self.show_error(format!("External editor failed: {}", e));
+                return Ok(());
+            }
+        };
+        // editors like to end the file with a newline the note never had
+        let edited = edited.strip_suffix('\n').map(|s| s.strip_suffix('\r').unwrap_or(s)).unwrap_or(&edited).to_string();
+        if edited == original {
+            self.status_message = Some("No changes from the external editor".to_string());
+            return Ok(());
+        }
+
+        if editing {
+            // replace the text as one edit so it can be undone
+            let (row, col) = self.content_textarea.cursor();
+            self.content_textarea.select_all();
+            self.content_textarea.insert_str(&edited);
+            self.content_textarea.move_cursor(tui_textarea::CursorMove::Jump(row as u16, col as u16));
+            self.edit_mode = EditMode::Content;
+            if config.behavior.auto_save && self.mode == AppMode::EditingNote && self.current_note_id.is_some() {
+                self.autosave_due = Some(Instant::now());
+                self.flush_autosave();
+            }
+        } else if let Some(id) = self.current_note_id.clone() {
+            self.record_undo("external edit", std::slice::from_ref(&id));
+            if let Some(note) = self.note_manager.get_note_mut(&id) {
+                note.update_content(edited);
             }
             self.note_manager.save_notes()?;
+            self.viewing_note = self.note_manager.get_note(&id).cloned();
+        }
+        self.status_message = Some("Updated from the external editor".to_string());
+        Ok(())
+    }
+
+    pub fn on_tick(&mut self) {
+        self.tick_count = self.tick_count.wrapping_add(1);
+    }
+
+    fn has_pending_edits(&self) -> bool {
+        match self.mode {
+            AppMode::EditingNote | AppMode::ConfirmingUnsavedExit => self.has_unsaved_changes(),
+            AppMode::CreatingNote => {
+                !self.title_textarea.lines().join("").trim().is_empty() ||
+                !self.content_textarea.lines().join("").trim().is_empty()
+            }
+            _ => false,
+        }
+    }
+
+    // keep whatever is on screen in sync with freshly reloaded notes
+    fn refresh_after_reload(&mut self) {
+        // the editor gets the reloaded text (or the user chose to keep theirs and saved it)
+        self.autosave_due = None;
+        let current = self.current_note_id.as_ref().and_then(|id| {
+            self.note_manager.get_all_notes().into_iter().find(|n| &n.id == id).cloned()
+        });
+
+        match self.mode {
+            AppMode::ViewingNote => match current {
+                Some(note) => self.viewing_note = Some(note),
+                None => self.return_to_list(),
+            },
+            AppMode::EditingNote => match current {
+                Some(note) => self.load_editor(&note),
+                None => self.return_to_list(),
+            },
+            AppMode::Searching => self.update_search_filter(),
+            _ => {}
+        }
+
+        let count = self.get_notes().len();
+        if self.selected_note_index >= count {
+            self.selected_note_index = count.saturating_sub(1);
+        }
+    }
+
+    fn handle_conflict_input(&mut self, key: KeyEvent, _config: &Config) -> io::Result<()> {
+        let previous = self.conflict_return_mode.take().unwrap_or(AppMode::NoteList);
+        let was_creating = previous == AppMode::CreatingNote;
+        let was_editing = matches!(previous, AppMode::EditingNote | AppMode::ConfirmingUnsavedExit);
+        // every choice below deals with the editor's text itself
+        self.autosave_due = None;
+
+        match key.code {
+            KeyCode::Char('k') | KeyCode::Char('K') => {
+                // keep mine: our version overwrites the file on disk
+                if was_creating {
+                    self.apply_new_note();
+                } else if was_editing {
+                    self.apply_current_edits();
+                }
+                if let Err(e) = self.note_manager.overwrite_disk() {
+                    self.show_error(format!("Save failed: {}", e));
+                    self.conflict_return_mode = Some(previous);
+                    return Ok(());
+                }
+                self.leave_conflict(previous);
+                self.status_message = Some("Kept your version, file on disk overwritten".to_string());
+            }
+            KeyCode::Char('m') | KeyCode::Char('M') => {
+                // merge: newest version of each note wins, notes only one side has are kept
+                if was_creating {
+                    self.apply_new_note();
+                } else if was_editing {
+                    self.apply_current_edits();
+                }
+                match self.note_manager.merge_from_disk() {
+                    Ok(taken) => {
+                        self.leave_conflict(previous);
+                        self.refresh_after_reload();
+                        self.status_message = Some(format!("Merged with the file on disk, {} note(s) taken from it", taken));
+                    }
+                    Err(e) => {
+                        self.show_error(format!("Merge failed: {}", e));
+                        self.conflict_return_mode = Some(previous);
+                    }
+                }
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') => {
+                // take theirs: drop the unsaved edits and reload
+                self.return_to_list();
+                self.status_message = Some(match self.note_manager.reload_from_disk() {
+                    Ok(()) => "Reloaded notes from disk, your unsaved edits were discarded".to_string(),
+                    Err(e) => format!("Reload failed: {}", e),
+                });
+                self.refresh_after_reload();
+            }
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                // export mine to a sidecar file, then reload
+                if was_creating {
+                    self.apply_new_note();
+                } else if was_editing {
+                    self.apply_current_edits();
+                }
+                match self.note_manager.export_sidecar() {
+                    Ok(path) => {
+                        self.return_to_list();
+                        self.status_message = Some(match self.note_manager.reload_from_disk() {
+                            Ok(()) => format!("Your version was saved to {}, reloaded from disk", path.display()),
+                            Err(e) => format!("Your version was saved to {}, but reload failed: {}", path.display(), e),
+                        });
+                        self.refresh_after_reload();
+                    }
+                    Err(e) => {
+                        self.show_error(format!("Could not export your version: {}", e));
+                        self.conflict_return_mode = Some(previous);
+                    }
+                }
+            }
+            _ => {
+                // not a choice, keep asking
+                self.conflict_return_mode = Some(previous);
+            }
         }
         Ok(())
     }
 
+    // back to where the conflict interrupted, with the editor's text now saved
+    fn leave_conflict(&mut self, previous: AppMode) {
+        match previous {
+            AppMode::CreatingNote => self.return_to_list(),
+            AppMode::EditingNote | AppMode::ConfirmingUnsavedExit => {
+                self.original_title = self.title_textarea.lines().join("");
+                self.original_content = self.content_textarea.lines().join("\n");
+                self.original_tags = self.tags_textarea.lines().join("");
+                self.mode = AppMode::EditingNote;
+            }
+            previous => self.mode = previous,
+        }
+    }
+
     fn has_unsaved_changes(&self) -> bool {
         let current_title = self.title_textarea.lines().join("");
         let current_content = self.content_textarea.lines().join("\n");
-        current_title != self.original_title || current_content != self.original_content
+        let current_tags = self.tags_textarea.lines().join("");
+        current_title != self.original_title || current_content != self.original_content ||
+            parse_tags(&current_tags) != parse_tags(&self.original_tags)
     }
 
 
     pub fn get_notes(&mut self) -> Vec<&Note> {
-        self.note_manager.get_all_notes()
+        self.note_manager.get_filtered_notes(&self.list_filter)
     }
 
     pub fn get_search_results(&mut self) -> Vec<&Note> {
@@ -888,4 +5053,83 @@ impl App {
             .collect()
     }
 
-}
\ No newline at end of file
+}
+
+// let users type ~/notes.json in path prompts
+// true if the typed keys are how the sequence starts (or all of it)
+fn sequence_starts_with(steps: &[KeyBinding], typed: &[KeyEvent]) -> bool {
+    steps.len() >= typed.len() && steps.iter().zip(typed).all(|(step, key)| step.matches(key.code, key.modifiers))
+}
+
+// the sequence the typed keys are exactly
+fn finished_sequence<'a>(sequences: &[&'a KeyBinding], typed: &[KeyEvent]) -> Option<&'a KeyBinding> {
+    sequences.iter().copied().find(|sequence| {
+        let steps = sequence.steps();
+        steps.len() == typed.len() && sequence_starts_with(&steps, typed)
+    })
+}
+
+pub fn expand_home(path: &str) -> std::path::PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => std::path::PathBuf::from(path),
+    }
+}
+
+// password fields are edited in a scratch buffer big enough for the longest password, so it never
+// reallocates and leaves copies behind, and it's wiped once the new secret holds the text
+fn edit_password(password: &mut SecretString, edit: impl FnOnce(&mut String)) {
+    let mut text = String::with_capacity(MAX_PASSWORD_LENGTH + 4);
+    text.push_str(password.expose_secret());
+    edit(&mut text);
+    *password = SecretString::new(Box::from(text.as_str()));
+    text.zeroize();
+}
+
+// a missing or unreadable dictionary just leaves spellcheck off
+fn load_spell_checker(behavior: &Behavior) -> Option<SpellChecker> {
+    if !behavior.spellcheck {
+        return None;
+    }
+    SpellChecker::load(spellcheck::dictionary_path(&behavior.spellcheck_language, &behavior.spellcheck_dictionaries, &behavior.spellcheck_dictionary)).ok()
+}
+
+// chrono panics on bad format strings, so fall back instead of trusting the config
+// same as regex::escape, the textarea only takes patterns
+// how well the letters of `pattern` show up in order in `text`, None if they don't.
+// runs of letters and letters starting a word count extra, gaps count against it
+fn fuzzy_score(text: &str, pattern: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let chars: Vec<char> = text.chars().collect();
+    let mut position = 0;
+    for wanted in pattern.chars() {
+        let found = (position..chars.len()).find(|&i| chars[i] == wanted)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        } else if found == 0 || !chars[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - position) as i64 / 4;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$-".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn format_or_default(time: &DateTime<Local>, format: &str, fallback: &str) -> String {
+    let valid = !StrftimeItems::new(format).any(|item| matches!(item, Item::Error));
+    time.format(if valid { format } else { fallback }).to_string()
+}
@@ -0,0 +1,180 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use crate::encryption::{EncryptedFile, EncryptionManager};
+use crate::fs_util::set_secure_permissions;
+
+// a file kept with a note, the data itself lives in the attachments folder under its hash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub name: String,
+    pub hash: String,
+    pub size: u64,
+    pub added_at: DateTime<Utc>,
+}
+
+// "attachments" folder next to the notes file
+pub fn attachments_dir(notes_file: &Path) -> PathBuf {
+    notes_file
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .join("attachments")
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// copy a file into the store, the same file attached twice is only stored once.
+// with an encryption key + salt the stored copy is encrypted like the vault
+pub fn store(dir: &Path, source: &Path, encryption: Option<(&EncryptionManager, &[u8])>) -> io::Result<Attachment> {
+    let data = fs::read(source)?;
+    let hash = sha256_hex(&data);
+    let name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| hash.clone());
+
+    if !dir.exists() {
+        fs::create_dir_all(dir)?;
+        set_secure_permissions(dir, true)?;
+    }
+
+    let blob = dir.join(&hash);
+    if !blob.exists() {
+        match encryption {
+            Some((encryption, salt)) => {
+                let encrypted = encryption.encrypt(&data, salt)?;
+                fs::write(&blob, serde_json::to_string(&encrypted)?)?;
+            }
+            None => fs::write(&blob, &data)?,
+        }
+        set_secure_permissions(&blob, false)?;
+    }
+
+    Ok(Attachment {
+        name,
+        hash,
+        size: data.len() as u64,
+        added_at: Utc::now(),
+    })
+}
+
+// the attachment's contents, decrypted if needed
+pub fn read(dir: &Path, attachment: &Attachment, encryption: Option<&EncryptionManager>) -> io::Result<Vec<u8>> {
+    let stored = fs::read(dir.join(&attachment.hash))?;
+    match encryption {
+        Some(encryption) => {
+            let encrypted: EncryptedFile = serde_json::from_slice(&stored)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("attachment is not encrypted: {}", e)))?;
+            encryption.decrypt(&encrypted)
+        }
+        None => Ok(stored),
+    }
+}
+
+// write a readable copy to a private temp folder and hand it to the system's default app
+pub fn open(dir: &Path, attachment: &Attachment, encryption: Option<&EncryptionManager>) -> io::Result<PathBuf> {
+    let data = read(dir, attachment, encryption)?;
+
+    let temp_dir = std::env::temp_dir().join(format!("tui-notes-{}", &attachment.hash[..attachment.hash.len().min(16)]));
+    if !temp_dir.exists() {
+        fs::create_dir_all(&temp_dir)?;
+        set_secure_permissions(&temp_dir, true)?;
+    }
+    // just the file name, never let a stored name point somewhere else
+    let file_name = Path::new(&attachment.name).file_name().map(|n| n.to_os_string()).unwrap_or_else(|| attachment.hash.clone().into());
+    let path = temp_dir.join(file_name);
+    fs::write(&path, data)?;
+    set_secure_permissions(&path, false)?;
+
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = Command::new("xdg-open");
+
+    command
+        .arg(&path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(path)
+}
+
+// stored attachments re-encrypted under another key (or decrypted), written next to the old
+// ones as <hash>.new and only swapped in by commit, so a failed vault rewrite leaves them readable
+pub struct Rewrite {
+    dir: PathBuf,
+    hashes: BTreeSet<String>,
+}
+
+pub fn prepare_rewrite<'a>(
+    dir: &Path,
+    attachments: impl Iterator<Item = &'a Attachment>,
+    from: Option<&EncryptionManager>,
+    to: Option<(&EncryptionManager, &[u8])>,
+) -> io::Result<Rewrite> {
+    let mut rewrite = Rewrite { dir: dir.to_path_buf(), hashes: BTreeSet::new() };
+    for attachment in attachments {
+        if rewrite.hashes.contains(&attachment.hash) || !dir.join(&attachment.hash).exists() {
+            continue;
+        }
+        let written = read(dir, attachment, from).and_then(|data| {
+            let contents = match to {
+                Some((encryption, salt)) => serde_json::to_vec(&encryption.encrypt(&data, salt)?)?,
+                None => data,
+            };
+            let path = rewrite.new_path(&attachment.hash);
+            fs::write(&path, contents)?;
+            set_secure_permissions(&path, false)
+        });
+        // the ones written so far are counted, so discard cleans them up
+        rewrite.hashes.insert(attachment.hash.clone());
+        if let Err(e) = written {
+            rewrite.discard();
+            return Err(io::Error::new(e.kind(), format!("attachment '{}': {}", attachment.name, e)));
+        }
+    }
+    Ok(rewrite)
+}
+
+impl Rewrite {
+    fn new_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.new", hash))
+    }
+
+    pub fn commit(self) -> io::Result<()> {
+        for hash in &self.hashes {
+            fs::rename(self.new_path(hash), self.dir.join(hash))?;
+        }
+        Ok(())
+    }
+
+    pub fn discard(self) {
+        for hash in &self.hashes {
+            let _ = fs::remove_file(self.new_path(hash));
+        }
+    }
+}
+
+// delete a stored file, fine if it's already gone
+pub fn remove(dir: &Path, hash: &str) -> io::Result<()> {
+    match fs::remove_file(dir.join(hash)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
@@ -0,0 +1,123 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::fs_util::set_secure_permissions;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Clone)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub created: Option<DateTime<Local>>,
+    pub size: u64,
+}
+
+impl BackupInfo {
+    pub fn file_name(&self) -> String {
+        self.path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    }
+}
+
+// empty config value means a "backups" folder next to the notes file
+pub fn backup_dir(notes_file: &Path, configured: &str) -> PathBuf {
+    if !configured.trim().is_empty() {
+        return PathBuf::from(configured);
+    }
+    notes_file
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .join("backups")
+}
+
+// "notes" and ".json" for notes.json, backups are named notes-<timestamp>.json
+fn name_parts(notes_file: &Path) -> (String, String) {
+    let stem = notes_file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| "notes".to_string());
+    let extension = notes_file.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (stem, extension)
+}
+
+// copies the notes file as it is on disk, so encrypted vaults stay encrypted
+pub fn create_backup(notes_file: &Path, dir: &Path) -> io::Result<PathBuf> {
+    if !dir.exists() {
+        fs::create_dir_all(dir)?;
+        set_secure_permissions(dir, true)?;
+    }
+
+    let (stem, extension) = name_parts(notes_file);
+    let mut backup = dir.join(format!("{}-{}{}", stem, Local::now().format(TIMESTAMP_FORMAT), extension));
+    // two backups in the same second, don't overwrite the first
+    let mut counter = 1;
+    while backup.exists() {
+        backup = dir.join(format!("{}-{}-{}{}", stem, Local::now().format(TIMESTAMP_FORMAT), counter, extension));
+        counter += 1;
+    }
+
+    fs::copy(notes_file, &backup)?;
+    set_secure_permissions(&backup, false)?;
+    Ok(backup)
+}
+
+// newest first
+pub fn list_backups(notes_file: &Path, dir: &Path) -> io::Result<Vec<BackupInfo>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let (stem, extension) = name_parts(notes_file);
+    let prefix = format!("{}-", stem);
+    let mut backups = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(timestamp) = name.strip_prefix(&prefix).and_then(|rest| rest.strip_suffix(&extension)) else {
+            continue;
+        };
+        // only our own files, the timestamp must parse (ignoring a -N suffix)
+        let Ok(created) = NaiveDateTime::parse_from_str(timestamp.get(..15).unwrap_or(""), TIMESTAMP_FORMAT) else {
+            continue;
+        };
+
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() {
+            continue;
+        }
+        backups.push(BackupInfo {
+            path: entry.path(),
+            created: Local.from_local_datetime(&created).single(),
+            size: metadata.len(),
+        });
+    }
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.file_name()));
+    Ok(backups)
+}
+
+// drop the oldest backups beyond `keep`, returns how many were removed
+pub fn rotate(notes_file: &Path, dir: &Path, keep: usize) -> io::Result<usize> {
+    let backups = list_backups(notes_file, dir)?;
+    let mut removed = 0;
+    for backup in backups.iter().skip(keep) {
+        fs::remove_file(&backup.path)?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+// put a backup back in place of the notes file (temp copy + rename)
+pub fn restore_backup(backup: &Path, notes_file: &Path) -> io::Result<()> {
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(notes_file.file_name().unwrap_or_default());
+    temp_name.push(".restore");
+    let temp_path = notes_file.with_file_name(temp_name);
+
+    fs::copy(backup, &temp_path)?;
+    set_secure_permissions(&temp_path, false)?;
+    if let Err(e) = fs::rename(&temp_path, notes_file) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use crate::attachments;
+use crate::config::Config;
+use crate::saver;
+use crate::theme;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+// what's in the bundle, the first entry of the archive
+const MANIFEST: &str = "tui-notes-bundle.json";
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    created_at: DateTime<Utc>,
+    vaults: Vec<BundledVault>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundledVault {
+    // None for the notes file from [behavior]
+    name: Option<String>,
+    // vaults/<n> in the archive, with the notes file and its attachments folder in it
+    dir: String,
+    file_name: String,
+}
+
+#[derive(Debug, Default)]
+pub struct BundleReport {
+    pub vaults: usize,
+    pub attachments: usize,
+    pub themes: usize,
+    // vaults on a server, they're not copied
+    pub remote: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub vaults: Vec<PathBuf>,
+    pub attachments: usize,
+    pub themes: usize,
+    // where the config that was there before went
+    pub config_backup: Option<PathBuf>,
+}
+
+// the config file, the custom themes and every local vault with its attachments in one tar file,
+// as they are on disk: encrypted vaults stay encrypted, keyfiles are left out
+pub fn create(path: &Path, config: &Config) -> io::Result<BundleReport> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut builder = tar::Builder::new(options.open(path)?);
+    let mut report = BundleReport::default();
+
+    let mut names: Vec<Option<String>> = config.vaults.keys().cloned().map(Some).collect();
+    names.push(None);
+    let mut manifest = Manifest { version: BUNDLE_VERSION, created_at: Utc::now(), vaults: Vec::new() };
+    let mut vault_files = Vec::new();
+    for name in names {
+        let vault = config.vault(name.as_deref())?;
+        if vault.remote {
+            report.remote += 1;
+            continue;
+        }
+        let notes_file = PathBuf::from(&vault.notes_file);
+        // the notes file from [behavior] is only there when it was ever used, and may be a vault's
+        if !notes_file.is_file() || vault_files.iter().any(|(_, _, file)| *file == notes_file) {
+            continue;
+        }
+        let file_name = notes_file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let dir = format!("vaults/{}", manifest.vaults.len());
+        manifest.vaults.push(BundledVault { name, dir: dir.clone(), file_name: file_name.clone() });
+        vault_files.push((dir, file_name, notes_file));
+    }
+    append(&mut builder, MANIFEST, &serde_json::to_vec_pretty(&manifest)?)?;
+
+    let config_path = Config::config_path()?;
+    if config_path.is_file() {
+        append(&mut builder, "config.toml", &fs::read(&config_path)?)?;
+    }
+    if let Ok(entries) = fs::read_dir(theme::themes_dir()?) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "toml") {
+                let name = entry.file_name().to_string_lossy().to_string();
+                append(&mut builder, &format!("themes/{}", name), &fs::read(&path)?)?;
+                report.themes += 1;
+            }
+        }
+    }
+    for (dir, file_name, notes_file) in vault_files {
+        append(&mut builder, &format!("{}/{}", dir, file_name), &fs::read(&notes_file)?)?;
+        report.vaults += 1;
+        let Ok(entries) = fs::read_dir(attachments::attachments_dir(&notes_file)) else { continue };
+        for entry in entries.flatten() {
+            let hash = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_file() && is_hash(&hash) {
+                append(&mut builder, &format!("{}/attachments/{}", dir, hash), &fs::read(entry.path())?)?;
+                report.attachments += 1;
+            }
+        }
+    }
+    builder.into_inner()?.sync_all()?;
+    Ok(report)
+}
+
+fn append(builder: &mut tar::Builder<File>, name: &str, data: &[u8]) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header.set_entry_type(tar::EntryType::Regular);
+    builder.append_data(&mut header, name, data)
+}
+
+// attachments are named by their sha-256, nothing else is taken out of the attachments folder
+fn is_hash(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// where a bundled vault goes: the notes file from [behavior] straight into `to`, named vaults into
+// vaults/<name> under it
+fn restore_path(to: &Path, vault: &BundledVault) -> PathBuf {
+    match &vault.name {
+        Some(name) => {
+            let folder: String = name.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+            to.join("vaults").join(folder).join(&vault.file_name)
+        }
+        None => to.join(&vault.file_name),
+    }
+}
+
+// unpack a bundle into `to` (the config folder unless asked otherwise) and point the config at the
+// restored notes files. a vault that's already there is never overwritten, the config that was is
+// kept as config.toml.bak
+pub fn restore(path: &Path, to: &Path) -> io::Result<RestoreReport> {
+    let manifest = read_manifest(path)?;
+    let mut targets = Vec::new();
+    for vault in &manifest.vaults {
+        if vault.file_name.is_empty() || vault.file_name.contains(['/', '\\']) || vault.file_name.starts_with('.') {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("the bundle has an odd notes file name '{}'", vault.file_name)));
+        }
+        let target = restore_path(to, vault);
+        // two vault names can come out as the same folder
+        if target.exists() || targets.iter().any(|(_, other)| *other == target) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists, restore somewhere else with --to <folder>", target.display()),
+            ));
+        }
+        targets.push((vault, target));
+    }
+
+    let mut report = RestoreReport::default();
+    let mut bundled_config = None;
+    let mut archive = tar::Archive::new(File::open(path)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().to_string();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+
+        if name == "config.toml" {
+            bundled_config = Some(String::from_utf8(data).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "the bundled config.toml isn't text")
+            })?);
+        } else if let Some(theme) = name.strip_prefix("themes/").filter(|theme| theme.ends_with(".toml") && !theme.contains(['/', '\\'])) {
+            let dir = theme::themes_dir()?;
+            fs::create_dir_all(&dir)?;
+            saver::write_atomically(&dir.join(theme), &data, false)?;
+            report.themes += 1;
+        } else if let Some((vault, target)) = targets.iter().find(|(vault, _)| name.starts_with(&format!("{}/", vault.dir))) {
+            let rest = &name[vault.dir.len() + 1..];
+            let destination = match rest.strip_prefix("attachments/") {
+                Some(hash) if is_hash(hash) => {
+                    report.attachments += 1;
+                    attachments::attachments_dir(target).join(hash)
+                }
+                None if rest == vault.file_name => target.clone(),
+                _ => continue,
+            };
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            saver::write_atomically(&destination, &data, false)?;
+        }
+    }
+
+    // the bundled config with the notes files moved to where they are now
+    let mut settings: toml::Table = match &bundled_config {
+        Some(contents) => toml::from_str(contents).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("the bundled config.toml doesn't parse: {}", e))
+        })?,
+        None => toml::Table::new(),
+    };
+    for (vault, target) in &targets {
+        let table = match &vault.name {
+            Some(name) => settings.entry("vaults").or_insert_with(|| toml::Table::new().into())
+                .as_table_mut()
+                .and_then(|vaults| vaults.entry(name.clone()).or_insert_with(|| toml::Table::new().into()).as_table_mut()),
+            None => settings.entry("behavior").or_insert_with(|| toml::Table::new().into()).as_table_mut(),
+        };
+        let key = if vault.name.is_some() { "notes_file" } else { "default_notes_file" };
+        if let Some(table) = table {
+            table.insert(key.to_string(), target.to_string_lossy().to_string().into());
+        }
+        report.vaults.push(target.clone());
+    }
+
+    let config_path = Config::config_path()?;
+    if config_path.exists() {
+        let backup = config_path.with_extension("toml.bak");
+        fs::copy(&config_path, &backup)?;
+        report.config_backup = Some(backup);
+    }
+    if let Some(parent) = config_path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = toml::to_string_pretty(&settings).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    saver::write_atomically(&config_path, contents.as_bytes(), false)?;
+    Ok(report)
+}
+
+fn read_manifest(path: &Path) -> io::Result<Manifest> {
+    let mut archive = tar::Archive::new(File::open(path)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.to_string_lossy() != MANIFEST {
+            continue;
+        }
+        let mut json = String::new();
+        entry.read_to_string(&mut json)?;
+        let manifest: Manifest = serde_json::from_str(&json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("the bundle's manifest is damaged: {}", e)))?;
+        if manifest.version > BUNDLE_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the bundle is from a newer version of tui-notes"));
+        }
+        return Ok(manifest);
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} isn't a tui-notes bundle", path.display())))
+}
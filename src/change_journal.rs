@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+use crate::encryption::{EncryptedFile, EncryptionManager};
+use crate::note::{Note, SavedNote};
+use crate::saver;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+// every saved change to a note, one json line each, next to the vault: notes.json -> notes.json.changes
+pub fn journal_path(notes_file: &Path) -> PathBuf {
+    let mut name = notes_file.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".changes");
+    notes_file.with_file_name(name)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    Created,
+    Edited,
+    Trashed,
+    Restored,
+    // gone for good, emptied from the trash or deleted on another machine and synced
+    Deleted,
+}
+
+impl Change {
+    pub fn label(self) -> &'static str {
+        match self {
+            Change::Created => "created",
+            Change::Edited => "edited",
+            Change::Trashed => "trashed",
+            Change::Restored => "restored",
+            Change::Deleted => "deleted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    pub change: Change,
+    pub note_id: String,
+    pub title: String,
+    // the text after the change, None when it's gone or sealed with the note's own password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Drop for Entry {
+    fn drop(&mut self) {
+        self.title.zeroize();
+        if let Some(content) = self.content.as_mut() {
+            content.zeroize();
+        }
+    }
+}
+
+// what happened to the notes since `before`, all stamped with the time of the save
+pub fn changes(before: &HashMap<String, SavedNote>, notes: &HashMap<String, Note>) -> Vec<Entry> {
+    let now = Utc::now();
+    let entry = |change, id: &str, note: &Note| Entry {
+        at: now,
+        change,
+        note_id: id.to_string(),
+        title: note.title.clone(),
+        content: note.encrypted.is_none().then(|| note.content.clone()),
+        tags: note.tags.clone(),
+    };
+
+    let mut entries = Vec::new();
+    for (id, note) in notes {
+        match before.get(id) {
+            None => entries.push(entry(Change::Created, id, note)),
+            Some(old) => {
+                let sealed = note.encrypted.is_some();
+                // a sealed note's text only changes while it's open, the title and tags still count
+                let text_changed = old.title != note.title || old.tags != note.tags || old.sealed != sealed || (!sealed && old.content != note.content);
+                if text_changed {
+                    entries.push(entry(Change::Edited, id, note));
+                }
+                match (old.trashed, note.is_trashed()) {
+                    (false, true) => entries.push(entry(Change::Trashed, id, note)),
+                    (true, false) => entries.push(entry(Change::Restored, id, note)),
+                    _ => {}
+                }
+            }
+        }
+    }
+    for (id, old) in before {
+        if !notes.contains_key(id) {
+            entries.push(Entry {
+                at: now,
+                change: Change::Deleted,
+                note_id: id.clone(),
+                title: old.title.clone(),
+                content: None,
+                tags: Vec::new(),
+            });
+        }
+    }
+    entries
+}
+
+// one line per entry, encrypted on its own with the vault key if there is one
+fn line(entry: &Entry, key: Option<(&EncryptionManager, &[u8])>) -> io::Result<String> {
+    let mut json = serde_json::to_string(entry)?;
+    let encoded = match key {
+        Some((encryption, salt)) => serde_json::to_string(&encryption.encrypt(json.as_bytes(), salt)?)?,
+        None => json.clone(),
+    };
+    json.zeroize();
+    Ok(encoded)
+}
+
+// add to the end, what's already in the journal is never touched
+pub fn append(path: &Path, entries: &[Entry], key: Option<(&EncryptionManager, &[u8])>) -> io::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&line(entry, key)?);
+        lines.push('\n');
+    }
+    let mut options = fs::OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    let result = file.write_all(lines.as_bytes()).and_then(|()| file.sync_data());
+    lines.zeroize();
+    result
+}
+
+// every entry, and how many lines couldn't be read (cut short by a crash, or damaged)
+pub fn read(path: &Path, key: Option<&EncryptionManager>) -> io::Result<(Vec<Entry>, usize)> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(e) => return Err(e),
+    };
+    let mut entries = Vec::new();
+    let mut unreadable = 0;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let entry = match key {
+            Some(encryption) => serde_json::from_str::<EncryptedFile>(line)
+                .ok()
+                .and_then(|encrypted| encryption.decrypt(&encrypted).ok())
+                .and_then(|mut json| {
+                    let entry = serde_json::from_slice::<Entry>(&json).ok();
+                    json.zeroize();
+                    entry
+                }),
+            None => serde_json::from_str::<Entry>(line).ok(),
+        };
+        match entry {
+            Some(entry) => entries.push(entry),
+            None => unreadable += 1,
+        }
+    }
+    Ok((entries, unreadable))
+}
+
+// the whole journal again under another key, the one time it isn't just appended to
+pub fn rewrite(path: &Path, entries: &[Entry], key: Option<(&EncryptionManager, &[u8])>) -> io::Result<()> {
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&line(entry, key)?);
+        lines.push('\n');
+    }
+    let result = saver::write_atomically(path, lines.as_bytes(), false);
+    lines.zeroize();
+    result
+}
@@ -0,0 +1,475 @@
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use chrono::{DateTime, Utc};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use crate::bundle;
+use crate::config::{Config, VaultConfig};
+use crate::credentials;
+use crate::encryption::MAX_PASSWORD_LENGTH;
+use crate::markdown;
+use crate::note::{parse_tags, ExportFormat, Note, NoteFilter, NoteManager};
+use zeroize::Zeroizing;
+
+// for scripts and cron jobs that can't type the password
+pub const PASSWORD_ENV: &str = "TUI_NOTES_PASSWORD";
+// which of the [vaults] the command works on, default_vault otherwise
+const VAULT_ENV: &str = "TUI_NOTES_VAULT";
+
+pub const USAGE: &str = "usage: tui-notes [options] [command]
+
+without a command the notes open in the terminal ui
+
+options, for this run only:
+  --config <path>         read the settings from this file instead of config.toml
+  --notes-file <path>     open this notes file instead of the vaults in the config
+  --read-only             change nothing in the vault, it isn't locked either
+  --no-encryption-prompt  never ask for the password, fail unless the keyring or
+                          TUI_NOTES_PASSWORD has it
+
+commands:
+  add <title> [--content <text>|-] [--tags <a,b>] [--notebook <name>]
+                          add a note, - reads the content from stdin, prints the new id
+  list [--tag <tag>] [--notebook <name>]
+                          id, last change and title of every note
+  search <query>          same, for the notes matching the query (#tag works too)
+  show <id>               a note as markdown with its metadata on top
+  cat <id or title> [--json]
+                          just the note's content, or the note as a json object
+  export <path> [--format json|markdown|simplenote|csv|encrypted]
+                          export every note, json unless told otherwise
+  delete <id>             move a note to the trash
+  sync                    merge the vault with its sync_remote
+  bundle <path>           the config, themes and every vault with its attachments in one
+                          file, to move the whole setup to another machine
+  restore-bundle <path> [--to <folder>]
+                          unpack a bundle, the vaults go into the config folder unless
+                          told otherwise and the config is pointed at them
+  forget-password         remove the vault's password from the system keyring
+  help                    this text
+
+ids can be shortened to any prefix that's unique. encrypted vaults ask for the
+password on the terminal, or read it from the TUI_NOTES_PASSWORD variable. with
+named vaults in the config, TUI_NOTES_VAULT picks one instead of default_vault";
+
+// positional arguments, --name value options and --name flags
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: &[String], allowed: &[&str]) -> io::Result<Self> {
+        Self::parse_flags(args, allowed, &[])
+    }
+
+    fn parse_flags(args: &[String], allowed: &[&str], flags: &[&str]) -> io::Result<Self> {
+        let mut parsed = Args { positional: Vec::new(), options: HashMap::new(), flags: Vec::new() };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if flags.contains(&name) => parsed.flags.push(name.to_string()),
+                Some(name) if allowed.contains(&name) => {
+                    let value = iter.next().ok_or_else(|| invalid(format!("--{} needs a value", name)))?;
+                    parsed.options.insert(name.to_string(), value.clone());
+                }
+                Some(name) => return Err(invalid(format!("unknown option --{}", name))),
+                None => parsed.positional.push(arg.clone()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    // the one positional argument a command takes
+    fn single(&self, what: &str) -> io::Result<&str> {
+        match self.positional.as_slice() {
+            [value] => Ok(value),
+            [] => Err(invalid(format!("missing {}", what))),
+            _ => Err(invalid(format!("expected a single {}, quote it if it has spaces", what))),
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+// run a subcommand instead of the ui, `args` doesn't include the program name
+pub fn run(args: &[String], config: &Config) -> io::Result<()> {
+    let (command, rest) = args.split_first().map(|(c, r)| (c.as_str(), r)).unwrap_or(("help", &[]));
+    match command {
+        "add" => add(rest, config),
+        "list" => list(rest, config),
+        "search" => search(rest, config),
+        "show" => show(rest, config),
+        "cat" => cat(rest, config),
+        "export" => export(rest, config),
+        "delete" => delete(rest, config),
+        "sync" => sync(rest, config),
+        "bundle" => bundle(rest, config),
+        "restore-bundle" => restore_bundle(rest),
+        "forget-password" => forget_password(config),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(invalid(format!("unknown command '{}', see tui-notes help", command))),
+    }
+}
+
+fn add(args: &[String], config: &Config) -> io::Result<()> {
+    let args = Args::parse(args, &["content", "tags", "notebook"])?;
+    let title = args.single("title")?.to_string();
+    let content = match args.option("content") {
+        Some("-") => {
+            let mut content = String::new();
+            io::stdin().read_to_string(&mut content)?;
+            // echo and most pipes end with a newline that isn't part of the note
+            content.trim_end_matches(['\r', '\n']).to_string()
+        }
+        Some(content) => content.to_string(),
+        None => String::new(),
+    };
+
+    let mut manager = open_vault(config)?;
+    let id = manager.add_note(title, content).id.clone();
+    if let Some(note) = manager.get_note_mut(&id) {
+        note.tags = parse_tags(args.option("tags").unwrap_or(""));
+        note.notebook = args.option("notebook").unwrap_or("").trim().to_string();
+    }
+    save(&mut manager, config)?;
+    println!("{}", id);
+    Ok(())
+}
+
+fn list(args: &[String], config: &Config) -> io::Result<()> {
+    let args = Args::parse(args, &["tag", "notebook"])?;
+    if !args.positional.is_empty() {
+        return Err(invalid("list doesn't take arguments, use search to filter".to_string()));
+    }
+    let tag = args.option("tag").map(|tag| tag.trim_start_matches('#').to_lowercase());
+
+    let mut manager = open_vault(config)?;
+    let filter = NoteFilter { notebook: args.option("notebook").map(str::to_string), ..NoteFilter::default() };
+    let notes: Vec<&Note> = manager
+        .get_filtered_notes(&filter)
+        .into_iter()
+        .filter(|note| tag.as_ref().is_none_or(|tag| note.tags.contains(tag)))
+        .collect();
+    print_notes(&notes)
+}
+
+fn search(args: &[String], config: &Config) -> io::Result<()> {
+    let args = Args::parse(args, &[])?;
+    let query = args.positional.join(" ");
+    if query.trim().is_empty() {
+        return Err(invalid("missing query".to_string()));
+    }
+    let mut manager = open_vault(config)?;
+    let notes: Vec<&Note> = manager.search_notes(&query, config.behavior.search_case_sensitive, None)
+        .into_iter()
+        .map(|(note, _)| note)
+        .collect();
+    print_notes(&notes)
+}
+
+fn show(args: &[String], config: &Config) -> io::Result<()> {
+    let args = Args::parse(args, &[])?;
+    let manager = open_vault(config)?;
+    let id = find_note(&manager, args.single("note id")?)?;
+    let note = readable(manager.get_note(&id))?;
+    print!("{}", markdown::to_markdown(note));
+    Ok(())
+}
+
+// what `cat --json` prints, the note without its history and attachments
+#[derive(Serialize)]
+struct NoteJson<'a> {
+    id: &'a str,
+    title: &'a str,
+    content: &'a str,
+    created: DateTime<Utc>,
+    updated: DateTime<Utc>,
+    pinned: bool,
+    tags: &'a [String],
+    notebook: &'a str,
+}
+
+fn cat(args: &[String], config: &Config) -> io::Result<()> {
+    let args = Args::parse_flags(args, &[], &["json"])?;
+    let manager = open_vault(config)?;
+    let id = find_note_or_title(&manager, &args.positional.join(" "))?;
+    let note = readable(manager.get_note(&id))?;
+
+    let mut text = if args.flag("json") {
+        serde_json::to_string(&NoteJson {
+            id: &note.id,
+            title: &note.title,
+            content: &note.content,
+            created: note.created_at,
+            updated: note.updated_at,
+            pinned: note.pinned,
+            tags: &note.tags,
+            notebook: &note.notebook,
+        })?
+    } else {
+        note.content.clone()
+    };
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    ignore_broken_pipe(io::stdout().lock().write_all(text.as_bytes()))
+}
+
+fn export(args: &[String], config: &Config) -> io::Result<()> {
+    let args = Args::parse(args, &["format"])?;
+    let path = args.single("export path")?;
+    let format = match args.option("format").unwrap_or("json") {
+        "json" => ExportFormat::Json,
+        "markdown" | "md" => ExportFormat::Markdown,
+        "simplenote" => ExportFormat::Simplenote,
+        "csv" => ExportFormat::Csv,
+        "encrypted" => ExportFormat::Encrypted,
+        other => return Err(invalid(format!("unknown export format '{}'", other))),
+    };
+    let manager = open_vault(config)?;
+    manager.export_plaintext(crate::app::expand_home(path), format)?;
+    eprintln!("Exported to {}", path);
+    Ok(())
+}
+
+fn delete(args: &[String], config: &Config) -> io::Result<()> {
+    let args = Args::parse(args, &[])?;
+    let mut manager = open_vault(config)?;
+    let id = find_note(&manager, args.single("note id")?)?;
+    let title = manager.get_note(&id).map(|note| note.title.clone()).unwrap_or_default();
+    if !manager.delete_note(&id) {
+        return Err(invalid(format!("'{}' is already in the trash", title)));
+    }
+    save(&mut manager, config)?;
+    eprintln!("Moved '{}' to the trash", title);
+    Ok(())
+}
+
+fn sync(args: &[String], config: &Config) -> io::Result<()> {
+    Args::parse(args, &[])?;
+    let remote = chosen_vault(config)?.sync_remote;
+    if remote.trim().is_empty() {
+        return Err(invalid("no sync_remote set for this vault in the config".to_string()));
+    }
+    let mut manager = open_vault(config)?;
+    let summary = manager.sync(&remote)?;
+    eprintln!("Synced with {}: {}", remote, summary);
+    Ok(())
+}
+
+fn bundle(args: &[String], config: &Config) -> io::Result<()> {
+    let args = Args::parse(args, &[])?;
+    let path = args.single("bundle path")?;
+    let report = bundle::create(Path::new(path), config)?;
+    eprintln!(
+        "Bundled {} vault(s), {} attachment(s) and {} theme(s) into {}",
+        report.vaults, report.attachments, report.themes, path
+    );
+    if report.remote > 0 {
+        eprintln!("{} vault(s) on a server were left out, they're fetched again from there", report.remote);
+    }
+    Ok(())
+}
+
+fn restore_bundle(args: &[String]) -> io::Result<()> {
+    let args = Args::parse(args, &["to"])?;
+    let path = args.single("bundle path")?;
+    let to = match args.option("to") {
+        Some(folder) => crate::app::expand_home(folder),
+        None => Config::config_dir()?,
+    };
+    let report = bundle::restore(Path::new(path), &to)?;
+    for vault in &report.vaults {
+        eprintln!("Restored {}", vault.display());
+    }
+    eprintln!("{} attachment(s) and {} theme(s) restored", report.attachments, report.themes);
+    if let Some(backup) = report.config_backup {
+        eprintln!("The previous config is in {}", backup.display());
+    }
+    Ok(())
+}
+
+fn forget_password(config: &Config) -> io::Result<()> {
+    let vault = chosen_vault(config)?;
+    let notes_file = std::path::Path::new(&vault.notes_file);
+    if credentials::forget(notes_file)? {
+        eprintln!("Removed the password for {} from the keyring", notes_file.display());
+    } else {
+        eprintln!("No password stored for {}", notes_file.display());
+    }
+    Ok(())
+}
+
+// one note per line: id, last change and title, tab separated so it cuts and awks nicely
+fn print_notes(notes: &[&Note]) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    for note in notes {
+        let updated = note.updated_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+        ignore_broken_pipe(writeln!(out, "{}\t{}\t{}", note.id, updated, note.title))?;
+    }
+    Ok(())
+}
+
+// a closed pipe (| head) isn't an error worth reporting
+fn ignore_broken_pipe(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(0),
+        result => result,
+    }
+}
+
+// notes with their own password are only opened in the app
+fn readable(note: Option<&Note>) -> io::Result<&Note> {
+    let note = note.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "note not found"))?;
+    if note.encrypted.is_some() {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, format!("'{}' is encrypted, open it in the app", note.title)));
+    }
+    Ok(note)
+}
+
+// the full id of the note `id` is, or starts, the id of
+fn find_note(manager: &NoteManager, id: &str) -> io::Result<String> {
+    if manager.get_note(id).is_some() {
+        return Ok(id.to_string());
+    }
+    let matches: Vec<&String> = manager.note_ids().filter(|note_id| note_id.starts_with(id)).collect();
+    match matches.as_slice() {
+        [only] => Ok(only.to_string()),
+        [] => Err(io::Error::new(io::ErrorKind::NotFound, format!("no note with id '{}'", id))),
+        _ => Err(invalid(format!("'{}' matches {} notes, use more of the id", id, matches.len()))),
+    }
+}
+
+// like find_note, falling back to the (case-insensitive) title of a note outside the trash
+fn find_note_or_title(manager: &NoteManager, query: &str) -> io::Result<String> {
+    if query.trim().is_empty() {
+        return Err(invalid("missing note id or title".to_string()));
+    }
+    match find_note(manager, query) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        found => return found,
+    }
+    let wanted = query.trim().to_lowercase();
+    let matches: Vec<&String> = manager
+        .note_ids()
+        .filter(|id| manager.get_note(id).is_some_and(|note| !note.is_trashed() && note.title.to_lowercase() == wanted))
+        .collect();
+    match matches.as_slice() {
+        [only] => Ok(only.to_string()),
+        [] => Err(io::Error::new(io::ErrorKind::NotFound, format!("no note with the id or title '{}'", query))),
+        _ => Err(invalid(format!("{} notes are titled '{}', use the id", matches.len(), query))),
+    }
+}
+
+// the vault named in the environment or default_vault, the notes file from [behavior] without either
+fn chosen_vault(config: &Config) -> io::Result<VaultConfig> {
+    let name = std::env::var(VAULT_ENV).ok().filter(|name| !name.is_empty())
+        .or_else(|| Some(config.behavior.default_vault.clone()).filter(|name| !name.is_empty()));
+    config.vault(name.as_deref())
+}
+
+// the vault from the config, unlocked and loaded. commands don't take the vault lock, they're
+// over in a moment and a running app picks up what they wrote as an outside change
+fn open_vault(config: &Config) -> io::Result<NoteManager> {
+    let vault = chosen_vault(config)?;
+    if vault.remote {
+        crate::sync::fetch_remote_vault(Path::new(&vault.notes_file), &vault.sync_remote)?;
+    }
+    let mut manager = NoteManager::new(&vault.notes_file, vault.encryption_enabled)?;
+    // add and delete then fail when they save
+    manager.set_read_only(config.session.read_only);
+    manager.set_history_limit(config.behavior.history_revisions);
+    manager.set_change_journal(config.behavior.change_journal);
+    manager.set_keyfile(vault.keyfile_path());
+    manager.set_sort_mode(config.behavior.sort_mode);
+    if manager.is_encrypted() {
+        // the environment variable still wins over a remembered password
+        let remembered = config.behavior.remember_password && std::env::var_os(PASSWORD_ENV).is_none();
+        let password = match remembered.then(|| credentials::load(manager.notes_file())) {
+            Some(Ok(Some(password))) => password,
+            _ => read_password(!config.session.no_encryption_prompt)?,
+        };
+        manager.unlock_encryption(password.expose_secret())?;
+    }
+    manager.wait_for_loading()?;
+    sync_remote_vault(&mut manager, config);
+    Ok(manager)
+}
+
+fn save(manager: &mut NoteManager, config: &Config) -> io::Result<()> {
+    manager.save_notes()?;
+    manager.flush_saves()?;
+    sync_remote_vault(manager, config);
+    Ok(())
+}
+
+// a vault on a server is synced before a command reads it and after one changes it. without the
+// server the local copy is used, the changes go across the next time
+fn sync_remote_vault(manager: &mut NoteManager, config: &Config) {
+    let Ok(vault) = chosen_vault(config) else { return };
+    if !vault.remote || manager.is_read_only() {
+        return;
+    }
+    if let Err(e) = manager.sync(&vault.sync_remote) {
+        eprintln!("tui-notes: working on the local copy, syncing with the server failed: {}", e);
+    }
+}
+
+fn read_password(prompt: bool) -> io::Result<SecretString> {
+    if let Ok(password) = std::env::var(PASSWORD_ENV) {
+        return Ok(SecretString::new(password.into()));
+    }
+    if !prompt {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("the vault is encrypted and there's no prompt, set {} or remember the password", PASSWORD_ENV),
+        ));
+    }
+    eprint!("Password: ");
+    io::stderr().flush()?;
+    // raw mode so the password isn't echoed. crossterm reads from the terminal even when
+    // stdin is a pipe, so `add --content -` still works
+    enable_raw_mode()?;
+    let password = read_secret_line();
+    disable_raw_mode()?;
+    eprintln!();
+    password
+}
+
+fn read_secret_line() -> io::Result<SecretString> {
+    // room for the longest password, so typing never reallocates and leaves copies behind
+    let mut password = Zeroizing::new(String::with_capacity(MAX_PASSWORD_LENGTH + 4));
+    loop {
+        let Event::Key(key) = event::read()? else { continue };
+        match key.code {
+            KeyCode::Enter => return Ok(SecretString::new(Box::from(password.as_str()))),
+            KeyCode::Esc => return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled")),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            KeyCode::Backspace => {
+                password.pop();
+            }
+            KeyCode::Char(c) if password.len() < MAX_PASSWORD_LENGTH => password.push(c),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+// clipboard tools to try, in order, for the current platform and display server
+fn copy_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        return vec![("pbcopy", &[])];
+    }
+    if cfg!(windows) {
+        return vec![("clip", &[])];
+    }
+    let mut commands: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        commands.push(("wl-copy", &[]));
+    }
+    if std::env::var_os("DISPLAY").is_some() {
+        commands.push(("xclip", &["-selection", "clipboard"]));
+        commands.push(("xsel", &["--clipboard", "--input"]));
+    }
+    commands
+}
+
+fn paste_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        return vec![("pbpaste", &[])];
+    }
+    if cfg!(windows) {
+        return vec![("powershell", &["-NoProfile", "-Command", "Get-Clipboard -Raw"])];
+    }
+    let mut commands: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        commands.push(("wl-paste", &["--no-newline"]));
+    }
+    if std::env::var_os("DISPLAY").is_some() {
+        commands.push(("xclip", &["-selection", "clipboard", "-o"]));
+        commands.push(("xsel", &["--clipboard", "--output"]));
+    }
+    commands
+}
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child.stdin.take().ok_or_else(|| io::Error::other("no stdin"))?.write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("'{}' exited with {}", program, status)));
+    }
+    Ok(())
+}
+
+fn read_from(program: &str, args: &[&str]) -> io::Result<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut text = String::new();
+    child.stdout.take().ok_or_else(|| io::Error::other("no stdout"))?.read_to_string(&mut text)?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("'{}' exited with {}", program, status)));
+    }
+    Ok(text)
+}
+
+// OSC 52 asks the terminal itself to set the clipboard, works over ssh and without a display server
+// as long as the terminal supports it
+fn copy_osc52(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
+
+// returns what the text went through, for the status line
+pub fn copy(text: &str) -> io::Result<&'static str> {
+    for (program, args) in copy_commands() {
+        if pipe_to(program, args, text).is_ok() {
+            return Ok(program);
+        }
+    }
+    copy_osc52(text)?;
+    Ok("the terminal (OSC 52)")
+}
+
+// terminals don't hand the clipboard back over OSC 52 reliably, so without a clipboard tool
+// the terminal's own paste is the way in
+pub fn paste() -> io::Result<String> {
+    let mut last_error = None;
+    for (program, args) in paste_commands() {
+        match read_from(program, args) {
+            Ok(text) => return Ok(text.replace("\r\n", "\n")),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no clipboard tool found, use your terminal's paste")))
+}
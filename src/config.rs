@@ -1,12 +1,19 @@
-use crossterm::event::{KeyCode, KeyModifiers};
-use ratatui::style::Color;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::style::{Color, Modifier, Style};
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+use crate::fs_util::set_secure_permissions;
+use crate::note::SortMode;
+use crate::saver::DiskState;
+use crate::theme;
 
-#[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
+// set by --config, the file in the config directory otherwise
+static CONFIG_FILE: OnceLock<PathBuf> = OnceLock::new();
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -14,11 +21,55 @@ pub struct Config {
     pub keybindings: KeyBindings,
     pub colors: ColorTheme,
     pub behavior: Behavior,
+    // named vaults, [vaults.work] and so on. without any the notes file from [behavior] is used
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub vaults: BTreeMap<String, VaultConfig>,
+    // settings from the file that couldn't be used and were reset to their defaults on load
+    #[serde(skip)]
+    pub invalid_settings: Vec<String>,
+    // what the command line overrides for this run, never written to the file
+    #[serde(skip)]
+    pub session: Session,
+}
+
+// --notes-file, --read-only and --no-encryption-prompt
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    // this file instead of any vault from the config
+    pub notes_file: Option<String>,
+    // nothing is written to the vault, it isn't locked either
+    pub read_only: bool,
+    // fail instead of asking for a password nobody is there to type
+    pub no_encryption_prompt: bool,
+}
+
+// where a vault's notes live and how they're protected
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VaultConfig {
+    pub notes_file: String,
+    pub encryption_enabled: bool,
+    // a file whose contents are needed along with the password, empty for none
+    pub keyfile: String,
+    // where the vault is synced to, empty for nowhere, see sync.rs
+    pub sync_remote: String,
+    // notes_file was an sftp:// url and is now the local copy, synced when the vault opens and closes
+    #[serde(skip)]
+    pub remote: bool,
+}
+
+impl VaultConfig {
+    pub fn keyfile_path(&self) -> Option<PathBuf> {
+        let keyfile = self.keyfile.trim();
+        (!keyfile.is_empty()).then(|| crate::app::expand_home(keyfile))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct KeyBindings {
+    // the preset the bindings start from, anything set below changes single keys of it
+    pub profile: KeymapProfile,
     #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
     pub quit: KeyBinding,
     #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
@@ -55,6 +106,8 @@ pub struct KeyBindings {
     pub search_select: KeyBinding,
     #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
     pub search_view: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub search_edit: KeyBinding,
     #[serde(serialize_with = "serialize_keybinding_vec", deserialize_with = "deserialize_keybinding_vec")]
     pub confirm_delete: Vec<KeyBinding>,
     #[serde(serialize_with = "serialize_keybinding_vec", deserialize_with = "deserialize_keybinding_vec")]
@@ -68,16 +121,197 @@ pub struct KeyBindings {
     #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
     pub toggle_highlighting: KeyBinding,
     #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_line_numbers: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
     pub toggle_pin: KeyBinding,
     #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_favorite: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_favorites_filter: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
     pub toggle_help: KeyBinding,
     #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
     pub manual_save: KeyBinding,
     #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
     pub export_plaintext: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub show_about: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub import_json: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub show_statistics: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub empty_trash: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub save_as: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub quick_capture: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub show_notebooks: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub move_to_notebook: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub show_trash: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub restore_note: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub undo: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub redo: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub show_history: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub show_backups: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_archive: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub show_archive: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_todo: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_mark: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub add_tags: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub cycle_sort: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub move_note_up: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub move_note_down: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub add_attachment: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub open_attachment: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub remove_attachment: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_lock: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub export_note: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub external_editor: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub editor_undo: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub editor_redo: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub find_in_note: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub replace_all: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub search_in_note: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub next_match: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub previous_match: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_wrap: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub spelling_suggestions: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_split_view: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub shrink_list: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub grow_list: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub next_tab: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub previous_tab: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub new_tab: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub close_tab: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub choose_theme: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub show_settings: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_relative_time: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_search_case: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub widen_search: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub quick_switch: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub change_password: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub encrypt_note: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub switch_vault: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub sync_vault: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub show_journal: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub toggle_vault_encryption: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding_vec", deserialize_with = "deserialize_keybinding_vec")]
+    pub jump_to_top: Vec<KeyBinding>,
+    #[serde(serialize_with = "serialize_keybinding_vec", deserialize_with = "deserialize_keybinding_vec")]
+    pub jump_to_bottom: Vec<KeyBinding>,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub copy_to_clipboard: KeyBinding,
+    #[serde(serialize_with = "serialize_keybinding", deserialize_with = "deserialize_keybinding")]
+    pub paste_from_clipboard: KeyBinding,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// comfortable shows a preview and timestamps under each title, compact only the title line
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListDensity {
+    Compact,
+    #[default]
+    Comfortable,
+}
+
+// how much goes into the log file, each level includes the ones above it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    #[default]
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+// which timestamp the note list shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListTimestamp {
+    #[default]
+    Updated,
+    Created,
+    Both,
+    None,
+}
+
+// the keymap presets, vim and emacs move other keys into place across the list, viewer and editor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeymapProfile {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+impl KeymapProfile {
+    fn name(self) -> &'static str {
+        match self {
+            KeymapProfile::Default => "default",
+            KeymapProfile::Vim => "vim",
+            KeymapProfile::Emacs => "emacs",
+        }
+    }
+}
+
+// a key, e.g. "n", "Enter" or "Ctrl+s", or a sequence of them pressed one after the other,
+// e.g. "g g" or "Space f s". ctrl/alt/shift go with the (last) key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct KeyBinding {
     pub key: String,
     #[serde(default, skip_serializing_if = "is_false")]
@@ -86,11 +320,16 @@ pub struct KeyBinding {
     pub alt: bool,
     #[serde(default, skip_serializing_if = "is_false")]
     pub shift: bool,
+    // a letter matches in either case, e.g. "y" for both y and Y
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub case_insensitive: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct ColorTheme {
+    // a built-in theme or a file in the themes folder, the entries below change single colors of it
+    pub theme: String,
     #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
     pub title_bar: ColorConfig,
     #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
@@ -111,6 +350,12 @@ pub struct ColorTheme {
     pub help_text: ColorConfig,
     #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
     pub delete_dialog_border: ColorConfig,
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub misspelled: ColorConfig,
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub diff_added: ColorConfig,
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub diff_removed: ColorConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -119,6 +364,9 @@ pub struct ColorConfig {
     pub fg: String,
     #[serde(default = "default_color", skip_serializing_if = "is_reset")]
     pub bg: String,
+    // bold, italic, underlined, dim, reversed or crossed_out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -126,6 +374,9 @@ pub struct ColorConfig {
 pub struct Behavior {
     pub default_notes_file: String,
     pub auto_save: bool,
+    pub auto_save_delay_ms: u64,
+    // above 0, save at most this often while typing instead of after every pause, 0 for off
+    pub auto_save_interval_secs: u64,
     pub search_case_sensitive: bool,
     pub confirm_delete: bool,
     pub max_events_per_frame: usize,
@@ -133,7 +384,64 @@ pub struct Behavior {
     pub show_line_numbers: bool,
     pub highlighting_enabled: bool,
     pub encryption_enabled: bool,
+    // keep the vault password in the system keyring and unlock without asking
+    pub remember_password: bool,
+    // a file whose contents are needed along with the password, empty for none
+    pub keyfile: String,
+    // where the vault from default_notes_file is synced to, empty for nowhere
+    pub sync_remote: String,
+    // with [vaults] set up, the one the commands use and the picker starts on
+    pub default_vault: String,
     pub use_native_dialog: bool,
+    pub spellcheck: bool,
+    pub spellcheck_dictionary: String,
+    // e.g. "de_DE", picks a dictionary from spellcheck_dictionaries or the system's hunspell ones
+    pub spellcheck_language: String,
+    pub spellcheck_dictionaries: BTreeMap<String, String>,
+    pub trash_retention_days: u32,
+    pub daily_note_title_format: String,
+    pub preview_skip_blank: bool,
+    pub preview_skip_heading: bool,
+    pub list_density: ListDensity,
+    pub preview_lines: usize,
+    pub preview_length: usize,
+    pub list_timestamp: ListTimestamp,
+    pub undo_depth: usize,
+    pub history_revisions: usize,
+    // append every saved change to notes.json.changes, encrypted along with the vault
+    pub change_journal: bool,
+    pub backup_interval_minutes: u64,
+    pub backup_keep: usize,
+    pub backup_dir: String,
+    pub quick_capture_timestamp_format: String,
+    pub sort_mode: SortMode,
+    // command for editing a note outside the app, empty uses $VISUAL, then $EDITOR, then vi
+    pub external_editor: String,
+    // normal/insert/visual modes in the content editor
+    pub vim_mode: bool,
+    // edits the editor can undo, per field and for as long as the note stays open
+    pub editor_undo_depth: usize,
+    // wrap long lines at word boundaries in the editor and viewer instead of cutting them off
+    pub soft_wrap: bool,
+    // with line numbers on, count away from the cursor's line instead
+    pub relative_line_numbers: bool,
+    // show the selected note next to the list
+    pub split_view: bool,
+    // how much of the width the list gets in the split view, in percent
+    pub split_ratio: u16,
+    // one line at the bottom with the mode, vault, note count, cursor position and last save
+    pub show_status_bar: bool,
+    // "3h ago" / "yesterday" in the list instead of dates
+    pub relative_timestamps: bool,
+    // strftime format for dates in the list, in local time
+    pub list_date_format: String,
+    // nerd font glyphs for note and vault states instead of ascii markers
+    pub use_icons: bool,
+    pub log_level: LogLevel,
+    // empty logs to tui-notes.log in the config directory
+    pub log_file: String,
+    // how long a key sequence ("g g") waits for its next key before the keys count on their own
+    pub sequence_timeout_ms: u64,
 }
 
 impl Default for Config {
@@ -142,6 +450,9 @@ impl Default for Config {
             keybindings: KeyBindings::default(),
             colors: ColorTheme::default(),
             behavior: Behavior::default(),
+            vaults: BTreeMap::new(),
+            invalid_settings: Vec::new(),
+            session: Session::default(),
         }
     }
 }
@@ -149,6 +460,7 @@ impl Default for Config {
 impl Default for KeyBindings {
     fn default() -> Self {
         KeyBindings {
+            profile: KeymapProfile::Default,
             quit: KeyBinding::new("q"),
             create_note: KeyBinding::new("n"),
             edit_note: KeyBinding::new("Enter"),
@@ -167,16 +479,80 @@ impl Default for KeyBindings {
             exit_search: KeyBinding::new("Esc"),
             search_select: KeyBinding::new("Enter"),
             search_view: KeyBinding::new("v"),
+            search_edit: KeyBinding { key: "o".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
             confirm_delete: vec![KeyBinding::new("y"), KeyBinding::new("Y")],
             cancel_delete: vec![KeyBinding::new("n"), KeyBinding::new("N"), KeyBinding::new("Esc")],
             save_and_exit_unsaved: vec![KeyBinding::new("s"), KeyBinding::new("S")],
             discard_and_exit: vec![KeyBinding::new("d"), KeyBinding::new("D")],
             cancel_exit: vec![KeyBinding::new("c"), KeyBinding::new("C"), KeyBinding::new("Esc")],
-            toggle_highlighting: KeyBinding { key: "h".to_string(), ctrl: true, alt: false, shift: false },
+            toggle_highlighting: KeyBinding { key: "h".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            toggle_line_numbers: KeyBinding { key: "l".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
             toggle_pin: KeyBinding::new("p"),
+            toggle_favorite: KeyBinding::new("f"),
+            toggle_favorites_filter: KeyBinding::new("*"),
             toggle_help: KeyBinding::new("F5"),
-            manual_save: KeyBinding { key: "s".to_string(), ctrl: true, alt: false, shift: false },
-            export_plaintext: KeyBinding { key: "e".to_string(), ctrl: true, alt: false, shift: false },
+            manual_save: KeyBinding { key: "s".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            export_plaintext: KeyBinding { key: "e".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            show_about: KeyBinding::new("a"),
+            import_json: KeyBinding::new("i"),
+            show_statistics: KeyBinding::new("s"),
+            empty_trash: KeyBinding { key: "t".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            save_as: KeyBinding::new("w"),
+            quick_capture: KeyBinding::new("c"),
+            show_notebooks: KeyBinding::new("b"),
+            move_to_notebook: KeyBinding::new("m"),
+            show_trash: KeyBinding::new("t"),
+            restore_note: KeyBinding::new("r"),
+            undo: KeyBinding::new("u"),
+            redo: KeyBinding { key: "r".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            show_history: KeyBinding::new("h"),
+            show_backups: KeyBinding { key: "b".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            toggle_archive: KeyBinding::new("x"),
+            show_archive: KeyBinding { key: "x".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            toggle_todo: KeyBinding::new("x"),
+            toggle_mark: KeyBinding::new("Space"),
+            add_tags: KeyBinding::new("#"),
+            cycle_sort: KeyBinding::new("o"),
+            move_note_up: KeyBinding { key: "Up".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            move_note_down: KeyBinding { key: "Down".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            add_attachment: KeyBinding::new("a"),
+            open_attachment: KeyBinding::new("o"),
+            remove_attachment: KeyBinding::new("d"),
+            toggle_lock: KeyBinding::new("l"),
+            export_note: KeyBinding { key: "e".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            external_editor: KeyBinding { key: "g".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            editor_undo: KeyBinding { key: "z".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            editor_redo: KeyBinding { key: "y".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            find_in_note: KeyBinding { key: "f".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            replace_all: KeyBinding { key: "a".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            search_in_note: KeyBinding::new("/"),
+            next_match: KeyBinding::new("n"),
+            previous_match: KeyBinding { key: "N".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false },
+            toggle_wrap: KeyBinding { key: "z".to_string(), ctrl: false, alt: true, shift: false, case_insensitive: false },
+            spelling_suggestions: KeyBinding { key: "s".to_string(), ctrl: false, alt: true, shift: false, case_insensitive: false },
+            copy_to_clipboard: KeyBinding { key: "c".to_string(), ctrl: false, alt: true, shift: false, case_insensitive: false },
+            paste_from_clipboard: KeyBinding { key: "v".to_string(), ctrl: false, alt: true, shift: false, case_insensitive: false },
+            toggle_split_view: KeyBinding::new("|"),
+            shrink_list: KeyBinding::new("<"),
+            grow_list: KeyBinding::new(">"),
+            next_tab: KeyBinding { key: "Right".to_string(), ctrl: false, alt: true, shift: false, case_insensitive: false },
+            previous_tab: KeyBinding { key: "Left".to_string(), ctrl: false, alt: true, shift: false, case_insensitive: false },
+            new_tab: KeyBinding { key: "t".to_string(), ctrl: false, alt: true, shift: false, case_insensitive: false },
+            close_tab: KeyBinding { key: "w".to_string(), ctrl: false, alt: true, shift: false, case_insensitive: false },
+            choose_theme: KeyBinding { key: "T".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false },
+            show_settings: KeyBinding { key: "S".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false },
+            toggle_relative_time: KeyBinding { key: "R".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false },
+            toggle_search_case: KeyBinding { key: "i".to_string(), ctrl: false, alt: true, shift: false, case_insensitive: false },
+            widen_search: KeyBinding { key: "a".to_string(), ctrl: false, alt: true, shift: false, case_insensitive: false },
+            quick_switch: KeyBinding { key: "p".to_string(), ctrl: true, alt: false, shift: false, case_insensitive: false },
+            change_password: KeyBinding { key: "P".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false },
+            encrypt_note: KeyBinding { key: "E".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false },
+            switch_vault: KeyBinding { key: "V".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false },
+            sync_vault: KeyBinding { key: "Y".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false },
+            show_journal: KeyBinding { key: "H".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false },
+            toggle_vault_encryption: KeyBinding { key: "K".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false },
+            jump_to_top: vec![KeyBinding::new("Home")],
+            jump_to_bottom: vec![KeyBinding::new("End"), KeyBinding { key: "G".to_string(), ctrl: false, alt: false, shift: true, case_insensitive: false }],
         }
     }
 }
@@ -184,16 +560,20 @@ impl Default for KeyBindings {
 impl Default for ColorTheme {
     fn default() -> Self {
         ColorTheme {
-            title_bar: ColorConfig { fg: "Cyan".to_string(), bg: "Reset".to_string() },
-            border_active: ColorConfig { fg: "Yellow".to_string(), bg: "Reset".to_string() },
-            border_inactive: ColorConfig { fg: "White".to_string(), bg: "Reset".to_string() },
-            text: ColorConfig { fg: "White".to_string(), bg: "Reset".to_string() },
-            text_secondary: ColorConfig { fg: "Gray".to_string(), bg: "Reset".to_string() },
-            text_highlight: ColorConfig { fg: "White".to_string(), bg: "Reset".to_string() },
-            background_selected: ColorConfig { fg: "Reset".to_string(), bg: "DarkGray".to_string() },
-            search_border: ColorConfig { fg: "Cyan".to_string(), bg: "Reset".to_string() },
-            help_text: ColorConfig { fg: "Yellow".to_string(), bg: "Reset".to_string() },
-            delete_dialog_border: ColorConfig { fg: "Red".to_string(), bg: "DarkGray".to_string() },
+            theme: String::new(),
+            title_bar: ColorConfig { fg: "Cyan".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
+            border_active: ColorConfig { fg: "Yellow".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
+            border_inactive: ColorConfig { fg: "White".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
+            text: ColorConfig { fg: "White".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
+            text_secondary: ColorConfig { fg: "Gray".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
+            text_highlight: ColorConfig { fg: "White".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
+            background_selected: ColorConfig { fg: "Reset".to_string(), bg: "DarkGray".to_string(), modifiers: Vec::new() },
+            search_border: ColorConfig { fg: "Cyan".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
+            help_text: ColorConfig { fg: "Yellow".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
+            delete_dialog_border: ColorConfig { fg: "Red".to_string(), bg: "DarkGray".to_string(), modifiers: Vec::new() },
+            misspelled: ColorConfig { fg: "LightRed".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
+            diff_added: ColorConfig { fg: "Green".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
+            diff_removed: ColorConfig { fg: "Red".to_string(), bg: "Reset".to_string(), modifiers: Vec::new() },
         }
     }
 }
@@ -207,6 +587,8 @@ impl Default for Behavior {
         Behavior {
             default_notes_file,
             auto_save: true,
+            auto_save_delay_ms: 500,
+            auto_save_interval_secs: 0,
             search_case_sensitive: false,
             confirm_delete: true,
             max_events_per_frame: 50,
@@ -214,11 +596,216 @@ impl Default for Behavior {
             show_line_numbers: false,
             highlighting_enabled: true,
             encryption_enabled: false,
+            remember_password: false,
+            keyfile: String::new(),
+            sync_remote: String::new(),
+            default_vault: String::new(),
             use_native_dialog: true,
+            spellcheck: false,
+            spellcheck_dictionary: "/usr/share/dict/words".to_string(),
+            spellcheck_language: String::new(),
+            spellcheck_dictionaries: BTreeMap::new(),
+            trash_retention_days: 30,
+            daily_note_title_format: "%Y-%m-%d".to_string(),
+            preview_skip_blank: true,
+            preview_skip_heading: false,
+            list_density: ListDensity::Comfortable,
+            preview_lines: 1,
+            preview_length: 50,
+            list_timestamp: ListTimestamp::Updated,
+            undo_depth: 50,
+            history_revisions: 20,
+            change_journal: false,
+            backup_interval_minutes: 60,
+            backup_keep: 10,
+            backup_dir: String::new(),
+            quick_capture_timestamp_format: "%H:%M".to_string(),
+            sort_mode: SortMode::Updated,
+            external_editor: String::new(),
+            vim_mode: false,
+            editor_undo_depth: 100,
+            soft_wrap: true,
+            relative_line_numbers: false,
+            split_view: false,
+            split_ratio: 40,
+            show_status_bar: true,
+            relative_timestamps: false,
+            list_date_format: "%Y-%m-%d %H:%M".to_string(),
+            use_icons: false,
+            log_level: LogLevel::Warn,
+            log_file: String::new(),
+            sequence_timeout_ms: 1000,
         }
     }
 }
 
+impl KeyBindings {
+    // the defaults of a profile, before anything from the file goes on top
+    pub fn for_profile(profile: KeymapProfile) -> Self {
+        let mut kb = KeyBindings { profile, ..KeyBindings::default() };
+        match profile {
+            KeymapProfile::Default => {}
+            KeymapProfile::Vim => {
+                kb.move_up = KeyBinding::new("k");
+                kb.move_down = KeyBinding::new("j");
+                kb.page_up = KeyBinding::new("Ctrl+u");
+                kb.page_down = KeyBinding::new("Ctrl+d");
+                kb.delete_note = KeyBinding::new("d d");
+                kb.remove_attachment = KeyBinding::new("Shift+D");
+                kb.jump_to_top = vec![KeyBinding::new("Home"), KeyBinding::new("g g")];
+            }
+            KeymapProfile::Emacs => {
+                kb.move_up = KeyBinding::new("Ctrl+p");
+                kb.move_down = KeyBinding::new("Ctrl+n");
+                kb.page_up = KeyBinding::new("Alt+v");
+                kb.page_down = KeyBinding::new("Ctrl+v");
+                kb.search_notes = KeyBinding::new("Ctrl+s");
+                kb.search_in_note = KeyBinding::new("Ctrl+s");
+                kb.quick_switch = KeyBinding::new("Ctrl+x b");
+                kb.close_tab = KeyBinding::new("Ctrl+x k");
+                kb.jump_to_top = vec![KeyBinding::new("Home"), KeyBinding::new("Alt+<")];
+                kb.jump_to_bottom = vec![KeyBinding::new("End"), KeyBinding::new("Alt+>")];
+                // ctrl+f and ctrl+a move the cursor in the editor
+                kb.manual_save = KeyBinding::new("Ctrl+x Ctrl+s");
+                kb.find_in_note = KeyBinding::new("Ctrl+s");
+                kb.replace_all = KeyBinding::new("Alt+r");
+            }
+        }
+        kb
+    }
+
+    // every binding with the name it has in the config, a list gives one entry per key
+    pub fn entries(&self) -> Vec<(&'static str, &KeyBinding)> {
+        let mut entries = vec![
+            ("quit", &self.quit),
+            ("create_note", &self.create_note),
+            ("edit_note", &self.edit_note),
+            ("view_note", &self.view_note),
+            ("delete_note", &self.delete_note),
+            ("search_notes", &self.search_notes),
+            ("move_up", &self.move_up),
+            ("move_down", &self.move_down),
+            ("save_and_exit", &self.save_and_exit),
+            ("switch_field", &self.switch_field),
+            ("title_to_content", &self.title_to_content),
+            ("edit_from_view", &self.edit_from_view),
+            ("return_to_list", &self.return_to_list),
+            ("page_up", &self.page_up),
+            ("page_down", &self.page_down),
+            ("exit_search", &self.exit_search),
+            ("search_select", &self.search_select),
+            ("search_view", &self.search_view),
+            ("search_edit", &self.search_edit),
+            ("toggle_highlighting", &self.toggle_highlighting),
+            ("toggle_line_numbers", &self.toggle_line_numbers),
+            ("toggle_pin", &self.toggle_pin),
+            ("toggle_favorite", &self.toggle_favorite),
+            ("toggle_favorites_filter", &self.toggle_favorites_filter),
+            ("toggle_help", &self.toggle_help),
+            ("manual_save", &self.manual_save),
+            ("export_plaintext", &self.export_plaintext),
+            ("show_about", &self.show_about),
+            ("import_json", &self.import_json),
+            ("show_statistics", &self.show_statistics),
+            ("empty_trash", &self.empty_trash),
+            ("save_as", &self.save_as),
+            ("quick_capture", &self.quick_capture),
+            ("show_notebooks", &self.show_notebooks),
+            ("move_to_notebook", &self.move_to_notebook),
+            ("show_trash", &self.show_trash),
+            ("restore_note", &self.restore_note),
+            ("undo", &self.undo),
+            ("redo", &self.redo),
+            ("show_history", &self.show_history),
+            ("show_backups", &self.show_backups),
+            ("toggle_archive", &self.toggle_archive),
+            ("show_archive", &self.show_archive),
+            ("toggle_todo", &self.toggle_todo),
+            ("toggle_mark", &self.toggle_mark),
+            ("add_tags", &self.add_tags),
+            ("cycle_sort", &self.cycle_sort),
+            ("move_note_up", &self.move_note_up),
+            ("move_note_down", &self.move_note_down),
+            ("add_attachment", &self.add_attachment),
+            ("open_attachment", &self.open_attachment),
+            ("remove_attachment", &self.remove_attachment),
+            ("toggle_lock", &self.toggle_lock),
+            ("export_note", &self.export_note),
+            ("external_editor", &self.external_editor),
+            ("editor_undo", &self.editor_undo),
+            ("editor_redo", &self.editor_redo),
+            ("find_in_note", &self.find_in_note),
+            ("replace_all", &self.replace_all),
+            ("search_in_note", &self.search_in_note),
+            ("next_match", &self.next_match),
+            ("previous_match", &self.previous_match),
+            ("toggle_wrap", &self.toggle_wrap),
+            ("spelling_suggestions", &self.spelling_suggestions),
+            ("toggle_split_view", &self.toggle_split_view),
+            ("shrink_list", &self.shrink_list),
+            ("grow_list", &self.grow_list),
+            ("next_tab", &self.next_tab),
+            ("previous_tab", &self.previous_tab),
+            ("new_tab", &self.new_tab),
+            ("close_tab", &self.close_tab),
+            ("choose_theme", &self.choose_theme),
+            ("show_settings", &self.show_settings),
+            ("toggle_relative_time", &self.toggle_relative_time),
+            ("toggle_search_case", &self.toggle_search_case),
+            ("widen_search", &self.widen_search),
+            ("quick_switch", &self.quick_switch),
+            ("change_password", &self.change_password),
+            ("encrypt_note", &self.encrypt_note),
+            ("switch_vault", &self.switch_vault),
+            ("sync_vault", &self.sync_vault),
+            ("show_journal", &self.show_journal),
+            ("toggle_vault_encryption", &self.toggle_vault_encryption),
+            ("copy_to_clipboard", &self.copy_to_clipboard),
+            ("paste_from_clipboard", &self.paste_from_clipboard),
+        ];
+        let lists = [
+            ("confirm_delete", &self.confirm_delete),
+            ("cancel_delete", &self.cancel_delete),
+            ("save_and_exit_unsaved", &self.save_and_exit_unsaved),
+            ("discard_and_exit", &self.discard_and_exit),
+            ("cancel_exit", &self.cancel_exit),
+            ("jump_to_top", &self.jump_to_top),
+            ("jump_to_bottom", &self.jump_to_bottom),
+        ];
+        for (name, bindings) in lists {
+            entries.extend(bindings.iter().map(|binding| (name, binding)));
+        }
+        entries
+    }
+
+    // the bindings that take more than one key
+    pub fn sequences(&self) -> Vec<&KeyBinding> {
+        self.entries().into_iter().map(|(_, binding)| binding).filter(|binding| binding.is_sequence()).collect()
+    }
+
+    // every binding's name once, in the order of entries
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self.entries().into_iter().map(|(name, _)| name).collect();
+        names.dedup();
+        names
+    }
+
+    // put a key in place of what the binding has, a list gets just that key
+    pub fn rebind(&mut self, name: &str, key: &str) -> io::Result<()> {
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, format!("couldn't rebind {}: {}", name, e));
+        let mut value = toml::Value::try_from(&*self).map_err(|e| invalid(e.to_string()))?;
+        let Some(table) = value.as_table_mut() else { return Err(invalid("not a table".to_string())) };
+        let entry = match table.get(name) {
+            Some(toml::Value::Array(_)) => toml::Value::Array(vec![toml::Value::String(key.to_string())]),
+            Some(_) => toml::Value::String(key.to_string()),
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, format!("no key binding named '{}'", name))),
+        };
+        table.insert(name.to_string(), entry);
+        *self = value.try_into().map_err(|e: toml::de::Error| invalid(e.to_string()))?;
+        Ok(())
+    }
+}
+
 impl KeyBinding {
     pub fn new(key: &str) -> Self {
         KeyBinding {
@@ -226,25 +813,131 @@ impl KeyBinding {
             ctrl: false,
             alt: false,
             shift: false,
+            case_insensitive: false,
+        }
+    }
+
+
+    // the pressed key the compact way, e.g. "Ctrl+x", "G" or "Space". None for a key no binding
+    // can name
+    pub fn from_key(key: &KeyEvent) -> Option<KeyBinding> {
+        let name = match key.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::SHIFT) => uppercase(c).to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) if (1..=12).contains(&n) => format!("F{}", n),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab | KeyCode::BackTab => "Tab".to_string(),
+            KeyCode::Backspace => "Backspace".to_string(),
+            KeyCode::Delete => "Delete".to_string(),
+            KeyCode::Up => "Up".to_string(),
+            KeyCode::Down => "Down".to_string(),
+            KeyCode::Left => "Left".to_string(),
+            KeyCode::Right => "Right".to_string(),
+            KeyCode::PageUp => "PageUp".to_string(),
+            KeyCode::PageDown => "PageDown".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            _ => return None,
+        };
+        let mut compact = String::new();
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            compact.push_str("Ctrl+");
+        }
+        if key.modifiers.contains(KeyModifiers::ALT) {
+            compact.push_str("Alt+");
         }
+        // a character shows shift itself
+        if (key.modifiers.contains(KeyModifiers::SHIFT) && !matches!(key.code, KeyCode::Char(_))) || key.code == KeyCode::BackTab {
+            compact.push_str("Shift+");
+        }
+        compact.push_str(&name);
+        Some(KeyBinding::new(&compact))
     }
 
+    pub fn is_sequence(&self) -> bool {
+        self.key.split_whitespace().nth(1).is_some()
+    }
 
+    // the single keys of a sequence, a plain binding is its own only step
+    pub fn steps(&self) -> Vec<KeyBinding> {
+        if !self.is_sequence() {
+            return vec![self.clone()];
+        }
+        let mut steps: Vec<KeyBinding> = self.key
+            .split_whitespace()
+            .map(|key| KeyBinding { case_insensitive: self.case_insensitive, ..KeyBinding::new(key) })
+            .collect();
+        if let Some(last) = steps.last_mut() {
+            last.ctrl |= self.ctrl;
+            last.alt |= self.alt;
+            last.shift |= self.shift;
+        }
+        steps
+    }
+
+    // true for a key held with ctrl or alt, which is never typed text
+    pub fn is_chord(&self) -> bool {
+        let (_, ctrl, alt, _) = self.parts();
+        ctrl || alt
+    }
+
+    // the key and its ctrl, alt and shift, "Ctrl+s" is the same as key = "s" with ctrl = true
+    fn parts(&self) -> (&str, bool, bool, bool) {
+        let (mut key, mut ctrl, mut alt, mut shift) = (self.key.as_str(), self.ctrl, self.alt, self.shift);
+        loop {
+            if let Some(rest) = key.strip_prefix("Ctrl+").filter(|rest| !rest.is_empty()) {
+                (key, ctrl) = (rest, true);
+            } else if let Some(rest) = key.strip_prefix("Alt+").filter(|rest| !rest.is_empty()) {
+                (key, alt) = (rest, true);
+            } else if let Some(rest) = key.strip_prefix("Shift+").filter(|rest| !rest.is_empty()) {
+                (key, shift) = (rest, true);
+            } else {
+                break;
+            }
+        }
+        (key, ctrl, alt, shift)
+    }
+
+    // a sequence only matches while App hands over the one just finished, see finish_sequence
     pub fn matches(&self, key_code: KeyCode, modifiers: KeyModifiers) -> bool {
-        let expected_modifiers = KeyModifiers::from_bits_truncate(
-            (if self.ctrl { KeyModifiers::CONTROL.bits() } else { 0 }) |
-            (if self.alt { KeyModifiers::ALT.bits() } else { 0 }) |
-            (if self.shift { KeyModifiers::SHIFT.bits() } else { 0 })
-        );
+        if self.is_sequence() {
+            return key_code == KeyCode::Null && FINISHED_SEQUENCE.with(|finished| finished.borrow().as_ref() == Some(self));
+        }
 
-        if modifiers != expected_modifiers {
+        let (key, ctrl, alt, shift) = self.parts();
+        if modifiers.contains(KeyModifiers::CONTROL) != ctrl || modifiers.contains(KeyModifiers::ALT) != alt {
+            return false;
+        }
+        // super, hyper and meta aren't part of any binding
+        if modifiers.intersects(!(KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT)) {
             return false;
         }
 
-        match self.key.as_str() {
+        // a character already says whether shift was held ("N" or "n") and terminals differ in
+        // whether they report shift on top, so for characters it only means the uppercase one
+        let mut chars = key.chars();
+        let bound = match (chars.next(), chars.next()) {
+            _ if key == "Space" => Some(' '),
+            (Some(c), None) => Some(c),
+            _ => None,
+        };
+        if let Some(bound) = bound {
+            let KeyCode::Char(typed) = key_code else { return false };
+            let bound = if shift { uppercase(bound) } else { bound };
+            let typed = if modifiers.contains(KeyModifiers::SHIFT) { uppercase(typed) } else { typed };
+            return if self.case_insensitive { uppercase(bound) == uppercase(typed) } else { bound == typed };
+        }
+
+        if modifiers.contains(KeyModifiers::SHIFT) != shift {
+            return false;
+        }
+        match key {
             "Enter" => key_code == KeyCode::Enter,
             "Esc" => key_code == KeyCode::Esc,
-            "Tab" => key_code == KeyCode::Tab,
+            // most terminals send shift+tab as a key of its own
+            "Tab" => key_code == KeyCode::Tab || (shift && key_code == KeyCode::BackTab),
             "Backspace" => key_code == KeyCode::Backspace,
             "Delete" => key_code == KeyCode::Delete,
             "Up" => key_code == KeyCode::Up,
@@ -267,18 +960,20 @@ impl KeyBinding {
             "F10" => key_code == KeyCode::F(10),
             "F11" => key_code == KeyCode::F(11),
             "F12" => key_code == KeyCode::F(12),
-            key if key.len() == 1 => {
-                if let Some(c) = key.chars().next() {
-                    key_code == KeyCode::Char(c)
-                } else {
-                    false
-                }
-            }
             _ => false,
         }
     }
 }
 
+// the uppercase of a letter, anything without a single uppercase character stays as it is
+fn uppercase(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) => upper,
+        _ => c,
+    }
+}
+
 
 fn default_color() -> String {
     "Reset".to_string()
@@ -292,17 +987,6 @@ fn is_reset(s: &str) -> bool {
     s == "Reset"
 }
 
-
-// helper function to set secure permissions on unix systems
-#[cfg(unix)]
-fn set_secure_permissions(path: &std::path::Path, is_directory: bool) -> io::Result<()> {
-    let mode = if is_directory { 0o700 } else { 0o600 };
-    let mut perms = fs::metadata(path)?.permissions();
-    perms.set_mode(mode);
-    fs::set_permissions(path, perms)?;
-    Ok(())
-}
-
 impl Config {
     pub fn load() -> io::Result<Self> {
         let config_path = Self::config_path()?;
@@ -315,40 +999,177 @@ impl Config {
 
         let contents = fs::read_to_string(&config_path)?;
         let config: Config = match toml::from_str::<Config>(&contents) {
-            Ok(config) => {
+            Ok(mut config) => {
+                if let Err(e) = config.apply_theme(&contents) {
+                    eprintln!("Warning: {}, using the colors from the config", e);
+                }
+                if let Err(e) = config.apply_keymap(&contents) {
+                    eprintln!("Warning: {}, using the keys from the config", e);
+                }
                 config.save()?;
                 config
             },
             Err(e) => {
-                eprintln!("Warning: Config file has missing or invalid fields: {}", e);
-                eprintln!("Creating updated config file with defaults for missing fields...");
-                
-                let default_config = Config::default();
-                default_config.save()?;
-                eprintln!("Config file has been updated. Your existing settings have been preserved where possible.");
-                
-                default_config
+                eprintln!("Warning: Config file has invalid fields: {}", e);
+                // the file as written is kept, rewriting it drops whatever couldn't be used
+                let backup = config_path.with_extension("toml.bak");
+                fs::copy(&config_path, &backup)?;
+
+                let (mut config, usable) = match contents.parse::<toml::Table>() {
+                    Ok(table) => Self::salvage(table),
+                    // not even valid toml, there are no single settings to pick out
+                    Err(_) => (Config { invalid_settings: vec!["the whole file".to_string()], ..Config::default() }, toml::Table::new()),
+                };
+                let usable = toml::to_string(&usable).unwrap_or_default();
+                if let Err(e) = config.apply_theme(&usable) {
+                    eprintln!("Warning: {}, using the colors from the config", e);
+                }
+                if let Err(e) = config.apply_keymap(&usable) {
+                    eprintln!("Warning: {}, using the keys from the config", e);
+                }
+                config.save()?;
+                eprintln!(
+                    "Reset to defaults: {}. Everything else was kept, the old file is at {}",
+                    config.invalid_settings.join(", "),
+                    backup.display()
+                );
+                config
             }
         };
 
         Ok(config)
     }
 
+    // the settings from the file that work, one at a time, along with the table they came from.
+    // everything left out gets its default
+    fn salvage(table: toml::Table) -> (Config, toml::Table) {
+        let mut usable = toml::Table::new();
+        let mut invalid = Vec::new();
+        let mut path = Vec::new();
+        for (key, value) in table {
+            salvage_setting(&mut usable, &mut path, key, value, &mut invalid);
+        }
+        let mut config: Config = toml::Value::Table(usable.clone()).try_into().unwrap_or_default();
+        config.invalid_settings = invalid;
+        (config, usable)
+    }
+
+    // the theme is the starting point and whatever [colors] sets in the file goes on top
+    fn apply_theme(&mut self, contents: &str) -> io::Result<()> {
+        if self.colors.theme.is_empty() {
+            return Ok(());
+        }
+        let base = theme::load(&self.colors.theme)?;
+        let overrides = contents
+            .parse::<toml::Table>()
+            .ok()
+            .and_then(|table| table.get("colors").and_then(|colors| colors.as_table()).cloned())
+            .unwrap_or_default();
+
+        let merged = toml::Value::try_from(&base).map(|mut value| {
+            if let Some(table) = value.as_table_mut() {
+                table.extend(overrides);
+            }
+            value
+        });
+        let not_applied = |e: String| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("couldn't apply theme '{}': {}", self.colors.theme, e))
+        };
+        match merged.map(|value| value.try_into::<ColorTheme>()) {
+            Ok(Ok(colors)) => self.colors = colors,
+            Ok(Err(e)) => return Err(not_applied(e.to_string())),
+            Err(e) => return Err(not_applied(e.to_string())),
+        }
+        Ok(())
+    }
+
+    // the same for the keymap profile, whatever [keybindings] sets goes on top of the profile's keys.
+    // an entry that's just the stock default counts as unset, so a file written out in full still
+    // follows the profile
+    fn apply_keymap(&mut self, contents: &str) -> io::Result<()> {
+        let profile = self.keybindings.profile;
+        if profile == KeymapProfile::Default {
+            return Ok(());
+        }
+        let not_applied = |e: String| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("couldn't apply keymap '{}': {}", profile.name(), e))
+        };
+        let stock = toml::Value::try_from(KeyBindings::default()).map_err(|e| not_applied(e.to_string()))?;
+        let mut merged = toml::Value::try_from(KeyBindings::for_profile(profile)).map_err(|e| not_applied(e.to_string()))?;
+        let overrides = contents
+            .parse::<toml::Table>()
+            .ok()
+            .and_then(|table| table.get("keybindings").and_then(|keys| keys.as_table()).cloned())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(key, entry)| key != "profile" && stock.get(key) != Some(entry));
+        if let Some(table) = merged.as_table_mut() {
+            table.extend(overrides);
+        }
+        self.keybindings = merged.try_into().map_err(|e: toml::de::Error| not_applied(e.to_string()))?;
+        Ok(())
+    }
+
+    // the vim profile brings the modal editor along
+    pub fn modal_editing(&self) -> bool {
+        self.behavior.vim_mode || self.keybindings.profile == KeymapProfile::Vim
+    }
+
+    // read the file again while the app runs. unlike load it never writes the file, and anything
+    // wrong with it is an error for the caller to show
+    pub fn reload() -> io::Result<Self> {
+        let contents = fs::read_to_string(Self::config_path()?)?;
+        // most likely caught halfway through an editor's save
+        if contents.trim().is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "the file is empty"));
+        }
+        let mut config: Config = toml::from_str(&contents).map_err(|e| {
+            let line = e.span().map(|span| contents[..span.start].matches('\n').count() + 1);
+            let message = match line {
+                Some(line) => format!("line {}: {}", line, e.message()),
+                None => e.message().to_string(),
+            };
+            io::Error::new(io::ErrorKind::InvalidData, message)
+        })?;
+        config.apply_theme(&contents)?;
+        config.apply_keymap(&contents)?;
+        Ok(config)
+    }
+
     pub fn save(&self) -> io::Result<()> {
         let config_path = Self::config_path()?;
         
-        if let Some(parent) = config_path.parent() {
+        if let Some(parent) = config_path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
             fs::create_dir_all(parent)?;
-            // set secure permissions on the config directory
-            set_secure_permissions(parent, true)?;
+            // set secure permissions on the config directory, not on wherever --config points
+            if CONFIG_FILE.get().is_none() {
+                set_secure_permissions(parent, true)?;
+            }
         }
 
-        let contents = toml::to_string_pretty(self).map_err(|e| {
+        let serialize_error = |e: toml::ser::Error| {
             io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("Failed to serialize config: {}", e),
             )
-        })?;
+        };
+        let mut value = toml::Value::try_from(self).map_err(serialize_error)?;
+        // with a theme only the colors that differ from it are written, so switching themes
+        // isn't undone by the old theme's colors still sitting in the file
+        if let Some(base) = Some(&self.colors.theme).filter(|name| !name.is_empty()).and_then(|name| theme::load(name).ok()) {
+            let base = toml::Value::try_from(&base).map_err(serialize_error)?;
+            if let (Some(colors), Some(base)) = (value.get_mut("colors").and_then(|v| v.as_table_mut()), base.as_table()) {
+                colors.retain(|key, entry| key == "theme" || base.get(key) != Some(entry));
+            }
+        }
+        // the same for the keys of a profile
+        if self.keybindings.profile != KeymapProfile::Default {
+            let base = toml::Value::try_from(KeyBindings::for_profile(self.keybindings.profile)).map_err(serialize_error)?;
+            if let (Some(keys), Some(base)) = (value.get_mut("keybindings").and_then(|v| v.as_table_mut()), base.as_table()) {
+                keys.retain(|key, entry| key == "profile" || base.get(key) != Some(entry));
+            }
+        }
+        let contents = toml::to_string_pretty(&value).map_err(serialize_error)?;
 
         fs::write(&config_path, contents)?;
         // set secure permissions on the config file
@@ -369,11 +1190,145 @@ impl Config {
         Ok(config_dir.join("tui-notes"))
     }
 
-    fn config_path() -> io::Result<PathBuf> {
-        Ok(Self::config_dir()?.join("config.toml"))
+    pub fn config_path() -> io::Result<PathBuf> {
+        match CONFIG_FILE.get() {
+            Some(path) => Ok(path.clone()),
+            None => Ok(Self::config_dir()?.join("config.toml")),
+        }
+    }
+
+    // use another config file for this run, before anything loads one
+    pub fn use_config_file(path: PathBuf) {
+        let _ = CONFIG_FILE.set(path);
+    }
+
+    // the named vault, or for None the notes file, encryption and keyfile from [behavior].
+    // --notes-file wins over both, it's encrypted if the config says so or the file already is
+    pub fn vault(&self, name: Option<&str>) -> io::Result<VaultConfig> {
+        let mut vault = self.configured_vault(name)?;
+        if let Some((local, remote)) = crate::sync::remote_vault(&vault.notes_file)? {
+            vault.notes_file = local;
+            vault.sync_remote = remote;
+            vault.remote = true;
+        }
+        Ok(vault)
+    }
+
+    fn configured_vault(&self, name: Option<&str>) -> io::Result<VaultConfig> {
+        if let Some(notes_file) = &self.session.notes_file {
+            let already_encrypted = fs::read_to_string(notes_file)
+                .is_ok_and(|content| crate::encryption::EncryptionManager::is_file_encrypted(&content));
+            return Ok(VaultConfig {
+                notes_file: notes_file.clone(),
+                encryption_enabled: self.behavior.encryption_enabled || already_encrypted,
+                keyfile: self.behavior.keyfile.clone(),
+                // another file than the configured one isn't synced anywhere
+                sync_remote: String::new(),
+                remote: false,
+            });
+        }
+        match name {
+            Some(name) => self.vaults.get(name).cloned().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("no vault named '{}' in the config", name))
+            }),
+            None => Ok(VaultConfig {
+                notes_file: self.behavior.default_notes_file.clone(),
+                encryption_enabled: self.behavior.encryption_enabled,
+                keyfile: self.behavior.keyfile.clone(),
+                sync_remote: self.behavior.sync_remote.clone(),
+                remote: false,
+            }),
+        }
+    }
+}
+
+// add `key` under `path` to what's usable if the config still parses with it. a table that doesn't
+// is gone through entry by entry, so one bad entry doesn't cost the whole section
+fn salvage_setting(usable: &mut toml::Table, path: &mut Vec<String>, key: String, value: toml::Value, invalid: &mut Vec<String>) {
+    let entries = match &value {
+        toml::Value::Table(entries) => Some(entries.clone()),
+        _ => None,
+    };
+    if try_setting(usable, path, &key, value) {
+        return;
+    }
+    match entries {
+        // the table itself is fine, it's something in it
+        Some(entries) if try_setting(usable, path, &key, toml::Value::Table(toml::Table::new())) => {
+            path.push(key);
+            for (key, value) in entries {
+                salvage_setting(usable, path, key, value, invalid);
+            }
+            path.pop();
+        }
+        _ => invalid.push(path.iter().chain([&key]).map(String::as_str).collect::<Vec<_>>().join(".")),
     }
 }
 
+// true if the config parses with the value in place, it's taken out again otherwise
+fn try_setting(usable: &mut toml::Table, path: &[String], key: &str, value: toml::Value) -> bool {
+    let Some(parent) = table_at(usable, path) else { return false };
+    parent.insert(key.to_string(), value);
+    if toml::Value::Table(usable.clone()).try_into::<Config>().is_ok() {
+        return true;
+    }
+    if let Some(parent) = table_at(usable, path) {
+        parent.remove(key);
+    }
+    false
+}
+
+fn table_at<'a>(table: &'a mut toml::Table, path: &[String]) -> Option<&'a mut toml::Table> {
+    path.iter().try_fold(table, |table, key| table.get_mut(key)?.as_table_mut())
+}
+
+// notices edits to config.toml while the app runs, the same way as for the notes file: the mtime
+// first, then whether the contents really changed. it's polled from the main loop rather than
+// watched with notify, a stat per iteration costs nothing and needs no extra thread or
+// dependency, and it also sees editors that save by renaming a new file over the old one
+pub struct ConfigWatcher {
+    path: PathBuf,
+    state: Option<DiskState>,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> io::Result<Self> {
+        let path = Config::config_path()?;
+        Ok(ConfigWatcher { state: DiskState::read(&path), path })
+    }
+
+    // the file as it is now counts as seen, e.g. after the app saved it itself
+    pub fn remember(&mut self) {
+        self.state = DiskState::read(&self.path);
+    }
+
+    // true once per change, a file that doesn't parse isn't reported again until it changes again
+    pub fn changed(&mut self) -> bool {
+        let mtime = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if mtime.is_none() || self.state.as_ref().is_some_and(|known| known.mtime == mtime) {
+            return false;
+        }
+        let Some(current) = DiskState::read(&self.path) else { return false };
+        let changed = self.state.as_ref().is_none_or(|known| known.hash != current.hash);
+        self.state = Some(current);
+        changed
+    }
+}
+
+thread_local! {
+    // the sequence whose last key App is handling right now
+    static FINISHED_SEQUENCE: RefCell<Option<KeyBinding>> = const { RefCell::new(None) };
+}
+
+// run `handle` with the sequence counted as pressed. it gets KeyCode::Null as its key, which no
+// single key binding matches, so only bindings with this sequence fire
+pub fn finish_sequence<R>(sequence: &KeyBinding, handle: impl FnOnce() -> R) -> R {
+    FINISHED_SEQUENCE.with(|finished| *finished.borrow_mut() = Some(sequence.clone()));
+    let result = handle();
+    FINISHED_SEQUENCE.with(|finished| *finished.borrow_mut() = None);
+    result
+}
+
 pub fn key_matches_any(keybindings: &[KeyBinding], key_code: KeyCode, modifiers: KeyModifiers) -> bool {
     keybindings.iter().any(|kb| kb.matches(key_code, modifiers))
 }
@@ -382,7 +1337,7 @@ fn serialize_keybinding<S>(kb: &KeyBinding, serializer: S) -> Result<S::Ok, S::E
 where
     S: serde::Serializer,
 {
-    if !kb.ctrl && !kb.alt && !kb.shift {
+    if !kb.ctrl && !kb.alt && !kb.shift && !kb.case_insensitive {
         serializer.serialize_str(&kb.key)
     } else {
         use serde::Serialize;
@@ -410,7 +1365,7 @@ where
 {
     use serde::Serialize;
     
-    if kbs.iter().all(|kb| !kb.ctrl && !kb.alt && !kb.shift) {
+    if kbs.iter().all(|kb| !kb.ctrl && !kb.alt && !kb.shift && !kb.case_insensitive) {
         let keys: Vec<&str> = kbs.iter().map(|kb| kb.key.as_str()).collect();
         keys.serialize(serializer)
     } else {
@@ -445,21 +1400,24 @@ fn serialize_color<S>(color: &ColorConfig, serializer: S) -> Result<S::Ok, S::Er
 where
     S: serde::Serializer,
 {
-    use serde::Serialize;
-    
-    if color.bg == "Reset" && color.fg != "Reset" {
+    use serde::ser::SerializeMap;
+
+    let plain = color.bg == "Reset" && color.modifiers.is_empty();
+    if plain {
+        // just a name, "Reset" when nothing is set at all
         serializer.serialize_str(&color.fg)
-    } else if color.fg == "Reset" && color.bg != "Reset" {
-        let mut map = std::collections::HashMap::new();
-        map.insert("bg", &color.bg);
-        map.serialize(serializer)
-    } else if color.fg != "Reset" && color.bg != "Reset" {
-        let mut map = std::collections::HashMap::new();
-        map.insert("fg", &color.fg);
-        map.insert("bg", &color.bg);
-        map.serialize(serializer)
     } else {
-        serializer.serialize_str("Reset")
+        let mut map = serializer.serialize_map(None)?;
+        if color.fg != "Reset" {
+            map.serialize_entry("fg", &color.fg)?;
+        }
+        if color.bg != "Reset" {
+            map.serialize_entry("bg", &color.bg)?;
+        }
+        if !color.modifiers.is_empty() {
+            map.serialize_entry("modifiers", &color.modifiers)?;
+        }
+        map.end()
     }
 }
 
@@ -475,11 +1433,18 @@ where
         Value::String(s) => Ok(ColorConfig {
             fg: s,
             bg: "Reset".to_string(),
+            modifiers: Vec::new(),
         }),
         Value::Object(obj) => {
             let fg = obj.get("fg").and_then(|v| v.as_str()).unwrap_or("Reset").to_string();
             let bg = obj.get("bg").and_then(|v| v.as_str()).unwrap_or("Reset").to_string();
-            Ok(ColorConfig { fg, bg })
+            // a list, or a single name
+            let modifiers = match obj.get("modifiers") {
+                Some(Value::Array(items)) => items.iter().filter_map(|v| v.as_str()).map(str::to_string).collect(),
+                Some(Value::String(name)) => vec![name.clone()],
+                _ => Vec::new(),
+            };
+            Ok(ColorConfig { fg, bg, modifiers })
         }
         _ => Err(D::Error::custom("expected string or object")),
     }
@@ -493,6 +1458,30 @@ impl ColorConfig {
     pub fn to_bg_color(&self) -> Color {
         parse_color(&self.bg)
     }
+
+    pub fn to_modifier(&self) -> Modifier {
+        self.modifiers.iter().fold(Modifier::empty(), |all, name| {
+            all | match name.to_lowercase().as_str() {
+                "bold" => Modifier::BOLD,
+                "dim" => Modifier::DIM,
+                "italic" => Modifier::ITALIC,
+                "underlined" | "underline" => Modifier::UNDERLINED,
+                "reversed" => Modifier::REVERSED,
+                "crossed_out" | "strikethrough" => Modifier::CROSSED_OUT,
+                _ => Modifier::empty(),
+            }
+        })
+    }
+
+    // the foreground color with the entry's modifiers
+    pub fn style(&self) -> Style {
+        Style::default().fg(self.to_color()).add_modifier(self.to_modifier())
+    }
+
+    // the background color with the entry's modifiers
+    pub fn bg_style(&self) -> Style {
+        Style::default().bg(self.to_bg_color()).add_modifier(self.to_modifier())
+    }
 }
 
 fn parse_color(color_str: &str) -> Color {
@@ -514,6 +1503,11 @@ fn parse_color(color_str: &str) -> Color {
         "LightMagenta" => Color::LightMagenta,
         "LightCyan" => Color::LightCyan,
         "White" => Color::White,
+        // #rgb is short for #rrggbb
+        _ if color_str.starts_with('#') && color_str.len() == 4 => {
+            let doubled: String = color_str[1..].chars().flat_map(|c| [c, c]).collect();
+            parse_color(&format!("#{}", doubled))
+        },
         _ if color_str.starts_with('#') && color_str.len() == 7 => {
             if let Ok(hex) = u32::from_str_radix(&color_str[1..], 16) {
                 let r = ((hex >> 16) & 0xFF) as u8;
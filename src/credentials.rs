@@ -0,0 +1,82 @@
+use secrecy::SecretString;
+use std::io;
+use std::path::Path;
+
+// the vault password in the system keyring (Secret Service, Keychain or Credential Manager),
+// one entry per notes file so several vaults don't share a password
+#[cfg(feature = "system-keyring")]
+mod store {
+    use keyring::{Entry, Error};
+    use secrecy::{ExposeSecret, SecretString};
+    use std::io;
+    use std::path::Path;
+
+    const SERVICE: &str = "tui-notes";
+
+    fn entry(notes_file: &Path) -> io::Result<Entry> {
+        // the same vault reached through another relative path is still the same vault
+        let path = notes_file.canonicalize().unwrap_or_else(|_| notes_file.to_path_buf());
+        Entry::new(SERVICE, &path.to_string_lossy()).map_err(to_io)
+    }
+
+    fn to_io(e: Error) -> io::Error {
+        io::Error::other(format!("keyring: {}", e))
+    }
+
+    pub fn load(notes_file: &Path) -> io::Result<Option<SecretString>> {
+        match entry(notes_file)?.get_password() {
+            Ok(password) => Ok(Some(SecretString::new(password.into()))),
+            Err(Error::NoEntry) => Ok(None),
+            Err(e) => Err(to_io(e)),
+        }
+    }
+
+    pub fn save(notes_file: &Path, password: &SecretString) -> io::Result<()> {
+        entry(notes_file)?.set_password(password.expose_secret()).map_err(to_io)
+    }
+
+    pub fn forget(notes_file: &Path) -> io::Result<bool> {
+        match entry(notes_file)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(Error::NoEntry) => Ok(false),
+            Err(e) => Err(to_io(e)),
+        }
+    }
+}
+
+#[cfg(not(feature = "system-keyring"))]
+mod store {
+    use secrecy::SecretString;
+    use std::io;
+    use std::path::Path;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(io::ErrorKind::Unsupported, "built without keyring support (the system-keyring feature)")
+    }
+
+    pub fn load(_notes_file: &Path) -> io::Result<Option<SecretString>> {
+        Ok(None)
+    }
+
+    pub fn save(_notes_file: &Path, _password: &SecretString) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn forget(_notes_file: &Path) -> io::Result<bool> {
+        Err(unsupported())
+    }
+}
+
+// None when nothing is stored for the vault
+pub fn load(notes_file: &Path) -> io::Result<Option<SecretString>> {
+    store::load(notes_file)
+}
+
+pub fn save(notes_file: &Path, password: &SecretString) -> io::Result<()> {
+    store::save(notes_file, password)
+}
+
+// true if there was a password to forget
+pub fn forget(notes_file: &Path) -> io::Result<bool> {
+    store::forget(notes_file)
+}
@@ -0,0 +1,225 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::io;
+use std::path::Path;
+use crate::note::Note;
+
+// the columns an export writes, in this order
+pub const HEADERS: [&str; 7] = ["id", "title", "content", "created", "updated", "pinned", "tags"];
+
+// note fields a csv column can be mapped to, same order as HEADERS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvField {
+    Id,
+    Title,
+    Content,
+    Created,
+    Updated,
+    Pinned,
+    Tags,
+}
+
+impl CsvField {
+    pub const ALL: [CsvField; 7] = [
+        CsvField::Id,
+        CsvField::Title,
+        CsvField::Content,
+        CsvField::Created,
+        CsvField::Updated,
+        CsvField::Pinned,
+        CsvField::Tags,
+    ];
+
+    pub fn name(self) -> &'static str {
+        HEADERS[self as usize]
+    }
+
+    // header names other tools use for the same thing
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            CsvField::Id => &["id", "uuid", "guid"],
+            CsvField::Title => &["title", "name", "subject", "heading"],
+            CsvField::Content => &["content", "body", "text", "note", "notes", "description"],
+            CsvField::Created => &["created", "created_at", "creation date", "date created", "date"],
+            CsvField::Updated => &["updated", "updated_at", "modified", "last modified", "date modified"],
+            CsvField::Pinned => &["pinned", "pin", "starred", "favorite"],
+            CsvField::Tags => &["tags", "tag", "labels", "keywords"],
+        }
+    }
+}
+
+// which csv column (by index) each field is read from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CsvMapping {
+    columns: [Option<usize>; 7],
+}
+
+impl CsvMapping {
+    // match the header names against the field names and their aliases
+    pub fn guess(headers: &[String]) -> Self {
+        let mut mapping = CsvMapping::default();
+        for field in CsvField::ALL {
+            mapping.columns[field as usize] = headers.iter().position(|header| {
+                let header = header.trim().to_lowercase();
+                field.aliases().contains(&header.as_str())
+            });
+        }
+        mapping
+    }
+
+    pub fn column(&self, field: CsvField) -> Option<usize> {
+        self.columns[field as usize]
+    }
+
+    // step through none, column 0, column 1, ... and back to none
+    pub fn cycle(&mut self, field: CsvField, column_count: usize, forward: bool) {
+        let slot = &mut self.columns[field as usize];
+        *slot = match (*slot, forward) {
+            (None, true) => (column_count > 0).then_some(0),
+            (None, false) => column_count.checked_sub(1),
+            (Some(i), true) => (i + 1 < column_count).then_some(i + 1),
+            (Some(i), false) => i.checked_sub(1),
+        };
+    }
+
+    pub fn clear(&mut self, field: CsvField) {
+        self.columns[field as usize] = None;
+    }
+
+    // a note needs at least something to show
+    pub fn is_usable(&self) -> bool {
+        self.column(CsvField::Title).is_some() || self.column(CsvField::Content).is_some()
+    }
+}
+
+// the header row and the first data row, for the mapping prompt
+#[derive(Debug, Clone, Default)]
+pub struct CsvPreview {
+    pub headers: Vec<String>,
+    pub sample: Vec<String>,
+}
+
+fn to_io(e: csv::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("csv error: {}", e))
+}
+
+fn reader(path: &Path) -> io::Result<csv::Reader<std::fs::File>> {
+    csv::ReaderBuilder::new().flexible(true).from_path(path).map_err(to_io)
+}
+
+pub fn preview(path: &Path) -> io::Result<CsvPreview> {
+    let mut reader = reader(path)?;
+    let headers: Vec<String> = reader.headers().map_err(to_io)?.iter().map(str::to_string).collect();
+    if headers.iter().all(|header| header.trim().is_empty()) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "the csv file has no header row"));
+    }
+    let sample = match reader.records().next() {
+        Some(record) => record.map_err(to_io)?.iter().map(str::to_string).collect(),
+        None => Vec::new(),
+    };
+    Ok(CsvPreview { headers, sample })
+}
+
+// a data row with the mapping applied, empty cells are None
+#[derive(Debug, Clone, Default)]
+pub struct CsvRow {
+    // 1-based line in the file, for the import report
+    pub line: u64,
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+    pub pinned: bool,
+    pub tags: String,
+}
+
+// rfc3339 like we write it, or the plain date/time formats spreadsheets produce
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(date.and_utc());
+        }
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+fn parse_bool(value: &str) -> bool {
+    ["true", "yes", "y", "1", "x"].contains(&value.to_lowercase().as_str())
+}
+
+// every data row, or the line and why it couldn't be read
+pub fn read_rows(path: &Path, mapping: &CsvMapping) -> io::Result<Vec<Result<CsvRow, (u64, String)>>> {
+    let mut reader = reader(path)?;
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map(|p| p.line()).unwrap_or(0);
+                rows.push(Err((line, e.to_string())));
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or(0);
+        let cell = |field: CsvField| {
+            mapping
+                .column(field)
+                .and_then(|i| record.get(i))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+
+        let created = cell(CsvField::Created);
+        let updated = cell(CsvField::Updated);
+        if let Some(bad) = [created, updated].into_iter().flatten().find(|value| parse_date(value).is_none()) {
+            rows.push(Err((line, format!("can't read the date '{}'", bad))));
+            continue;
+        }
+
+        rows.push(Ok(CsvRow {
+            line,
+            id: cell(CsvField::Id).map(str::to_string),
+            title: cell(CsvField::Title).map(str::to_string),
+            // keep the content's own indentation, only the title gets trimmed
+            content: mapping
+                .column(CsvField::Content)
+                .and_then(|i| record.get(i))
+                .filter(|value| !value.trim().is_empty())
+                .map(|value| value.replace("\r\n", "\n")),
+            created: created.and_then(parse_date),
+            updated: updated.and_then(parse_date),
+            pinned: cell(CsvField::Pinned).is_some_and(parse_bool),
+            tags: cell(CsvField::Tags).unwrap_or_default().to_string(),
+        }));
+    }
+    Ok(rows)
+}
+
+// one row per note with the HEADERS columns, tags comma separated in one cell. there's no
+// column for the trash, so trashed notes are left out
+pub fn export(notes: &[&Note]) -> io::Result<String> {
+    let mut notes: Vec<&&Note> = notes.iter().filter(|note| !note.is_trashed()).collect();
+    notes.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(HEADERS).map_err(to_io)?;
+    for note in notes {
+        writer
+            .write_record([
+                note.id.as_str(),
+                note.title.as_str(),
+                note.content.as_str(),
+                &note.created_at.to_rfc3339(),
+                &note.updated_at.to_rfc3339(),
+                if note.pinned { "true" } else { "false" },
+                &note.tags.join(", "),
+            ])
+            .map_err(to_io)?;
+    }
+    let bytes = writer.into_inner().map_err(|e| io::Error::other(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
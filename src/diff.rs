@@ -0,0 +1,102 @@
+// line based diff (longest common subsequence), good enough for note sized texts
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+// one row of a side-by-side view, either side may be blank
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffRow<'a> {
+    pub left: Option<DiffLine<'a>>,
+    pub right: Option<DiffLine<'a>>,
+}
+
+// past this many cells the lcs table gets silly, just show everything as replaced
+const MAX_TABLE_CELLS: usize = 4_000_000;
+
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    // trim the common prefix/suffix first, edits are usually small
+    let prefix = old_lines.iter().zip(&new_lines).take_while(|(a, b)| a == b).count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_mid = &old_lines[prefix..old_lines.len() - suffix];
+    let new_mid = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut result: Vec<DiffLine> = old_lines[..prefix].iter().map(|l| DiffLine::Same(l)).collect();
+
+    if old_mid.len() * new_mid.len() > MAX_TABLE_CELLS {
+        result.extend(old_mid.iter().map(|l| DiffLine::Removed(l)));
+        result.extend(new_mid.iter().map(|l| DiffLine::Added(l)));
+    } else {
+        // lengths[i][j] = lcs of old_mid[i..] and new_mid[j..]
+        let (n, m) = (old_mid.len(), new_mid.len());
+        let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i][j] = if old_mid[i] == new_mid[j] {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if old_mid[i] == new_mid[j] {
+                result.push(DiffLine::Same(old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+                result.push(DiffLine::Removed(old_mid[i]));
+                i += 1;
+            } else {
+                result.push(DiffLine::Added(new_mid[j]));
+                j += 1;
+            }
+        }
+        result.extend(old_mid[i..].iter().map(|l| DiffLine::Removed(l)));
+        result.extend(new_mid[j..].iter().map(|l| DiffLine::Added(l)));
+    }
+
+    result.extend(old_lines[old_lines.len() - suffix..].iter().map(|l| DiffLine::Same(l)));
+    result
+}
+
+// pair up removals with the additions that follow them so changed lines sit side by side
+pub fn side_by_side<'a>(diff: &[DiffLine<'a>]) -> Vec<DiffRow<'a>> {
+    let mut rows = Vec::new();
+    let mut i = 0;
+
+    while i < diff.len() {
+        match diff[i] {
+            DiffLine::Same(_) => {
+                rows.push(DiffRow { left: Some(diff[i].clone()), right: Some(diff[i].clone()) });
+                i += 1;
+            }
+            _ => {
+                let removed: Vec<&DiffLine> = diff[i..].iter().take_while(|l| matches!(l, DiffLine::Removed(_))).collect();
+                let added: Vec<&DiffLine> = diff[i + removed.len()..].iter().take_while(|l| matches!(l, DiffLine::Added(_))).collect();
+                for k in 0..removed.len().max(added.len()) {
+                    rows.push(DiffRow {
+                        left: removed.get(k).map(|l| (*l).clone()),
+                        right: added.get(k).map(|l| (*l).clone()),
+                    });
+                }
+                i += removed.len() + added.len();
+            }
+        }
+    }
+    rows
+}
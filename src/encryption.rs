@@ -1,4 +1,5 @@
 use std::io;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use chacha20poly1305::{
     aead::{Aead, AeadCore, KeyInit, OsRng},
@@ -8,6 +9,7 @@ use argon2::{Argon2, PasswordHasher, password_hash::{rand_core::RngCore, SaltStr
 use zeroize::Zeroize;
 use base64::{Engine as _, engine::general_purpose};
 use subtle::ConstantTimeEq;
+use sha2::{Digest, Sha256};
 
 pub const MIN_PASSWORD_LENGTH: usize = 8;
 pub const MAX_PASSWORD_LENGTH: usize = 256;
@@ -15,17 +17,120 @@ const MAX_CONTENT_SIZE: usize = 100 * 1024 * 1024; // 100MB limit
 
 const MAGIC_HEADER: &str = "ENCRYPTED_NOTES";
 
+pub const CIPHER_NAME: &str = "ChaCha20-Poly1305";
+pub const KDF_NAME: &str = "Argon2id";
+pub const ARGON2_MEMORY_KIB: u32 = 65536; // 64MB memory cost
+pub const ARGON2_ITERATIONS: u32 = 3;
+pub const ARGON2_PARALLELISM: u32 = 1;
+
+// wrong passwords allowed before unlocking starts to slow down
+const FREE_UNLOCK_ATTEMPTS: u32 = 3;
+const MAX_UNLOCK_DELAY_SECS: i64 = 15 * 60;
+
+// rough guess at how hard a password is to brute force, shown while a new one is picked
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum PasswordStrength {
+    TooShort,
+    Weak,
+    Fair,
+    Good,
+    Strong,
+}
+
+impl PasswordStrength {
+    // bits of a search over the character classes used. repeated characters only count
+    // twice, so "aaaaaaaaaaaa" doesn't pass for a long password
+    pub fn of(password: &str) -> Self {
+        let length = password.chars().count();
+        if length < MIN_PASSWORD_LENGTH {
+            return PasswordStrength::TooShort;
+        }
+
+        let has = |test: fn(&char) -> bool| password.chars().any(|c| test(&c));
+        let pool = [
+            (has(char::is_ascii_lowercase), 26),
+            (has(char::is_ascii_uppercase), 26),
+            (has(char::is_ascii_digit), 10),
+            (has(|c| c.is_ascii() && !c.is_ascii_alphanumeric()), 33),
+            (has(|c| !c.is_ascii()), 100),
+        ]
+        .into_iter()
+        .filter(|(used, _)| *used)
+        .map(|(_, size)| size)
+        .sum::<u32>();
+
+        let distinct = password.chars().collect::<std::collections::HashSet<_>>().len();
+        let bits = length.min(distinct * 2) as f64 * (pool as f64).log2();
+        match bits {
+            b if b < 40.0 => PasswordStrength::Weak,
+            b if b < 60.0 => PasswordStrength::Fair,
+            b if b < 80.0 => PasswordStrength::Good,
+            _ => PasswordStrength::Strong,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PasswordStrength::TooShort => "Too short",
+            PasswordStrength::Weak => "Weak",
+            PasswordStrength::Fair => "Fair",
+            PasswordStrength::Good => "Good",
+            PasswordStrength::Strong => "Strong",
+        }
+    }
+
+    // 0 to 4, how much of the meter is filled
+    pub fn level(&self) -> usize {
+        *self as usize
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptedFile {
     pub magic: String,
     pub salt: String,
     pub nonce: String,
     pub data: String,
+    // wrong passwords tried since the last unlock. the header isn't covered by the encryption,
+    // so this only slows down guessing through the app, Argon2 is what protects the file itself
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub failed_unlocks: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_failed_unlock: Option<DateTime<Utc>>,
+    // the key was derived from the password and a keyfile, the password alone won't open it
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub keyfile: bool,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
 }
 
-#[derive(Debug)]
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+// what goes into the key derivation from a keyfile: its sha-256, so any file of any size works
+pub fn keyfile_secret(contents: &[u8]) -> [u8; 32] {
+    Sha256::digest(contents).into()
+}
+
+// how long to wait after `failed` wrong passwords: nothing for the first few,
+// then 1s, 2s, 4s, ... up to 15 minutes
+pub fn unlock_delay(failed: u32) -> Duration {
+    if failed < FREE_UNLOCK_ATTEMPTS {
+        return Duration::zero();
+    }
+    let doublings = (failed - FREE_UNLOCK_ATTEMPTS).min(20);
+    Duration::seconds((1i64 << doublings).min(MAX_UNLOCK_DELAY_SECS))
+}
+
+// clones carry a copy of the key (each copy zeroizes itself on drop)
+#[derive(Debug, Clone)]
 pub struct EncryptionManager {
     key: Option<Key>,
+    // whether a keyfile went into the key, recorded in the files it encrypts
+    keyfile: bool,
 }
 
 impl Default for EncryptionManager {
@@ -38,30 +143,42 @@ impl EncryptionManager {
     pub fn new() -> Self {
         Self {
             key: None,
+            keyfile: false,
         }
     }
 
-    // derive key from password using argon2 (constant time operation)
-    fn derive_key(&self, password: &str, salt: &[u8]) -> Result<Key, io::Error> {
+    // derive key from password using argon2 (constant time operation), a keyfile's
+    // secret goes in as argon2's secret input
+    fn derive_key(&self, password: &str, salt: &[u8], keyfile: Option<&[u8]>) -> Result<Key, io::Error> {
         let salt_string = SaltString::encode_b64(salt).map_err(|_| {
             io::Error::new(io::ErrorKind::InvalidData, "invalid salt")
         })?;
         
         // use stronger argon2 parameters for better security
         let params = argon2::Params::new(
-            65536, // 64MB memory cost
-            3,     // time cost 
-            1,     // parallelism
+            ARGON2_MEMORY_KIB,
+            ARGON2_ITERATIONS,  // time cost
+            ARGON2_PARALLELISM,
             Some(32) // output length
         ).map_err(|_| {
             io::Error::new(io::ErrorKind::InvalidData, "parameter error")
         })?;
         
-        let argon2 = Argon2::new(
-            argon2::Algorithm::Argon2id, // most secure variant
-            argon2::Version::V0x13,
-            params,
-        );
+        let argon2 = match keyfile {
+            Some(secret) => Argon2::new_with_secret(
+                secret,
+                argon2::Algorithm::Argon2id,
+                argon2::Version::V0x13,
+                params,
+            ).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "parameter error")
+            })?,
+            None => Argon2::new(
+                argon2::Algorithm::Argon2id, // most secure variant
+                argon2::Version::V0x13,
+                params,
+            ),
+        };
         
         let password_hash = argon2.hash_password(password.as_bytes(), &salt_string).map_err(|_| {
             io::Error::new(io::ErrorKind::InvalidData, "key derivation failed")
@@ -81,7 +198,7 @@ impl EncryptionManager {
     }
 
     // unlock the encryption manager with a password
-    pub fn unlock(&mut self, password: &str, salt: &[u8]) -> Result<(), io::Error> {
+    pub fn unlock(&mut self, password: &str, salt: &[u8], keyfile: Option<&[u8]>) -> Result<(), io::Error> {
         // validate password length for security
         if password.len() < MIN_PASSWORD_LENGTH {
             return Err(io::Error::new(io::ErrorKind::InvalidInput, "password too short"));
@@ -95,11 +212,19 @@ impl EncryptionManager {
             return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid salt length"));
         }
         
-        let key = self.derive_key(password, salt)?;
+        let key = self.derive_key(password, salt, keyfile)?;
         self.key = Some(key);
+        self.keyfile = keyfile.is_some();
         Ok(())
     }
 
+    // a fresh manager keyed for the password and salt, leaves any existing one alone
+    pub fn with_password(password: &str, salt: &[u8], keyfile: Option<&[u8]>) -> Result<Self, io::Error> {
+        let mut manager = Self::new();
+        manager.unlock(password, salt, keyfile)?;
+        Ok(manager)
+    }
+
     // lock the manager and clear keys from memory
     pub fn lock(&mut self) {
         if let Some(mut key) = self.key.take() {
@@ -140,6 +265,9 @@ impl EncryptionManager {
             salt: general_purpose::STANDARD.encode(&salt),
             nonce: general_purpose::STANDARD.encode(&nonce),
             data: general_purpose::STANDARD.encode(&ciphertext),
+            failed_unlocks: 0,
+            last_failed_unlock: None,
+            keyfile: self.keyfile,
         })
     }
 
@@ -0,0 +1,47 @@
+use std::fs;
+use std::io;
+use std::process::Command;
+use uuid::Uuid;
+use crate::fs_util::set_secure_permissions;
+
+// the configured command, then $VISUAL, then $EDITOR, then plain vi
+pub fn editor_command(configured: &str) -> String {
+    if !configured.trim().is_empty() {
+        return configured.trim().to_string();
+    }
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+// hand the text to an editor through a private temp file and read back whatever was saved,
+// the caller has to give up the terminal first
+pub fn edit(configured: &str, content: &str) -> io::Result<String> {
+    let command = editor_command(configured);
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no editor configured"))?;
+
+    let temp_dir = std::env::temp_dir().join(format!("tui-notes-edit-{}", Uuid::new_v4()));
+    fs::create_dir_all(&temp_dir)?;
+    set_secure_permissions(&temp_dir, true)?;
+    // .md so editors pick up markdown highlighting
+    let path = temp_dir.join("note.md");
+
+    let result = (|| {
+        fs::write(&path, content)?;
+        set_secure_permissions(&path, false)?;
+
+        let status = Command::new(program).args(parts).arg(&path).status()
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't run '{}': {}", program, e)))?;
+        if !status.success() {
+            return Err(io::Error::other(format!("'{}' exited with {}", program, status)));
+        }
+        fs::read_to_string(&path)
+    })();
+
+    // never leave the plaintext lying around
+    let _ = fs::remove_dir_all(&temp_dir);
+    result
+}
@@ -0,0 +1,16 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+// owner-only permissions on unix systems: 0700 for directories, 0600 for files
+#[cfg(unix)]
+pub fn set_secure_permissions(path: &Path, is_directory: bool) -> io::Result<()> {
+    let mode = if is_directory { 0o700 } else { 0o600 };
+    let mut perms = fs::metadata(path)?.permissions();
+    perms.set_mode(mode);
+    fs::set_permissions(path, perms)?;
+    Ok(())
+}
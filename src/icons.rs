@@ -0,0 +1,37 @@
+// markers for note and vault states, nerd font glyphs need a patched font in the terminal
+pub struct Icons {
+    pub pinned: &'static str,
+    pub favorite: &'static str,
+    pub locked: &'static str,
+    pub archived: &'static str,
+    pub tag: &'static str,
+    pub attachment: &'static str,
+    pub encrypted: &'static str,
+    pub unencrypted: &'static str,
+}
+
+const NERD_FONT: Icons = Icons {
+    pinned: "\u{f08d}",
+    favorite: "\u{f004}",
+    locked: "\u{f023}",
+    archived: "\u{f187}",
+    tag: "\u{f02b} ",
+    attachment: "\u{f0c6}",
+    encrypted: "\u{f023}",
+    unencrypted: "\u{f09c}",
+};
+
+const ASCII: Icons = Icons {
+    pinned: "*",
+    favorite: "+",
+    locked: "[L]",
+    archived: "[A]",
+    tag: "#",
+    attachment: "@",
+    encrypted: "[E]",
+    unencrypted: "[-]",
+};
+
+pub fn icons(use_icons: bool) -> &'static Icons {
+    if use_icons { &NERD_FONT } else { &ASCII }
+}
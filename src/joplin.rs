@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+// joplin's item types, from the type_ property
+const TYPE_NOTE: &str = "1";
+const TYPE_FOLDER: &str = "2";
+const TYPE_RESOURCE: &str = "4";
+const TYPE_TAG: &str = "5";
+const TYPE_NOTE_TAG: &str = "6";
+
+// notebooks nested deeper than this are probably a parent_id loop
+const MAX_NOTEBOOK_DEPTH: usize = 32;
+
+// a note from a .jex archive with its notebook and tags already resolved
+#[derive(Debug, Clone)]
+pub struct JoplinNote {
+    // name of the file in the archive, "<id>.md"
+    pub entry: String,
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    // "Parent/Child" for nested notebooks
+    pub notebook: String,
+    pub tags: Vec<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+pub struct JexContents {
+    pub notes: Vec<JoplinNote>,
+    // archive entry and why it wasn't imported
+    pub skipped: Vec<(String, String)>,
+}
+
+// every item is a markdown file: title, blank line, body, blank line, then "key: value"
+// properties (id, parent_id, timestamps, type_, ...)
+struct Item {
+    title: String,
+    body: String,
+    props: HashMap<String, String>,
+}
+
+impl Item {
+    fn prop(&self, key: &str) -> &str {
+        self.props.get(key).map(String::as_str).unwrap_or("")
+    }
+
+    // joplin keeps the dates the user sees in user_*_time, the others track syncing
+    fn time(&self, user_key: &str, key: &str) -> Option<DateTime<Utc>> {
+        [user_key, key]
+            .iter()
+            .find_map(|k| DateTime::parse_from_rfc3339(self.prop(k)).ok())
+            .map(|date| date.with_timezone(&Utc))
+    }
+}
+
+fn parse_property(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once(':')?;
+    let is_key = !key.is_empty() && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    is_key.then(|| (key, value.trim()))
+}
+
+fn parse_item(text: &str) -> Item {
+    let lines: Vec<&str> = text.lines().collect();
+    // the properties are the run of key: value lines at the very end
+    let mut split = lines.len();
+    while split > 0 && parse_property(lines[split - 1]).is_some() {
+        split -= 1;
+    }
+    let props = lines[split..]
+        .iter()
+        .filter_map(|line| parse_property(line))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    let head = &lines[..split];
+    let title = head.first().map(|line| line.trim().to_string()).unwrap_or_default();
+    let body = head.get(1..).unwrap_or(&[]).join("\n").trim_matches('\n').to_string();
+    Item { title, body, props }
+}
+
+// "Parent/Child" from a folder's parent_id chain
+fn notebook_path<'a>(folders: &'a HashMap<String, Item>, mut id: &'a str) -> String {
+    let mut names = Vec::new();
+    while let Some(folder) = folders.get(id) {
+        if names.len() >= MAX_NOTEBOOK_DEPTH {
+            break;
+        }
+        names.push(folder.title.replace('/', "-"));
+        id = folder.prop("parent_id");
+    }
+    names.reverse();
+    names.join("/")
+}
+
+// read a joplin export (.jex, a plain tar archive) and put notes, notebooks and tags together
+pub fn read_jex(path: &Path) -> io::Result<JexContents> {
+    let mut archive = tar::Archive::new(File::open(path)?);
+    let mut items = Vec::new();
+    let mut contents = JexContents::default();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        // the resources/ folder holds attachment data, their .md items are reported below
+        if !name.ends_with(".md") || name.starts_with("resources/") {
+            continue;
+        }
+
+        let mut text = String::new();
+        if entry.read_to_string(&mut text).is_err() {
+            contents.skipped.push((name, "not UTF-8 text".to_string()));
+            continue;
+        }
+        items.push((name, parse_item(&text)));
+    }
+
+    let mut folders = HashMap::new();
+    let mut tag_names = HashMap::new();
+    let mut note_tags: HashMap<String, Vec<String>> = HashMap::new();
+    let mut notes = Vec::new();
+    for (name, item) in items {
+        match item.prop("type_") {
+            TYPE_NOTE => notes.push((name, item)),
+            TYPE_FOLDER => {
+                folders.insert(item.prop("id").to_string(), item);
+            }
+            TYPE_TAG => {
+                tag_names.insert(item.prop("id").to_string(), item.title);
+            }
+            TYPE_NOTE_TAG => {
+                note_tags.entry(item.prop("note_id").to_string()).or_default().push(item.prop("tag_id").to_string());
+            }
+            TYPE_RESOURCE => contents.skipped.push((name, format!("attachment '{}' isn't imported", item.title))),
+            // sync metadata, revisions, master keys...
+            _ => {}
+        }
+    }
+
+    for (entry, item) in notes {
+        let id = item.prop("id").to_string();
+        let tags = note_tags
+            .get(&id)
+            .map(|ids| ids.iter().filter_map(|tag_id| tag_names.get(tag_id).cloned()).collect())
+            .unwrap_or_default();
+        contents.notes.push(JoplinNote {
+            entry,
+            notebook: notebook_path(&folders, item.prop("parent_id")),
+            created: item.time("user_created_time", "created_time"),
+            updated: item.time("user_updated_time", "updated_time"),
+            tags,
+            id,
+            title: item.title,
+            body: item.body,
+        });
+    }
+    Ok(contents)
+}
@@ -0,0 +1,103 @@
+use chrono::Utc;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::config::{Behavior, Config, LogLevel};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+// past this the log is moved to <name>.old at startup and a fresh one started
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+// the terminal is taken over by the app, so errors go to a file instead of stderr.
+// only messages are logged, never note text or passwords
+struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if let Ok(mut file) = self.file.lock() {
+            // nowhere left to report a failed log write
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+// log_file from the config, <config dir>/tui-notes.log when it's empty
+pub fn log_path(behavior: &Behavior) -> io::Result<PathBuf> {
+    if behavior.log_file.is_empty() {
+        Ok(Config::config_dir()?.join("tui-notes.log"))
+    } else {
+        Ok(PathBuf::from(&behavior.log_file))
+    }
+}
+
+// start logging at the configured level, nothing is opened with logging off
+pub fn init(behavior: &Behavior) -> io::Result<()> {
+    if behavior.log_level == LogLevel::Off {
+        return Ok(());
+    }
+    let path = log_path(behavior)?;
+    let file = open(&path)?;
+    log::set_boxed_logger(Box::new(FileLogger { file: Mutex::new(file) })).map_err(io::Error::other)?;
+    set_level(behavior.log_level);
+    Ok(())
+}
+
+// a level changed while running, only takes effect if logging was on at startup
+pub fn set_level(level: LogLevel) {
+    log::set_max_level(level.filter());
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::metadata(path).is_ok_and(|metadata| metadata.len() > MAX_LOG_SIZE) {
+        let mut old = path.as_os_str().to_os_string();
+        old.push(".old");
+        fs::rename(path, old)?;
+    }
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options.open(path)
+}
+
+impl LogLevel {
+    fn filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
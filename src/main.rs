@@ -1,5 +1,9 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
+    cursor::Show,
+    event::{
+        self, DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste, EnableFocusChange,
+        EnableMouseCapture, Event, KeyCode, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -7,58 +11,272 @@ use ratatui::{
     backend::CrosstermBackend,
     Terminal,
 };
-use std::{error::Error, io};
+use std::{error::Error, io, path::PathBuf, time::{Duration, Instant}};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 mod app;
+mod attachments;
+mod backup;
+mod bundle;
+mod change_journal;
+mod cli;
+mod clipboard;
 mod config;
+mod credentials;
+mod csv_notes;
+mod diff;
 mod encryption;
+mod external_editor;
+mod fs_util;
+mod icons;
+mod joplin;
+mod logging;
+mod markdown;
 mod note;
+mod query;
+mod recovery;
+mod saver;
+mod search_index;
+mod simplenote;
+mod spellcheck;
+mod stats;
+mod sync;
+mod theme;
 mod ui;
+mod vault_lock;
+mod vim;
+mod wrap;
 
 use app::App;
-use config::Config;
+use config::{Config, ConfigWatcher, Session};
 
 fn main() -> Result<(), Box<dyn Error>> {
-        let config = Config::load()?;
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let session = match take_options(&mut args) {
+        Ok(session) => session,
+        Err(err) => {
+            eprintln!("tui-notes: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let mut config = Config::load()?;
+    config.session = session;
+    if let Err(err) = logging::init(&config.behavior) {
+        eprintln!("tui-notes: not logging, {}", err);
+    }
+    if !config.invalid_settings.is_empty() {
+        log::warn!("invalid config settings reset to defaults: {}", config.invalid_settings.join(", "));
+    }
+
+    // any arguments left mean a headless subcommand, no terminal ui
+    if !args.is_empty() {
+        if let Err(err) = cli::run(&args, &config) {
+            log::error!("{}: {}", args[0], err);
+            eprintln!("tui-notes: {}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    log::info!("tui-notes {} starting", env!("CARGO_PKG_VERSION"));
+    install_panic_hook();
+    let stop = register_stop_signals()?;
+    let guard = TerminalGuard::enter()?;
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new(&config)?;
-    let res = run_app(&mut terminal, &mut app, &config);
+    let mut app = match App::new(&config) {
+        Ok(app) => app,
+        // e.g. --no-encryption-prompt without a password to go on
+        Err(err) => {
+            drop(guard);
+            log::error!("not starting: {}", err);
+            eprintln!("tui-notes: {}", err);
+            std::process::exit(1);
+        }
+    };
+    if !config.invalid_settings.is_empty() {
+        app.status_message.get_or_insert_with(|| {
+            format!("Invalid config settings reset to defaults: {} (old file in config.toml.bak)", config.invalid_settings.join(", "))
+        });
+    }
+    let res = run_app(&mut terminal, &mut app, &mut config, &stop);
+    // don't exit while a save is still being written
+    app.flush_autosave();
+    let flushed = app.note_manager.flush_saves();
+    // a journal is only left behind for text that didn't make it into the notes
+    let mut synced = Ok(());
+    if flushed.is_ok() {
+        app.sync_recovery();
+        synced = app.sync_before_exit(&config);
+    }
+    app.wipe();
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    drop(guard);
 
     if let Err(err) = res {
+        log::error!("exiting after an error: {}", err);
         println!("{:?}", err);
     }
+    if let Err(err) = flushed {
+        log::error!("saving before exit failed: {}", err);
+        println!("Failed to save notes: {}", err);
+    }
+    if let Err(err) = synced {
+        log::warn!("syncing before exit failed: {}", err);
+        println!("Couldn't sync with the server, the changes are kept in the local copy until the next time: {}", err);
+    }
+    log::logger().flush();
 
     Ok(())
 }
 
+// the options in front of the command (or of nothing, for the ui), removed from `args`. they
+// override the config for this run only
+fn take_options(args: &mut Vec<String>) -> io::Result<Session> {
+    let mut session = Session::default();
+    let mut taken = 0;
+    while let Some(arg) = args.get(taken) {
+        match arg.as_str() {
+            "--read-only" => session.read_only = true,
+            "--no-encryption-prompt" => session.no_encryption_prompt = true,
+            "--config" | "--notes-file" => {
+                let path = args.get(taken + 1).filter(|path| !path.is_empty()).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, format!("{} needs a path", arg))
+                })?;
+                if arg == "--config" {
+                    Config::use_config_file(PathBuf::from(path));
+                } else {
+                    session.notes_file = Some(path.clone());
+                }
+                taken += 1;
+            }
+            _ => break,
+        }
+        taken += 1;
+    }
+    args.drain(..taken);
+    Ok(session)
+}
+
+// raw mode and the alternate screen for as long as it lives. dropping it puts the terminal back,
+// also when main returns early with an error or a panic unwinds through it
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        enable_raw_mode()?;
+        // from here on a failure still has to be undone
+        let guard = TerminalGuard;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste, EnableFocusChange)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+// best effort, the terminal may already be (partly) restored
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste, DisableFocusChange, Show);
+}
+
+// the default hook would print into the alternate screen, where the message vanishes, with raw
+// mode mangling its line breaks. put the terminal back first, then let it print as usual
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        log::error!("panic: {}", info);
+        log::logger().flush();
+        default_hook(info);
+    }));
+}
+
+// set by SIGTERM (a shutdown, kill), SIGINT or SIGHUP (the terminal closing), the loop then saves
+// and leaves the way quitting does
+fn register_stop_signals() -> io::Result<Arc<AtomicBool>> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    let stop = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(SIGTERM, Arc::clone(&stop))?;
+    signal_hook::flag::register(SIGINT, Arc::clone(&stop))?;
+    #[cfg(unix)]
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&stop))?;
+    Ok(stop)
+}
+
+// redraw cadence while something is animating, regardless of input
+const TICK_RATE: Duration = Duration::from_millis(250);
+
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
-    config: &Config,
+    config: &mut Config,
+    stop: &AtomicBool,
 ) -> io::Result<()> {
+    let mut last_tick = Instant::now();
+    // without a config directory there's nothing to watch
+    let mut config_watcher = ConfigWatcher::new().ok();
+
     loop {
+        if stop.load(Ordering::Relaxed) {
+            return app.save_before_exit();
+        }
+        // config.toml edited outside the app, this hands the new config over just below
+        if config_watcher.as_mut().is_some_and(|watcher| watcher.changed()) {
+            app.reload_config(config);
+        }
+        // settings changed from inside the app, e.g. picking a theme
+        if let Some(updated) = app.config_update.take() {
+            logging::set_level(updated.behavior.log_level);
+            *config = updated;
+            // the app saves the config itself for some of these, that's no outside edit
+            if let Some(watcher) = config_watcher.as_mut() {
+                watcher.remember();
+            }
+        }
+        app.poll_loading(config)?;
+        app.run_autosave();
+        app.run_sequence_timeout(config)?;
+        app.run_recovery();
+        app.poll_saves();
+        app.check_external_changes();
+        app.run_scheduled_backup(config);
         terminal.draw(|f| ui::draw(f, app, config))?;
 
-        if event::poll(std::time::Duration::from_millis(config.behavior.ui_timeout_ms))? {
-            if let Event::Key(key) = event::read()? {
-                app.handle_input(key, config)?;
+        // only wake up early when there's an animation to keep moving
+        let idle_timeout = Duration::from_millis(config.behavior.ui_timeout_ms);
+        let timeout = if app.needs_tick() {
+            TICK_RATE.saturating_sub(last_tick.elapsed()).min(idle_timeout)
+        } else {
+            idle_timeout
+        };
+
+        if last_tick.elapsed() >= TICK_RATE {
+            app.on_tick();
+            last_tick = Instant::now();
+        }
+
+        if event::poll(timeout)? {
+            let event = event::read()?;
+            // redraw at the new size straight away
+            if let Event::Resize(..) = event {
+                terminal.autoresize()?;
+                continue;
+            }
+            if handle_event(app, event, config)? {
                 if app.should_quit {
                     return Ok(());
                 }
+                if app.external_edit_requested {
+                    run_external_editor(terminal, app, config)?;
+                    continue;
+                }
             }
 
             // batch process paste spam so the ui doesn't shit itself
@@ -66,15 +284,60 @@ fn run_app<B: ratatui::backend::Backend>(
             let max_events = config.behavior.max_events_per_frame;
             
             while events_processed < max_events 
-                && event::poll(std::time::Duration::from_millis(0))? {
-                if let Event::Key(key) = event::read()? {
-                    app.handle_input(key, config)?;
+                && event::poll(Duration::from_millis(0))? {
+                let event = event::read()?;
+                if let Event::Resize(..) = event {
+                    terminal.autoresize()?;
+                    break;
+                }
+                if handle_event(app, event, config)? {
                     if app.should_quit {
                         return Ok(());
                     }
+                    if app.external_edit_requested {
+                        run_external_editor(terminal, app, config)?;
+                        break;
+                    }
                     events_processed += 1;
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// true when the event was input the app acted on
+fn handle_event(app: &mut App, event: Event, config: &Config) -> io::Result<bool> {
+    match event {
+        // raw mode turns Ctrl+C into a key instead of SIGINT, it does what the signal does
+        Event::Key(key) if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.save_before_exit()?;
+            app.should_quit = true;
+        }
+        Event::Key(key) => app.handle_input(key, config)?,
+        // a bracketed paste arrives in one piece instead of as a key per character
+        Event::Paste(text) => app.handle_paste(&text, config)?,
+        Event::Mouse(mouse) => app.handle_mouse(mouse)?,
+        // switching to another window is a good moment to write what's been typed
+        Event::FocusLost => app.flush_autosave(),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+// give the terminal to the external editor and take it back once it exits
+fn run_external_editor<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    config: &Config,
+) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste, DisableFocusChange)?;
+    terminal.show_cursor()?;
+
+    let res = app.run_external_editor(config);
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste, EnableFocusChange)?;
+    terminal.clear()?;
+    res
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use crate::note::Note;
+
+// yaml strings are written json style ("..." with escapes), which every yaml parser reads
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("\"{}\"", value))
+}
+
+// the note as a markdown/text file: a yaml front-matter header with its metadata, then the content
+pub fn to_markdown(note: &Note) -> String {
+    let tags: Vec<String> = note.tags.iter().map(|tag| quote(tag)).collect();
+    let mut out = String::from("---\n");
+    out.push_str(&format!("id: {}\n", quote(&note.id)));
+    out.push_str(&format!("title: {}\n", quote(&note.title)));
+    out.push_str(&format!("created: {}\n", note.created_at.to_rfc3339()));
+    out.push_str(&format!("updated: {}\n", note.updated_at.to_rfc3339()));
+    out.push_str(&format!("tags: [{}]\n", tags.join(", ")));
+    if !note.notebook.is_empty() {
+        out.push_str(&format!("notebook: {}\n", quote(&note.notebook)));
+    }
+    out.push_str("---\n\n");
+    out.push_str(&note.content);
+    if !note.content.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+// a title turned into something every filesystem accepts, without the extension
+pub fn file_stem(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+        .take(100)
+        .collect();
+    // no hidden files or trailing dots (windows drops those)
+    let cleaned = cleaned.trim().trim_matches('.').trim();
+    if cleaned.is_empty() {
+        "untitled".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+// what an imported file says about itself in its front matter, everything is optional
+#[derive(Debug, Default)]
+pub struct FrontMatter {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    pub notebook: Option<String>,
+}
+
+// "value", 'value' or value
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if value.starts_with('"')
+        && let Ok(parsed) = serde_json::from_str::<String>(value)
+    {
+        return parsed;
+    }
+    if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        return value[1..value.len() - 1].replace("''", "'");
+    }
+    value.to_string()
+}
+
+// rfc3339 like we write it, or just a date like most other tools do
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = unquote(value);
+    if let Ok(date) = DateTime::parse_from_rfc3339(&value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+// [a, "b"] or a, b or a b, with or without the leading #
+fn parse_tag_list(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let comma_separated = value.starts_with('[') || value.contains(',');
+    value
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(|c: char| if comma_separated { c == ',' } else { c.is_whitespace() })
+        .map(|tag| unquote(tag).trim_start_matches('#').to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+// split a leading "---" front-matter block off the text. only the simple key: value
+// subset of yaml is understood (plus "tags:" followed by "- tag" lines), other keys are ignored
+pub fn parse(text: &str) -> (FrontMatter, &str) {
+    let mut front = FrontMatter::default();
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return (front, text);
+    };
+
+    let mut header_len = None;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        offset += line.len();
+        if trimmed == "---" || trimmed == "..." {
+            header_len = Some(offset);
+            break;
+        }
+    }
+    // no closing line, it wasn't front matter after all
+    let Some(header_len) = header_len else { return (front, text) };
+    let (header, body) = rest.split_at(header_len);
+
+    let mut in_tags = false;
+    for line in header.lines() {
+        if in_tags {
+            if let Some(item) = line.trim_start().strip_prefix("- ") {
+                front.tags.extend(parse_tag_list(item));
+                continue;
+            }
+            in_tags = false;
+        }
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        match key.trim() {
+            "id" if !value.is_empty() => front.id = Some(unquote(value)),
+            "title" if !value.is_empty() => front.title = Some(unquote(value)),
+            "created" | "date" => front.created = front.created.or(parse_date(value)),
+            "updated" | "modified" => front.updated = parse_date(value),
+            "tags" if value.is_empty() => in_tags = true,
+            "tags" => front.tags = parse_tag_list(value),
+            "notebook" => front.notebook = Some(unquote(value)),
+            _ => {}
+        }
+    }
+
+    (front, body)
+}
+
+// text of the first "# heading" line
+pub fn first_heading(body: &str) -> Option<&str> {
+    body.lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(str::trim)
+        .filter(|heading| !heading.is_empty())
+}
@@ -1,25 +1,33 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::io;
 use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
 use uuid::Uuid;
-use crate::encryption::{EncryptionManager, EncryptedFile, MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH};
+use crate::encryption::{self, EncryptionManager, EncryptedFile, MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH};
+use crate::fs_util::set_secure_permissions;
+use crate::saver::{self, DiskState, SaveJob, SaveWorker};
+use crate::stats;
+use crate::change_journal;
+use crate::query::SearchQuery;
+use crate::recovery::{self, Recovery};
+use crate::search_index::{self, SearchIndex};
+use crate::attachments::{self, Attachment};
+use crate::joplin;
+use crate::markdown;
+use crate::csv_notes::{self, CsvMapping};
+use crate::simplenote::{self, SimplenoteExport};
+use crate::sync::{self, SyncBase, SyncSummary};
+use crate::vault_lock::VaultLock;
 use base64::Engine;
+use zeroize::{Zeroize, Zeroizing};
 
-#[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
-
-// helper function to set secure permissions on unix systems
-#[cfg(unix)]
-fn set_secure_permissions(path: &std::path::Path, is_directory: bool) -> io::Result<()> {
-    let mode = if is_directory { 0o700 } else { 0o600 };
-    let mut perms = fs::metadata(path)?.permissions();
-    perms.set_mode(mode);
-    fs::set_permissions(path, perms)?;
-    Ok(())
-}
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+// edits closer together than this count as one revision
+const REVISION_INTERVAL: chrono::Duration = chrono::Duration::minutes(10);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Note {
@@ -30,6 +38,106 @@ pub struct Note {
     pub updated_at: DateTime<Utc>,
     #[serde(default)]
     pub pinned: bool,
+    // unlike pinning this doesn't change where the note sits in the list
+    #[serde(default, alias = "starred")]
+    pub favorite: bool,
+    #[serde(default)]
+    pub archived: bool,
+    // locked notes can't be edited until they're unlocked again
+    #[serde(default)]
+    pub locked: bool,
+    // position in the manual sort order (and among pinned notes in every order), 0 sorts first
+    #[serde(default)]
+    pub sort_index: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // empty means the note isn't filed in any notebook
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notebook: String,
+    // older versions, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revisions: Vec<Revision>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    // the content sealed with the note's own password. on disk `content` is empty then, in memory
+    // it holds the text once the note has been opened with its password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted: Option<EncryptedFile>,
+}
+
+// how the note list is ordered, pinned notes always come first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    #[default]
+    Updated,
+    Created,
+    Title,
+    Manual,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Updated => SortMode::Created,
+            SortMode::Created => SortMode::Title,
+            SortMode::Title => SortMode::Manual,
+            SortMode::Manual => SortMode::Updated,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Updated => "last updated",
+            SortMode::Created => "date created",
+            SortMode::Title => "title A-Z",
+            SortMode::Manual => "manual order",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub title: String,
+    pub content: String,
+    pub saved_at: DateTime<Utc>,
+}
+
+// a note as of the last load/save, for revisions and the change journal
+pub struct SavedNote {
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    pub trashed: bool,
+    pub sealed: bool,
+}
+
+impl SavedNote {
+    fn of(note: &Note) -> Self {
+        SavedNote {
+            title: note.title.clone(),
+            content: note.content.clone(),
+            tags: note.tags.clone(),
+            trashed: note.is_trashed(),
+            sealed: note.encrypted.is_some(),
+        }
+    }
+}
+
+// the text stays out of debug output
+impl std::fmt::Debug for SavedNote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SavedNote").field("trashed", &self.trashed).field("sealed", &self.sealed).finish_non_exhaustive()
+    }
+}
+
+impl Drop for SavedNote {
+    fn drop(&mut self) {
+        self.title.zeroize();
+        self.content.zeroize();
+    }
 }
 
 impl Note {
@@ -44,6 +152,16 @@ impl Note {
             created_at: now,
             updated_at: now,
             pinned: false,
+            favorite: false,
+            archived: false,
+            locked: false,
+            sort_index: 0,
+            deleted_at: None,
+            tags: Vec::new(),
+            notebook: String::new(),
+            revisions: Vec::new(),
+            attachments: Vec::new(),
+            encrypted: None,
         }
     }
 
@@ -61,6 +179,271 @@ impl Note {
         self.pinned = !self.pinned;
         self.updated_at = Utc::now();
     }
+
+    pub fn toggle_archived(&mut self) {
+        self.archived = !self.archived;
+        self.updated_at = Utc::now();
+    }
+
+    // a lock is a safety catch, not an edit, so it doesn't bump updated_at either
+    // overwrite the text in memory before the note is dropped
+    pub fn wipe(&mut self) {
+        self.title.zeroize();
+        self.content.zeroize();
+        for revision in &mut self.revisions {
+            revision.title.zeroize();
+            revision.content.zeroize();
+        }
+    }
+
+    pub fn toggle_locked(&mut self) {
+        self.locked = !self.locked;
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        if self.tags != tags {
+            self.tags = tags;
+            self.updated_at = Utc::now();
+        }
+    }
+
+    // adds whichever of `tags` the note doesn't have yet
+    pub fn add_tags(&mut self, tags: &[String]) {
+        let mut merged = self.tags.clone();
+        for tag in tags {
+            if !merged.contains(tag) {
+                merged.push(tag.clone());
+            }
+        }
+        self.set_tags(merged);
+    }
+
+    pub fn has_tag_containing(&self, fragment: &str) -> bool {
+        self.tags.iter().any(|tag| tag.contains(fragment))
+    }
+
+    pub fn is_trashed(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    // favorites are just a marker, so unlike pinning this doesn't bump updated_at
+    pub fn toggle_favorite(&mut self) {
+        self.favorite = !self.favorite;
+    }
+
+    // the lines shown under the title in lists
+    pub fn preview_lines(&self, count: usize, skip_blank: bool, skip_heading: bool) -> Vec<&str> {
+        let mut lines = self.content.lines().peekable();
+        if skip_blank {
+            while lines.next_if(|line| line.trim().is_empty()).is_some() {}
+        }
+        if skip_heading && lines.next_if(|line| line.trim_start().starts_with("# ")).is_some() && skip_blank {
+            while lines.next_if(|line| line.trim().is_empty()).is_some() {}
+        }
+        lines
+            .filter(|line| !skip_blank || !line.trim().is_empty())
+            .map(|line| line.trim_end())
+            .take(count)
+            .collect()
+    }
+
+    pub fn open_todo_count(&self) -> usize {
+        self.content.lines().filter(|line| matches!(parse_todo(line), Some((false, _)))).count()
+    }
+
+    // flip "- [ ]" <-> "- [x]" on the given line, false if it isn't a todo item
+    pub fn toggle_todo(&mut self, line_index: usize) -> bool {
+        let mut lines: Vec<String> = self.content.split('\n').map(|line| line.to_string()).collect();
+        let Some(line) = lines.get_mut(line_index) else { return false };
+        let Some((done, _)) = parse_todo(line) else { return false };
+
+        // the box sits right after the indent and the "- "
+        let mark = line.len() - line.trim_start().len() + 3;
+        line.replace_range(mark..mark + 1, if done { " " } else { "x" });
+
+        self.content = lines.join("\n");
+        self.updated_at = Utc::now();
+        true
+    }
+}
+
+// "- [ ] buy milk" -> Some((false, "buy milk")), "* [x] done" -> Some((true, "done"))
+pub fn parse_todo(line: &str) -> Option<(bool, &str)> {
+    let trimmed = line.trim_start();
+    let rest = trimmed
+        .strip_prefix("- ")
+        .or_else(|| trimmed.strip_prefix("* "))
+        .or_else(|| trimmed.strip_prefix("+ "))?;
+
+    let (done, text) = if let Some(text) = rest.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+        (true, text)
+    } else {
+        return None;
+    };
+
+    // "- [x]foo" isn't a checkbox
+    if !text.is_empty() && !text.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some((done, text.trim()))
+}
+
+// byte ranges of every occurrence of the query in the text, without overlaps
+pub fn find_matches(text: &str, query: &str, case_sensitive: bool) -> Vec<(usize, usize)> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    if case_sensitive {
+        return text.match_indices(query).map(|(start, found)| (start, start + found.len())).collect();
+    }
+
+    let mut matches = Vec::new();
+    let mut next_free = 0;
+    for (start, _) in text.char_indices() {
+        if start < next_free {
+            continue;
+        }
+        let mut rest = text[start..].char_indices();
+        let mut end = Some(start);
+        for wanted in query.chars() {
+            match rest.next() {
+                Some((offset, c)) if c.to_lowercase().eq(wanted.to_lowercase()) => end = Some(start + offset + c.len_utf8()),
+                _ => {
+                    end = None;
+                    break;
+                }
+            }
+        }
+        if let Some(end) = end {
+            matches.push((start, end));
+            next_free = end;
+        }
+    }
+    matches
+}
+
+// what to do when an imported note's id already exists in the vault
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergePolicy {
+    KeepNewer,
+    KeepBoth,
+    SkipExisting,
+}
+
+// what an export writes
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ExportFormat {
+    // one json file, the same map of notes as the vault, can be imported again
+    #[default]
+    Json,
+    // a folder with one markdown file per note
+    Markdown,
+    // the notes.json simplenote exports and imports
+    Simplenote,
+    // one row per note, for spreadsheets
+    Csv,
+    // the json export inside an EncryptedFile, same as an encrypted vault
+    Encrypted,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+impl std::fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} added, {} updated, {} skipped", self.added, self.updated, self.skipped)
+    }
+}
+
+// what a folder/archive import did with each file, paths are relative to the imported folder
+#[derive(Debug, Default, Clone)]
+pub struct ImportReport {
+    // file and the title of the note it became
+    pub imported: Vec<(PathBuf, String)>,
+    // file and why it was left out
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+// every file under `dir`, sorted, leaving out hidden files and folders (.obsidian, .git, ...)
+fn collect_files(dir: &std::path::Path, files: &mut Vec<PathBuf>, report: &mut ImportReport) -> io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<io::Result<_>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            // one unreadable folder shouldn't stop the whole import
+            if let Err(e) = collect_files(&path, files, report) {
+                report.skipped.push((path, e.to_string()));
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// "work, ideas #todo" -> ["work", "ideas", "todo"], lowercased and deduplicated
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(|c: char| c == ',' || c.is_whitespace()) {
+        let tag = tag.trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+// notebooks only exist through the notes filed in them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notebook {
+    pub name: String,
+    pub note_count: usize,
+}
+
+impl Notebook {
+    pub fn display_name(&self) -> &str {
+        if self.name.is_empty() { UNFILED_NOTEBOOK } else { &self.name }
+    }
+}
+
+pub const UNFILED_NOTEBOOK: &str = "Unfiled";
+
+// which subset of notes the list shows
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NoteFilter {
+    pub favorites_only: bool,
+    // None shows every notebook, Some("") only unfiled notes
+    pub notebook: Option<String>,
+    // the archive view shows archived notes and nothing else
+    pub archived: bool,
+}
+
+impl NoteFilter {
+    pub fn matches(&self, note: &Note) -> bool {
+        note.archived == self.archived && self.matches_scope(note)
+    }
+
+    // everything but the archive part, which searches leave to is:archived
+    pub fn matches_scope(&self, note: &Note) -> bool {
+        (!self.favorites_only || note.favorite) &&
+        self.notebook.as_ref().is_none_or(|notebook| &note.notebook == notebook)
+    }
+
+    // narrower than the whole vault, archive aside
+    pub fn is_scoped(&self) -> bool {
+        self.favorites_only || self.notebook.is_some()
+    }
 }
 
 #[derive(Debug)]
@@ -72,6 +455,40 @@ pub struct NoteManager {
     encryption: EncryptionManager,
     encryption_enabled: bool,
     salt: Option<Vec<u8>>,
+    keyfile: Option<PathBuf>,
+    // keys of the encrypted notes opened this session, their content is sealed again on every save
+    note_keys: HashMap<String, (EncryptionManager, Vec<u8>)>,
+    // wrong passwords so far and when the last one was tried, as recorded in the vault header
+    failed_unlocks: u32,
+    last_failed_unlock: Option<DateTime<Utc>>,
+    disk_state: Option<DiskState>,
+    // a save was refused because the file changed on disk, see save_notes
+    save_blocked: bool,
+    word_frequency_cache: Option<Vec<(String, usize)>>,
+    vault_stats_cache: Option<stats::VaultStats>,
+    // last search query and the ids it matched
+    search_cache: Option<(SearchQuery, Vec<String>)>,
+    search_index: SearchIndex,
+    // notes changed since the index last caught up with them
+    index_stale: bool,
+    // the index changed since it was last written next to the vault
+    index_unsaved: bool,
+    // every note as of the last load/save, to spot what a save changes
+    saved_state: HashMap<String, SavedNote>,
+    history_limit: usize,
+    change_journal: bool,
+    // changes a save couldn't write to the change journal, tried again with the next one
+    journal_pending: Vec<change_journal::Entry>,
+    sort_mode: SortMode,
+    saver: SaveWorker,
+    // notes being parsed on a background thread, see start_loading
+    loading: Option<mpsc::Receiver<io::Result<HashMap<String, Note>>>>,
+    // the file held plaintext notes but encryption is on, save them encrypted once loaded
+    migrate_after_load: bool,
+    // keeps other instances off the notes file while it's open here, see acquire_lock
+    lock: Option<VaultLock>,
+    // --read-only, every write to the vault is refused
+    read_only: bool,
 }
 
 impl NoteManager {
@@ -84,10 +501,34 @@ impl NoteManager {
             encryption: EncryptionManager::new(),
             encryption_enabled,
             salt: None,
+            keyfile: None,
+            note_keys: HashMap::new(),
+            failed_unlocks: 0,
+            last_failed_unlock: None,
+            disk_state: None,
+            save_blocked: false,
+            word_frequency_cache: None,
+            vault_stats_cache: None,
+            search_cache: None,
+            search_index: SearchIndex::default(),
+            index_stale: true,
+            index_unsaved: false,
+            saved_state: HashMap::new(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            change_journal: false,
+            journal_pending: Vec::new(),
+            sort_mode: SortMode::default(),
+            saver: SaveWorker::new(),
+            loading: None,
+            migrate_after_load: false,
+            lock: None,
+            read_only: false,
         };
         
-        if !encryption_enabled {
-            manager.load_notes()?;
+        if encryption_enabled {
+            manager.read_unlock_failures();
+        } else {
+            manager.start_loading()?;
         }
         Ok(manager)
     }
@@ -109,7 +550,8 @@ impl NoteManager {
         if !self.notes_file.exists() {
             // new encrypted vault - generate salt and enable encryption
             let salt = EncryptionManager::generate_salt();
-            self.encryption.unlock(password, &salt)?;
+            let keyfile = self.keyfile_secret()?;
+            self.encryption.unlock(password, &salt, keyfile.as_ref().map(|k| k.as_slice()))?;
             self.salt = Some(salt.to_vec());
             return Ok(());
         }
@@ -137,22 +579,121 @@ impl NoteManager {
                 return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid file format"));
             }
 
-            self.encryption.unlock(password, &salt)?;
+            // the file is behind when the last failure couldn't be written, so what's
+            // counted in memory is a floor
+            self.failed_unlocks = self.failed_unlocks.max(encrypted.failed_unlocks);
+            self.last_failed_unlock = self.last_failed_unlock.max(encrypted.last_failed_unlock);
+            if let Some(wait) = self.unlock_wait() {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    format!("too many wrong passwords, try again in {} s", wait.num_seconds() + 1),
+                ));
+            }
+
+            let keyfile = self.keyfile_for(&encrypted)?;
+            self.encryption.unlock(password, &salt, keyfile.as_ref().map(|k| k.as_slice()))?;
+            if let Ok(mut notes) = self.encryption.decrypt(&encrypted) {
+                // only a check, the notes are read again by start_loading
+                notes.zeroize();
+            } else {
+                self.encryption.lock();
+                self.failed_unlocks += 1;
+                self.last_failed_unlock = Some(Utc::now());
+                // if this can't be written (read-only vault) the in-memory count still
+                // gives the delay for as long as the app runs
+                let _ = self.write_unlock_failures(encrypted);
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, "invalid password or corrupted data"));
+            }
+            if self.failed_unlocks > 0 {
+                self.failed_unlocks = 0;
+                self.last_failed_unlock = None;
+                let _ = self.write_unlock_failures(encrypted);
+            }
+
             self.salt = Some(salt);
-            self.load_notes()?;
+            self.start_loading()?;
         } else {
             // file contains unencrypted notes - migrate to encrypted format
             let salt = EncryptionManager::generate_salt();
-            self.encryption.unlock(password, &salt)?;
+            let keyfile = self.keyfile_secret()?;
+            self.encryption.unlock(password, &salt, keyfile.as_ref().map(|k| k.as_slice()))?;
             self.salt = Some(salt.to_vec());
-            self.load_notes()?;
+            self.start_loading()?;
         }
 
         Ok(())
     }
 
+    pub fn set_keyfile(&mut self, keyfile: Option<PathBuf>) {
+        self.keyfile = keyfile;
+    }
+
+    // the configured keyfile's secret, read when it's needed so a vault without one
+    // isn't bothered by a missing file
+    fn keyfile_secret(&self) -> io::Result<Option<[u8; 32]>> {
+        let Some(path) = &self.keyfile else { return Ok(None) };
+        let contents = fs::read(path).map_err(|e| {
+            io::Error::new(e.kind(), format!("can't read the keyfile {}: {}", path.display(), e))
+        })?;
+        Ok(Some(encryption::keyfile_secret(&contents)))
+    }
+
+    // the keyfile secret an encrypted file was made with, if it was made with one
+    fn keyfile_for(&self, encrypted: &EncryptedFile) -> io::Result<Option<[u8; 32]>> {
+        if !encrypted.keyfile {
+            return Ok(None);
+        }
+        match self.keyfile_secret()? {
+            Some(secret) => Ok(Some(secret)),
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "this vault needs its keyfile, set keyfile in the config")),
+        }
+    }
+
+    pub fn failed_unlocks(&self) -> u32 {
+        self.failed_unlocks
+    }
+
+    // time left before another password may be tried, None when one can be tried now
+    pub fn unlock_wait(&self) -> Option<Duration> {
+        let last = self.last_failed_unlock?;
+        let delay = encryption::unlock_delay(self.failed_unlocks);
+        // a last attempt in the future (clock changes) never waits longer than the delay itself
+        let wait = (last + delay - Utc::now()).min(delay);
+        (wait > Duration::zero()).then_some(wait)
+    }
+
+    fn read_unlock_failures(&mut self) {
+        let Ok(content) = fs::read_to_string(&self.notes_file) else { return };
+        if let Ok(encrypted) = serde_json::from_str::<EncryptedFile>(&content) {
+            self.failed_unlocks = encrypted.failed_unlocks;
+            self.last_failed_unlock = encrypted.last_failed_unlock;
+        }
+    }
+
+    // only the header changes, the encrypted notes are written back as they were
+    // so there's nothing worth a .bak copy
+    fn write_unlock_failures(&self, mut encrypted: EncryptedFile) -> io::Result<()> {
+        self.check_writable()?;
+        encrypted.failed_unlocks = self.failed_unlocks;
+        encrypted.last_failed_unlock = self.last_failed_unlock;
+        let json = serde_json::to_string_pretty(&encrypted)?;
+        saver::write_atomically(&self.notes_file, json.as_bytes(), false)
+    }
+
+    pub fn notes_file(&self) -> &std::path::Path {
+        &self.notes_file
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption_enabled
+    }
+
     // check if this manager is ready to use (unlocked if encrypted)
     pub fn is_ready(&self) -> bool {
+        // saving a half loaded vault would wipe the file
+        if self.loading.is_some() {
+            return false;
+        }
         if self.encryption_enabled {
             self.encryption.is_unlocked()
         } else {
@@ -160,6 +701,49 @@ impl NoteManager {
         }
     }
 
+    // claim the notes file for this instance, fails if another one already has it open
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn check_writable(&self) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "the vault is open read-only"));
+        }
+        Ok(())
+    }
+
+    pub fn acquire_lock(&mut self) -> io::Result<()> {
+        if self.lock.is_none() {
+            self.lock = Some(VaultLock::acquire(&self.notes_file)?);
+        }
+        Ok(())
+    }
+
+    // forget the keys and the notes before another vault is opened or the app quits, save first
+    pub fn lock(&mut self) {
+        self.encryption.lock();
+        self.note_keys.clear();
+        self.wipe_notes();
+    }
+
+    // overwrite the text of every note, and the copies kept for history and change checks,
+    // so it doesn't linger in freed memory
+    fn wipe_notes(&mut self) {
+        for note in self.notes.values_mut() {
+            note.wipe();
+        }
+        self.notes.clear();
+        self.saved_state.clear();
+        self.journal_pending.clear();
+        self.sorted_note_ids.clear();
+        self.mark_dirty();
+    }
+
     // verify password without affecting the current encryption state
     pub fn verify_password(&self, password: &str) -> io::Result<()> {
         if !self.encryption_enabled {
@@ -200,150 +784,1589 @@ impl NoteManager {
         }
 
         // create a temporary encryption manager to test the password
+        let keyfile = self.keyfile_for(&encrypted)?;
         let mut temp_encryption = EncryptionManager::new();
-        temp_encryption.unlock(password, &salt)?;
-        temp_encryption.decrypt(&encrypted)?;
+        temp_encryption.unlock(password, &salt, keyfile.as_ref().map(|k| k.as_slice()))?;
+        temp_encryption.decrypt(&encrypted)?.zeroize();
         
         Ok(())
     }
 
 
-    pub fn add_note(&mut self, title: String, content: String) -> &Note {
-        let note = Note::new(title, content);
-        let id = note.id.clone();
-        self.notes.insert(id.clone(), note);
-        self.cache_dirty = true;
-        &self.notes[&id]
-    }
-
-
-    pub fn get_note_mut(&mut self, id: &str) -> Option<&mut Note> {
-        if let Some(note) = self.notes.get_mut(id) {
-            self.cache_dirty = true;
-            Some(note)
-        } else {
-            None
+    // re-encrypt the vault under a new password and a fresh salt. the current password has to
+    // check out first, and the file is rewritten in one atomic write so it's never half old, half new
+    pub fn change_password(&mut self, current: &str, new: &str) -> io::Result<()> {
+        self.verify_password(current)?;
+        if new.len() < MIN_PASSWORD_LENGTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "password too short"));
         }
-    }
-
-    pub fn delete_note(&mut self, id: &str) -> Option<Note> {
-        let result = self.notes.remove(id);
-        if result.is_some() {
-            self.cache_dirty = true;
+        if new.len() > MAX_PASSWORD_LENGTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "password too long"));
         }
-        result
-    }
 
-    pub fn get_all_notes(&mut self) -> Vec<&Note> {
-        self.update_sorted_cache();
-        self.sorted_note_ids
-            .iter()
-            .filter_map(|id| self.notes.get(id))
-            .collect()
-    }
+        // anything still queued is encrypted with the old key
+        self.flush_saves()?;
+        if self.disk_changed() {
+            return Err(io::Error::other("notes file changed on disk, save or reload it first"));
+        }
 
+        // the configured keyfile (or none) goes into the new key, so this is also how one is added or dropped
+        let salt = EncryptionManager::generate_salt();
+        let keyfile = self.keyfile_secret()?;
+        let encryption = EncryptionManager::with_password(new, &salt, keyfile.as_ref().map(|k| k.as_slice()))?;
+        let old_encryption = std::mem::replace(&mut self.encryption, encryption);
+        let old_salt = self.salt.replace(salt.to_vec());
 
-    pub fn search_notes(&mut self, query: &str) -> Vec<&Note> {
-        if query.is_empty() {
-            return self.get_all_notes();
+        if let Err(e) = self.rewrite_vault(Some(&old_encryption)) {
+            // nothing was replaced on disk, keep using the old password
+            self.encryption = old_encryption;
+            self.salt = old_salt;
+            return Err(e);
         }
-        
-        self.update_sorted_cache();
-        let query_lower = query.to_lowercase();
-        
-        self.sorted_note_ids
-            .iter()
-            .filter_map(|id| self.notes.get(id))
-            .filter(|note| {
-                note.title.to_lowercase().contains(&query_lower) ||
-                note.content.to_lowercase().contains(&query_lower)
-            })
-            .collect()
+        Ok(())
     }
 
-    fn update_sorted_cache(&mut self) {
-        if !self.cache_dirty {
-            return;
+    // encrypt a plain vault in place, the caller turns encryption_enabled on in the config
+    pub fn enable_encryption(&mut self, password: &str) -> io::Result<()> {
+        if self.encryption_enabled {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the vault is already encrypted"));
         }
-        
-        // pinned stuff goes first, then newest shit on top
-        let mut note_refs: Vec<(&String, &Note)> = self.notes.iter().collect();
-        note_refs.sort_by(|(_, a), (_, b)| {
-            match b.pinned.cmp(&a.pinned) {
-                std::cmp::Ordering::Equal => {
-                    b.updated_at.cmp(&a.updated_at)
-                }
-                other => other,
-            }
-        });
-        
-        self.sorted_note_ids = note_refs.into_iter().map(|(id, _)| id.clone()).collect();
-        self.cache_dirty = false;
-    }
-
-    pub fn save_notes(&self) -> io::Result<()> {
         if !self.is_ready() {
             return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
         }
-
-        // Ensure parent directory exists and has secure permissions
-        if let Some(parent) = self.notes_file.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent)?;
-                set_secure_permissions(parent, true)?;
-            }
+        if password.len() < MIN_PASSWORD_LENGTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "password too short"));
+        }
+        if password.len() > MAX_PASSWORD_LENGTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "password too long"));
+        }
+        self.flush_saves()?;
+        if self.disk_changed() {
+            return Err(io::Error::other("notes file changed on disk, save or reload it first"));
         }
 
-        let json = serde_json::to_string_pretty(&self.notes)?;
-        
-        if self.encryption_enabled {
-            let salt = self.salt.as_ref().ok_or_else(|| {
-                io::Error::new(io::ErrorKind::InvalidData, "no salt available for encryption")
-            })?;
-            let encrypted = self.encryption.encrypt(json.as_bytes(), salt)?;
-            let encrypted_json = serde_json::to_string_pretty(&encrypted)?;
-            fs::write(&self.notes_file, encrypted_json)?;
-        } else {
-            fs::write(&self.notes_file, json)?;
+        let salt = EncryptionManager::generate_salt();
+        let keyfile = self.keyfile_secret()?;
+        self.encryption = EncryptionManager::with_password(password, &salt, keyfile.as_ref().map(|k| k.as_slice()))?;
+        self.salt = Some(salt.to_vec());
+        self.encryption_enabled = true;
+
+        if let Err(e) = self.rewrite_vault(None) {
+            self.encryption = EncryptionManager::new();
+            self.salt = None;
+            self.encryption_enabled = false;
+            return Err(e);
         }
-        
-        // set secure permissions on the notes file
-        set_secure_permissions(&self.notes_file, false)?;
         Ok(())
     }
 
-    pub fn export_plaintext<P: Into<PathBuf>>(&self, export_file: P) -> io::Result<()> {
-        if !self.is_ready() {
-            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+    // store an encrypted vault as plain text again, the caller turns encryption_enabled off in the config
+    pub fn disable_encryption(&mut self, password: &str) -> io::Result<()> {
+        self.verify_password(password)?;
+        self.flush_saves()?;
+        if self.disk_changed() {
+            return Err(io::Error::other("notes file changed on disk, save or reload it first"));
         }
 
-        let json = serde_json::to_string_pretty(&self.notes)?;
-        let export_path = export_file.into();
-        
-        // ensure parent directory exists and has secure permissions
-        if let Some(parent) = export_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent)?;
-                set_secure_permissions(parent, true)?;
-            }
+        let old_encryption = std::mem::take(&mut self.encryption);
+        self.encryption_enabled = false;
+
+        if let Err(e) = self.rewrite_vault(Some(&old_encryption)) {
+            self.encryption = old_encryption;
+            self.encryption_enabled = true;
+            return Err(e);
         }
-        
-        fs::write(&export_path, json)?;
-        // set secure permissions on the export file
-        set_secure_permissions(&export_path, false)?;
+        self.salt = None;
+        self.failed_unlocks = 0;
+        self.last_failed_unlock = None;
         Ok(())
     }
 
-    fn load_notes(&mut self) -> io::Result<()> {
-        if !self.notes_file.exists() {
+    // write the notes file, the index and the attachments again after the key (or whether there
+    // is one) changed. `previous` reads the attachments as they are now. the notes file is
+    // replaced in one atomic write, the attachments only after it went through
+    fn rewrite_vault(&mut self, previous: Option<&EncryptionManager>) -> io::Result<()> {
+        self.check_writable()?;
+        let to = self.vault_key();
+        let attachments = attachments::prepare_rewrite(
+            &self.attachments_dir(),
+            self.notes.values().flat_map(|note| note.attachments.iter()),
+            previous,
+            to,
+        )?;
+
+        let changes = self.journal_changes();
+        self.record_revisions();
+        self.refresh_index();
+        // read with the old key now, written with the new one once the vault went through
+        let journal_path = change_journal::journal_path(&self.notes_file);
+        let journal = change_journal::read(&journal_path, previous);
+        let result = self.snapshot(self.notes_file.clone()).and_then(|mut job| {
+            // the index is encrypted with the vault key too, so it has to be rewritten as well
+            job.index = Some((search_index::index_path(&self.notes_file), self.search_index.to_json()?));
+            // the file being replaced is plaintext or under the old key, it mustn't end up in the .bak
+            job.keep_backup = false;
+            job.write()
+        });
+
+        match result {
+            Ok(state) => {
+                self.disk_state = Some(state);
+                self.index_unsaved = false;
+                // nor should the one left from the last save
+                if let Err(e) = saver::remove_backup(&self.notes_file) {
+                    log::error!("removing the old .bak copy failed: {}", e);
+                }
+                match &journal {
+                    Ok((entries, _)) if !entries.is_empty() => {
+                        if let Err(e) = change_journal::rewrite(&journal_path, entries, self.vault_key()) {
+                            log::error!("re-encrypting the change journal failed: {}", e);
+                        }
+                    }
+                    _ => {}
+                }
+                self.record_changes(changes);
+                attachments.commit()
+            }
+            Err(e) => {
+                self.journal_pending.extend(changes);
+                attachments.discard();
+                Err(e)
+            }
+        }
+    }
+
+    pub fn add_note(&mut self, title: String, content: String) -> &Note {
+        let note = Note::new(title, content);
+        let id = note.id.clone();
+        self.notes.insert(id.clone(), note);
+        self.mark_dirty();
+        &self.notes[&id]
+    }
+
+    pub fn get_note(&self, id: &str) -> Option<&Note> {
+        self.notes.get(id)
+    }
+
+    // every id, trashed notes included, in no particular order
+    pub fn note_ids(&self) -> impl Iterator<Item = &String> {
+        self.notes.keys()
+    }
+
+    pub fn get_note_mut(&mut self, id: &str) -> Option<&mut Note> {
+        if self.notes.contains_key(id) {
+            self.mark_dirty();
+            self.notes.get_mut(id)
+        } else {
+            None
+        }
+    }
+
+    // append a line to the note titled `title`, creating it if there isn't one yet
+    pub fn append_to_note_titled(&mut self, title: &str, line: &str) -> &Note {
+        let existing = self.notes
+            .values()
+            .find(|note| !note.is_trashed() && note.title == title)
+            .map(|note| note.id.clone())
+            // a line added to an encrypted note nobody opened would be sealed over its content
+            .filter(|id| !self.is_note_sealed(id));
+
+        let id = match existing {
+            Some(id) => id,
+            None => self.add_note(title.to_string(), String::new()).id.clone(),
+        };
+
+        if let Some(note) = self.notes.get_mut(&id) {
+            let content = if note.content.trim().is_empty() {
+                line.to_string()
+            } else {
+                format!("{}\n{}", note.content.trim_end_matches('\n'), line)
+            };
+            note.update_content(content);
+        }
+        self.mark_dirty();
+        &self.notes[&id]
+    }
+
+    // an encrypted note whose password hasn't been given yet this session
+    pub fn is_note_sealed(&self, id: &str) -> bool {
+        self.notes.get(id).is_some_and(|note| note.encrypted.is_some()) && !self.note_keys.contains_key(id)
+    }
+
+    // give a note its own password. it stays open until the app quits, the file only has it sealed
+    pub fn encrypt_note(&mut self, id: &str, password: &str) -> io::Result<()> {
+        let salt = EncryptionManager::generate_salt();
+        let encryption = EncryptionManager::with_password(password, &salt, None)?;
+        let note = self.notes.get(id).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "note not found"))?;
+        let sealed = encryption.encrypt(note.content.as_bytes(), &salt)?;
+        // the change journal would keep the text readable next to the file
+        self.redact_journal(id)?;
+        if let Some(note) = self.notes.get_mut(id) {
+            note.encrypted = Some(sealed);
+            // older versions would keep the text readable in the file
+            note.revisions.clear();
+            note.updated_at = Utc::now();
+        }
+        self.note_keys.insert(id.to_string(), (encryption, salt.to_vec()));
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // take a note's text out of every change already in the journal, written or still pending
+    fn redact_journal(&mut self, id: &str) -> io::Result<()> {
+        for entry in self.journal_pending.iter_mut().filter(|entry| entry.note_id == id) {
+            if let Some(mut content) = entry.content.take() {
+                content.zeroize();
+            }
+        }
+
+        let path = change_journal::journal_path(&self.notes_file);
+        let key = self.vault_key();
+        let (mut entries, _) = change_journal::read(&path, key.map(|(encryption, _)| encryption))?;
+        let mut redacted = false;
+        for entry in entries.iter_mut().filter(|entry| entry.note_id == id) {
+            if let Some(mut content) = entry.content.take() {
+                content.zeroize();
+                redacted = true;
+            }
+        }
+        if redacted {
+            self.check_writable()?;
+            change_journal::rewrite(&path, &entries, key)?;
+        }
+        Ok(())
+    }
+
+    pub fn unlock_note(&mut self, id: &str, password: &str) -> io::Result<()> {
+        let note = self.notes.get(id).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "note not found"))?;
+        let Some(encrypted) = &note.encrypted else { return Ok(()) };
+        let salt = base64::engine::general_purpose::STANDARD.decode(&encrypted.salt).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "invalid note format")
+        })?;
+        let encryption = EncryptionManager::with_password(password, &salt, None)?;
+        let content = encryption.decrypt(encrypted)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::PermissionDenied, "wrong password"))?;
+
+        if let Some(note) = self.notes.get_mut(id) {
+            note.content = content;
+            // opening isn't an edit, it mustn't leave an empty revision behind
+            self.saved_state.insert(id.to_string(), SavedNote::of(note));
+        }
+        self.note_keys.insert(id.to_string(), (encryption, salt));
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // back to a plain note, only after it's been opened with its password
+    pub fn remove_note_encryption(&mut self, id: &str) -> io::Result<()> {
+        if self.is_note_sealed(id) {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "the note has to be unlocked first"));
+        }
+        let note = self.notes.get_mut(id).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "note not found"))?;
+        note.encrypted = None;
+        note.updated_at = Utc::now();
+        self.note_keys.remove(id);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // notes replaced from disk (a reload or a merge) come back sealed, open them again with
+    // the keys from this session
+    fn reopen_notes(&mut self) {
+        let keys = std::mem::take(&mut self.note_keys);
+        for (id, (encryption, salt)) in keys {
+            let Some(note) = self.notes.get_mut(&id) else { continue };
+            let Some(encrypted) = &note.encrypted else { continue };
+            if note.content.is_empty() {
+                match encryption.decrypt(encrypted).ok().and_then(|bytes| String::from_utf8(bytes).ok()) {
+                    Some(content) => note.content = content,
+                    // sealed with another password now
+                    None => continue,
+                }
+            }
+            self.note_keys.insert(id, (encryption, salt));
+        }
+    }
+
+    // the notes as they go to disk, with the open encrypted notes sealed again
+    fn sealed_notes(&self) -> io::Result<HashMap<String, Note>> {
+        let mut notes = self.notes.clone();
+        for (id, (encryption, salt)) in &self.note_keys {
+            let Some(note) = notes.get_mut(id) else { continue };
+            note.encrypted = Some(encryption.encrypt(note.content.as_bytes(), salt)?);
+            // a copy of the plaintext, don't leave it behind in freed memory
+            note.content.zeroize();
+            for revision in &mut note.revisions {
+                revision.title.zeroize();
+                revision.content.zeroize();
+            }
+            note.revisions.clear();
+        }
+        Ok(notes)
+    }
+
+    // deleting only moves the note to the trash, purging removes it for good
+    pub fn delete_note(&mut self, id: &str) -> bool {
+        match self.notes.get_mut(id) {
+            Some(note) if !note.is_trashed() => {
+                note.deleted_at = Some(Utc::now());
+                self.mark_dirty();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // permanently remove trashed notes deleted more than `retention` ago
+    pub fn purge_trash(&mut self, retention: chrono::Duration) -> usize {
+        let cutoff = Utc::now() - retention;
+        let before = self.notes.len();
+        self.notes.retain(|_, note| match note.deleted_at {
+            Some(deleted_at) => deleted_at > cutoff,
+            None => true,
+        });
+        let purged = before - self.notes.len();
+        if purged > 0 {
+            self.mark_dirty();
+        }
+        purged
+    }
+
+    pub fn empty_trash(&mut self) -> usize {
+        let before = self.notes.len();
+        self.notes.retain(|_, note| !note.is_trashed());
+        let purged = before - self.notes.len();
+        if purged > 0 {
+            self.mark_dirty();
+        }
+        purged
+    }
+
+    // every notebook in use, sorted by name, with unfiled notes first
+    pub fn notebooks(&self) -> Vec<Notebook> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for note in self.notes.values().filter(|note| !note.is_trashed()) {
+            *counts.entry(note.notebook.as_str()).or_insert(0) += 1;
+        }
+
+        let mut notebooks: Vec<Notebook> = counts
+            .into_iter()
+            .map(|(name, note_count)| Notebook { name: name.to_string(), note_count })
+            .collect();
+        notebooks.sort_by_key(|notebook| notebook.name.to_lowercase());
+        notebooks
+    }
+
+    // every tag in use, sorted, for completion
+    pub fn all_tags(&self) -> Vec<String> {
+        let tags: BTreeSet<&String> = self.notes
+            .values()
+            .filter(|note| !note.is_trashed())
+            .flat_map(|note| note.tags.iter())
+            .collect();
+        tags.into_iter().cloned().collect()
+    }
+
+    // titles of the notes that aren't in the trash, sorted, for completing [[links]]
+    pub fn note_titles(&self) -> Vec<String> {
+        let titles: BTreeSet<&String> = self.notes
+            .values()
+            .filter(|note| !note.is_trashed() && !note.title.is_empty())
+            .map(|note| &note.title)
+            .collect();
+        titles.into_iter().cloned().collect()
+    }
+
+    pub fn move_to_notebook(&mut self, id: &str, notebook: &str) -> bool {
+        match self.notes.get_mut(id) {
+            Some(note) if note.notebook != notebook => {
+                note.notebook = notebook.to_string();
+                note.updated_at = Utc::now();
+                self.mark_dirty();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // copies of the given notes as they are now, None for ids that don't exist
+    pub fn snapshot_notes(&self, ids: &[String]) -> Vec<(String, Option<Note>)> {
+        ids.iter().map(|id| (id.clone(), self.notes.get(id).cloned())).collect()
+    }
+
+    // put notes back the way a snapshot had them, returns what they looked like before that
+    pub fn restore_notes(&mut self, snapshot: Vec<(String, Option<Note>)>) -> Vec<(String, Option<Note>)> {
+        let mut replaced = Vec::with_capacity(snapshot.len());
+        for (id, note) in snapshot {
+            let previous = match note {
+                Some(note) => self.notes.insert(id.clone(), note),
+                None => self.notes.remove(&id),
+            };
+            replaced.push((id, previous));
+        }
+        self.mark_dirty();
+        replaced
+    }
+
+    // trashed notes, most recently deleted first
+    pub fn trashed_notes(&self) -> Vec<&Note> {
+        let mut trashed: Vec<&Note> = self.notes.values().filter(|note| note.is_trashed()).collect();
+        trashed.sort_by_key(|note| std::cmp::Reverse(note.deleted_at));
+        trashed
+    }
+
+    pub fn restore_note(&mut self, id: &str) -> bool {
+        match self.notes.get_mut(id) {
+            Some(note) if note.is_trashed() => {
+                note.deleted_at = None;
+                self.mark_dirty();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // only works on notes already in the trash
+    pub fn purge_note(&mut self, id: &str) -> bool {
+        if !self.notes.get(id).is_some_and(|note| note.is_trashed()) {
+            return false;
+        }
+        self.notes.remove(id);
+        self.mark_dirty();
+        true
+    }
+
+    // open todo items per note, notes without any are left out
+    pub fn open_todo_counts(&self) -> HashMap<String, usize> {
+        self.notes
+            .values()
+            .filter(|note| !note.is_trashed())
+            .map(|note| (note.id.clone(), note.open_todo_count()))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    pub fn sort_mode(&self) -> SortMode {
+        self.sort_mode
+    }
+
+    pub fn set_sort_mode(&mut self, mode: SortMode) {
+        if self.sort_mode != mode {
+            self.sort_mode = mode;
+            self.mark_dirty();
+        }
+    }
+
+    // swap the note with its neighbour in the filtered list, pinned and unpinned notes
+    // stay in their own sections. unpinned notes only move in manual order
+    pub fn move_note(&mut self, id: &str, filter: &NoteFilter, up: bool) -> bool {
+        if self.sort_mode != SortMode::Manual && !self.notes.get(id).is_some_and(|note| note.pinned) {
+            return false;
+        }
+
+        let visible: Vec<String> = self.get_filtered_notes(filter).iter().map(|note| note.id.clone()).collect();
+        let Some(position) = visible.iter().position(|visible_id| visible_id == id) else { return false };
+        let neighbour = if up { position.checked_sub(1) } else { Some(position + 1) };
+        let Some(other) = neighbour.and_then(|i| visible.get(i)) else { return false };
+        if self.notes[other].pinned != self.notes[id].pinned {
+            return false;
+        }
+
+        // number everything in its current order first so no two notes share an index
+        for (i, sorted_id) in self.sorted_note_ids.iter().enumerate() {
+            if let Some(note) = self.notes.get_mut(sorted_id) {
+                note.sort_index = i as u64 + 1;
+            }
+        }
+        let (index, other_index) = (self.notes[id].sort_index, self.notes[other].sort_index);
+        if let Some(note) = self.notes.get_mut(id) {
+            note.sort_index = other_index;
+        }
+        if let Some(note) = self.notes.get_mut(other) {
+            note.sort_index = index;
+        }
+        self.mark_dirty();
+        true
+    }
+
+    // notes outside the trash
+    pub fn note_count(&self) -> usize {
+        self.notes.values().filter(|note| !note.is_trashed()).count()
+    }
+
+    pub fn trash_count(&self) -> usize {
+        self.notes.values().filter(|note| note.is_trashed()).count()
+    }
+
+    pub fn get_all_notes(&mut self) -> Vec<&Note> {
+        self.update_sorted_cache();
+        self.sorted_note_ids
+            .iter()
+            .filter_map(|id| self.notes.get(id))
+            .collect()
+    }
+
+
+    pub fn get_filtered_notes(&mut self, filter: &NoteFilter) -> Vec<&Note> {
+        self.update_sorted_cache();
+        self.sorted_note_ids
+            .iter()
+            .filter_map(|id| self.notes.get(id))
+            .filter(|note| filter.matches(note))
+            .collect()
+    }
+
+    // the matching notes with where the text matched in their content, if it did.
+    // with a scope only the notes in that notebook/favorites are searched
+    pub fn search_notes(&mut self, query: &str, case_sensitive: bool, scope: Option<&NoteFilter>) -> Vec<(&Note, Option<(usize, usize)>)> {
+        if query.is_empty() {
+            let filter = NoteFilter { archived: false, ..scope.cloned().unwrap_or_default() };
+            return self.get_filtered_notes(&filter).into_iter().map(|note| (note, None)).collect();
+        }
+        
+        self.update_sorted_cache();
+        let query = SearchQuery { scope: scope.cloned(), ..SearchQuery::parse(query, case_sensitive) };
+
+        // a note matching the new query also matches any query it narrows, so when the
+        // query only grew we just filter the previous results
+        let candidates = match self.search_cache.take() {
+            Some((previous, ids)) if query.narrows(&previous) => ids,
+            _ => self.indexed_candidates(&query.text),
+        };
+
+        let matches: Vec<String> = candidates
+            .into_iter()
+            .filter(|id| self.notes.get(id).is_some_and(|note| query.matches(note)))
+            .collect();
+        self.search_cache = Some((query, matches));
+
+        match &self.search_cache {
+            Some((query, ids)) => ids.iter().filter_map(|id| self.notes.get(id)).map(|note| (note, query.first_match(note))).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // the notes that can contain the text according to the index, in list order
+    fn indexed_candidates(&mut self, text: &str) -> Vec<String> {
+        self.refresh_index();
+        match self.search_index.candidates(text) {
+            Some(ids) => self.sorted_note_ids.iter().filter(|id| ids.contains(*id)).cloned().collect(),
+            None => self.sorted_note_ids.clone(),
+        }
+    }
+
+    fn refresh_index(&mut self) {
+        if self.index_stale {
+            self.index_unsaved |= self.search_index.refresh(&self.notes);
+            self.index_stale = false;
+        }
+    }
+
+    // the index saved next to the vault, None when there isn't one or it can't be used
+    fn load_index(&self) -> Option<SearchIndex> {
+        let content = fs::read_to_string(search_index::index_path(&self.notes_file)).ok()?;
+        let json = if self.encryption_enabled {
+            let encrypted: EncryptedFile = serde_json::from_str(&content).ok()?;
+            Zeroizing::new(String::from_utf8(self.encryption.decrypt(&encrypted).ok()?).ok()?)
+        } else {
+            Zeroizing::new(content)
+        };
+        SearchIndex::from_json(&json)
+    }
+
+    // write the recovery journal next to the vault, encrypted with the vault key when it has one
+    pub fn write_recovery(&self, entry: &Recovery) -> io::Result<()> {
+        self.check_writable()?;
+        let json = Zeroizing::new(serde_json::to_string(entry)?);
+        let path = recovery::recovery_path(&self.notes_file);
+        if self.encryption_enabled {
+            let salt = self.salt.as_ref().ok_or_else(|| io::Error::other("vault is locked"))?;
+            let encrypted = self.encryption.encrypt(json.as_bytes(), salt)?;
+            saver::write_atomically(&path, serde_json::to_string(&encrypted)?.as_bytes(), false)
+        } else {
+            saver::write_atomically(&path, json.as_bytes(), false)
+        }
+    }
+
+    // the journal a crash left behind, None when there is none
+    pub fn read_recovery(&self) -> io::Result<Option<Recovery>> {
+        let content = match fs::read_to_string(recovery::recovery_path(&self.notes_file)) {
+            Ok(content) => Zeroizing::new(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let json = if self.encryption_enabled {
+            let encrypted: EncryptedFile = serde_json::from_str(&content)?;
+            Zeroizing::new(String::from_utf8(self.encryption.decrypt(&encrypted)?).map_err(io::Error::other)?)
+        } else {
+            content
+        };
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    // true if there was a journal to remove
+    pub fn clear_recovery(&self) -> io::Result<bool> {
+        self.check_writable()?;
+        recovery::remove(&recovery::recovery_path(&self.notes_file))
+    }
+
+    // anything that changes notes invalidates the derived caches
+    fn mark_dirty(&mut self) {
+        self.cache_dirty = true;
+        self.word_frequency_cache = None;
+        self.vault_stats_cache = None;
+        self.search_cache = None;
+        self.index_stale = true;
+    }
+
+    pub fn vault_stats(&mut self) -> &stats::VaultStats {
+        let live_notes = self.notes.values().filter(|note| !note.is_trashed());
+        self.vault_stats_cache.get_or_insert_with(|| stats::vault_stats(live_notes))
+    }
+
+    pub fn word_frequencies(&mut self) -> &[(String, usize)] {
+        if self.word_frequency_cache.is_none() {
+            let live_notes = self.notes.values().filter(|note| !note.is_trashed());
+            self.word_frequency_cache = Some(stats::word_frequencies(live_notes, stats::TOP_WORDS));
+        }
+        self.word_frequency_cache.as_deref().unwrap_or(&[])
+    }
+
+    fn update_sorted_cache(&mut self) {
+        if !self.cache_dirty {
+            return;
+        }
+        
+        // pinned stuff goes first in the order you gave it, then whatever the sort mode says
+        // (trash stays out of the way)
+        let mode = self.sort_mode;
+        let mut note_refs: Vec<(&String, &Note)> = self.notes.iter().filter(|(_, note)| !note.is_trashed()).collect();
+        note_refs.sort_by(|(_, a), (_, b)| {
+            match b.pinned.cmp(&a.pinned) {
+                std::cmp::Ordering::Equal if a.pinned && a.sort_index != b.sort_index => a.sort_index.cmp(&b.sort_index),
+                std::cmp::Ordering::Equal => match mode {
+                    SortMode::Updated => b.updated_at.cmp(&a.updated_at),
+                    SortMode::Created => b.created_at.cmp(&a.created_at),
+                    SortMode::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+                    SortMode::Manual => a.sort_index.cmp(&b.sort_index).then_with(|| b.updated_at.cmp(&a.updated_at)),
+                },
+                other => other,
+            }
+        });
+        
+        self.sorted_note_ids = note_refs.into_iter().map(|(id, _)| id.clone()).collect();
+        self.cache_dirty = false;
+    }
+
+    // serialize now, encrypt and write on the save worker (see poll_saves)
+    pub fn save_notes(&mut self) -> io::Result<()> {
+        self.check_writable()?;
+        // never write over changes someone else made, the app asks what to do about them first
+        if self.disk_changed() {
+            self.save_blocked = true;
+            return Err(io::Error::other("notes file changed on disk, not overwriting it"));
+        }
+        let changes = self.journal_changes();
+        self.record_changes(changes);
+        self.record_revisions();
+        let mut job = self.snapshot(self.notes_file.clone())?;
+        self.refresh_index();
+        if self.index_unsaved {
+            job.index = Some((search_index::index_path(&self.notes_file), self.search_index.to_json()?));
+            self.index_unsaved = false;
+        }
+        self.saver.submit(job)
+    }
+
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+    }
+
+    // keep a change journal from the next save on (or stop)
+    pub fn set_change_journal(&mut self, enabled: bool) {
+        self.change_journal = enabled;
+    }
+
+    // key and salt to encrypt with, None for a plain vault
+    fn vault_key(&self) -> Option<(&EncryptionManager, &[u8])> {
+        match (&self.salt, self.encryption_enabled) {
+            (Some(salt), true) => Some((&self.encryption, salt.as_slice())),
+            _ => None,
+        }
+    }
+
+    // what this save changes for the change journal, taken before record_revisions moves
+    // saved_state on
+    fn journal_changes(&self) -> Vec<change_journal::Entry> {
+        if !self.change_journal {
+            return Vec::new();
+        }
+        change_journal::changes(&self.saved_state, &self.notes)
+    }
+
+    // append to the change journal. a failed write is tried again with the next save, the save
+    // itself goes ahead
+    fn record_changes(&mut self, entries: Vec<change_journal::Entry>) {
+        self.journal_pending.extend(entries);
+        if self.journal_pending.is_empty() {
+            return;
+        }
+        match change_journal::append(&change_journal::journal_path(&self.notes_file), &self.journal_pending, self.vault_key()) {
+            Ok(()) => self.journal_pending.clear(),
+            Err(e) => log::error!("writing the change journal failed: {}", e),
+        }
+    }
+
+    // what the change journal has on one note, oldest first, and how many lines of the journal
+    // couldn't be read
+    pub fn journal_entries(&self, id: &str) -> io::Result<(Vec<change_journal::Entry>, usize)> {
+        if !self.is_ready() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+        }
+        let key = self.encryption_enabled.then_some(&self.encryption);
+        let (mut entries, unreadable) = change_journal::read(&change_journal::journal_path(&self.notes_file), key)?;
+        entries.retain(|entry| entry.note_id == id);
+        Ok((entries, unreadable))
+    }
+
+    // keep the last saved version of every note this save changes, but only once per
+    // editing burst, otherwise auto-save would store a revision per keystroke
+    fn record_revisions(&mut self) {
+        let now = Utc::now();
+
+        if self.history_limit > 0 {
+            for note in self.notes.values_mut() {
+                let Some(saved) = self.saved_state.get(&note.id) else { continue };
+                if saved.title == note.title && saved.content == note.content {
+                    continue;
+                }
+                let recent = note.revisions.last().is_some_and(|r| now - r.saved_at < REVISION_INTERVAL);
+                if !recent {
+                    note.revisions.push(Revision { title: saved.title.clone(), content: saved.content.clone(), saved_at: now });
+                }
+                if note.revisions.len() > self.history_limit {
+                    let excess = note.revisions.len() - self.history_limit;
+                    note.revisions.drain(..excess);
+                }
+            }
+        }
+
+        self.remember_saved_state();
+    }
+
+    fn remember_saved_state(&mut self) {
+        self.saved_state = self.notes
+            .values()
+            .map(|note| (note.id.clone(), SavedNote::of(note)))
+            .collect();
+    }
+
+    // swap a note back to one of its revisions, the current text becomes a revision itself
+    pub fn restore_revision(&mut self, id: &str, index: usize) -> bool {
+        let Some(note) = self.notes.get_mut(id) else { return false };
+        let Some(revision) = note.revisions.get(index).cloned() else { return false };
+
+        note.revisions.push(Revision {
+            title: note.title.clone(),
+            content: note.content.clone(),
+            saved_at: Utc::now(),
+        });
+        note.update_title(revision.title);
+        note.update_content(revision.content);
+        self.mark_dirty();
+        true
+    }
+
+    // picks up finished background saves, Some(result) if one completed since last time
+    pub fn poll_saves(&mut self) -> Option<io::Result<()>> {
+        let result = self.saver.poll()?;
+        Some(self.finish_save(result))
+    }
+
+    // wait for any queued save to hit the disk (used on quit)
+    pub fn flush_saves(&mut self) -> io::Result<()> {
+        match self.saver.flush() {
+            Some(result) => self.finish_save(result),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_saving(&self) -> bool {
+        self.saver.is_busy()
+    }
+
+    fn finish_save(&mut self, result: io::Result<DiskState>) -> io::Result<()> {
+        let state = result?;
+        // our own write, don't mistake it for an external change
+        self.disk_state = Some(state);
+        Ok(())
+    }
+
+    // true when a save was refused because of an external change that hasn't been dealt with
+    pub fn has_blocked_save(&self) -> bool {
+        self.save_blocked
+    }
+
+    // save even though the file changed on disk, the other program's changes are lost
+    pub fn overwrite_disk(&mut self) -> io::Result<()> {
+        self.check_writable()?;
+        self.record_disk_state();
+        self.save_blocked = false;
+        self.save_notes()
+    }
+
+    // take every note the file on disk has that we don't, or has a newer version of, then
+    // save the result over it. returns how many notes came from disk
+    pub fn merge_from_disk(&mut self) -> io::Result<usize> {
+        let theirs = match self.read_vault()? {
+            Some((json, _)) => parse_notes(&json)?,
+            None => HashMap::new(),
+        };
+
+        let mut taken = 0;
+        for (id, note) in theirs {
+            if self.notes.get(&id).is_none_or(|mine| note.updated_at > mine.updated_at) {
+                self.notes.insert(id, note);
+                taken += 1;
+            }
+        }
+        self.reopen_notes();
+        self.mark_dirty();
+        self.overwrite_disk()?;
+        Ok(taken)
+    }
+
+    // merge with the copy of the vault at `remote` and leave the same notes on both sides, see sync.rs.
+    // the vault file and the attachments either side is missing go across as they are on disk
+    pub fn sync(&mut self, remote: &str) -> io::Result<SyncSummary> {
+        self.check_writable()?;
+        let backend = sync::backend(remote)?;
+        self.flush_saves()?;
+        let name = self.notes_file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "notes.json".to_string());
+
+        let theirs = match backend.fetch(&name)? {
+            Some(data) => {
+                let content = String::from_utf8(data).map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("the remote vault is not valid utf-8: {}", e))
+                })?;
+                if content.trim().is_empty() { HashMap::new() } else { parse_notes(&self.decode_vault(content)?.0)? }
+            }
+            None => HashMap::new(),
+        };
+        let their_attachments: HashSet<String> = theirs.values().flat_map(|note| &note.attachments).map(|a| a.hash.clone()).collect();
+        let merge = sync::merge(&SyncBase::read(&self.notes_file, remote), self.sealed_notes()?, theirs);
+
+        // what stays ours is kept as it is in memory, with the open encrypted notes still open
+        for id in &merge.removed {
+            self.notes.remove(id);
+            self.note_keys.remove(id);
+        }
+        for id in &merge.from_remote {
+            if let Some(note) = merge.notes.get(id) {
+                self.notes.insert(id.clone(), note.clone());
+            }
+        }
+        self.reopen_notes();
+        self.mark_dirty();
+        self.overwrite_disk()?;
+        self.flush_saves()?;
+        backend.push(&name, &fs::read(&self.notes_file)?)?;
+
+        let dir = self.attachments_dir();
+        for hash in merge.notes.values().flat_map(|note| &note.attachments).map(|a| &a.hash).collect::<BTreeSet<_>>() {
+            let remote_name = format!("attachments/{}", hash);
+            let local = dir.join(hash);
+            if !their_attachments.contains(hash) && local.exists() {
+                backend.push(&remote_name, &fs::read(&local)?)?;
+            } else if !local.exists()
+                && let Some(data) = backend.fetch(&remote_name)?
+            {
+                fs::create_dir_all(&dir)?;
+                saver::write_atomically(&local, &data, false)?;
+            }
+        }
+
+        // as written, saving can add revisions to what the merge put together
+        SyncBase::write(&self.notes_file, remote, &self.sealed_notes()?)?;
+        Ok(merge.summary)
+    }
+
+    fn snapshot(&self, path: PathBuf) -> io::Result<SaveJob> {
+        if !self.is_ready() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+        }
+
+        let json = if self.note_keys.is_empty() {
+            serde_json::to_string_pretty(&self.notes)?
+        } else {
+            serde_json::to_string_pretty(&self.sealed_notes()?)?
+        };
+
+        let encryption = if self.encryption_enabled {
+            let salt = self.salt.as_ref().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "no salt available for encryption")
+            })?;
+            Some((self.encryption.clone(), salt.clone()))
+        } else {
+            None
+        };
+
+        Ok(SaveJob { path, json, encryption, index: None, keep_backup: true })
+    }
+
+    // write the vault to a new file and make that the active one from now on
+    pub fn save_as(&mut self, path: PathBuf) -> io::Result<()> {
+        self.check_writable()?;
+        // anything still queued belongs to the old file
+        self.flush_saves()?;
+        // don't write over a vault another instance has open
+        let lock = match &self.lock {
+            Some(_) if path != self.notes_file => Some(VaultLock::acquire(&path)?),
+            _ => None,
+        };
+        self.write_vault_to(&path)?;
+        self.copy_attachments_to(&path)?;
+        self.carry_over_journals(&path)?;
+        self.notes_file = path;
+        if lock.is_some() {
+            self.lock = lock;
+        }
+        self.record_disk_state();
+        // the new file gets its own index with the next save
+        self.index_unsaved = true;
+        Ok(())
+    }
+
+    // the change journal is copied so the history goes on in the new file, an unsaved
+    // editor recovery moves with the vault. backups stay where they are
+    fn carry_over_journals(&self, notes_file: &std::path::Path) -> io::Result<()> {
+        let (from, to) = (change_journal::journal_path(&self.notes_file), change_journal::journal_path(notes_file));
+        if from != to && from.exists() {
+            fs::copy(&from, &to)?;
+            set_secure_permissions(&to, false)?;
+        }
+        let (from, to) = (recovery::recovery_path(&self.notes_file), recovery::recovery_path(notes_file));
+        if from != to && from.exists() {
+            fs::copy(&from, &to)?;
+            set_secure_permissions(&to, false)?;
+            recovery::remove(&from)?;
+        }
+        Ok(())
+    }
+
+    // write a copy of the vault without switching to it
+    pub fn save_copy(&self, path: &std::path::Path) -> io::Result<()> {
+        self.write_vault_to(path)?;
+        self.copy_attachments_to(path)
+    }
+
+    // attachments live next to the notes file, so a vault written elsewhere needs its own copies
+    fn copy_attachments_to(&self, notes_file: &std::path::Path) -> io::Result<()> {
+        let (from, to) = (attachments::attachments_dir(&self.notes_file), attachments::attachments_dir(notes_file));
+        if from == to {
             return Ok(());
         }
+        for attachment in self.notes.values().flat_map(|note| &note.attachments) {
+            let target = to.join(&attachment.hash);
+            if !target.exists() {
+                fs::create_dir_all(&to)?;
+                fs::copy(from.join(&attachment.hash), target)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn attachments_dir(&self) -> PathBuf {
+        attachments::attachments_dir(&self.notes_file)
+    }
+
+    // store a copy of `source` (encrypted along with the vault) and list it on the note
+    pub fn attach_file(&mut self, id: &str, source: &std::path::Path) -> io::Result<Attachment> {
+        if !self.is_ready() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+        }
+        if !self.notes.contains_key(id) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "note not found"));
+        }
+
+        let dir = self.attachments_dir();
+        let attachment = if self.encryption_enabled {
+            let salt = self.salt.as_ref().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "no salt available for encryption")
+            })?;
+            attachments::store(&dir, source, Some((&self.encryption, salt)))?
+        } else {
+            attachments::store(&dir, source, None)?
+        };
+
+        if let Some(note) = self.get_note_mut(id) {
+            note.attachments.push(attachment.clone());
+            note.updated_at = Utc::now();
+        }
+        Ok(attachment)
+    }
+
+    // decrypt (if needed) to a temp file and open it with the default app
+    pub fn open_attachment(&self, id: &str, index: usize) -> io::Result<PathBuf> {
+        let attachment = self.notes.get(id).and_then(|note| note.attachments.get(index)).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "attachment not found")
+        })?;
+        let encryption = if self.encryption_enabled { Some(&self.encryption) } else { None };
+        attachments::open(&self.attachments_dir(), attachment, encryption)
+    }
+
+    // take the attachment off the note, the stored file goes too once no note uses it
+    pub fn remove_attachment(&mut self, id: &str, index: usize) -> io::Result<Option<Attachment>> {
+        let Some(note) = self.notes.get_mut(id).filter(|note| index < note.attachments.len()) else {
+            return Ok(None);
+        };
+        let removed = note.attachments.remove(index);
+        note.updated_at = Utc::now();
+        self.mark_dirty();
+
+        let still_used = self.notes.values().any(|note| note.attachments.iter().any(|a| a.hash == removed.hash));
+        if !still_used {
+            attachments::remove(&self.attachments_dir(), &removed.hash)?;
+        }
+        Ok(Some(removed))
+    }
+
+    // write the vault (encrypted if enabled) to an arbitrary path, right away
+    fn write_vault_to(&self, path: &std::path::Path) -> io::Result<()> {
+        self.snapshot(path.to_path_buf())?.write()?;
+        Ok(())
+    }
+
+    // remember the file's mtime and hash so we can tell when someone else writes to it
+    fn record_disk_state(&mut self) {
+        self.disk_state = DiskState::read(&self.notes_file);
+    }
+
+    // true if the notes file was modified by something other than us since we last touched it
+    pub fn disk_changed(&mut self) -> bool {
+        if self.saver.is_busy() || self.loading.is_some() {
+            // the file is probably mid-write by us
+            return false;
+        }
+        let mtime = fs::metadata(&self.notes_file).and_then(|m| m.modified()).ok();
+        if mtime.is_none() || self.disk_state.as_ref().is_some_and(|known| known.mtime == mtime) {
+            return false;
+        }
+
+        // the mtime moved, but only different contents count as a change
+        match DiskState::read(&self.notes_file) {
+            Some(current) if self.disk_state.as_ref().is_some_and(|known| known.hash == current.hash) => {
+                self.disk_state = Some(current);
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    // throw away the in-memory notes and load whatever is on disk now
+    pub fn reload_from_disk(&mut self) -> io::Result<()> {
+        let previous = std::mem::take(&mut self.notes);
+        if let Err(e) = self.load_notes() {
+            self.notes = previous;
+            // don't keep nagging about the same change
+            self.record_disk_state();
+            self.save_blocked = false;
+            return Err(e);
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // copy of the in-memory vault next to the notes file, kept encrypted if the vault is
+    pub fn export_sidecar(&self) -> io::Result<PathBuf> {
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+        let file_name = format!(
+            "{}.conflict-{}.json",
+            self.notes_file.file_stem().and_then(|s| s.to_str()).unwrap_or("notes"),
+            timestamp
+        );
+        let sidecar = self.notes_file.with_file_name(file_name);
+
+        if self.encryption_enabled {
+            self.write_vault_to(&sidecar)?;
+        } else {
+            self.export_plaintext(&sidecar, ExportFormat::Json)?;
+        }
+        Ok(sidecar)
+    }
+
+    pub fn export_plaintext<P: Into<PathBuf>>(&self, export_file: P, format: ExportFormat) -> io::Result<()> {
+        match format {
+            ExportFormat::Json => self.write_export(&self.notes, export_file.into()),
+            ExportFormat::Markdown => self.write_markdown_export(self.notes.values().collect(), export_file.into()),
+            ExportFormat::Simplenote => {
+                let notes: Vec<&Note> = self.notes.values().collect();
+                self.write_export(&simplenote::export(&notes), export_file.into())
+            }
+            ExportFormat::Csv => {
+                let notes: Vec<&Note> = self.notes.values().collect();
+                self.write_export_file(&csv_notes::export(&notes)?, export_file.into())
+            }
+            ExportFormat::Encrypted => self.export_encrypted(None, export_file, None),
+        }
+    }
+
+    // same formats as export_plaintext, but only the given notes
+    pub fn export_notes<P: Into<PathBuf>>(&self, ids: &[String], export_file: P, format: ExportFormat) -> io::Result<()> {
+        let notes: HashMap<&String, &Note> = self.notes.iter().filter(|(id, _)| ids.contains(id)).collect();
+        match format {
+            ExportFormat::Json => self.write_export(&notes, export_file.into()),
+            ExportFormat::Markdown => self.write_markdown_export(notes.into_values().collect(), export_file.into()),
+            ExportFormat::Simplenote => {
+                let notes: Vec<&Note> = notes.into_values().collect();
+                self.write_export(&simplenote::export(&notes), export_file.into())
+            }
+            ExportFormat::Csv => {
+                let notes: Vec<&Note> = notes.into_values().collect();
+                self.write_export_file(&csv_notes::export(&notes)?, export_file.into())
+            }
+            ExportFormat::Encrypted => self.export_encrypted(Some(ids), export_file, None),
+        }
+    }
+
+    // the notes (all of them, or just `ids`) encrypted with `password`, or with the vault's own
+    // key when there's none. the file can be opened as an encrypted vault with that password
+    pub fn export_encrypted<P: Into<PathBuf>>(&self, ids: Option<&[String]>, export_file: P, password: Option<&str>) -> io::Result<()> {
+        let notes: HashMap<&String, &Note> = self.notes.iter().filter(|(id, _)| ids.is_none_or(|ids| ids.contains(id))).collect();
+        let json = serde_json::to_string_pretty(&notes)?;
+
+        let encrypted = match password {
+            Some(password) => {
+                // a fresh salt, so the export doesn't share a key with the vault
+                let salt = EncryptionManager::generate_salt();
+                let mut encryption = EncryptionManager::new();
+                encryption.unlock(password, &salt, None)?;
+                encryption.encrypt(json.as_bytes(), &salt)?
+            }
+            None if self.encryption_enabled => {
+                let salt = self.salt.as_ref().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "no salt available for encryption")
+                })?;
+                self.encryption.encrypt(json.as_bytes(), salt)?
+            }
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "the vault isn't encrypted, the export needs a password")),
+        };
+        self.write_export(&encrypted, export_file.into())
+    }
+
+    // one <title>.md per note in a new (or empty) folder, trashed notes are left out
+    fn write_markdown_export(&self, mut notes: Vec<&Note>, dir: PathBuf) -> io::Result<()> {
+        if !self.is_ready() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+        }
+        // never mix the export with (or overwrite) files that are already there
+        if dir.exists() && fs::read_dir(&dir)?.next().is_some() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} is not an empty folder", dir.display())));
+        }
+        fs::create_dir_all(&dir)?;
+        set_secure_permissions(&dir, true)?;
+
+        // oldest first, so the same vault always gets the same file names
+        notes.retain(|note| !note.is_trashed());
+        notes.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+
+        let mut used = HashSet::new();
+        for note in notes {
+            let stem = markdown::file_stem(&note.title);
+            let mut name = format!("{}.md", stem);
+            let mut counter = 2;
+            // titles that only differ in case still collide on some filesystems
+            while !used.insert(name.to_lowercase()) {
+                name = format!("{} ({}).md", stem, counter);
+                counter += 1;
+            }
+            let path = dir.join(&name);
+            fs::write(&path, markdown::to_markdown(note))?;
+            set_secure_permissions(&path, false)?;
+        }
+        Ok(())
+    }
+
+    // a single note as markdown/text with a front-matter header, whatever the extension
+    pub fn export_note<P: Into<PathBuf>>(&self, id: &str, export_file: P) -> io::Result<()> {
+        let note = self.notes.get(id).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "note not found"))?;
+        self.write_export_file(&markdown::to_markdown(note), export_file.into())
+    }
+
+    fn write_export<T: Serialize>(&self, notes: &T, export_path: PathBuf) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(notes)?;
+        self.write_export_file(&json, export_path)
+    }
+
+    fn write_export_file(&self, contents: &str, export_path: PathBuf) -> io::Result<()> {
+        if !self.is_ready() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+        }
+
+        // ensure parent directory exists and has secure permissions
+        if let Some(parent) = export_path.parent() {
+            if !parent.exists() {
+                fs::create_dir_all(parent)?;
+                set_secure_permissions(parent, true)?;
+            }
+        }
+        
+        fs::write(&export_path, contents)?;
+        // set secure permissions on the export file
+        set_secure_permissions(&export_path, false)?;
+        Ok(())
+    }
+
+    // merge notes from a plaintext json export (the format export_plaintext writes)
+    pub fn import_json<P: AsRef<std::path::Path>>(&mut self, import_file: P, policy: MergePolicy) -> io::Result<ImportSummary> {
+        if !self.is_ready() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+        }
+
+        let content = fs::read_to_string(import_file)?;
+        if EncryptionManager::is_file_encrypted(&content) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "cannot import an encrypted file, export it as plaintext first"));
+        }
+
+        // accept both the id -> note map we export and a bare array of notes
+        let incoming: Vec<Note> = match serde_json::from_str::<HashMap<String, Note>>(&content) {
+            Ok(map) => map.into_values().collect(),
+            Err(_) => serde_json::from_str::<Vec<Note>>(&content).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("failed to parse import file: {}", e))
+            })?,
+        };
+
+        let mut summary = ImportSummary::default();
+        for mut note in incoming {
+            match self.notes.get(&note.id) {
+                None => {
+                    self.notes.insert(note.id.clone(), note);
+                    summary.added += 1;
+                }
+                Some(existing) => match policy {
+                    MergePolicy::KeepNewer => {
+                        if note.updated_at > existing.updated_at {
+                            self.notes.insert(note.id.clone(), note);
+                            summary.updated += 1;
+                        } else {
+                            summary.skipped += 1;
+                        }
+                    }
+                    MergePolicy::KeepBoth => {
+                        // mint a fresh id for the incoming copy, everything else stays as-is
+                        note.id = Uuid::new_v4().to_string();
+                        self.notes.insert(note.id.clone(), note);
+                        summary.added += 1;
+                    }
+                    MergePolicy::SkipExisting => {
+                        summary.skipped += 1;
+                    }
+                },
+            }
+        }
+
+        self.mark_dirty();
+        Ok(summary)
+    }
+
+    // turn every .md/.txt file under `dir` into a note. subfolders become notebooks unless the
+    // file's front matter names one, files that were exported from this vault aren't imported twice
+    pub fn import_dir<P: AsRef<std::path::Path>>(&mut self, dir: P) -> io::Result<ImportReport> {
+        if !self.is_ready() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+        }
+
+        let dir = dir.as_ref();
+        let mut files = Vec::new();
+        let mut report = ImportReport::default();
+        collect_files(dir, &mut files, &mut report)?;
+        for (path, _) in &mut report.skipped {
+            *path = path.strip_prefix(dir).unwrap_or(path).to_path_buf();
+        }
+
+        for path in files {
+            let relative = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+            match self.import_file(&path, &relative) {
+                Ok(title) => report.imported.push((relative, title)),
+                Err(reason) => report.skipped.push((relative, reason)),
+            }
+        }
+
+        self.mark_dirty();
+        Ok(report)
+    }
+
+    // notes from a joplin export, with their notebooks, tags and dates. joplin ids are kept,
+    // so importing the same export twice doesn't duplicate anything
+    pub fn import_jex<P: AsRef<std::path::Path>>(&mut self, archive: P) -> io::Result<ImportReport> {
+        if !self.is_ready() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+        }
+
+        let contents = joplin::read_jex(archive.as_ref())?;
+        let mut report = ImportReport::default();
+        report.skipped.extend(contents.skipped.into_iter().map(|(entry, reason)| (PathBuf::from(entry), reason)));
+
+        for joplin_note in contents.notes {
+            let entry = PathBuf::from(&joplin_note.entry);
+            if self.notes.contains_key(&joplin_note.id) {
+                report.skipped.push((entry, "already in the vault".to_string()));
+                continue;
+            }
+
+            let title = if joplin_note.title.is_empty() { "Untitled".to_string() } else { joplin_note.title };
+            let mut note = Note::new(title.clone(), joplin_note.body);
+            if !joplin_note.id.is_empty() {
+                note.id = joplin_note.id;
+            }
+            note.updated_at = joplin_note.updated.unwrap_or(note.updated_at);
+            note.created_at = joplin_note.created.unwrap_or(note.created_at).min(note.updated_at);
+            note.tags = parse_tags(&joplin_note.tags.join(","));
+            note.notebook = joplin_note.notebook;
+
+            self.notes.insert(note.id.clone(), note);
+            report.imported.push((entry, title));
+        }
+
+        self.mark_dirty();
+        Ok(report)
+    }
+
+    // notes from a simplenote notes.json. ids survive a round trip through simplenote, so a note
+    // that's already here is updated when the imported copy was modified later
+    pub fn import_simplenote<P: AsRef<std::path::Path>>(&mut self, import_file: P) -> io::Result<ImportReport> {
+        if !self.is_ready() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+        }
+
+        let content = fs::read_to_string(import_file)?;
+        let export: SimplenoteExport = serde_json::from_str(&content).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("not a simplenote export: {}", e))
+        })?;
+
+        let mut report = ImportReport::default();
+        let incoming = export.active_notes.into_iter().map(|n| (n, false))
+            .chain(export.trashed_notes.into_iter().map(|n| (n, true)));
+        for (simple, trashed) in incoming {
+            let entry = PathBuf::from(&simple.id);
+            let (title, content) = simple.title_and_content();
+
+            let existing = self.notes.get(&simple.id);
+            if existing.is_some_and(|note| note.updated_at >= simple.last_modified) {
+                report.skipped.push((entry, "already up to date".to_string()));
+                continue;
+            }
+            let label = if existing.is_some() { format!("{} (updated)", title) } else { title.clone() };
+
+            // keep what simplenote doesn't know about (notebook, attachments, history...)
+            let mut note = existing.cloned().unwrap_or_else(|| Note::new(title.clone(), String::new()));
+            if !simple.id.is_empty() {
+                note.id = simple.id.clone();
+            }
+            note.title = title;
+            note.content = content;
+            note.created_at = simple.creation_date.min(simple.last_modified);
+            note.updated_at = simple.last_modified;
+            note.pinned = simple.is_pinned();
+            note.tags = parse_tags(&simple.tags.join(","));
+            note.deleted_at = trashed.then_some(simple.last_modified);
+
+            self.notes.insert(note.id.clone(), note);
+            report.imported.push((entry, label));
+        }
+
+        self.mark_dirty();
+        Ok(report)
+    }
+
+    // notes from the rows of a csv file, read through the column mapping the user picked.
+    // rows with an id that's already in the vault are skipped, so re-importing an export is harmless
+    pub fn import_csv<P: AsRef<std::path::Path>>(&mut self, import_file: P, mapping: &CsvMapping) -> io::Result<ImportReport> {
+        if !self.is_ready() {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "notes manager is not ready"));
+        }
+        if !mapping.is_usable() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "map a column to the title or the content"));
+        }
+
+        let mut report = ImportReport::default();
+        for row in csv_notes::read_rows(import_file.as_ref(), mapping)? {
+            let row = match row {
+                Ok(row) => row,
+                Err((line, reason)) => {
+                    report.skipped.push((PathBuf::from(format!("line {}", line)), reason));
+                    continue;
+                }
+            };
+            let entry = PathBuf::from(format!("line {}", row.line));
+            if row.title.is_none() && row.content.is_none() {
+                report.skipped.push((entry, "empty row".to_string()));
+                continue;
+            }
+            if row.id.as_ref().is_some_and(|id| self.notes.contains_key(id)) {
+                report.skipped.push((entry, "already in the vault".to_string()));
+                continue;
+            }
+
+            let content = row.content.unwrap_or_default();
+            // no title column, use the first line of the content like a new note would
+            let title = row.title.unwrap_or_else(|| {
+                let first = content.lines().next().unwrap_or("").trim().trim_start_matches('#').trim();
+                if first.is_empty() { "Untitled".to_string() } else { first.chars().take(100).collect() }
+            });
+
+            let mut note = Note::new(title.clone(), content);
+            if let Some(id) = row.id {
+                note.id = id;
+            }
+            note.updated_at = row.updated.unwrap_or(note.updated_at);
+            note.created_at = row.created.unwrap_or(note.created_at).min(note.updated_at);
+            note.pinned = row.pinned;
+            note.tags = parse_tags(&row.tags);
+
+            self.notes.insert(note.id.clone(), note);
+            report.imported.push((entry, title));
+        }
+
+        self.mark_dirty();
+        Ok(report)
+    }
+
+    // the new note's title, or why the file was skipped
+    fn import_file(&mut self, path: &std::path::Path, relative: &std::path::Path) -> Result<String, String> {
+        let is_text = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| ["md", "markdown", "txt"].contains(&e.to_lowercase().as_str()));
+        if !is_text {
+            return Err("not a .md or .txt file".to_string());
+        }
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        let text = String::from_utf8(bytes).map_err(|_| "not UTF-8 text".to_string())?;
+
+        let (front, body) = markdown::parse(&text);
+        if front.id.as_ref().is_some_and(|id| self.notes.contains_key(id)) {
+            return Err("already in the vault".to_string());
+        }
+
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let title = front.title.clone()
+            .or_else(|| markdown::first_heading(body).map(str::to_string))
+            .unwrap_or(stem);
+        let content = body.trim_start_matches(['\r', '\n']).trim_end().to_string();
+
+        let mut note = Note::new(title.clone(), content);
+        if let Some(id) = front.id {
+            note.id = id;
+        }
+        // the file's own dates when the front matter doesn't have them
+        let metadata = fs::metadata(path).ok();
+        let modified = metadata.as_ref().and_then(|m| m.modified().ok()).map(DateTime::<Utc>::from);
+        let created = metadata.as_ref().and_then(|m| m.created().ok()).map(DateTime::<Utc>::from);
+        note.updated_at = front.updated.or(modified).unwrap_or(note.updated_at);
+        note.created_at = front.created.or(created).or(modified).unwrap_or(note.created_at).min(note.updated_at);
+        note.tags = parse_tags(&front.tags.join(","));
+        note.notebook = front.notebook.unwrap_or_else(|| {
+            relative
+                .parent()
+                .map(|parent| parent.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+                .unwrap_or_default()
+        });
+
+        self.notes.insert(note.id.clone(), note);
+        Ok(title)
+    }
+
+    fn load_notes(&mut self) -> io::Result<()> {
+        let Some((json, needs_migration)) = self.read_vault()? else { return Ok(()) };
+        let notes = parse_notes(&json)?;
+        self.install_notes(notes, needs_migration)
+    }
+
+    // like load_notes, but the json is parsed on a background thread so a big vault doesn't
+    // hold up the first frame. reading and decrypting stay here, a wrong password or an
+    // unexpected file format should still fail right away. all of the content is still
+    // parsed up front, notes aren't loaded lazily
+    fn start_loading(&mut self) -> io::Result<()> {
+        let Some((json, needs_migration)) = self.read_vault()? else { return Ok(()) };
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(parse_notes(&json));
+        });
+        self.loading = Some(receiver);
+        self.migrate_after_load = needs_migration;
+        Ok(())
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading.is_some()
+    }
+
+    // non-blocking, Some once the background load has finished
+    pub fn poll_loading(&mut self) -> Option<io::Result<()>> {
+        let result = match self.loading.as_ref()?.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Err(mpsc::TryRecvError::Disconnected) => Err(io::Error::other("notes loader stopped unexpectedly")),
+        };
+        self.loading = None;
+        Some(result.and_then(|notes| self.install_notes(notes, self.migrate_after_load)))
+    }
+
+    // blocks until the background load is done, for when there's no ui to keep responsive
+    pub fn wait_for_loading(&mut self) -> io::Result<()> {
+        let Some(loading) = self.loading.take() else { return Ok(()) };
+        let notes = loading.recv().map_err(|_| io::Error::other("notes loader stopped unexpectedly"))?;
+        self.install_notes(notes?, self.migrate_after_load)
+    }
+
+    fn install_notes(&mut self, notes: HashMap<String, Note>, needs_migration: bool) -> io::Result<()> {
+        self.notes = notes;
+        self.save_blocked = false;
+        self.mark_dirty();
+        // a missing or damaged index is built again from scratch, and then saved with the next save
+        self.search_index = match self.load_index() {
+            Some(index) => index,
+            None => {
+                self.index_unsaved = true;
+                SearchIndex::build(&self.notes)
+            }
+        };
+        self.refresh_index();
+        self.reopen_notes();
+        self.remember_saved_state();
+        self.record_disk_state();
+        
+        // if we loaded unencrypted notes but encryption is enabled, migrate them immediately
+        if needs_migration && !self.read_only {
+            self.save_notes()?;
+        }
+        
+        Ok(())
+    }
+
+    // the notes file as json (decrypted if needed) and whether it still has to be encrypted,
+    // None when there's no file or it's empty
+    // the notes json, wiped from memory once it's parsed and dropped
+    fn read_vault(&mut self) -> io::Result<Option<(Zeroizing<String>, bool)>> {
+        if !self.notes_file.exists() {
+            return Ok(None);
+        }
 
         let content = fs::read_to_string(&self.notes_file)?;
         if content.trim().is_empty() {
-            return Ok(());
+            self.record_disk_state();
+            return Ok(None);
         }
+        self.decode_vault(content).map(Some)
+    }
 
+    // the json in a vault file's contents (decrypted if needed) and whether it still has to be encrypted
+    fn decode_vault(&self, content: String) -> io::Result<(Zeroizing<String>, bool)> {
         let (json, needs_migration) = if self.encryption_enabled {
             if !self.encryption.is_unlocked() {
                 return Err(io::Error::new(io::ErrorKind::PermissionDenied, "encryption key not available"));
@@ -359,10 +2382,10 @@ impl NoteManager {
                 let json = String::from_utf8(decrypted_bytes).map_err(|e| {
                     io::Error::new(io::ErrorKind::InvalidData, format!("decrypted data is not valid utf-8: {}", e))
                 })?;
-                (json, false)
+                (Zeroizing::new(json), false)
             } else {
                 // file contains unencrypted notes - load them and mark for encryption migration
-                (content, true)
+                (Zeroizing::new(content), true)
             }
         } else {
             // check if file contains encrypted data when encryption is disabled
@@ -372,22 +2395,175 @@ impl NoteManager {
                     "ENCRYPTED_FILE_DETECTED: The notes file appears to be encrypted, but encryption is disabled in config. Please enable encryption in config or use a different notes file."
                 ));
             }
-            (content, false)
+            (Zeroizing::new(content), false)
         };
 
-        self.notes = serde_json::from_str(&json).map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("failed to parse notes data: {}", e),
-            )
-        })?;
-        self.cache_dirty = true;
-        
-        // if we loaded unencrypted notes but encryption is enabled, migrate them immediately
-        if needs_migration {
-            self.save_notes()?;
+        Ok((json, needs_migration))
+    }
+}
+
+// the notes don't outlive the manager in memory, the cli drops it without locking
+impl Drop for NoteManager {
+    fn drop(&mut self) {
+        self.wipe_notes();
+    }
+}
+
+fn parse_notes(json: &str) -> io::Result<HashMap<String, Note>> {
+    serde_json::from_str(json).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("failed to parse notes data: {}", e),
+        )
+    })
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> (NoteManager, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("tui-notes-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut manager = NoteManager::new(dir.join("notes.json"), false).unwrap();
+        manager.wait_for_loading().unwrap();
+        (manager, dir)
+    }
+
+    fn search(manager: &mut NoteManager, query: &str, case_sensitive: bool, scope: Option<&NoteFilter>) -> Vec<(String, Option<(usize, usize)>)> {
+        manager.search_notes(query, case_sensitive, scope).into_iter().map(|(note, at)| (note.id.clone(), at)).collect()
+    }
+
+    // each step runs on whatever the step before left in the cache, and is checked against a
+    // search that starts from nothing
+    #[test]
+    fn cached_search_matches_a_full_scan() {
+        let (mut manager, dir) = manager();
+        let notes: [(&str, &str, &[&str], &str, bool); 5] = [
+            ("Meeting notes", "Agenda for the Monday meeting", &["work"], "work", true),
+            ("meeting prep", "slides and the MEETING room", &["work", "prep"], "", false),
+            ("Shopping", "milk, eggs, snacks for the meeting", &["home"], "home", false),
+            ("Work log", "met with the team", &["work"], "work", false),
+            ("Archived meeting", "the old meeting", &[], "", false),
+        ];
+        for (title, content, tags, notebook, pinned) in notes {
+            let id = manager.add_note(title.to_string(), content.to_string()).id.clone();
+            let note = manager.get_note_mut(&id).unwrap();
+            note.tags = tags.iter().map(|tag| tag.to_string()).collect();
+            note.notebook = notebook.to_string();
+            note.pinned = pinned;
+            note.archived = title.starts_with("Archived");
         }
-        
-        Ok(())
+
+        let work = NoteFilter { notebook: Some("work".to_string()), ..NoteFilter::default() };
+        let steps: [(&str, bool, Option<&NoteFilter>); 16] = [
+            ("m", false, None),
+            ("me", false, None),
+            ("meet", false, None),
+            ("meeting", false, None),
+            ("meeting #wo", false, None),
+            ("meeting #work", false, None),
+            ("meeting #work pinned:true", false, None),
+            ("meeting #work", false, None),
+            ("meeting", false, None),
+            ("meeting", true, None),
+            ("MEETING", true, None),
+            ("meeting", false, None),
+            ("meeting", false, Some(&work)),
+            ("meeting the", false, Some(&work)),
+            ("meeting is:archived", false, None),
+            ("mee", false, None),
+        ];
+        for (query, case_sensitive, scope) in steps {
+            let cached = search(&mut manager, query, case_sensitive, scope);
+            let cache = manager.search_cache.take();
+            let full = search(&mut manager, query, case_sensitive, scope);
+            manager.search_cache = cache;
+            assert_eq!(cached, full, "query {:?}, case sensitive {}, scope {:?}", query, case_sensitive, scope);
+        }
+        fs::remove_dir_all(dir).ok();
+    }
+
+    fn preview(content: &str, skip_blank: bool) -> Vec<String> {
+        let note = Note::new("title".to_string(), content.to_string());
+        note.preview_lines(2, skip_blank, true).into_iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn preview_skips_leading_blank_lines() {
+        assert_eq!(preview("\n  \nfirst\n\nsecond\nthird", true), ["first", "second"]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn preview_of_a_heading_only_note_is_empty() {
+        assert!(preview("# Heading", true).is_empty());
+        assert!(preview("\n# Heading\n\n", true).is_empty());
+    }
+
+    #[test]
+    fn preview_skips_blank_lines_after_the_heading() {
+        assert_eq!(preview("# Heading\n\n\nbody\nmore", true), ["body", "more"]);
+        // only a heading at the top is skipped
+        assert_eq!(preview("intro\n# Heading", true), ["intro", "# Heading"]);
+    }
+
+    #[test]
+    fn preview_keeps_blank_lines_when_asked_to() {
+        assert_eq!(preview("\nfirst\nsecond", false), ["", "first"]);
+        assert_eq!(preview("# Heading\n\nbody", false), ["", "body"]);
+        let note = Note::new("title".to_string(), "# Heading\nbody".to_string());
+        assert_eq!(note.preview_lines(2, false, false), ["# Heading", "body"]);
+    }
+
+    // a read-only vault can't record wrong passwords in its header, so the count kept in
+    // memory has to hold the delay on its own
+    #[test]
+    fn unlock_delay_outlasts_a_header_that_cant_be_written() {
+        let dir = std::env::temp_dir().join(format!("tui-notes-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notes.json");
+        fs::write(&path, r#"{"magic": "ENCRYPTED_NOTES", "salt": "AAAAAAAAAAAAAAAAAAAAAA==", "nonce": "", "data": ""}"#).unwrap();
+        let mut manager = NoteManager::new(path, true).unwrap();
+        manager.set_read_only(true);
+        manager.failed_unlocks = 5;
+        manager.last_failed_unlock = Some(Utc::now());
+
+        let error = manager.unlock_encryption("not the password").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(manager.failed_unlocks(), 5);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn encrypting_the_vault_leaves_no_plaintext_bak() {
+        let (mut manager, dir) = manager();
+        manager.add_note("first".to_string(), "plain text".to_string());
+        manager.save_notes().unwrap();
+        manager.flush_saves().unwrap();
+        manager.add_note("second".to_string(), "more plain text".to_string());
+        manager.save_notes().unwrap();
+        manager.flush_saves().unwrap();
+        assert!(dir.join("notes.json.bak").exists());
+
+        manager.enable_encryption("correct horse").unwrap();
+        assert!(!dir.join("notes.json.bak").exists());
+        assert!(!fs::read_to_string(dir.join("notes.json")).unwrap().contains("plain text"));
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn encrypting_a_note_takes_its_text_out_of_the_journal() {
+        let (mut manager, dir) = manager();
+        manager.set_change_journal(true);
+        let secret = manager.add_note("secret".to_string(), "the plain text".to_string()).id.clone();
+        let other = manager.add_note("other".to_string(), "stays".to_string()).id.clone();
+        manager.save_notes().unwrap();
+        manager.flush_saves().unwrap();
+
+        manager.encrypt_note(&secret, "correct horse").unwrap();
+        let (entries, unreadable) = change_journal::read(&change_journal::journal_path(&dir.join("notes.json")), None).unwrap();
+        assert_eq!(unreadable, 0);
+        assert!(entries.iter().filter(|entry| entry.note_id == secret).all(|entry| entry.content.is_none()));
+        assert!(entries.iter().any(|entry| entry.note_id == other && entry.content.as_deref() == Some("stays")));
+        fs::remove_dir_all(dir).ok();
+    }
+}
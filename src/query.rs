@@ -0,0 +1,151 @@
+use chrono::{Local, NaiveDate};
+use crate::note::{find_matches, Note, NoteFilter};
+
+// one `key:value` term of a search, tags are always lowercase, titles and notebooks only
+// when the search ignores case
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Tag(String),
+    Title(String),
+    Notebook(String),
+    Pinned(bool),
+    Favorite(bool),
+    Locked(bool),
+    // last updated before/after the day, in local time
+    Before(NaiveDate),
+    After(NaiveDate),
+}
+
+impl Filter {
+    // None when the key isn't an operator at all, Some(None) when the value doesn't make sense (yet),
+    // `text` is the value as it's matched against titles and notebooks
+    fn parse(key: &str, value: &str, text: &str) -> Option<Option<Filter>> {
+        let flag = || match value {
+            "true" | "yes" => Some(true),
+            "false" | "no" => Some(false),
+            _ => None,
+        };
+        let date = || NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+        let non_empty = |value: &str| Some(value.to_string()).filter(|value| !value.is_empty());
+        let filter = match key {
+            "tag" => non_empty(value).map(Filter::Tag),
+            "title" => non_empty(text).map(Filter::Title),
+            "notebook" => non_empty(text).map(Filter::Notebook),
+            "pinned" => flag().map(Filter::Pinned),
+            "favorite" => flag().map(Filter::Favorite),
+            "locked" => flag().map(Filter::Locked),
+            "before" => date().map(Filter::Before),
+            "after" => date().map(Filter::After),
+            "is" => match value {
+                "pinned" => Some(Filter::Pinned(true)),
+                "favorite" => Some(Filter::Favorite(true)),
+                "locked" => Some(Filter::Locked(true)),
+                _ => None,
+            },
+            _ => return None,
+        };
+        Some(filter)
+    }
+
+    fn matches(&self, note: &Note, case_sensitive: bool) -> bool {
+        let updated = || note.updated_at.with_timezone(&Local).date_naive();
+        match self {
+            Filter::Tag(tag) => note.has_tag_containing(tag),
+            Filter::Title(title) => contains(&note.title, title, case_sensitive),
+            Filter::Notebook(notebook) => contains(&note.notebook, notebook, case_sensitive),
+            Filter::Pinned(pinned) => note.pinned == *pinned,
+            Filter::Favorite(favorite) => note.favorite == *favorite,
+            Filter::Locked(locked) => note.locked == *locked,
+            Filter::Before(day) => updated() < *day,
+            Filter::After(day) => updated() > *day,
+        }
+    }
+
+    // true if everything passing self also passes `previous`
+    fn narrows(&self, previous: &Filter) -> bool {
+        match (self, previous) {
+            (Filter::Tag(new), Filter::Tag(old)) |
+            (Filter::Title(new), Filter::Title(old)) |
+            (Filter::Notebook(new), Filter::Notebook(old)) => new.contains(old.as_str()),
+            _ => self == previous,
+        }
+    }
+}
+
+fn contains(haystack: &str, needle: &str, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        haystack.contains(needle)
+    } else {
+        haystack.to_lowercase().contains(needle)
+    }
+}
+
+// a search query split into free text and filters, like `meeting #work before:2024-01-01`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub filters: Vec<Filter>,
+    // archived notes only show up when asked for with is:archived, and then nothing else does
+    pub archived: bool,
+    pub case_sensitive: bool,
+    // the notebook/favorites the list was showing when the search started, None searches everything
+    pub scope: Option<NoteFilter>,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str, case_sensitive: bool) -> Self {
+        let fold = |text: &str| if case_sensitive { text.to_string() } else { text.to_lowercase() };
+        let mut text = Vec::new();
+        let mut filters = Vec::new();
+        let mut archived = false;
+        for word in query.split_whitespace() {
+            if let Some(tag) = word.strip_prefix('#') {
+                // a lone '#' is a tag still being typed, don't filter on it yet
+                if !tag.is_empty() {
+                    filters.push(Filter::Tag(tag.to_lowercase()));
+                }
+                continue;
+            }
+            let Some((key, value)) = word.split_once(':') else {
+                text.push(fold(word));
+                continue;
+            };
+            let (key, lowercase_value) = (key.to_lowercase(), value.to_lowercase());
+            match (key.as_str(), lowercase_value.as_str()) {
+                ("is", "archived") | ("archived", "true" | "yes") => archived = true,
+                ("archived", _) => {}
+                (key, lowercase_value) => match Filter::parse(key, lowercase_value, &fold(value)) {
+                    Some(Some(filter)) => filters.push(filter),
+                    // known operator with a half typed value, ignore it for now
+                    Some(None) => {}
+                    // something like a url, search for it as it is
+                    None => text.push(fold(word)),
+                },
+            }
+        }
+        SearchQuery { text: text.join(" "), filters, archived, case_sensitive, scope: None }
+    }
+
+    pub fn matches(&self, note: &Note) -> bool {
+        note.archived == self.archived &&
+        self.scope.as_ref().is_none_or(|scope| scope.matches_scope(note)) &&
+        self.filters.iter().all(|filter| filter.matches(note, self.case_sensitive)) &&
+        (self.text.is_empty() ||
+            contains(&note.title, &self.text, self.case_sensitive) ||
+            contains(&note.content, &self.text, self.case_sensitive))
+    }
+
+    // byte range of the first place the free text shows up in the note's content
+    pub fn first_match(&self, note: &Note) -> Option<(usize, usize)> {
+        find_matches(&note.content, &self.text, self.case_sensitive).first().copied()
+    }
+
+    // true if everything matching self also matches `previous`
+    pub fn narrows(&self, previous: &SearchQuery) -> bool {
+        self.archived == previous.archived &&
+        self.case_sensitive == previous.case_sensitive &&
+        self.scope == previous.scope &&
+        self.text.contains(previous.text.as_str()) &&
+        previous.filters.iter().all(|old| self.filters.iter().any(|filter| filter.narrows(old)))
+    }
+}
@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+// where the journal lives, next to the vault: notes.json -> notes.json.recovery
+pub fn recovery_path(notes_file: &Path) -> PathBuf {
+    let mut name = notes_file.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".recovery");
+    notes_file.with_file_name(name)
+}
+
+// the editor's unsaved text, written every few seconds while there is some so a crash
+// doesn't lose it. it's removed again once the text is saved or thrown away
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recovery {
+    // the note being edited, None for a new one
+    pub note_id: Option<String>,
+    pub title: String,
+    pub tags: String,
+    pub content: String,
+    pub written_at: DateTime<Utc>,
+}
+
+impl Recovery {
+    // same text, whenever it was written
+    pub fn same_text(&self, other: &Recovery) -> bool {
+        self.note_id == other.note_id && self.title == other.title && self.tags == other.tags && self.content == other.content
+    }
+}
+
+impl Drop for Recovery {
+    fn drop(&mut self) {
+        self.title.zeroize();
+        self.tags.zeroize();
+        self.content.zeroize();
+    }
+}
+
+// true if there was a journal to remove
+pub fn remove(path: &Path) -> io::Result<bool> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,286 @@
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+use std::time::SystemTime;
+use crate::attachments::sha256_hex;
+use crate::encryption::EncryptionManager;
+use zeroize::Zeroize;
+use crate::fs_util::set_secure_permissions;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+// what the notes file looked like when we last read or wrote it. the mtime is the cheap
+// check, the hash tells a real change apart from a touch or a sync tool rewriting the same bytes
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskState {
+    pub mtime: Option<SystemTime>,
+    pub hash: String,
+}
+
+impl DiskState {
+    // None when the file doesn't exist (or can't be read)
+    pub fn read(path: &Path) -> Option<DiskState> {
+        let data = fs::read(path).ok()?;
+        Some(DiskState {
+            mtime: fs::metadata(path).and_then(|m| m.modified()).ok(),
+            hash: sha256_hex(&data),
+        })
+    }
+}
+
+// a serialized snapshot of the vault, everything the worker needs to put it on disk
+pub struct SaveJob {
+    pub path: PathBuf,
+    pub json: String,
+    // key and salt when the vault is encrypted
+    pub encryption: Option<(EncryptionManager, Vec<u8>)>,
+    // the search index and where it goes, when it changed since it was last written
+    pub index: Option<(PathBuf, String)>,
+    // copy the file being replaced to <name>.bak first, off when the key changes
+    pub keep_backup: bool,
+}
+
+impl SaveJob {
+    // encrypt (if needed) and write, returns the file's new state
+    pub fn write(&self) -> io::Result<DiskState> {
+        // Ensure parent directory exists and has secure permissions
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty() && !p.exists()) {
+            fs::create_dir_all(parent)?;
+            set_secure_permissions(parent, true)?;
+        }
+
+        let hash = if let Some((encryption, salt)) = &self.encryption {
+            let encrypted = encryption.encrypt(self.json.as_bytes(), salt)?;
+            let encrypted_json = serde_json::to_string_pretty(&encrypted)?;
+            write_atomically(&self.path, encrypted_json.as_bytes(), self.keep_backup)?;
+            sha256_hex(encrypted_json.as_bytes())
+        } else {
+            write_atomically(&self.path, self.json.as_bytes(), self.keep_backup)?;
+            sha256_hex(self.json.as_bytes())
+        };
+
+        // set secure permissions on the notes file
+        set_secure_permissions(&self.path, false)?;
+
+        // the index can always be rebuilt from the notes, so failing to write it doesn't fail the save
+        let _ = self.write_index();
+
+        Ok(DiskState {
+            mtime: fs::metadata(&self.path).and_then(|m| m.modified()).ok(),
+            hash,
+        })
+    }
+
+    fn write_index(&self) -> io::Result<()> {
+        let Some((path, json)) = &self.index else { return Ok(()) };
+        let data = match &self.encryption {
+            Some((encryption, salt)) => serde_json::to_string(&encryption.encrypt(json.as_bytes(), salt)?)?,
+            None => json.clone(),
+        };
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        options.open(path)?.write_all(data.as_bytes())
+    }
+}
+
+// the json is the plaintext of the whole vault, wipe it once it's written
+impl Drop for SaveJob {
+    fn drop(&mut self) {
+        self.json.zeroize();
+        if let Some((_, index)) = self.index.as_mut() {
+            index.zeroize();
+        }
+    }
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+// drop the .bak copy of `path`, fine if there isn't one
+pub fn remove_backup(path: &Path) -> io::Result<()> {
+    match fs::remove_file(backup_path(path)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+// write to a temp file next to the target, fsync, then rename over it so a crash
+// leaves either the old file or the new one, never half of each. with keep_backup
+// the previous file is kept as <name>.bak
+pub fn write_atomically(path: &Path, data: &[u8], keep_backup: bool) -> io::Result<()> {
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let mut file = options.open(&temp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        drop(file);
+
+        if keep_backup && path.exists() {
+            fs::copy(path, backup_path(path))?;
+        }
+        fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // make the rename itself durable
+    #[cfg(unix)]
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct Slot {
+    // only the newest snapshot matters, older ones waiting here just get replaced
+    job: Option<(u64, SaveJob)>,
+    shutdown: bool,
+}
+
+type SaveResult = (u64, io::Result<DiskState>);
+
+// writes vault snapshots on a background thread, one at a time
+pub struct SaveWorker {
+    slot: Arc<(Mutex<Slot>, Condvar)>,
+    results: mpsc::Receiver<SaveResult>,
+    handle: Option<thread::JoinHandle<()>>,
+    submitted: u64,
+    completed: u64,
+}
+
+impl Default for SaveWorker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SaveWorker {
+    pub fn new() -> Self {
+        let slot = Arc::new((Mutex::new(Slot::default()), Condvar::new()));
+        let (sender, results) = mpsc::channel();
+
+        let worker_slot = Arc::clone(&slot);
+        let handle = thread::spawn(move || run_worker(worker_slot, sender));
+
+        SaveWorker {
+            slot,
+            results,
+            handle: Some(handle),
+            submitted: 0,
+            completed: 0,
+        }
+    }
+
+    // queue a snapshot, replacing any snapshot that hasn't been picked up yet
+    pub fn submit(&mut self, job: SaveJob) -> io::Result<()> {
+        self.submitted += 1;
+        let (lock, condvar) = &*self.slot;
+        let mut slot = lock.lock().map_err(|_| io::Error::other("save worker panicked"))?;
+        slot.job = Some((self.submitted, job));
+        condvar.notify_one();
+        Ok(())
+    }
+
+    // true while the newest snapshot hasn't hit the disk yet
+    pub fn is_busy(&self) -> bool {
+        self.completed < self.submitted
+    }
+
+    // non-blocking, returns the outcome of the newest finished save (if any finished)
+    pub fn poll(&mut self) -> Option<io::Result<DiskState>> {
+        let mut latest = None;
+        while let Ok((generation, result)) = self.results.try_recv() {
+            self.completed = generation;
+            latest = Some(result);
+        }
+        latest
+    }
+
+    // block until everything submitted so far is written
+    pub fn flush(&mut self) -> Option<io::Result<DiskState>> {
+        let mut latest = self.poll();
+        while self.is_busy() {
+            match self.results.recv() {
+                Ok((generation, result)) => {
+                    self.completed = generation;
+                    latest = Some(result);
+                }
+                Err(_) => {
+                    self.completed = self.submitted;
+                    return Some(Err(io::Error::other("save worker stopped before finishing")));
+                }
+            }
+        }
+        latest
+    }
+}
+
+impl std::fmt::Debug for SaveWorker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SaveWorker")
+            .field("submitted", &self.submitted)
+            .field("completed", &self.completed)
+            .finish()
+    }
+}
+
+impl Drop for SaveWorker {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.slot;
+        if let Ok(mut slot) = lock.lock() {
+            slot.shutdown = true;
+            condvar.notify_one();
+        }
+        // the worker finishes whatever is queued before exiting
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn run_worker(slot: Arc<(Mutex<Slot>, Condvar)>, sender: mpsc::Sender<SaveResult>) {
+    let (lock, condvar) = &*slot;
+    loop {
+        let (generation, job) = {
+            let Ok(mut guard) = lock.lock() else { return };
+            while guard.job.is_none() && !guard.shutdown {
+                guard = match condvar.wait(guard) {
+                    Ok(guard) => guard,
+                    Err(_) => return,
+                };
+            }
+            match guard.job.take() {
+                Some(job) => job,
+                None => return,
+            }
+        };
+
+        let result = job.write();
+        if sender.send((generation, result)).is_err() {
+            return;
+        }
+    }
+}
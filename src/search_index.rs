@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use crate::note::Note;
+
+// bump when the format or the way words are split changes, older indexes get rebuilt
+const INDEX_VERSION: u32 = 1;
+
+// lowercase words of letters and digits, the same split for notes and queries
+pub fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+}
+
+// where the index lives, next to the vault: notes.json -> notes.json.index
+pub fn index_path(notes_file: &Path) -> PathBuf {
+    let mut name = notes_file.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".index");
+    notes_file.with_file_name(name)
+}
+
+// word -> notes using it, in the title or the content. it only narrows down which notes a
+// search has to look at, the search itself still checks every candidate
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    version: u32,
+    postings: BTreeMap<String, BTreeSet<String>>,
+    // the updated_at each note had when it was indexed, anything newer gets indexed again
+    indexed: HashMap<String, DateTime<Utc>>,
+}
+
+impl SearchIndex {
+    pub fn build(notes: &HashMap<String, Note>) -> Self {
+        let mut index = SearchIndex { version: INDEX_VERSION, ..SearchIndex::default() };
+        index.refresh(notes);
+        index
+    }
+
+    // None when the json is damaged or from another version, the caller rebuilds then
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str::<SearchIndex>(json).ok().filter(|index| index.version == INDEX_VERSION)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    // catch up with added, changed and removed notes, true if anything changed
+    pub fn refresh(&mut self, notes: &HashMap<String, Note>) -> bool {
+        let gone: Vec<String> = self.indexed.keys().filter(|id| !notes.contains_key(*id)).cloned().collect();
+        let changed: Vec<&Note> = notes
+            .values()
+            .filter(|note| self.indexed.get(&note.id) != Some(&note.updated_at))
+            .collect();
+        if gone.is_empty() && changed.is_empty() {
+            return false;
+        }
+
+        let stale: HashSet<&String> = gone.iter().chain(changed.iter().map(|note| &note.id)).collect();
+        self.postings.retain(|_, ids| {
+            ids.retain(|id| !stale.contains(id));
+            !ids.is_empty()
+        });
+        for id in &gone {
+            self.indexed.remove(id);
+        }
+        for note in changed {
+            // the index isn't encrypted with the note, so encrypted notes only go in by title
+            let content = if note.encrypted.is_some() { "" } else { note.content.as_str() };
+            for word in words(&note.title).chain(words(content)) {
+                self.postings.entry(word).or_default().insert(note.id.clone());
+            }
+            self.indexed.insert(note.id.clone(), note.updated_at);
+        }
+        true
+    }
+
+    // ids of the notes that could contain the text, None when the text has no words to go by.
+    // query words can be parts of longer words ("meet" finds "meeting"), so every indexed word
+    // containing one counts
+    pub fn candidates(&self, text: &str) -> Option<HashSet<String>> {
+        let mut result: Option<HashSet<String>> = None;
+        for query_word in words(text) {
+            let found: HashSet<String> = self.postings
+                .iter()
+                .filter(|(word, _)| word.contains(query_word.as_str()))
+                .flat_map(|(_, ids)| ids.iter().cloned())
+                .collect();
+            result = Some(match result {
+                Some(previous) => previous.intersection(&found).cloned().collect(),
+                None => found,
+            });
+        }
+        result
+    }
+}
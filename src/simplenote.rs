@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::note::Note;
+
+// the notes.json inside a simplenote export
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimplenoteExport {
+    #[serde(default)]
+    pub active_notes: Vec<SimplenoteNote>,
+    #[serde(default)]
+    pub trashed_notes: Vec<SimplenoteNote>,
+}
+
+// simplenote has no titles, the first line of the content is the title
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimplenoteNote {
+    pub id: String,
+    pub content: String,
+    pub creation_date: DateTime<Utc>,
+    pub last_modified: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // older exports flag pinned notes here instead
+    #[serde(default, skip_serializing)]
+    pub system_tags: Vec<String>,
+}
+
+impl SimplenoteNote {
+    pub fn from_note(note: &Note) -> Self {
+        let content = if note.content.is_empty() {
+            note.title.clone()
+        } else {
+            format!("{}\r\n{}", note.title, note.content.replace("\r\n", "\n").replace('\n', "\r\n"))
+        };
+        SimplenoteNote {
+            id: note.id.clone(),
+            content,
+            creation_date: note.created_at,
+            last_modified: note.updated_at,
+            pinned: note.pinned,
+            tags: note.tags.clone(),
+            system_tags: Vec::new(),
+        }
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pinned || self.system_tags.iter().any(|tag| tag == "pinned")
+    }
+
+    // (title, content) split off the first line
+    pub fn title_and_content(&self) -> (String, String) {
+        let text = self.content.replace("\r\n", "\n");
+        let (title, content) = text.split_once('\n').unwrap_or((&text, ""));
+        let title = title.trim().trim_start_matches('#').trim();
+        let title = if title.is_empty() { "Untitled" } else { title };
+        (title.to_string(), content.trim_start_matches('\n').to_string())
+    }
+}
+
+// trashed notes go to trashedNotes, like simplenote does it
+pub fn export(notes: &[&Note]) -> SimplenoteExport {
+    let mut export = SimplenoteExport::default();
+    for note in notes {
+        let converted = SimplenoteNote::from_note(note);
+        if note.is_trashed() {
+            export.trashed_notes.push(converted);
+        } else {
+            export.active_notes.push(converted);
+        }
+    }
+    export
+}
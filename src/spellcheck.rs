@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// cap the highlight pattern so a note full of jargon doesn't build a monster regex
+const MAX_HIGHLIGHTED_WORDS: usize = 500;
+// suggestions further away than this are rarely what was meant
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+#[derive(Debug)]
+pub struct SpellChecker {
+    dictionary: HashSet<String>,
+    // per-line cache of (line text, misspelled words) so we only re-check what changed
+    line_cache: Vec<(String, Vec<String>)>,
+    pattern: Option<String>,
+}
+
+impl SpellChecker {
+    // load a plain word list (one word per line, hunspell .dic files work too)
+    pub fn load<P: AsRef<Path>>(dictionary_path: P) -> io::Result<Self> {
+        let contents = fs::read_to_string(dictionary_path)?;
+        let dictionary: HashSet<String> = contents
+            .lines()
+            .filter_map(|line| {
+                // hunspell dictionaries look like "word/FLAGS", first line is a count
+                let word = line.split('/').next().unwrap_or("").trim();
+                if word.is_empty() || word.chars().all(|c| c.is_ascii_digit()) {
+                    None
+                } else {
+                    Some(word.to_lowercase())
+                }
+            })
+            .collect();
+
+        if dictionary.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "dictionary is empty"));
+        }
+
+        Ok(SpellChecker {
+            dictionary,
+            line_cache: Vec::new(),
+            pattern: None,
+        })
+    }
+
+    pub fn is_correct(&self, word: &str) -> bool {
+        let lower = word.to_lowercase();
+        if self.dictionary.contains(&lower) {
+            return true;
+        }
+        // possessives aren't in most word lists
+        if let Some(stem) = lower.strip_suffix("'s") {
+            return self.dictionary.contains(stem);
+        }
+        false
+    }
+
+    // closest dictionary words by edit distance, best first, in the word's capitalisation
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<String> {
+        let lower = word.to_lowercase();
+        let length = lower.chars().count();
+        let mut scored: Vec<(usize, &String)> = self.dictionary
+            .iter()
+            .filter(|candidate| candidate.chars().count().abs_diff(length) <= MAX_SUGGESTION_DISTANCE)
+            .filter_map(|candidate| {
+                let distance = edit_distance(&lower, candidate);
+                (distance <= MAX_SUGGESTION_DISTANCE).then_some((distance, candidate))
+            })
+            .collect();
+        scored.sort();
+        scored.truncate(limit);
+
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        scored
+            .into_iter()
+            .map(|(_, candidate)| {
+                if capitalized {
+                    let mut chars = candidate.chars();
+                    chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+                } else {
+                    candidate.clone()
+                }
+            })
+            .collect()
+    }
+
+    // re-check changed lines and return the highlight pattern for the textarea
+    pub fn update(&mut self, lines: &[String]) -> Option<&str> {
+        let mut changed = self.line_cache.len() > lines.len();
+        self.line_cache.truncate(lines.len());
+
+        for (i, line) in lines.iter().enumerate() {
+            if self.line_cache.get(i).is_some_and(|(cached, _)| cached == line) {
+                continue;
+            }
+            let misspelled = self.check_line(line);
+            if i < self.line_cache.len() {
+                self.line_cache[i] = (line.clone(), misspelled);
+            } else {
+                self.line_cache.push((line.clone(), misspelled));
+            }
+            changed = true;
+        }
+
+        if changed {
+            let words: BTreeSet<&str> = self.line_cache
+                .iter()
+                .flat_map(|(_, words)| words.iter().map(|w| w.as_str()))
+                .take(MAX_HIGHLIGHTED_WORDS)
+                .collect();
+
+            self.pattern = if words.is_empty() {
+                None
+            } else {
+                Some(format!(r"\b(?:{})\b", words.into_iter().collect::<Vec<_>>().join("|")))
+            };
+        }
+
+        self.pattern.as_deref()
+    }
+
+    fn check_line(&self, line: &str) -> Vec<String> {
+        let mut misspelled = Vec::new();
+
+        // backticks toggle code spans, so every odd segment is code
+        for (segment_index, segment) in line.split('`').enumerate() {
+            if segment_index % 2 == 1 {
+                continue;
+            }
+
+            for token in segment.split_whitespace() {
+                if is_url(token) {
+                    continue;
+                }
+                for word in token.split(|c: char| !(c.is_alphabetic() || c == '\'')) {
+                    let word = word.trim_matches('\'');
+                    if word.chars().count() < 2 || word.chars().all(|c| c.is_uppercase()) {
+                        // skip single letters and acronyms
+                        continue;
+                    }
+                    if !self.is_correct(word) && !misspelled.iter().any(|w| w == word) {
+                        misspelled.push(word.to_string());
+                    }
+                }
+            }
+        }
+
+        misspelled
+    }
+}
+
+// the dictionary for a language: a path set for it in the config, else the usual
+// hunspell/myspell locations, else the single configured word list
+pub fn dictionary_path(language: &str, dictionaries: &BTreeMap<String, String>, fallback: &str) -> PathBuf {
+    if language.is_empty() {
+        return PathBuf::from(fallback);
+    }
+    if let Some(path) = dictionaries.get(language) {
+        return PathBuf::from(path);
+    }
+    ["/usr/share/hunspell", "/usr/share/myspell", "/usr/share/myspell/dicts"]
+        .iter()
+        .map(|dir| Path::new(dir).join(format!("{}.dic", language)))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(fallback))
+}
+
+// levenshtein distance, counted in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+fn is_url(token: &str) -> bool {
+    token.contains("://") || token.starts_with("www.") || token.starts_with("mailto:")
+}
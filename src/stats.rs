@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use crate::note::Note;
+
+pub const TOP_WORDS: usize = 50;
+
+// common english filler that would otherwise dominate every vault
+const STOPWORDS: &[&str] = &[
+    "about", "after", "again", "all", "also", "and", "any", "are", "because", "been",
+    "before", "being", "but", "can", "could", "did", "does", "doing", "don't", "down",
+    "each", "few", "for", "from", "further", "had", "has", "have", "having", "her",
+    "here", "hers", "him", "his", "how", "i'm", "into", "it's", "its", "just",
+    "more", "most", "not", "now", "off", "once", "only", "other", "our", "ours",
+    "out", "over", "own", "same", "she", "should", "some", "such", "than", "that",
+    "the", "their", "theirs", "them", "then", "there", "these", "they", "this", "those",
+    "through", "too", "under", "until", "very", "was", "were", "what", "when", "where",
+    "which", "while", "who", "whom", "why", "will", "with", "would", "you", "your",
+    "yours",
+];
+
+// top n words across all notes, most frequent first (ties broken alphabetically)
+pub fn word_frequencies<'a, I>(notes: I, top_n: usize) -> Vec<(String, usize)>
+where
+    I: IntoIterator<Item = &'a Note>,
+{
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    for note in notes {
+        for text in [note.title.as_str(), note.content.as_str()] {
+            for word in text.split(|c: char| !(c.is_alphabetic() || c == '\'')) {
+                let word = word.trim_matches('\'').to_lowercase();
+                if word.chars().count() < 3 || STOPWORDS.contains(&word.as_str()) {
+                    continue;
+                }
+                *counts.entry(word).or_insert(0) += 1;
+            }
+        }
+    }
+
+    rank(counts, top_n)
+}
+
+fn rank(counts: HashMap<String, usize>, top_n: usize) -> Vec<(String, usize)> {
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|(a_word, a_count), (b_word, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_word.cmp(b_word))
+    });
+    ranked.truncate(top_n);
+    ranked
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TextCounts {
+    pub words: usize,
+    pub chars: usize,
+    pub lines: usize,
+}
+
+pub fn count_text(text: &str) -> TextCounts {
+    TextCounts {
+        words: text.split_whitespace().count(),
+        chars: text.chars().count(),
+        lines: text.lines().count(),
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VaultStats {
+    pub notes: usize,
+    pub words: usize,
+    pub chars: usize,
+    // (tag, notes carrying it), most used first
+    pub tags: Vec<(String, usize)>,
+    // ("2024-05", notes created that month), oldest first, months without notes left out
+    pub per_month: Vec<(String, usize)>,
+}
+
+pub fn vault_stats<'a, I>(notes: I) -> VaultStats
+where
+    I: IntoIterator<Item = &'a Note>,
+{
+    let mut stats = VaultStats::default();
+    let mut months: HashMap<String, usize> = HashMap::new();
+    let mut tags: HashMap<String, usize> = HashMap::new();
+
+    for note in notes {
+        let counts = count_text(&note.content);
+        stats.notes += 1;
+        stats.words += counts.words;
+        stats.chars += counts.chars;
+        *months.entry(note.created_at.format("%Y-%m").to_string()).or_insert(0) += 1;
+        for tag in &note.tags {
+            *tags.entry(tag.clone()).or_insert(0) += 1;
+        }
+    }
+
+    stats.tags = rank(tags, usize::MAX);
+    stats.per_month = months.into_iter().collect();
+    stats.per_month.sort();
+    stats
+}
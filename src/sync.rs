@@ -0,0 +1,597 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use uuid::Uuid;
+use crate::note::Note;
+use crate::saver;
+
+// somewhere another copy of the vault lives. files are named relative to the remote folder,
+// e.g. "notes.json" or "attachments/<hash>", and go back and forth exactly as they are on disk,
+// so an encrypted vault stays encrypted on the way
+pub trait SyncBackend {
+    // None when the remote doesn't have the file (yet)
+    fn fetch(&self, name: &str) -> io::Result<Option<Vec<u8>>>;
+    fn push(&self, name: &str, data: &[u8]) -> io::Result<()>;
+}
+
+// the backend for sync_remote: s3://bucket/prefix, an http(s) WebDAV folder, user@host:path or
+// ssh://user@host/path over ssh, sftp://user@host/path over sftp, anything else is a local folder
+// (a mounted or synced drive)
+pub fn backend(remote: &str) -> io::Result<Box<dyn SyncBackend>> {
+    let remote = remote.trim();
+    if remote.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no sync_remote set for this vault"));
+    }
+    if remote.starts_with("s3://") {
+        return Ok(Box::new(S3 { url: remote.trim_end_matches('/').to_string() }));
+    }
+    if remote.starts_with("http://") || remote.starts_with("https://") {
+        return Ok(Box::new(WebDav { url: remote.trim_end_matches('/').to_string() }));
+    }
+    if let Some(rest) = remote.strip_prefix("sftp://") {
+        let (host, dir) = split_url(rest);
+        return Ok(Box::new(Sftp { host: host.to_string(), dir }));
+    }
+    if let Some(rest) = remote.strip_prefix("ssh://") {
+        let (host, dir) = split_url(rest);
+        return Ok(Box::new(Ssh { host: host.to_string(), dir }));
+    }
+    // host:path, but not a windows drive like C:\notes
+    if let Some((host, path)) = remote.split_once(':').filter(|(host, _)| host.len() > 1 && !host.contains(['/', '\\'])) {
+        return Ok(Box::new(Ssh { host: host.to_string(), dir: path.to_string() }));
+    }
+    Ok(Box::new(LocalDir { dir: crate::app::expand_home(remote) }))
+}
+
+// host[:port] and the folder of what follows sftp:// or ssh://. the path is absolute, except that
+// /~/ (or none at all) starts in the login's home folder
+fn split_url(rest: &str) -> (&str, String) {
+    match rest.split_once('/') {
+        Some((host, path)) => match path.strip_prefix('~') {
+            Some(home) => (host, home.trim_start_matches('/').to_string()),
+            None => (host, format!("/{}", path)),
+        },
+        None => (rest, String::new()),
+    }
+}
+
+// a file in the folder on the server
+fn remote_path(dir: &str, name: &str) -> String {
+    match dir.trim_end_matches('/') {
+        "" if dir.starts_with('/') => format!("/{}", name),
+        "" => name.to_string(),
+        dir => format!("{}/{}", dir, name),
+    }
+}
+
+// host:port from a url, the port goes to ssh and sftp as an option (-p and -P of all things)
+fn with_port<'a>(command: &mut Command, option: &str, host: &'a str) -> &'a str {
+    match host.rsplit_once(':') {
+        Some((host, port)) => {
+            command.args([option, port]);
+            host
+        }
+        None => host,
+    }
+}
+
+// a vault with an sftp:// notes file is worked on in a local copy, so it opens (and can be
+// edited) without the server and is synced with the folder on it. returns the local copy and the
+// sync_remote for the folder, None for a notes file that's already local
+pub fn remote_vault(notes_file: &str) -> io::Result<Option<(String, String)>> {
+    let Some(rest) = notes_file.trim().strip_prefix("sftp://") else { return Ok(None) };
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let parts: Vec<&str> = dir.split('/').filter(|part| !part.is_empty()).collect();
+    if host.is_empty() || name.is_empty() || parts.contains(&"..") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' isn't a notes file on a server, expected sftp://user@host/path/notes.json", notes_file),
+        ));
+    }
+    // one folder per server and path, the port's colon isn't welcome in every file system
+    let mut local = crate::config::Config::config_dir()?.join("remote").join(host.replace(':', "_"));
+    for part in parts.iter().filter(|part| **part != "~") {
+        local.push(part);
+    }
+    local.push(name);
+    let remote = format!("sftp://{}/{}", host, dir);
+    Ok(Some((local.to_string_lossy().to_string(), remote)))
+}
+
+// the first time a remote vault is opened on this machine the local copy is fetched as it is,
+// before anything tries to read it. an encrypted one is only decrypted once it's unlocked
+pub fn fetch_remote_vault(notes_file: &Path, remote: &str) -> io::Result<()> {
+    if notes_file.exists() {
+        return Ok(());
+    }
+    let name = notes_file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let Some(data) = backend(remote)?.fetch(&name)? else { return Ok(()) };
+    if let Some(parent) = notes_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    saver::write_atomically(notes_file, &data, false)
+}
+
+struct LocalDir {
+    dir: PathBuf,
+}
+
+impl SyncBackend for LocalDir {
+    fn fetch(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.dir.join(name)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn push(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // whoever reads the folder on the other end never sees half a file
+        saver::write_atomically(&path, data, false)
+    }
+}
+
+// plain ssh with cat on the other end, so nothing but a shell is needed there. it never asks for
+// a password, that would end up in the middle of the ui, so it takes a key or an agent
+struct Ssh {
+    host: String,
+    dir: String,
+}
+
+// exit code of the fetch script when the file isn't there
+const SSH_MISSING: i32 = 3;
+
+impl Ssh {
+    fn command(&self, script: String) -> Command {
+        let mut command = Command::new("ssh");
+        command.args(["-o", "BatchMode=yes"]);
+        let host = with_port(&mut command, "-p", &self.host);
+        command.args(["--", host, &script]);
+        command
+    }
+}
+
+impl SyncBackend for Ssh {
+    fn fetch(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let path = quote(&remote_path(&self.dir, name));
+        let output = run(&mut self.command(format!("if [ -e {0} ]; then cat {0}; else exit {1}; fi", path, SSH_MISSING)), None)?;
+        match output.status.code() {
+            Some(0) => Ok(Some(output.stdout)),
+            Some(SSH_MISSING) => Ok(None),
+            _ => Err(failed("ssh", &output)),
+        }
+    }
+
+    fn push(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        let path = remote_path(&self.dir, name);
+        let mut script = "umask 077".to_string();
+        if let Some((dir, _)) = path.rsplit_once('/').filter(|(dir, _)| !dir.is_empty()) {
+            script.push_str(&format!(" && mkdir -p {}", quote(dir)));
+        }
+        let temp = format!("{}.tmp", path);
+        script.push_str(&format!(" && cat > {} && mv {} {}", quote(&temp), quote(&temp), quote(&path)));
+        let output = run(&mut self.command(script), Some(data))?;
+        if !output.status.success() {
+            return Err(failed("ssh", &output));
+        }
+        Ok(())
+    }
+}
+
+// the sftp client in batch mode, for servers that only allow sftp. it works on files, so each one
+// goes through a private temp folder on this side
+struct Sftp {
+    host: String,
+    dir: String,
+}
+
+impl Sftp {
+    // run the batch commands, "-" in front of one lets it fail without stopping the rest
+    fn batch(&self, commands: &[String]) -> io::Result<Output> {
+        let mut command = Command::new("sftp");
+        command.args(["-q", "-o", "BatchMode=yes", "-b", "-"]);
+        let host = with_port(&mut command, "-P", &self.host);
+        command.args(["--", host]);
+        run(&mut command, Some(commands.join("\n").as_bytes()))
+    }
+
+    // a temp folder only this user can read, removed again whatever happens in `use_it`
+    fn with_temp_dir<T>(use_it: impl FnOnce(&Path) -> io::Result<T>) -> io::Result<T> {
+        let temp_dir = std::env::temp_dir().join(format!("tui-notes-sync-{}", Uuid::new_v4()));
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&temp_dir)?;
+        let result = use_it(&temp_dir);
+        let _ = fs::remove_dir_all(&temp_dir);
+        result
+    }
+}
+
+impl SyncBackend for Sftp {
+    fn fetch(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        Self::with_temp_dir(|temp_dir| {
+            let local = temp_dir.join("fetched");
+            let output = self.batch(&[format!("get {} {}", batch_quote(&remote_path(&self.dir, name)), batch_quote(&local.to_string_lossy()))])?;
+            if output.status.success() {
+                return fs::read(&local).map(Some);
+            }
+            let error = String::from_utf8_lossy(&output.stderr);
+            if error.contains("not found") || error.contains("No such file") {
+                return Ok(None);
+            }
+            Err(failed("sftp", &output))
+        })
+    }
+
+    fn push(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        Self::with_temp_dir(|temp_dir| {
+            let local = temp_dir.join("pushed");
+            saver::write_atomically(&local, data, false)?;
+            let path = remote_path(&self.dir, name);
+            // sftp can't make parent folders, each one is tried and the ones that exist fail
+            let mut commands = Vec::new();
+            let mut dir = String::new();
+            let parents: Vec<&str> = path.split('/').collect();
+            for part in &parents[..parents.len() - 1] {
+                dir.push_str(part);
+                if !part.is_empty() {
+                    commands.push(format!("-mkdir {}", batch_quote(&dir)));
+                }
+                dir.push('/');
+            }
+            // uploaded next to it and renamed over it, so a broken connection doesn't leave half a file
+            let temp = format!("{}.tmp", path);
+            commands.push(format!("put {} {}", batch_quote(&local.to_string_lossy()), batch_quote(&temp)));
+            commands.push(format!("rename {} {}", batch_quote(&temp), batch_quote(&path)));
+            let output = self.batch(&commands)?;
+            if !output.status.success() {
+                return Err(failed("sftp", &output));
+            }
+            Ok(())
+        })
+    }
+}
+
+// through the aws cli, which brings its own credentials and region
+struct S3 {
+    url: String,
+}
+
+impl SyncBackend for S3 {
+    fn fetch(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let url = format!("{}/{}", self.url, name);
+        let output = run(Command::new("aws").args(["s3", "cp", "--only-show-errors", &url, "-"]), None)?;
+        if output.status.success() {
+            return Ok(Some(output.stdout));
+        }
+        let error = String::from_utf8_lossy(&output.stderr);
+        if error.contains("404") || error.contains("Not Found") || error.contains("NoSuchKey") {
+            return Ok(None);
+        }
+        Err(failed("aws", &output))
+    }
+
+    fn push(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        let url = format!("{}/{}", self.url, name);
+        let output = run(Command::new("aws").args(["s3", "cp", "--only-show-errors", "-", &url]), Some(data))?;
+        if !output.status.success() {
+            return Err(failed("aws", &output));
+        }
+        Ok(())
+    }
+}
+
+// through curl, the login comes from ~/.netrc so it isn't in the config
+struct WebDav {
+    url: String,
+}
+
+impl WebDav {
+    fn curl(&self, args: &[&str], input: Option<&[u8]>) -> io::Result<Output> {
+        run(Command::new("curl").args(["--silent", "--show-error", "--fail", "--netrc-optional"]).args(args), input)
+    }
+}
+
+impl SyncBackend for WebDav {
+    fn fetch(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let output = self.curl(&[&format!("{}/{}", self.url, name)], None)?;
+        if output.status.success() {
+            return Ok(Some(output.stdout));
+        }
+        // curl's exit code for an http error
+        if output.status.code() == Some(22) && String::from_utf8_lossy(&output.stderr).contains("404") {
+            return Ok(None);
+        }
+        Err(failed("curl", &output))
+    }
+
+    fn push(&self, name: &str, data: &[u8]) -> io::Result<()> {
+        // webdav doesn't make folders on the way, one that's already there just fails
+        if let Some((dir, _)) = name.rsplit_once('/') {
+            self.curl(&["--request", "MKCOL", &format!("{}/{}", self.url, dir)], None)?;
+        }
+        let output = self.curl(&["--upload-file", "-", &format!("{}/{}", self.url, name)], Some(data))?;
+        if !output.status.success() {
+            return Err(failed("curl", &output));
+        }
+        Ok(())
+    }
+}
+
+fn run(command: &mut Command, input: Option<&[u8]>) -> io::Result<Output> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let mut child = command
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("couldn't run {}: {}", program, e)))?;
+    if let Some(input) = input {
+        let mut stdin = child.stdin.take().ok_or_else(|| io::Error::other("no stdin"))?;
+        stdin.write_all(input)?;
+    }
+    child.wait_with_output()
+}
+
+fn failed(program: &str, output: &Output) -> io::Error {
+    let error = String::from_utf8_lossy(&output.stderr);
+    match error.trim().lines().last() {
+        Some(line) => io::Error::other(format!("{}: {}", program, line)),
+        None => io::Error::other(format!("{} exited with {}", program, output.status)),
+    }
+}
+
+// single quotes for the remote shell
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+// double quotes for an sftp batch command
+fn batch_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// where the last sync is remembered, next to the vault: notes.json -> notes.json.sync
+pub fn base_path(notes_file: &Path) -> PathBuf {
+    let mut name = notes_file.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".sync");
+    notes_file.with_file_name(name)
+}
+
+// every note as of the last sync with a remote, by fingerprint. it's the common ancestor of both
+// sides, so a note that differs from it was changed on that side
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncBase {
+    remote: String,
+    notes: HashMap<String, String>,
+}
+
+impl SyncBase {
+    // an empty base when there's none or it's from another remote, everything counts as new then
+    pub fn read(notes_file: &Path, remote: &str) -> Self {
+        fs::read_to_string(base_path(notes_file))
+            .ok()
+            .and_then(|json| serde_json::from_str::<SyncBase>(&json).ok())
+            .filter(|base| base.remote == remote)
+            .unwrap_or_default()
+    }
+
+    pub fn write(notes_file: &Path, remote: &str, notes: &HashMap<String, Note>) -> io::Result<()> {
+        let base = SyncBase {
+            remote: remote.to_string(),
+            notes: notes.iter().map(|(id, note)| (id.clone(), fingerprint(note))).collect(),
+        };
+        saver::write_atomically(&base_path(notes_file), serde_json::to_string(&base)?.as_bytes(), false)
+    }
+}
+
+// what a note is as far as syncing goes. the sealed content of a note with its own password is
+// encrypted again on every save, its updated_at stands in for it
+fn fingerprint(note: &Note) -> String {
+    let mut note = note.clone();
+    if note.encrypted.is_some() {
+        note.encrypted = None;
+        note.content.clear();
+    }
+    let json = serde_json::to_vec(&note).unwrap_or_default();
+    Sha256::digest(json).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[derive(Debug, Default)]
+pub struct Merge {
+    // both sides put together
+    pub notes: HashMap<String, Note>,
+    // the notes whose version came from the remote, conflict copies included
+    pub from_remote: Vec<String>,
+    // removed for good on the remote, they go here too
+    pub removed: Vec<String>,
+    pub summary: SyncSummary,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SyncSummary {
+    pub received: usize,
+    pub sent: usize,
+    pub conflicts: usize,
+}
+
+impl fmt::Display for SyncSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} received, {} sent", self.received, self.sent)?;
+        if self.conflicts > 0 {
+            write!(f, ", {} conflicting (kept both)", self.conflicts)?;
+        }
+        Ok(())
+    }
+}
+
+// three-way merge note by note against the last sync. a note changed on one side only takes that
+// side's version, one changed on both keeps the newer version and the other as a copy titled
+// "... (sync conflict)", so neither edit is lost
+pub fn merge(base: &SyncBase, mut ours: HashMap<String, Note>, mut theirs: HashMap<String, Note>) -> Merge {
+    let mut merge = Merge::default();
+    let ids: BTreeSet<String> = ours.keys().chain(theirs.keys()).cloned().collect();
+    for id in ids {
+        let known = base.notes.get(&id);
+        match (ours.remove(&id), theirs.remove(&id)) {
+            (Some(mine), Some(other)) => {
+                let (mine_print, other_print) = (fingerprint(&mine), fingerprint(&other));
+                if mine_print == other_print || known == Some(&other_print) {
+                    if mine_print != other_print {
+                        merge.summary.sent += 1;
+                    }
+                    merge.notes.insert(id, mine);
+                } else if known == Some(&mine_print) {
+                    merge.summary.received += 1;
+                    merge.from_remote.push(id.clone());
+                    merge.notes.insert(id, other);
+                } else {
+                    merge.summary.conflicts += 1;
+                    let (kept, copied, kept_remote) = if other.updated_at > mine.updated_at {
+                        (other, mine, true)
+                    } else {
+                        (mine, other, false)
+                    };
+                    let mut copy = copied;
+                    copy.id = Uuid::new_v4().to_string();
+                    copy.title = format!("{} (sync conflict)", copy.title);
+                    // both sides end up with both versions
+                    merge.from_remote.push(copy.id.clone());
+                    if kept_remote {
+                        merge.from_remote.push(id.clone());
+                    }
+                    merge.notes.insert(copy.id.clone(), copy);
+                    merge.notes.insert(id, kept);
+                }
+            }
+            // gone on the remote: removed there, unless it's new here or changed since
+            (Some(mine), None) => {
+                if known == Some(&fingerprint(&mine)) {
+                    merge.removed.push(id);
+                } else {
+                    merge.summary.sent += 1;
+                    merge.notes.insert(id, mine);
+                }
+            }
+            // gone here: stays gone, unless it's new there or changed since
+            (None, Some(other)) => {
+                if known == Some(&fingerprint(&other)) {
+                    merge.summary.sent += 1;
+                } else {
+                    merge.summary.received += 1;
+                    merge.from_remote.push(id.clone());
+                    merge.notes.insert(id, other);
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    merge
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn vault(notes: &[&Note]) -> HashMap<String, Note> {
+        notes.iter().map(|note| (note.id.clone(), (*note).clone())).collect()
+    }
+
+    fn base(notes: &[&Note]) -> SyncBase {
+        SyncBase {
+            remote: "remote".to_string(),
+            notes: notes.iter().map(|note| (note.id.clone(), fingerprint(note))).collect(),
+        }
+    }
+
+    fn edited(note: &Note, content: &str, minutes_later: i64) -> Note {
+        let mut note = note.clone();
+        note.content = content.to_string();
+        note.updated_at += Duration::minutes(minutes_later);
+        note
+    }
+
+    fn sealed(note: &Note, data: &str) -> Note {
+        let mut note = note.clone();
+        note.content.clear();
+        note.encrypted = Some(serde_json::from_str(&format!(
+            r#"{{"magic": "ENCRYPTED_NOTES", "salt": "salt", "nonce": "{}", "data": "{}"}}"#, data, data
+        )).unwrap());
+        note
+    }
+
+    #[test]
+    fn a_note_changed_on_one_side_takes_that_version() {
+        let (a, b) = (Note::new("a".to_string(), "a".to_string()), Note::new("b".to_string(), "b".to_string()));
+        let (a_mine, b_theirs) = (edited(&a, "a mine", 1), edited(&b, "b theirs", 1));
+
+        let merge = merge(&base(&[&a, &b]), vault(&[&a_mine, &b]), vault(&[&a, &b_theirs]));
+        assert_eq!(merge.notes[&a.id].content, "a mine");
+        assert_eq!(merge.notes[&b.id].content, "b theirs");
+        assert_eq!(merge.notes.len(), 2);
+        assert_eq!(merge.from_remote, [b.id.as_str()]);
+        assert!(merge.removed.is_empty());
+        assert_eq!((merge.summary.sent, merge.summary.received, merge.summary.conflicts), (1, 1, 0));
+    }
+
+    #[test]
+    fn a_note_changed_on_both_sides_keeps_the_newer_and_a_conflict_copy() {
+        let note = Note::new("note".to_string(), "before".to_string());
+        let (mine, theirs) = (edited(&note, "mine", 1), edited(&note, "theirs", 2));
+
+        let merge = merge(&base(&[&note]), vault(&[&mine]), vault(&[&theirs]));
+        assert_eq!(merge.notes.len(), 2);
+        assert_eq!(merge.notes[&note.id].content, "theirs");
+        let copy = merge.notes.values().find(|n| n.id != note.id).unwrap();
+        assert_eq!(copy.title, "note (sync conflict)");
+        assert_eq!(copy.content, "mine");
+        // the remote gets the copy, and the kept version came from it
+        assert_eq!(merge.from_remote, [copy.id.as_str(), note.id.as_str()]);
+        assert_eq!(merge.summary.conflicts, 1);
+    }
+
+    #[test]
+    fn a_note_deleted_on_one_side_and_edited_on_the_other_is_kept() {
+        let (a, b) = (Note::new("a".to_string(), "a".to_string()), Note::new("b".to_string(), "b".to_string()));
+        let untouched = Note::new("c".to_string(), "c".to_string());
+        let (a_theirs, b_mine) = (edited(&a, "a theirs", 1), edited(&b, "b mine", 1));
+
+        // a deleted here, b deleted there, c deleted there without changes here
+        let merge = merge(&base(&[&a, &b, &untouched]), vault(&[&b_mine, &untouched]), vault(&[&a_theirs]));
+        assert_eq!(merge.notes[&a.id].content, "a theirs");
+        assert_eq!(merge.notes[&b.id].content, "b mine");
+        assert!(!merge.notes.contains_key(&untouched.id));
+        assert_eq!(merge.from_remote, [a.id.as_str()]);
+        assert_eq!(merge.removed, [untouched.id.as_str()]);
+        assert_eq!(merge.summary.conflicts, 0);
+    }
+
+    #[test]
+    fn sealed_notes_are_compared_without_their_ciphertext() {
+        let note = sealed(&Note::new("sealed".to_string(), String::new()), "first");
+        // saving seals the content again, same note with different bytes
+        let resealed = sealed(&note, "second");
+        let merge_one = merge(&base(&[&note]), vault(&[&resealed]), vault(&[&note]));
+        assert!(merge_one.from_remote.is_empty());
+        assert_eq!((merge_one.summary.sent, merge_one.summary.received, merge_one.summary.conflicts), (0, 0, 0));
+
+        // an edit does show, through updated_at
+        let changed = sealed(&edited(&note, "", 1), "third");
+        let merge_two = merge(&base(&[&note]), vault(&[&resealed]), vault(&[&changed]));
+        assert_eq!(merge_two.from_remote, [note.id.as_str()]);
+        assert_eq!(merge_two.notes[&note.id].encrypted.as_ref().unwrap().data, "third");
+        assert_eq!(merge_two.summary.conflicts, 0);
+    }
+}
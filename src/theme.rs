@@ -0,0 +1,116 @@
+use crate::config::{ColorConfig, ColorTheme, Config};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+pub const PRESETS: [&str; 5] = ["dark", "light", "solarized", "gruvbox", "high-contrast"];
+
+fn color(fg: &str, bg: &str) -> ColorConfig {
+    ColorConfig { fg: fg.to_string(), bg: bg.to_string(), modifiers: Vec::new() }
+}
+
+pub fn preset(name: &str) -> Option<ColorTheme> {
+    let theme = match name {
+        "dark" => ColorTheme::default(),
+        "light" => ColorTheme {
+            title_bar: color("Blue", "Reset"),
+            border_active: color("Blue", "Reset"),
+            border_inactive: color("#8a8a8a", "Reset"),
+            text: color("Black", "Reset"),
+            text_secondary: color("#5f5f5f", "Reset"),
+            text_highlight: color("Black", "Reset"),
+            background_selected: color("Reset", "#d0d0d0"),
+            search_border: color("Magenta", "Reset"),
+            help_text: color("#875f00", "Reset"),
+            delete_dialog_border: color("Red", "#eeeeee"),
+            misspelled: color("Red", "Reset"),
+            diff_added: color("#008700", "Reset"),
+            diff_removed: color("#af0000", "Reset"),
+            ..ColorTheme::default()
+        },
+        "solarized" => ColorTheme {
+            title_bar: color("#2aa198", "Reset"),
+            border_active: color("#b58900", "Reset"),
+            border_inactive: color("#586e75", "Reset"),
+            text: color("#839496", "Reset"),
+            text_secondary: color("#586e75", "Reset"),
+            text_highlight: color("#93a1a1", "Reset"),
+            background_selected: color("Reset", "#073642"),
+            search_border: color("#268bd2", "Reset"),
+            help_text: color("#b58900", "Reset"),
+            delete_dialog_border: color("#dc322f", "#073642"),
+            misspelled: color("#cb4b16", "Reset"),
+            diff_added: color("#859900", "Reset"),
+            diff_removed: color("#dc322f", "Reset"),
+            ..ColorTheme::default()
+        },
+        "gruvbox" => ColorTheme {
+            title_bar: color("#8ec07c", "Reset"),
+            border_active: color("#fabd2f", "Reset"),
+            border_inactive: color("#928374", "Reset"),
+            text: color("#ebdbb2", "Reset"),
+            text_secondary: color("#a89984", "Reset"),
+            text_highlight: color("#fbf1c7", "Reset"),
+            background_selected: color("Reset", "#504945"),
+            search_border: color("#83a598", "Reset"),
+            help_text: color("#fabd2f", "Reset"),
+            delete_dialog_border: color("#fb4934", "#3c3836"),
+            misspelled: color("#fe8019", "Reset"),
+            diff_added: color("#b8bb26", "Reset"),
+            diff_removed: color("#fb4934", "Reset"),
+            ..ColorTheme::default()
+        },
+        "high-contrast" => ColorTheme {
+            title_bar: color("White", "Reset"),
+            border_active: color("LightYellow", "Reset"),
+            border_inactive: color("White", "Reset"),
+            text: color("White", "Reset"),
+            text_secondary: color("White", "Reset"),
+            text_highlight: color("LightYellow", "Reset"),
+            background_selected: color("Reset", "Blue"),
+            search_border: color("LightCyan", "Reset"),
+            help_text: color("LightYellow", "Reset"),
+            delete_dialog_border: color("LightRed", "Black"),
+            misspelled: color("LightRed", "Reset"),
+            diff_added: color("LightGreen", "Reset"),
+            diff_removed: color("LightRed", "Reset"),
+            ..ColorTheme::default()
+        },
+        _ => return None,
+    };
+    Some(theme)
+}
+
+// custom themes are toml files with the same entries as [colors], anything left out comes from the dark theme
+pub fn themes_dir() -> io::Result<PathBuf> {
+    Ok(Config::config_dir()?.join("themes"))
+}
+
+// the built-in themes followed by the ones in the themes folder
+pub fn available() -> Vec<String> {
+    let mut custom: Vec<String> = themes_dir()
+        .and_then(fs::read_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+                .filter(|name| preset(name).is_none())
+                .collect()
+        })
+        .unwrap_or_default();
+    custom.sort();
+    PRESETS.iter().map(|name| name.to_string()).chain(custom).collect()
+}
+
+// a built-in theme, or <config dir>/themes/<name>.toml
+pub fn load(name: &str) -> io::Result<ColorTheme> {
+    if let Some(theme) = preset(name) {
+        return Ok(theme);
+    }
+    let path = themes_dir()?.join(format!("{}.toml", name));
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| io::Error::new(e.kind(), format!("unknown theme '{}', not one of {} ({}: {})", name, PRESETS.join(", "), path.display(), e)))?;
+    toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
@@ -1,15 +1,25 @@
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
     Frame,
 };
 
-use crate::app::{App, AppMode, EditMode};
-use crate::config::{Config, KeyBinding};
+use crate::app::{App, AppMode, Completion, CompletionKind, EditMode, FindBar, NotePasswordPurpose, PasswordChangeStage, PathPrompt, Setting, SpellingPopup, TextPrompt, VaultPasswordAction};
+use crate::config::{Config, KeyBinding, ListDensity, ListTimestamp};
 use secrecy::ExposeSecret;
-use crate::note::Note;
+use crate::note::{self, ExportFormat, Note, UNFILED_NOTEBOOK};
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Local, Utc};
+use crate::diff::{self, DiffLine};
+use crate::stats;
+use crate::theme;
+use crate::icons;
+use crate::wrap;
+use crate::csv_notes::CsvField;
+use crate::change_journal;
+use crate::encryption::{ARGON2_ITERATIONS, ARGON2_MEMORY_KIB, ARGON2_PARALLELISM, CIPHER_NAME, KDF_NAME, PasswordStrength};
 
 fn calculate_help_height(help_text: &str, available_width: u16) -> u16 {
     if help_text.is_empty() {
@@ -49,41 +59,136 @@ fn calculate_help_height(help_text: &str, available_width: u16) -> u16 {
 
 fn generate_help_text(app: &App, config: &Config) -> String {
     let kb = &config.keybindings;
+    if app.mode == AppMode::ViewingNote {
+        match &app.viewer_search {
+            Some(search) if search.typing => return "Type to search this note | Enter: Done | Esc: Cancel".to_string(),
+            Some(_) => return format!("{}: Next Match | {}: Previous Match | {}: New Search | Esc: Clear Search",
+                format_keybinding(&kb.next_match),
+                format_keybinding(&kb.previous_match),
+                format_keybinding(&kb.search_in_note)),
+            None => {}
+        }
+    }
+    if app.spelling_popup.is_some() && matches!(app.mode, AppMode::EditingNote | AppMode::CreatingNote) {
+        return "↑/↓: Pick a Suggestion | Enter: Replace the Word | Esc: Close".to_string();
+    }
+    if app.completion.is_some() && matches!(app.mode, AppMode::EditingNote | AppMode::CreatingNote) {
+        return "↑/↓: Pick | Enter/Tab: Complete | Esc: Close (or keep typing)".to_string();
+    }
+    if app.find_bar.is_some() && matches!(app.mode, AppMode::EditingNote | AppMode::CreatingNote) {
+        return format!("Type to find | Tab: Find/Replace | Enter: Next (Replace in the replace field) | ↑/↓: Previous/Next | {}: Replace All | Esc: Close",
+            format_keybinding(&kb.replace_all));
+    }
     match app.mode {
         AppMode::PasswordPrompt => {
             "Enter password to unlock encrypted notes | Esc: Quit".to_string()
         }
         AppMode::PasswordSetup => {
-            "Create a password for your new encrypted notes vault | Esc: Quit".to_string()
+            if app.password_setup_first.is_some() {
+                "Type the password again | Enter: Confirm | Esc: Start Over".to_string()
+            } else {
+                "Create a password for your new encrypted notes vault | Esc: Quit".to_string()
+            }
         }
         AppMode::NoteList => {
-            let base_help = format!("{}: Navigate | {}: View | {}: Edit | {}: New Note | {}: Search | {}: Pin | {}: Delete | {}: Quit",
-                format!("{}/{}", format_keybinding(&kb.move_up), format_keybinding(&kb.move_down)),
+            let base_help = format!("{}/{}: Navigate | {}/{}: Page | {}/gg: Top | {}: Bottom | Number+Enter: Go to Note | {}: View | {}: Edit | {}: New Note | {}: Search | {}: Pin | {}: Favorite | {}: {} | {}: Delete | {}: Quit",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
+                format_keybinding(&kb.page_up),
+                format_keybinding(&kb.page_down),
+                format_keybinding_vec(&kb.jump_to_top),
+                format_keybinding_vec(&kb.jump_to_bottom),
                 format_keybinding(&kb.view_note),
                 format_keybinding(&kb.edit_note),
                 format_keybinding(&kb.create_note),
                 format_keybinding(&kb.search_notes),
                 format_keybinding(&kb.toggle_pin),
+                format_keybinding(&kb.toggle_favorite),
+                format_keybinding(&kb.toggle_favorites_filter),
+                if app.list_filter.favorites_only { "All Notes" } else { "Favorites" },
                 format_keybinding(&kb.delete_note),
                 format_keybinding(&kb.quit)
             );
-            format!("{} | {}: Export Backup", base_help, format_keybinding(&kb.export_plaintext))
+            format!("{} | {}: Go to Note | {}: Sort ({}) | {}: Split View | {}/{}: Resize Split | {}: {} | {}: Theme | {}: Settings | {}: Switch Vault | {}: Sync | {}: Change Password | {}: {} | {}/{}: Reorder | {}: Mark | {}: Add Tags | {}: Lock | {}: Encrypt Note | {}: Archive | {}: {} | {}: Undo | {}: Redo | {}: Notebooks | {}: Move to Notebook | {}: Trash | {}: Backups | {}: Export Backup | {}: Save As | {}: Import | {}: Statistics | {}: Empty Trash | {}: About",
+                base_help,
+                format_keybinding(&kb.quick_switch),
+                format_keybinding(&kb.cycle_sort),
+                app.note_manager.sort_mode().label(),
+                format_keybinding(&kb.toggle_split_view),
+                format_keybinding(&kb.shrink_list),
+                format_keybinding(&kb.grow_list),
+                format_keybinding(&kb.toggle_relative_time),
+                if app.relative_timestamps { "Full Dates" } else { "Relative Dates" },
+                format_keybinding(&kb.choose_theme),
+                format_keybinding(&kb.show_settings),
+                format_keybinding(&kb.switch_vault),
+                format_keybinding(&kb.sync_vault),
+                format_keybinding(&kb.change_password),
+                format_keybinding(&kb.toggle_vault_encryption),
+                if app.note_manager.is_encrypted() { "Remove Encryption" } else { "Encrypt Vault" },
+                format_keybinding(&kb.move_note_up),
+                format_keybinding(&kb.move_note_down),
+                format_keybinding(&kb.toggle_mark),
+                format_keybinding(&kb.add_tags),
+                format_keybinding(&kb.toggle_lock),
+                format_keybinding(&kb.encrypt_note),
+                format_keybinding(&kb.toggle_archive),
+                format_keybinding(&kb.show_archive),
+                if app.list_filter.archived { "Back to Notes" } else { "Show Archive" },
+                format_keybinding(&kb.undo),
+                format_keybinding(&kb.redo),
+                format_keybinding(&kb.show_notebooks),
+                format_keybinding(&kb.move_to_notebook),
+                format_keybinding(&kb.show_trash),
+                format_keybinding(&kb.show_backups),
+                format_keybinding(&kb.export_plaintext),
+                format_keybinding(&kb.save_as),
+                format_keybinding(&kb.import_json),
+                format_keybinding(&kb.show_statistics),
+                format_keybinding(&kb.empty_trash),
+                format_keybinding(&kb.show_about))
         }
         AppMode::Searching => {
-            format!("Type to search | {}: Navigate Results | {}/{}: View Selected | {}: Exit Search | {}: Quit",
-                format!("{}/{}", format_keybinding(&kb.move_up), format_keybinding(&kb.move_down)),
+            format!("Type to search (#tag, title:, notebook:, is:pinned, before:/after:YYYY-MM-DD filter) | {}/{}: Navigate Results | {}/{}: View Selected | {}: Edit Selected | {}: {} | {}: Exit Search | {}: Quit",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
                 format_keybinding(&kb.search_select),
                 format_keybinding(&kb.search_view),
+                format_keybinding(&kb.search_edit),
+                format_keybinding(&kb.toggle_search_case),
+                if app.search_case_sensitive { "Ignore Case" } else { "Match Case" },
                 format_keybinding(&kb.exit_search),
                 format_keybinding(&kb.quit)
             )
         }
         AppMode::ViewingNote => {
-            format!("{}: Return to List | {}: Edit Note | {}: Scroll | {}: Page | {}: Quit",
+            format!("{}: Return to List | {}: Edit Note | {}: Go to Note | {}: Search | {}: External Editor | {}: Copy | {}: Export | {}: {} | {}: {} | {}: Toggle Todo | {}: Attach File | {}: Next Attachment | {}: Open Attachment | {}: Remove Attachment | {}: History | {}: Change Journal | {}: Line Numbers ({}) | {}: Wrap ({}) | {}/{}: Scroll | {}/{}: Page | {}: Quit",
                 format_keybinding(&kb.return_to_list),
                 format_keybinding(&kb.edit_from_view),
-                format!("{}/{}", format_keybinding(&kb.move_up), format_keybinding(&kb.move_down)),
-                format!("{}/{}", format_keybinding(&kb.page_up), format_keybinding(&kb.page_down)),
+                format_keybinding(&kb.quick_switch),
+                format_keybinding(&kb.search_in_note),
+                format_keybinding(&kb.external_editor),
+                format_keybinding(&kb.copy_to_clipboard),
+                format_keybinding(&kb.export_note),
+                format_keybinding(&kb.toggle_lock),
+                if app.viewing_note.as_ref().is_some_and(|note| note.locked) { "Unlock" } else { "Lock" },
+                format_keybinding(&kb.encrypt_note),
+                if app.viewing_note.as_ref().is_some_and(|note| note.encrypted.is_some()) { "Decrypt Note" } else { "Encrypt Note" },
+                format_keybinding(&kb.toggle_todo),
+                format_keybinding(&kb.add_attachment),
+                format_keybinding(&kb.switch_field),
+                format_keybinding(&kb.open_attachment),
+                format_keybinding(&kb.remove_attachment),
+                format_keybinding(&kb.show_history),
+                format_keybinding(&kb.show_journal),
+                format_keybinding(&kb.toggle_line_numbers),
+                if app.line_numbers_enabled { "On" } else { "Off" },
+                format_keybinding(&kb.toggle_wrap),
+                if app.wrap_enabled { "On" } else { "Off" },
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
+                format_keybinding(&kb.page_up),
+                format_keybinding(&kb.page_down),
                 format_keybinding(&kb.quit)
             )
         }
@@ -97,20 +202,42 @@ fn generate_help_text(app: &App, config: &Config) -> String {
                     format_keybinding(&kb.save_and_exit),
                     format_keybinding(&kb.manual_save))
             };
-            format!("{} | {}: Switch | {}: Toggle Selection | ←/→/↑/↓: Move | Ctrl+↑/↓: Scroll | {}: Page",
+            format!("{} | {}: Switch | {}: Find/Replace | {}: External Editor | {}: Copy | {}: Paste | {}: Undo | {}: Redo | {}: Toggle Selection | {}: Line Numbers ({}) | {}: Wrap ({}) | ←/→/↑/↓: Move | Ctrl+↑/↓: Scroll | {}/{}: Page",
                 save_text,
                 format_keybinding(&kb.switch_field),
+                format_keybinding(&kb.find_in_note),
+                format_keybinding(&kb.external_editor),
+                format_keybinding(&kb.copy_to_clipboard),
+                format_keybinding(&kb.paste_from_clipboard),
+                format_keybinding(&kb.editor_undo),
+                format_keybinding(&kb.editor_redo),
                 format_keybinding(&kb.toggle_highlighting),
-                format!("{}/{}", format_keybinding(&kb.page_up), format_keybinding(&kb.page_down))
+                format_keybinding(&kb.toggle_line_numbers),
+                if app.line_numbers_enabled { "On" } else { "Off" },
+                format_keybinding(&kb.toggle_wrap),
+                if app.wrap_enabled { "On" } else { "Off" },
+                format_keybinding(&kb.page_up),
+                format_keybinding(&kb.page_down)
             )
         }
         AppMode::CreatingNote => {
-            format!("{}: Save & Return | {}: Save Now | {}: Switch | {}: Toggle Selection | ←/→/↑/↓: Move | Ctrl+↑/↓: Scroll | {}: Page",
+            format!("{}: Save & Return | {}: Save Now | {}: Switch | {}: Find/Replace | {}: External Editor | {}: Copy | {}: Paste | {}: Undo | {}: Redo | {}: Toggle Selection | {}: Line Numbers ({}) | {}: Wrap ({}) | ←/→/↑/↓: Move | Ctrl+↑/↓: Scroll | {}/{}: Page",
                 format_keybinding(&kb.save_and_exit),
                 format_keybinding(&kb.manual_save),
                 format_keybinding(&kb.switch_field),
+                format_keybinding(&kb.find_in_note),
+                format_keybinding(&kb.external_editor),
+                format_keybinding(&kb.copy_to_clipboard),
+                format_keybinding(&kb.paste_from_clipboard),
+                format_keybinding(&kb.editor_undo),
+                format_keybinding(&kb.editor_redo),
                 format_keybinding(&kb.toggle_highlighting),
-                format!("{}/{}", format_keybinding(&kb.page_up), format_keybinding(&kb.page_down))
+                format_keybinding(&kb.toggle_line_numbers),
+                if app.line_numbers_enabled { "On" } else { "Off" },
+                format_keybinding(&kb.toggle_wrap),
+                if app.wrap_enabled { "On" } else { "Off" },
+                format_keybinding(&kb.page_up),
+                format_keybinding(&kb.page_down)
             )
         }
         AppMode::ConfirmingDelete => {
@@ -130,25 +257,188 @@ fn generate_help_text(app: &App, config: &Config) -> String {
             #[cfg(feature = "native-dialogs")]
             {
                 if config.behavior.use_native_dialog {
-                    "Y/y: Confirm Export (native dialog, fallback to terminal) | N/n/Esc: Cancel".to_string()
+                    "Y/y: Confirm Export (native dialog, fallback to terminal) | M/m: Markdown Folder | S/s: Simplenote | C/c: CSV | E/e: Encrypted | N/n/Esc: Cancel".to_string()
                 } else {
-                    "Y/y: Confirm Export (terminal dialog) | N/n/Esc: Cancel".to_string()
+                    "Y/y: Confirm Export (terminal dialog) | M/m: Markdown Folder | S/s: Simplenote | C/c: CSV | E/e: Encrypted | N/n/Esc: Cancel".to_string()
                 }
             }
             #[cfg(not(feature = "native-dialogs"))]
             {
-                "Y/y: Confirm Export (terminal dialog) | N/n/Esc: Cancel".to_string()
+                "Y/y: Confirm Export (terminal dialog) | M/m: Markdown Folder | S/s: Simplenote | C/c: CSV | E/e: Encrypted | N/n/Esc: Cancel".to_string()
             }
         }
         AppMode::ReauthenticatingForExport => {
-            "Re-enter password to authorize plaintext export | Esc: Cancel".to_string()
+            "Re-enter password to authorize the export | Esc: Cancel".to_string()
+        }
+        AppMode::EnteringExportPassword => {
+            if app.export_password_first.is_some() {
+                "Type the export password again | Enter: Confirm | Esc: Cancel".to_string()
+            } else {
+                "Type a password for the export | Enter: Confirm | Esc: Cancel".to_string()
+            }
         }
         AppMode::SelectingExportLocation => {
-            "Type file path for backup export | Enter: Export | Esc: Cancel | ←/→: Move cursor | Home/End: Jump".to_string()
+            "Type file path for the export | Enter: Export | Esc: Cancel | ←/→: Move cursor | Home/End: Jump".to_string()
         }
         AppMode::EncryptedFileWarning => {
             "Your notes file is encrypted, but encryption is disabled in config | Esc/q: Quit".to_string()
         }
+        AppMode::VaultInUse => {
+            "The notes file is open in another tui-notes | Esc/q: Quit".to_string()
+        }
+        AppMode::ConfirmingRecovery => {
+            "Enter/r: Restore the unsaved changes | d: Discard them".to_string()
+        }
+        AppMode::About => {
+            format!("{}: Return to List | {}: Quit",
+                format_keybinding(&kb.return_to_list),
+                format_keybinding(&kb.quit)
+            )
+        }
+        AppMode::EnteringPath => {
+            "Type a file path (~ is your home folder) | Enter: Confirm | Esc: Cancel | ←/→: Move cursor | Home/End: Jump".to_string()
+        }
+        AppMode::EnteringText => {
+            let what = match app.text_prompt {
+                Some(TextPrompt::MoveToNotebook) => "Type a notebook name",
+                Some(TextPrompt::AddTags) => "Type tags",
+                Some(TextPrompt::QuickCapture) | None => "Type a line",
+            };
+            format!("{} | Enter: Confirm | Esc: Cancel | ←/→: Move cursor | Home/End: Jump", what)
+        }
+        AppMode::ChoosingImportSource => {
+            "J: JSON Export | M: Markdown/Text Folder | X: Joplin (.jex) | S: Simplenote | C: CSV | Esc: Cancel".to_string()
+        }
+        AppMode::MappingCsvColumns => {
+            format!("{}/{}: Field | ←/→: Column | Backspace: Skip Field | Enter: Import | Esc: Cancel",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down)
+            )
+        }
+        AppMode::ChoosingImportPolicy => {
+            "N: Keep Newer | B: Keep Both | S: Skip Existing | Esc: Cancel".to_string()
+        }
+        AppMode::ChoosingTheme => {
+            format!("{}/{}: Try a Theme | Enter: Keep It | Esc: Cancel",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down)
+            )
+        }
+        AppMode::Settings if app.rebinding.is_some() => {
+            format!("Press the new key for {} | Esc: Cancel", app.rebinding.unwrap_or_default())
+        }
+        AppMode::Settings => {
+            format!("{}/{}: Pick | Enter/Space: Change (saved to the config) | Esc: Back",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down)
+            )
+        }
+        AppMode::PickingVault => {
+            format!("{}/{}: Pick | Enter: Open | Esc: {}",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
+                if app.vault_picker_return.is_some() { "Cancel" } else { "Quit" }
+            )
+        }
+        AppMode::QuickSwitch => {
+            "Type part of a title | ↑/↓: Pick | Enter: Open | Esc: Cancel".to_string()
+        }
+        AppMode::ChangingPassword | AppMode::NotePassword => {
+            "Enter: Confirm | Esc: Cancel".to_string()
+        }
+        AppMode::ChoosingSaveAsMode => {
+            "S: Save & Switch | C: Write Copy | Esc: Cancel".to_string()
+        }
+        AppMode::ResolvingConflict => {
+            "K: Keep Mine | T: Take Theirs | M: Merge | E: Export Mine & Reload".to_string()
+        }
+        AppMode::ViewingHistory => {
+            format!("{}/{}: Pick Version | {}/{}: Scroll Diff | {}: Restore This Version | {}: Back to Note | {}: Quit",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
+                format_keybinding(&kb.page_up),
+                format_keybinding(&kb.page_down),
+                format_keybinding(&kb.restore_note),
+                format_keybinding(&kb.return_to_list),
+                format_keybinding(&kb.quit)
+            )
+        }
+        AppMode::ViewingJournal => {
+            format!("{}/{}: Pick Change | {}/{}: Scroll | {}: Back to Note | {}: Quit",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
+                format_keybinding(&kb.page_up),
+                format_keybinding(&kb.page_down),
+                format_keybinding(&kb.return_to_list),
+                format_keybinding(&kb.quit)
+            )
+        }
+        AppMode::BackupList => {
+            format!("{}/{}: Navigate | {}: Restore Backup | {}: Return to List | {}: Quit",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
+                format_keybinding(&kb.restore_note),
+                format_keybinding(&kb.return_to_list),
+                format_keybinding(&kb.quit)
+            )
+        }
+        AppMode::ConfirmingRestoreBackup => {
+            format!("{}: Restore | {}: Cancel",
+                format_keybinding_vec(&kb.confirm_delete),
+                format_keybinding_vec(&kb.cancel_delete)
+            )
+        }
+        AppMode::TrashView => {
+            format!("{}/{}: Navigate | {}: Restore | {}: Delete Forever | {}: Return to List | {}: Quit",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
+                format_keybinding(&kb.restore_note),
+                format_keybinding(&kb.delete_note),
+                format_keybinding(&kb.return_to_list),
+                format_keybinding(&kb.quit)
+            )
+        }
+        AppMode::ConfirmingPurge => {
+            format!("{}: Delete Forever | {}: Cancel",
+                format_keybinding_vec(&kb.confirm_delete),
+                format_keybinding_vec(&kb.cancel_delete)
+            )
+        }
+        AppMode::NotebookList => {
+            format!("{}/{}: Navigate | {}: Open Notebook | {}: Return to List | {}: Quit",
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
+                format_keybinding(&kb.edit_note),
+                format_keybinding(&kb.return_to_list),
+                format_keybinding(&kb.quit)
+            )
+        }
+        AppMode::ConfirmingEmptyTrash => {
+            format!("{}: Empty Trash | {}: Cancel",
+                format_keybinding_vec(&kb.confirm_delete),
+                format_keybinding_vec(&kb.cancel_delete)
+            )
+        }
+        AppMode::Statistics => {
+            format!("{}: Return to List | {}/{}: Scroll | {}/{}: Page | {}: Quit",
+                format_keybinding(&kb.return_to_list),
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
+                format_keybinding(&kb.page_up),
+                format_keybinding(&kb.page_down),
+                format_keybinding(&kb.quit)
+            )
+        }
+        AppMode::Importing => {
+            format!("{}/Enter: Return to List | {}/{}: Scroll | {}/{}: Page | {}: Quit",
+                format_keybinding(&kb.return_to_list),
+                format_keybinding(&kb.move_up),
+                format_keybinding(&kb.move_down),
+                format_keybinding(&kb.page_up),
+                format_keybinding(&kb.page_down),
+                format_keybinding(&kb.quit)
+            )
+        }
     }
 }
 
@@ -163,13 +453,13 @@ pub fn draw(f: &mut Frame, app: &mut App, config: &Config) {
         );
         
         let warning = Paragraph::new(warning_text)
-            .style(Style::default().fg(config.colors.text.to_color()))
+            .style(config.colors.text.style())
             .alignment(Alignment::Center)
             .block(
                 Block::default()
                     .title("Warning")
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(config.colors.delete_dialog_border.to_color())),
+                    .border_style(config.colors.delete_dialog_border.style()),
             )
             .wrap(Wrap { trim: true });
         
@@ -177,94 +467,449 @@ pub fn draw(f: &mut Frame, app: &mut App, config: &Config) {
         return;
     }
 
-    let constraints = if app.help_visible {
+    let mut constraints = vec![
+        Constraint::Length(3),    // title
+        Constraint::Min(0),       // main content, takes all remaining space
+    ];
+    if config.behavior.show_status_bar {
+        constraints.push(Constraint::Length(1)); // status bar
+    }
+    if app.help_visible {
         let help_text = generate_help_text(app, config);
         let help_height = calculate_help_height(&help_text, f.area().width);
-        
-        vec![
-            Constraint::Length(3),           // title
-            Constraint::Min(0),              // main content
-            Constraint::Length(help_height), // help (with dynamic height)
-        ]
-    } else {
-        vec![
-            Constraint::Length(3),    // title
-            Constraint::Min(0),       // main content, takes all remaining space
-        ]
-    };
+        constraints.push(Constraint::Length(help_height)); // help (with dynamic height)
+    }
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(constraints)
         .split(f.area());
 
-    draw_title(f, chunks[0], config);
+    draw_title(f, chunks[0], app, config);
+
+    // open tabs get a row of their own above whatever is on screen
+    let main_area = if app.tabs.is_empty() {
+        chunks[1]
+    } else {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(chunks[1]);
+        draw_tab_bar(f, rows[0], app, config);
+        rows[1]
+    };
     
     match app.mode {
         AppMode::PasswordPrompt => {
-            draw_password_prompt(f, chunks[1], app, config);
+            draw_password_prompt(f, main_area, app, config);
         }
         AppMode::PasswordSetup => {
-            draw_password_setup(f, chunks[1], app, config);
+            draw_password_setup(f, main_area, app, config);
         }
         AppMode::NoteList => {
-            draw_note_list(f, chunks[1], app, config);
+            draw_note_list(f, main_area, app, config);
         }
         AppMode::Searching => {
-            draw_search_mode(f, chunks[1], app, config);
+            draw_search_mode(f, main_area, app, config);
         }
         AppMode::ViewingNote => {
-            draw_viewer(f, chunks[1], app, config);
+            draw_viewer(f, main_area, app, config);
         }
         AppMode::EditingNote | AppMode::CreatingNote => {
-            draw_editor(f, chunks[1], app, config);
+            draw_editor(f, main_area, app, config);
         }
         AppMode::ConfirmingDelete => {
-            draw_note_list(f, chunks[1], app, config);
+            draw_note_list(f, main_area, app, config);
             draw_delete_confirmation(f, f.area(), app, config);
         }
         AppMode::ConfirmingUnsavedExit => {
-            draw_editor(f, chunks[1], app, config);
+            draw_editor(f, main_area, app, config);
             draw_unsaved_changes_confirmation(f, f.area(), app, config);
         }
         AppMode::ConfirmingExport => {
-            draw_note_list(f, chunks[1], app, config);
+            if app.export_note_id.is_some() {
+                draw_viewer(f, main_area, app, config);
+            } else {
+                draw_note_list(f, main_area, app, config);
+            }
             draw_export_confirmation(f, f.area(), app, config);
         }
         AppMode::ReauthenticatingForExport => {
-            draw_reauthentication_prompt(f, chunks[1], app, config);
+            draw_reauthentication_prompt(f, main_area, app, config);
+        }
+        AppMode::EnteringExportPassword => {
+            draw_export_password_prompt(f, main_area, app, config);
         }
         AppMode::SelectingExportLocation => {
-            draw_note_list(f, chunks[1], app, config);
+            if app.export_note_id.is_some() {
+                draw_viewer(f, main_area, app, config);
+            } else {
+                draw_note_list(f, main_area, app, config);
+            }
             draw_export_location_dialog(f, f.area(), app, config);
         }
         AppMode::EncryptedFileWarning => {
-            draw_encrypted_file_warning(f, chunks[1], app, config);
+            draw_encrypted_file_warning(f, main_area, app, config);
+        }
+        AppMode::VaultInUse => {
+            draw_vault_in_use(f, main_area, app, config);
+        }
+        AppMode::ConfirmingRecovery => {
+            draw_note_list(f, main_area, app, config);
+            draw_recovery_confirmation(f, f.area(), app, config);
+        }
+        AppMode::About => {
+            draw_about(f, main_area, app, config);
+        }
+        AppMode::EnteringPath => {
+            if app.path_prompt == Some(PathPrompt::AttachFile) {
+                draw_viewer(f, main_area, app, config);
+            } else {
+                draw_note_list(f, main_area, app, config);
+            }
+            draw_path_prompt(f, f.area(), app, config);
+        }
+        AppMode::EnteringText => {
+            draw_note_list(f, main_area, app, config);
+            draw_text_prompt(f, f.area(), app, config);
+        }
+        AppMode::ChoosingImportSource => {
+            draw_note_list(f, main_area, app, config);
+            draw_import_source_dialog(f, f.area(), config);
+        }
+        AppMode::ChoosingImportPolicy => {
+            draw_note_list(f, main_area, app, config);
+            draw_import_policy_dialog(f, f.area(), app, config);
+        }
+        AppMode::ChoosingSaveAsMode => {
+            draw_note_list(f, main_area, app, config);
+            draw_save_as_mode_dialog(f, f.area(), app, config);
+        }
+        AppMode::ResolvingConflict => {
+            match app.conflict_return_mode {
+                Some(AppMode::EditingNote | AppMode::CreatingNote | AppMode::ConfirmingUnsavedExit) => draw_editor(f, main_area, app, config),
+                Some(AppMode::ViewingNote) => draw_viewer(f, main_area, app, config),
+                _ => draw_note_list(f, main_area, app, config),
+            }
+            draw_conflict_dialog(f, f.area(), app, config);
+        }
+        AppMode::Statistics => {
+            draw_statistics(f, main_area, app, config);
+        }
+        AppMode::Importing => {
+            draw_import_report(f, main_area, app, config);
+        }
+        AppMode::MappingCsvColumns => {
+            draw_note_list(f, main_area, app, config);
+            draw_csv_mapping_dialog(f, f.area(), app, config);
+        }
+        AppMode::NotebookList => {
+            draw_notebook_list(f, main_area, app, config);
+        }
+        AppMode::TrashView => {
+            draw_trash_view(f, main_area, app, config);
+        }
+        AppMode::BackupList => {
+            draw_backup_list(f, main_area, app, config);
+        }
+        AppMode::ConfirmingRestoreBackup => {
+            draw_backup_list(f, main_area, app, config);
+            draw_restore_backup_confirmation(f, f.area(), app, config);
+        }
+        AppMode::ViewingHistory => {
+            draw_history(f, main_area, app, config);
+        }
+        AppMode::ViewingJournal => {
+            draw_journal(f, main_area, app, config);
+        }
+        AppMode::ConfirmingPurge => {
+            draw_trash_view(f, main_area, app, config);
+            draw_purge_confirmation(f, f.area(), app, config);
+        }
+        AppMode::ConfirmingEmptyTrash => {
+            draw_note_list(f, main_area, app, config);
+            draw_empty_trash_confirmation(f, f.area(), app, config);
+        }
+        AppMode::ChoosingTheme => {
+            draw_note_list(f, main_area, app, config);
+            draw_theme_dialog(f, f.area(), app, config);
+        }
+        AppMode::Settings => {
+            draw_note_list(f, main_area, app, config);
+            draw_settings(f, f.area(), app, config);
+        }
+        AppMode::PickingVault => {
+            // at startup there's no vault open to show behind it
+            if app.vault_picker_return.is_some() {
+                draw_note_list(f, main_area, app, config);
+            }
+            draw_vault_picker(f, f.area(), app, config);
+        }
+        AppMode::QuickSwitch => {
+            if app.switcher_return == AppMode::ViewingNote {
+                draw_viewer(f, main_area, app, config);
+            } else {
+                draw_note_list(f, main_area, app, config);
+            }
+            draw_quick_switcher(f, f.area(), app, config);
+        }
+        AppMode::ChangingPassword => {
+            draw_change_password_prompt(f, main_area, app, config);
+        }
+        AppMode::NotePassword => {
+            if app.note_password_return == AppMode::ViewingNote {
+                draw_viewer(f, main_area, app, config);
+            } else {
+                draw_note_list(f, main_area, app, config);
+            }
+            draw_note_password_prompt(f, main_area, app, config);
         }
     }
     
+    if config.behavior.show_status_bar {
+        draw_status_bar(f, chunks[2], app, config);
+    }
     if app.help_visible {
-        draw_help(f, chunks[2], app, config);
+        draw_help(f, chunks[chunks.len() - 1], app, config);
     }
 }
 
-fn draw_title(f: &mut Frame, area: Rect, config: &Config) {
+fn mode_label(mode: AppMode) -> &'static str {
+    match mode {
+        AppMode::PasswordPrompt | AppMode::PasswordSetup | AppMode::EncryptedFileWarning | AppMode::VaultInUse => "LOCKED",
+        AppMode::NoteList => "LIST",
+        AppMode::Searching => "SEARCH",
+        AppMode::ViewingNote => "VIEW",
+        AppMode::EditingNote | AppMode::ConfirmingUnsavedExit => "EDIT",
+        AppMode::CreatingNote => "NEW",
+        AppMode::ViewingHistory => "HISTORY",
+        AppMode::ViewingJournal => "JOURNAL",
+        AppMode::NotebookList => "NOTEBOOKS",
+        AppMode::TrashView | AppMode::ConfirmingPurge | AppMode::ConfirmingEmptyTrash => "TRASH",
+        AppMode::BackupList | AppMode::ConfirmingRestoreBackup => "BACKUPS",
+        AppMode::Statistics => "STATS",
+        AppMode::QuickSwitch => "GO TO",
+        AppMode::PickingVault => "VAULT",
+        AppMode::Settings => "SETTINGS",
+        _ => "DIALOG",
+    }
+}
+
+fn draw_status_bar(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let secondary = config.colors.text_secondary.style();
+    let separator = Span::styled(" │ ", secondary);
+
+    let mut left = vec![Span::styled(
+        format!(" {} ", mode_label(app.mode)),
+        Style::default()
+            .fg(config.colors.text.to_color())
+            .bg(config.colors.background_selected.to_bg_color())
+            .add_modifier(Modifier::BOLD),
+    )];
+    let icons = icons::icons(config.behavior.use_icons);
+    let mut vault = match (app.note_manager.is_encrypted(), app.note_manager.is_ready()) {
+        (true, true) => format!("{} Encrypted, unlocked", icons.encrypted),
+        (true, false) => format!("{} Encrypted, locked", icons.encrypted),
+        (false, _) => format!("{} Not encrypted", icons.unencrypted),
+    };
+    if let Some(name) = &app.vault_name {
+        vault = format!("{}: {}", name, vault);
+    }
+    if app.note_manager.is_read_only() {
+        vault.push_str(", read-only");
+    }
+    left.push(Span::raw(" "));
+    left.push(Span::styled(vault, secondary));
+    left.push(separator.clone());
+    left.push(Span::styled(format!("{} notes", app.note_manager.note_count()), secondary));
+
+    if let Some(note) = app.viewing_note.as_ref().filter(|_| app.mode == AppMode::ViewingNote) {
+        let states = [
+            (note.pinned, icons.pinned, "pinned"),
+            (note.locked, icons.locked, "locked"),
+            (note.encrypted.is_some(), icons.encrypted, "encrypted"),
+            (note.archived, icons.archived, "archived"),
+        ];
+        for (_, icon, label) in states.iter().filter(|(set, _, _)| *set) {
+            left.push(separator.clone());
+            left.push(Span::styled(format!("{} {}", icon, label), secondary));
+        }
+        if !note.tags.is_empty() {
+            left.push(separator.clone());
+            let tags = note.tags.iter().map(|tag| format!("{}{}", icons.tag, tag)).collect::<Vec<_>>().join(" ");
+            left.push(Span::styled(tags, secondary));
+        }
+        if !note.attachments.is_empty() {
+            left.push(separator.clone());
+            left.push(Span::styled(format!("{} {} attached", icons.attachment, note.attachments.len()), secondary));
+        }
+    }
+
+    if matches!(app.mode, AppMode::EditingNote | AppMode::CreatingNote) {
+        let (row, col) = match app.edit_mode {
+            EditMode::Title => app.title_textarea.cursor(),
+            EditMode::Content => app.content_textarea.cursor(),
+            EditMode::Tags => app.tags_textarea.cursor(),
+        };
+        left.push(separator.clone());
+        left.push(Span::styled(format!("Ln {}, Col {}", row + 1, col + 1), secondary));
+    }
+
+    // the start of a key sequence, waiting for the rest
+    if !app.pending_keys.is_empty() {
+        let typed: Vec<String> = app.pending_keys
+            .iter()
+            .map(|key| KeyBinding::from_key(key).map_or_else(|| format!("{:?}", key.code), |binding| binding.key))
+            .collect();
+        left.push(separator.clone());
+        left.push(Span::styled(
+            format!("{} …", typed.join(" ")),
+            config.colors.text.style().add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    let save = if app.note_manager.is_saving() || app.autosave_imminent() {
+        "Saving...".to_string()
+    } else if app.last_save_failed {
+        "Save failed".to_string()
+    } else if app.unsaved_in_tab(config) {
+        "Unsaved changes".to_string()
+    } else if let Some(at) = app.last_saved_at {
+        format!("Saved at {}", at.format("%H:%M:%S"))
+    } else {
+        "Nothing saved yet".to_string()
+    };
+    let save_style = if app.last_save_failed {
+        config.colors.delete_dialog_border.style().add_modifier(Modifier::BOLD)
+    } else {
+        secondary
+    };
+
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(save.chars().count() as u16 + 1)])
+        .split(area);
+    f.render_widget(Paragraph::new(Line::from(left)), halves[0]);
+    f.render_widget(Paragraph::new(Span::styled(save, save_style)), halves[1]);
+}
+
+fn draw_tab_bar(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let mut spans = Vec::new();
+    for (i, tab) in app.tabs.iter().enumerate() {
+        let active = app.active_tab == Some(i);
+        // the tab on screen is checked live, the others remember it from when they were left
+        let dirty = if active { app.unsaved_in_tab(config) } else { tab.dirty };
+        let label = format!(" {}{} ", tab.label, if dirty { " ●" } else { "" });
+        let style = if active {
+            Style::default()
+                .fg(config.colors.text.to_color())
+                .bg(config.colors.background_selected.to_bg_color())
+                .add_modifier(Modifier::BOLD)
+        } else {
+            config.colors.text_secondary.style()
+        };
+        spans.push(Span::styled(label, style));
+        spans.push(Span::raw("│"));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn draw_title(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(config.colors.border_inactive.style());
+
+    const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+    let spinner = SPINNER[(app.tick_count % SPINNER.len() as u64) as usize];
+    let status = if app.note_manager.is_loading() {
+        Some(format!("{} Loading notes", spinner))
+    } else if app.note_manager.is_saving() || app.autosave_imminent() {
+        Some(format!("{} Saving", spinner))
+    } else {
+        app.status_message.clone()
+    };
+
+    if let Some(message) = status {
+        block = block.title_bottom(
+            Line::from(Span::styled(
+                format!(" {} ", message),
+                config.colors.help_text.style(),
+            ))
+            .right_aligned(),
+        );
+    }
+
     let title = Paragraph::new("Notes")
-        .style(Style::default().fg(config.colors.title_bar.to_color()).add_modifier(Modifier::BOLD))
+        .style(config.colors.title_bar.style().add_modifier(Modifier::BOLD))
         .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(config.colors.border_inactive.to_color())),
-        );
+        .block(block);
     f.render_widget(title, area);
 }
 
 fn draw_note_list(f: &mut Frame, area: Rect, app: &mut App, config: &Config) {
     let selected_index = app.selected_note_index;
+    let base = match (app.list_filter.archived, app.list_filter.favorites_only) {
+        (true, true) => "Archived Favorites",
+        (true, false) => "Archive",
+        (false, true) => "Favorites",
+        (false, false) => "Notes",
+    };
+    let title = match &app.list_filter.notebook {
+        Some(notebook) if notebook.is_empty() => format!("{} ({})", base, UNFILED_NOTEBOOK),
+        Some(notebook) => format!("{} ({})", base, notebook),
+        None => base.to_string(),
+    };
+    let title = if app.marked_notes.is_empty() {
+        title
+    } else {
+        format!("{} - {} marked", title, app.marked_notes.len())
+    };
+    let title = if app.note_manager.is_loading() {
+        format!("{} - loading", title)
+    } else if !app.jump_input.is_empty() {
+        format!("{} - go to {}_", title, app.jump_input)
+    } else {
+        title
+    };
+    let todo_counts = app.note_manager.open_todo_counts();
+    let marked = app.marked_notes.clone();
+    let (list_area, preview_area) = split_list_area(area, app);
+    let relative = app.relative_timestamps;
+    let mut list_state = app.list_state.clone();
     let notes = app.get_notes();
     let notes_len = notes.len();
-    draw_note_list_generic(f, area, &notes, selected_index, "Notes", notes_len, config);
+    if let Some(preview_area) = preview_area {
+        draw_note_preview(f, preview_area, notes.get(selected_index).copied(), config);
+    }
+    app.list_item_rows = draw_note_list_generic(f, list_area, &notes, &todo_counts, &marked, &[], selected_index, &title, notes_len, relative, &mut list_state, config);
+    app.list_state = list_state;
+}
+
+// the list and, in the split view, the pane next to it for the selected note
+fn split_list_area(area: Rect, app: &App) -> (Rect, Option<Rect>) {
+    if !app.split_view {
+        return (area, None);
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(app.split_ratio),
+            Constraint::Percentage(100 - app.split_ratio),
+        ])
+        .split(area);
+    (chunks[0], Some(chunks[1]))
+}
+
+fn draw_note_preview(f: &mut Frame, area: Rect, note: Option<&Note>, config: &Config) {
+    let block = Block::default()
+        .title(note.map_or("Preview", |note| note.title.as_str()))
+        .borders(Borders::ALL)
+        .border_style(config.colors.border_inactive.style());
+    let preview = Paragraph::new(note.map_or("", |note| note.content.as_str()))
+        .style(config.colors.text.style())
+        .block(block)
+        .wrap(Wrap { trim: false });
+    f.render_widget(preview, area);
 }
 
 fn draw_search_mode(f: &mut Frame, area: Rect, app: &mut App, config: &Config) {
@@ -276,13 +921,25 @@ fn draw_search_mode(f: &mut Frame, area: Rect, app: &mut App, config: &Config) {
         ])
         .split(area);
 
+    let scope = match &app.search_scope {
+        Some(scope) => {
+            let place = match &scope.notebook {
+                Some(notebook) if notebook.is_empty() => UNFILED_NOTEBOOK,
+                Some(notebook) => notebook.as_str(),
+                None => "Favorites",
+            };
+            let favorites = if scope.favorites_only && scope.notebook.is_some() { " favorites" } else { "" };
+            format!(" in {}{} [{}: whole vault]", place, favorites, format_keybinding(&config.keybindings.widen_search))
+        }
+        None => String::new(),
+    };
     let search_paragraph = Paragraph::new(app.search_query.as_str())
-        .style(Style::default().fg(config.colors.text.to_color()))
+        .style(config.colors.text.style())
         .block(
             Block::default()
-                .title(format!("Search ({})", app.search_results.len()))
+                .title(format!("Search{} ({}){}", scope, app.search_results.len(), if app.search_case_sensitive { " [Aa: match case]" } else { "" }))
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(config.colors.search_border.to_color())),
+                .border_style(config.colors.search_border.style()),
         );
 
     f.render_widget(search_paragraph, chunks[0]);
@@ -293,11 +950,24 @@ fn draw_search_mode(f: &mut Frame, area: Rect, app: &mut App, config: &Config) {
 
     let selected_index = app.selected_note_index;
     let search_results_len = app.search_results.len();
+    let todo_counts = app.note_manager.open_todo_counts();
+    let (list_area, preview_area) = split_list_area(chunks[1], app);
+    let snippets = app.search_snippets.clone();
+    let relative = app.relative_timestamps;
+    let mut list_state = app.list_state.clone();
     let search_notes = app.get_search_results();
-    draw_note_list_generic(f, chunks[1], &search_notes, selected_index, "Search Results", search_results_len, config);
+    if let Some(preview_area) = preview_area {
+        draw_note_preview(f, preview_area, search_notes.get(selected_index).copied(), config);
+    }
+    app.list_item_rows = draw_note_list_generic(f, list_area, &search_notes, &todo_counts, &HashSet::new(), &snippets, selected_index, "Search Results", search_results_len, relative, &mut list_state, config);
+    app.list_state = list_state;
 }
 
-fn draw_note_list_generic(f: &mut Frame, area: Rect, notes: &[&Note], selected_index: usize, title: &str, total_count: usize, config: &Config) {
+// returns the screen rows each visible note takes up, starting at the state's offset, for mapping mouse clicks.
+// `snippets` has where a search matched in each note's content, shown instead of the preview
+#[allow(clippy::too_many_arguments)]
+fn draw_note_list_generic(f: &mut Frame, area: Rect, notes: &[&Note], todo_counts: &HashMap<String, usize>, marked: &HashSet<String>, snippets: &[Option<(usize, usize)>], selected_index: usize, title: &str, total_count: usize, relative: bool, state: &mut ListState, config: &Config) -> Vec<(u16, u16)> {
+    let now = Utc::now();
     if notes.is_empty() {
         let empty_msg = if title == "Search Results" {
             if total_count == 0 {
@@ -305,64 +975,118 @@ fn draw_note_list_generic(f: &mut Frame, area: Rect, notes: &[&Note], selected_i
             } else {
                 "No notes match your search."
             }
+        } else if title.ends_with(" - loading") {
+            "Loading notes..."
+        } else if title.starts_with("Archive") {
+            &format!("Nothing archived. Press '{}' on a note to archive it.", format_keybinding(&config.keybindings.toggle_archive))
+        } else if title.starts_with("Favorites") {
+            &format!("No favorite notes. Press '{}' on a note to add it.", format_keybinding(&config.keybindings.toggle_favorite))
         } else {
             &format!("No notes available. Press '{}' to create a new note.", format_keybinding(&config.keybindings.create_note))
         };
         
         let empty_paragraph = Paragraph::new(empty_msg)
-            .style(Style::default().fg(config.colors.text_secondary.to_color()))
+            .style(config.colors.text_secondary.style())
             .alignment(Alignment::Center)
             .block(
                 Block::default()
                     .title(title)
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(config.colors.border_inactive.to_color())),
+                    .border_style(config.colors.border_inactive.style()),
             );
         f.render_widget(empty_paragraph, area);
-        return;
+        *state = ListState::default();
+        return Vec::new();
     }
 
     let items: Vec<ListItem> = notes
         .iter()
         .enumerate()
         .map(|(i, note)| {
-            let preview = note.content
-                .lines()
-                .next()
-                .unwrap_or("")
-                .chars()
-                .take(50)
-                .collect::<String>();
-            
-            let preview = if preview.len() < note.content.len() {
-                format!("{}...", preview)
-            } else {
-                preview
-            };
+            let behavior = &config.behavior;
+            let secondary = config.colors.text_secondary.style();
+            let icons = icons::icons(behavior.use_icons);
 
-            let content = vec![
+            let mut content = vec![
                 Line::from({
                     let mut spans = vec![];
-                    if note.pinned {
-                        spans.push(Span::styled("* ", Style::default().add_modifier(Modifier::BOLD)));
+                    if marked.contains(&note.id) {
+                        spans.push(Span::styled("[✓] ", config.colors.text_highlight.style().add_modifier(Modifier::BOLD)));
+                    }
+                    let markers = [
+                        (note.pinned, icons.pinned),
+                        (note.favorite, icons.favorite),
+                        (note.locked, icons.locked),
+                        (note.encrypted.is_some(), icons.encrypted),
+                        (note.archived, icons.archived),
+                    ];
+                    for (_, icon) in markers.iter().filter(|(set, _)| *set) {
+                        spans.push(Span::styled(format!("{} ", icon), Style::default().add_modifier(Modifier::BOLD)));
                     }
                     spans.push(Span::styled(&note.title, Style::default().add_modifier(Modifier::BOLD)));
+                    if let Some(open) = todo_counts.get(&note.id) {
+                        spans.push(Span::styled(
+                            format!("  ☐ {}", open),
+                            config.colors.text_secondary.style(),
+                        ));
+                    }
+                    if !note.attachments.is_empty() {
+                        spans.push(Span::styled(
+                            format!("  {}{}", icons.attachment, note.attachments.len()),
+                            config.colors.text_secondary.style(),
+                        ));
+                    }
+                    if !note.tags.is_empty() {
+                        let tags = note.tags.iter().map(|tag| format!("{}{}", icons.tag, tag)).collect::<Vec<_>>().join(" ");
+                        spans.push(Span::styled(
+                            format!("  {}", tags),
+                            config.colors.text_secondary.style(),
+                        ));
+                    }
                     spans
                 }),
-                Line::from(vec![
-                    Span::styled(preview, Style::default().fg(config.colors.text_secondary.to_color())),
-                ]),
-                Line::from(vec![
-                    Span::styled(
-                        format!("Updated: {}", note.updated_at.format("%Y-%m-%d %H:%M")),
-                        Style::default().fg(config.colors.text_secondary.to_color()),
-                    ),
-                ]),
             ];
 
+            let snippet = snippets.get(i).copied().flatten().filter(|&(start, end)| note.content.get(start..end).is_some());
+            if behavior.list_density == ListDensity::Comfortable {
+                if let Some(found) = snippet {
+                    let matched = config.colors.text_highlight.style().add_modifier(Modifier::REVERSED);
+                    content.push(snippet_line(&note.content, found, behavior.preview_length, secondary, matched));
+                } else {
+                    let preview = note.preview_lines(behavior.preview_lines, behavior.preview_skip_blank, behavior.preview_skip_heading);
+                    let shown: usize = preview.iter().map(|line| line.len()).sum();
+                    let preview_count = preview.len();
+                    for (n, line) in preview.into_iter().enumerate() {
+                        let mut text: String = line.chars().take(behavior.preview_length).collect();
+                        // cut short, or more of the note follows the last preview line
+                        if text.len() < line.len() || (n + 1 == preview_count && shown < note.content.trim().len()) {
+                            text.push_str("...");
+                        }
+                        content.push(Line::from(Span::styled(text, secondary)));
+                    }
+                }
+
+                let stamp = |at: DateTime<Utc>| if relative {
+                    time_ago(at, now, &behavior.list_date_format)
+                } else {
+                    at.with_timezone(&Local).format(&behavior.list_date_format).to_string()
+                };
+                let created = format!("Created: {}", stamp(note.created_at));
+                let updated = format!("Updated: {}", stamp(note.updated_at));
+                let timestamp = match behavior.list_timestamp {
+                    ListTimestamp::Updated => Some(updated),
+                    ListTimestamp::Created => Some(created),
+                    ListTimestamp::Both => Some(format!("{} | {}", created, updated)),
+                    ListTimestamp::None => None,
+                };
+                if let Some(timestamp) = timestamp {
+                    content.push(Line::from(Span::styled(timestamp, secondary)));
+                }
+            }
+
             ListItem::new(content).style(
                 if i == selected_index {
-                    Style::default().bg(config.colors.background_selected.to_bg_color())
+                    config.colors.background_selected.bg_style()
                 } else {
                     Style::default()
                 }
@@ -370,132 +1094,626 @@ fn draw_note_list_generic(f: &mut Frame, area: Rect, notes: &[&Note], selected_i
         })
         .collect();
 
+    let heights: Vec<u16> = items.iter().map(|item| item.height() as u16).collect();
     let list = List::new(items)
         .block(
             Block::default()
                 .title(title)
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(config.colors.border_inactive.to_color())),
+                .border_style(config.colors.border_inactive.style()),
         );
 
-    f.render_widget(list, area);
+    // the list moves its offset just enough to keep the selected note on screen
+    state.select(Some(selected_index.min(notes.len() - 1)));
+    f.render_stateful_widget(list, area, state);
+
+    let bottom = area.y + area.height.saturating_sub(1);
+    let mut rows = Vec::new();
+    let mut top = area.y + 1;
+    for height in heights.iter().skip(state.offset()) {
+        if top >= bottom {
+            break;
+        }
+        let end = (top + height).min(bottom);
+        rows.push((top, end));
+        top = end;
+    }
+
+    if rows.len() < notes.len() {
+        draw_scrollbar(f, area, notes.len(), selected_index, rows.len(), config);
+    }
+    rows
+}
+
+// a scrollbar over the right border of a bordered box whose content doesn't fit
+fn draw_scrollbar(f: &mut Frame, area: Rect, content_length: usize, position: usize, viewport_length: usize, config: &Config) {
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .style(config.colors.border_active.style());
+    let mut scrollbar_state = ScrollbarState::new(content_length)
+        .position(position)
+        .viewport_content_length(viewport_length);
+    f.render_stateful_widget(scrollbar, area.inner(Margin { vertical: 1, horizontal: 0 }), &mut scrollbar_state);
 }
 
-fn draw_viewer(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+fn draw_viewer(f: &mut Frame, area: Rect, app: &mut App, config: &Config) {
     if let Some(note) = &app.viewing_note {
+        // attachments get a box at the bottom, up to 5 rows before it scrolls
+        let attachment_rows = if note.attachments.is_empty() { 0 } else { note.attachments.len().min(5) as u16 + 2 };
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3),
                 Constraint::Min(0),
+                Constraint::Length(attachment_rows),
             ])
             .split(area);
 
+        if !note.attachments.is_empty() {
+            let visible = note.attachments.len().min(5);
+            let first = app.attachment_index.saturating_sub(visible - 1);
+            let items: Vec<ListItem> = note.attachments
+                .iter()
+                .enumerate()
+                .skip(first)
+                .take(visible)
+                .map(|(i, attachment)| {
+                    ListItem::new(format!("{} ({:.1} KB)", attachment.name, attachment.size as f64 / 1024.0)).style(
+                        if i == app.attachment_index {
+                            config.colors.background_selected.bg_style()
+                        } else {
+                            Style::default()
+                        }
+                    )
+                })
+                .collect();
+
+            let list = List::new(items)
+                .style(config.colors.text.style())
+                .block(
+                    Block::default()
+                        .title(format!("Attachments ({})", note.attachments.len()))
+                        .borders(Borders::ALL)
+                        .border_style(config.colors.border_inactive.style()),
+                );
+            f.render_widget(list, chunks[2]);
+        }
+
         let title_paragraph = Paragraph::new(note.title.as_str())
-            .style(Style::default().fg(config.colors.text.to_color()).add_modifier(Modifier::BOLD))
+            .style(config.colors.text.style().add_modifier(Modifier::BOLD))
             .block(
                 Block::default()
-                    .title("Title (Read-Only)")
+                    .title(if note.locked { "Title (Locked 🔒)" } else { "Title (Read-Only)" })
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(config.colors.border_active.to_color())),
+                    .border_style(config.colors.border_active.style()),
             );
 
         f.render_widget(title_paragraph, chunks[0]);
 
         let content_lines: Vec<&str> = note.content.lines().collect();
         let visible_height = chunks[1].height.saturating_sub(2) as usize;
-        let start_line = app.scroll_offset.min(content_lines.len().saturating_sub(1));
-        let end_line = (start_line + visible_height).min(content_lines.len());
+        let digits = content_lines.len().max(1).to_string().len();
+        let gutter = if app.line_numbers_enabled { digits + 1 } else { 0 };
+        let width = (chunks[1].width.saturating_sub(2) as usize).saturating_sub(gutter);
+        let line_rows = |line: &str| if app.wrap_enabled { wrap::rows(line, width) } else { 1 };
+
+        // scrolling counts lines, so work out the last top line that still fills the screen
+        let mut max_start = content_lines.len();
+        let mut used = 0;
+        while max_start > 0 {
+            let rows = line_rows(content_lines[max_start - 1]);
+            if used + rows > visible_height {
+                break;
+            }
+            used += rows;
+            max_start -= 1;
+        }
+        let max_start = max_start.min(content_lines.len().saturating_sub(1));
+        let query = app.viewer_search.as_ref().map_or("", |search| search.query.as_str());
+        let matches = app.viewer_matches(config);
+        let current_match = app.viewer_search.as_ref().and_then(|search| matches.get(search.current)).copied();
+        let match_style = config.colors.text_highlight.style().add_modifier(Modifier::REVERSED);
+        let current_style = match_style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        let start_line = app.scroll_offset.min(max_start);
+        let mut end_line = start_line;
+        let mut used = 0;
+        while end_line < content_lines.len() && used < visible_height {
+            used += line_rows(content_lines[end_line]);
+            end_line += 1;
+        }
         
-        let visible_content = if start_line < content_lines.len() {
-            content_lines[start_line..end_line].join("\n")
+        // highlight the cursor line when there are checkboxes to toggle
+        let has_todos = content_lines.iter().any(|line| note::parse_todo(line).is_some());
+        let visible_content: Vec<Line> = if start_line < content_lines.len() {
+            content_lines[start_line..end_line]
+                .iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    let line_index = start_line + i;
+                    let highlight = |text: &str, base: Style| {
+                        // the current match is only marked on plain lines, checkbox text is trimmed
+                        let current = current_match
+                            .filter(|(line, _, _)| *line == line_index && text.len() == content_lines[line_index].len())
+                            .map(|(_, start, _)| start);
+                        highlight_matches(text, query, config.behavior.search_case_sensitive, current, base, match_style, current_style)
+                    };
+                    let mut rendered = match note::parse_todo(line) {
+                        Some((done, text)) => {
+                            let indent = &line[..line.len() - line.trim_start().len()];
+                            let style = if done {
+                                config.colors.text_secondary.style().add_modifier(Modifier::CROSSED_OUT)
+                            } else {
+                                Style::default()
+                            };
+                            let mut spans = vec![Span::raw(format!("{}{} ", indent, if done { "☑" } else { "☐" }))];
+                            spans.extend(highlight(text, style));
+                            Line::from(spans)
+                        }
+                        None => Line::from(highlight(line, Style::default())),
+                    };
+                    if gutter > 0 {
+                        let number = line_number_label(line_index, start_line, digits, config.behavior.relative_line_numbers);
+                        rendered.spans.insert(0, Span::styled(number, config.colors.text_secondary.style()));
+                    }
+                    if has_todos && i == 0 {
+                        rendered = rendered.style(config.colors.background_selected.bg_style());
+                    }
+                    rendered
+                })
+                .collect()
         } else {
-            String::new()
+            Vec::new()
         };
 
-        let scroll_indicator = if content_lines.len() > visible_height {
+        let mut scroll_indicator = if max_start > 0 {
             format!(" (Line {}/{}) ↑/↓ Scroll, PgUp/PgDn", start_line + 1, content_lines.len())
         } else {
             " (Read-Only)".to_string()
         };
+        if let Some(search) = &app.viewer_search {
+            let position = match current_match {
+                Some(_) => format!("{}/{}", search.current + 1, matches.len()),
+                None => "no matches".to_string(),
+            };
+            scroll_indicator = format!("{} | /{}{} ({})", scroll_indicator, search.query, if search.typing { "_" } else { "" }, position);
+        }
 
-        let content_paragraph = Paragraph::new(visible_content)
-            .style(Style::default().fg(config.colors.text.to_color()))
+        let mut content_paragraph = Paragraph::new(visible_content)
+            .style(config.colors.text.style())
             .block(
                 Block::default()
                     .title(format!("Content{}", scroll_indicator))
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(config.colors.border_active.to_color())),
-            )
-            .wrap(Wrap { trim: false });
+                    .border_style(config.colors.border_active.style()),
+            );
+        if app.wrap_enabled {
+            content_paragraph = content_paragraph.wrap(Wrap { trim: false });
+        }
 
         f.render_widget(content_paragraph, chunks[1]);
+        if max_start > 0 {
+            draw_scrollbar(f, chunks[1], max_start + 1, start_line, visible_height, config);
+        }
+        app.viewer_max_scroll = max_start;
+        app.viewer_page_height = visible_height;
+    }
+}
+
+// split text into spans with every match of the query styled
+// the line a search matched on, cut down to about `width` characters around the match
+fn snippet_line(content: &str, (start, end): (usize, usize), width: usize, base: Style, matched: Style) -> Line<'static> {
+    let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[end..].find('\n').map_or(content.len(), |i| end + i);
+    let found = &content[start..end.min(line_end)];
+
+    // a bit of the text before the match for context, the rest of the room goes to what follows
+    let before: Vec<char> = content[line_start..start].chars().collect();
+    let lead = before.len().min(width / 3);
+    let room = width.saturating_sub(lead + found.chars().count());
+    let after = &content[end.min(line_end)..line_end];
+
+    let mut spans = Vec::new();
+    if lead < before.len() {
+        spans.push(Span::styled("...", base));
+    }
+    spans.push(Span::styled(before[before.len() - lead..].iter().collect::<String>(), base));
+    spans.push(Span::styled(found.to_string(), matched));
+    spans.push(Span::styled(after.chars().take(room).collect::<String>(), base));
+    if after.chars().count() > room {
+        spans.push(Span::styled("...", base));
     }
+    Line::from(spans)
+}
+
+fn highlight_matches(text: &str, query: &str, case_sensitive: bool, current: Option<usize>, base: Style, matched: Style, current_style: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for (start, end) in note::find_matches(text, query, case_sensitive) {
+        if start > last {
+            spans.push(Span::styled(text[last..start].to_string(), base));
+        }
+        let style = if current == Some(start) { current_style } else { matched };
+        spans.push(Span::styled(text[start..end].to_string(), style));
+        last = end;
+    }
+    if last < text.len() || spans.is_empty() {
+        spans.push(Span::styled(text[last..].to_string(), base));
+    }
+    spans
 }
 
 fn draw_editor(f: &mut Frame, area: Rect, app: &mut App, config: &Config) {
+    let find_height = if app.find_bar.is_some() { 3 } else { 0 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
             Constraint::Min(0),
+            Constraint::Length(find_height),
+            Constraint::Length(3),
         ])
         .split(area);
 
+    app.editor_field_areas = vec![
+        (EditMode::Title, chunks[0]),
+        (EditMode::Content, chunks[1]),
+        (EditMode::Tags, chunks[3]),
+    ];
+
     app.title_textarea.set_block(
         Block::default()
             .title("Title")
             .borders(Borders::ALL)
             .border_style(if app.edit_mode == EditMode::Title {
-                Style::default().fg(config.colors.border_active.to_color())
+                config.colors.border_active.style()
             } else {
-                Style::default().fg(config.colors.border_inactive.to_color())
+                config.colors.border_inactive.style()
             }),
     );
 
-    if app.edit_mode == EditMode::Title {
-        app.title_textarea.set_cursor_style(Style::default().bg(config.colors.text_highlight.to_color()));
-        app.content_textarea.set_cursor_style(Style::default());
-    } else {
-        app.title_textarea.set_cursor_style(Style::default());
-        app.content_textarea.set_cursor_style(Style::default().bg(config.colors.text_highlight.to_color()));
-    }
+    app.tags_textarea.set_block(
+        Block::default()
+            .title("Tags (comma separated)")
+            .borders(Borders::ALL)
+            .border_style(if app.edit_mode == EditMode::Tags {
+                config.colors.border_active.style()
+            } else {
+                config.colors.border_inactive.style()
+            }),
+    );
 
-    if app.highlighting_enabled {
-        if app.edit_mode == EditMode::Title {
-            app.title_textarea.set_cursor_line_style(Style::default().add_modifier(Modifier::UNDERLINED));
-            app.content_textarea.set_cursor_line_style(Style::default());
+    let edit_mode = &app.edit_mode;
+    for (textarea, mode) in [
+        (&mut app.title_textarea, EditMode::Title),
+        (&mut app.content_textarea, EditMode::Content),
+        (&mut app.tags_textarea, EditMode::Tags),
+    ] {
+        let active = *edit_mode == mode;
+        textarea.set_cursor_style(if active {
+            Style::default().bg(config.colors.text_highlight.to_color())
         } else {
-            app.title_textarea.set_cursor_line_style(Style::default());
-            app.content_textarea.set_cursor_line_style(Style::default().add_modifier(Modifier::UNDERLINED));
-        }
+            Style::default()
+        });
+        textarea.set_cursor_line_style(if active && app.highlighting_enabled {
+            Style::default().add_modifier(Modifier::UNDERLINED)
+        } else {
+            Style::default()
+        });
+    }
+
+    if app.line_numbers_enabled {
+        app.content_textarea.set_line_number_style(config.colors.text_secondary.style());
     } else {
-        app.title_textarea.set_cursor_line_style(Style::default());
-        app.content_textarea.set_cursor_line_style(Style::default());
+        app.content_textarea.remove_line_number();
     }
 
-    let title_text = match app.mode {
+    let mut title_text = match app.mode {
         AppMode::CreatingNote => "Creating New Note",
         AppMode::EditingNote => "Editing Note",
         _ => "Content",
-    };
+    }.to_string();
+    if let Some(vim) = &app.vim {
+        title_text = format!("{} -- {} --", title_text, vim.mode.label());
+    }
     
-    app.content_textarea.set_block(
-        Block::default()
-            .title(title_text)
-            .borders(Borders::ALL)
-            .border_style(if app.edit_mode == EditMode::Content {
-                Style::default().fg(config.colors.border_active.to_color())
-            } else {
-                Style::default().fg(config.colors.border_inactive.to_color())
-            }),
-    );
+    let counts = stats::count_text(&app.content_textarea.lines().join("\n"));
+    let content_block = Block::default()
+        .title(title_text)
+        .title_bottom(
+            Line::from(format!(" {} words | {} chars | {} lines ", counts.words, counts.chars, counts.lines))
+                .right_aligned(),
+        )
+        .borders(Borders::ALL)
+        .border_style(if app.edit_mode == EditMode::Content {
+            config.colors.border_active.style()
+        } else {
+            config.colors.border_inactive.style()
+        });
+    app.content_textarea.set_block(content_block.clone());
+
+    // spellcheck piggybacks on the textarea's search highlighting, the find bar takes it over while open
+    if app.find_bar.is_some() {
+        app.content_textarea.set_search_style(
+            Style::default()
+                .fg(config.colors.text_highlight.to_color())
+                .add_modifier(Modifier::REVERSED),
+        );
+    } else if let Some(checker) = &mut app.spell_checker {
+        let pattern = checker.update(app.content_textarea.lines()).unwrap_or("").to_string();
+        let current = app.content_textarea.search_pattern().map(|r| r.as_str().to_string()).unwrap_or_default();
+        if pattern != current {
+            let _ = app.content_textarea.set_search_pattern(&pattern);
+        }
+        app.content_textarea.set_search_style(
+            Style::default()
+                .fg(config.colors.misspelled.to_color())
+                .add_modifier(Modifier::UNDERLINED),
+        );
+    }
 
     f.render_widget(&app.title_textarea, chunks[0]);
-    f.render_widget(&app.content_textarea, chunks[1]);
+    // the textarea only knows absolute line numbers
+    let relative_numbers = app.line_numbers_enabled && config.behavior.relative_line_numbers;
+    app.editor_cursor_screen = None;
+    if app.wrap_enabled || relative_numbers {
+        draw_content_lines(f, chunks[1], app, config, content_block);
+    } else {
+        f.render_widget(&app.content_textarea, chunks[1]);
+    }
+    f.render_widget(&app.tags_textarea, chunks[3]);
+
+    if let Some(bar) = &app.find_bar {
+        draw_find_bar(f, chunks[2], bar, app.find_match_count(), config);
+    }
+    if let Some(popup) = &app.spelling_popup {
+        draw_spelling_popup(f, chunks[1], popup, config);
+    }
+    if let Some(completion) = &app.completion {
+        draw_completion_popup(f, chunks[1], completion, app.editor_cursor_screen, config);
+    }
+}
+
+// small list under the cursor when we know where it is, otherwise in the bottom left of the editor
+fn draw_completion_popup(f: &mut Frame, area: Rect, completion: &Completion, cursor: Option<(u16, u16)>, config: &Config) {
+    let longest = completion.candidates.iter().map(|c| c.chars().count()).max().unwrap_or(0) as u16;
+    let dialog_width = (longest + 4).max(14).min(area.width);
+    let dialog_height = (completion.candidates.len() as u16 + 2).min(area.height);
+    let (x, y) = match cursor {
+        Some((x, y)) if y + 1 + dialog_height <= area.y + area.height => (x, y + 1),
+        Some((x, y)) if y >= area.y + dialog_height => (x, y - dialog_height),
+        _ => (area.x + 1, area.y + area.height.saturating_sub(dialog_height + 1)),
+    };
+    let dialog_area = Rect {
+        x: x.min(area.x + area.width.saturating_sub(dialog_width)),
+        y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let items: Vec<ListItem> = completion.candidates
+        .iter()
+        .enumerate()
+        .map(|(i, candidate)| {
+            ListItem::new(candidate.as_str()).style(if i == completion.selected {
+                config.colors.background_selected.bg_style().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            })
+        })
+        .collect();
+
+    let title = match completion.kind {
+        CompletionKind::Tag => "Tags",
+        CompletionKind::Wikilink => "Notes",
+    };
+    let list = List::new(items)
+        .style(config.colors.text.style())
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD)),
+        );
+    f.render_widget(list, dialog_area);
+}
+
+fn draw_spelling_popup(f: &mut Frame, area: Rect, popup: &SpellingPopup, config: &Config) {
+    let dialog_width = 40.min(area.width.saturating_sub(4));
+    let dialog_height = (popup.suggestions.len() as u16 + 2).min(area.height);
+    let dialog_area = Rect {
+        x: area.x + (area.width.saturating_sub(dialog_width)) / 2,
+        y: area.y + (area.height.saturating_sub(dialog_height)) / 2,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let items: Vec<ListItem> = popup.suggestions
+        .iter()
+        .enumerate()
+        .map(|(i, suggestion)| {
+            ListItem::new(suggestion.as_str()).style(if i == popup.selected {
+                config.colors.background_selected.bg_style().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            })
+        })
+        .collect();
+
+    let list = List::new(items)
+        .style(config.colors.text.style())
+        .block(
+            Block::default()
+                .title(format!("Suggestions for '{}'", popup.word))
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD)),
+        );
+    f.render_widget(list, dialog_area);
+}
+
+// tui-textarea can't wrap or number lines relatively, so for those the content is drawn here
+// and the terminal cursor goes where the textarea's would be
+fn draw_content_lines(f: &mut Frame, area: Rect, app: &mut App, config: &Config, block: Block) {
+    let inner = block.inner(area);
+    let lines = app.content_textarea.lines();
+    let digits = lines.len().to_string().len();
+    let gutter = if app.line_numbers_enabled { digits + 1 } else { 0 };
+    let width = (inner.width as usize).saturating_sub(gutter);
+    let height = inner.height as usize;
+    let (cursor_row, cursor_col) = app.content_textarea.cursor();
+
+    // without wrapping long lines scroll sideways to keep the cursor in view
+    if app.wrap_enabled {
+        app.editor_wrap_left = 0;
+    } else if cursor_col < app.editor_wrap_left {
+        app.editor_wrap_left = cursor_col;
+    } else if width > 0 && cursor_col >= app.editor_wrap_left + width {
+        app.editor_wrap_left = cursor_col + 1 - width;
+    }
+    let left = app.editor_wrap_left;
+    let active = app.edit_mode == EditMode::Content;
+    // find bar matches or misspelled words, same as the textarea would show
+    let pattern = app.content_textarea.search_pattern();
+    let search_style = app.content_textarea.search_style();
+    let number_style = config.colors.text_secondary.style();
+
+    let mut rows: Vec<Line> = Vec::new();
+    let mut cursor_at = (0, 0);
+    for (index, line) in lines.iter().enumerate() {
+        let segments = if app.wrap_enabled {
+            wrap::segments(line, width)
+        } else {
+            let length = line.chars().count();
+            vec![(left.min(length), (left + width).min(length))]
+        };
+        if index == cursor_row {
+            let (row, column) = if app.wrap_enabled { wrap::locate(&segments, cursor_col) } else { (0, cursor_col - left) };
+            cursor_at = (rows.len() + row, column.min(width.saturating_sub(1)));
+        }
+        let matches: Vec<(usize, usize)> = pattern
+            .map(|pattern| pattern.find_iter(line).map(|found| (found.start(), found.end())).collect())
+            .unwrap_or_default();
+        let line_style = if active && index == cursor_row && app.highlighting_enabled {
+            Style::default().add_modifier(Modifier::UNDERLINED)
+        } else {
+            Style::default()
+        };
+        let byte_at = |column: usize| line.char_indices().nth(column).map_or(line.len(), |(i, _)| i);
+
+        for (n, (start, end)) in segments.iter().enumerate() {
+            let mut spans = Vec::new();
+            if gutter > 0 {
+                let number = if n == 0 {
+                    line_number_label(index, cursor_row, digits, config.behavior.relative_line_numbers)
+                } else {
+                    " ".repeat(gutter)
+                };
+                spans.push(Span::styled(number, number_style));
+            }
+            let (from, to) = (byte_at(*start), byte_at(*end));
+            let mut position = from;
+            for &(match_start, match_end) in &matches {
+                let (match_start, match_end) = (match_start.max(from), match_end.min(to));
+                if match_start >= match_end {
+                    continue;
+                }
+                if match_start > position {
+                    spans.push(Span::raw(line[position..match_start].to_string()));
+                }
+                spans.push(Span::styled(line[match_start..match_end].to_string(), search_style));
+                position = match_end;
+            }
+            if position < to {
+                spans.push(Span::raw(line[position..to].to_string()));
+            }
+            rows.push(Line::from(spans).style(line_style));
+        }
+    }
+
+    // scroll just enough to keep the cursor's row on screen
+    if cursor_at.0 < app.editor_wrap_top {
+        app.editor_wrap_top = cursor_at.0;
+    } else if height > 0 && cursor_at.0 >= app.editor_wrap_top + height {
+        app.editor_wrap_top = cursor_at.0 + 1 - height;
+    }
+    let top = app.editor_wrap_top;
+    let visible: Vec<Line> = rows.into_iter().skip(top).take(height).collect();
+    f.render_widget(Paragraph::new(visible).block(block), area);
+
+    if active && cursor_at.0 >= top && cursor_at.0 < top + height {
+        let position = (inner.x + (gutter + cursor_at.1) as u16, inner.y + (cursor_at.0 - top) as u16);
+        f.set_cursor_position(position);
+        app.editor_cursor_screen = Some(position);
+    }
+}
+
+// gutter text for a line, relative numbers count away from the cursor's line like vim's relativenumber
+fn line_number_label(index: usize, cursor: usize, digits: usize, relative: bool) -> String {
+    let number = if relative && index != cursor { index.abs_diff(cursor) } else { index + 1 };
+    format!("{:>digits$} ", number)
+}
+
+fn draw_find_bar(f: &mut Frame, area: Rect, bar: &FindBar, matches: usize, config: &Config) {
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let fields = [
+        (format!("Find ({} matches)", matches), &bar.query, !bar.in_replacement),
+        ("Replace".to_string(), &bar.replacement, bar.in_replacement),
+    ];
+    for ((title, text, active), half) in fields.into_iter().zip(halves.iter()) {
+        let paragraph = Paragraph::new(text.as_str())
+            .style(config.colors.text.style())
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(if active {
+                        config.colors.search_border.to_color()
+                    } else {
+                        config.colors.border_inactive.to_color()
+                    })),
+            );
+        f.render_widget(paragraph, *half);
+        if active {
+            let cursor_x = half.x + 1 + (text.chars().count() as u16).min(half.width.saturating_sub(2));
+            f.set_cursor_position((cursor_x, half.y + 1));
+        }
+    }
+}
+
+// how long ago, roughly, falling back to the list's date format once it's more than a few weeks
+fn time_ago(at: DateTime<Utc>, now: DateTime<Utc>, date_format: &str) -> String {
+    let elapsed = now.signed_duration_since(at);
+    let local_at = at.with_timezone(&Local);
+    let days = now.with_timezone(&Local).date_naive().signed_duration_since(local_at.date_naive()).num_days();
+    if elapsed.num_minutes() < 1 {
+        "just now".to_string()
+    } else if elapsed.num_hours() < 1 {
+        format!("{}m ago", elapsed.num_minutes())
+    } else if days == 0 {
+        format!("{}h ago", elapsed.num_hours())
+    } else if days == 1 {
+        format!("yesterday {}", local_at.format("%H:%M"))
+    } else if days < 7 {
+        format!("{} days ago", days)
+    } else if days < 28 {
+        format!("{}w ago", days / 7)
+    } else {
+        local_at.format(date_format).to_string()
+    }
 }
 
 fn format_keybinding(kb: &KeyBinding) -> String {
+    if kb.is_sequence() {
+        return kb.steps().iter().map(format_keybinding).collect::<Vec<_>>().join(" ");
+    }
     let mut parts = Vec::new();
     
     if kb.ctrl {
@@ -504,7 +1722,8 @@ fn format_keybinding(kb: &KeyBinding) -> String {
     if kb.alt {
         parts.push("Alt");
     }
-    if kb.shift {
+    // "G" rather than "Shift+G", the character shows it
+    if kb.shift && kb.key.chars().count() != 1 {
         parts.push("Shift");
     }
     
@@ -528,12 +1747,12 @@ fn draw_help(f: &mut Frame, area: Rect, app: &App, config: &Config) {
     let centered_text = center_text_lines(text_lines, available_width.saturating_sub(2));
     
     let help = Paragraph::new(centered_text)
-        .style(Style::default().fg(config.colors.help_text.to_color()))
+        .style(config.colors.help_text.style())
         .alignment(Alignment::Left)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(config.colors.border_inactive.to_color()))
+                .border_style(config.colors.border_inactive.style())
                 .padding(ratatui::widgets::Padding::horizontal(1)),
         );
 
@@ -606,22 +1825,115 @@ fn draw_delete_confirmation(f: &mut Frame, area: Rect, app: &App, config: &Confi
         note_title.clone()
     };
 
+    let confirmation_text = if app.marked_notes.is_empty() {
+        format!(
+            "Delete note: '{}'\n\nThe note will be moved to the trash.\n\nPress '{}' to confirm, '{}' to cancel.",
+            truncated_title,
+            format_keybinding_vec(&config.keybindings.confirm_delete),
+            format_keybinding_vec(&config.keybindings.cancel_delete)
+        )
+    } else {
+        format!(
+            "Delete {}?\n\nThey will be moved to the trash.\n\nPress '{}' to confirm, '{}' to cancel.",
+            truncated_title,
+            format_keybinding_vec(&config.keybindings.confirm_delete),
+            format_keybinding_vec(&config.keybindings.cancel_delete)
+        )
+    };
+
+    let dialog = Paragraph::new(confirmation_text)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Confirm Deletion")
+                .borders(Borders::ALL)
+                .border_style(config.colors.delete_dialog_border.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.delete_dialog_border.bg_style()),
+        )
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_empty_trash_confirmation(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 60.min(area.width - 4);
+    let dialog_height = 7;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+    
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
     let confirmation_text = format!(
-        "Delete note: '{}'\n\nThis action cannot be undone.\n\nPress '{}' to confirm, '{}' to cancel.",
-        truncated_title,
+        "Permanently delete {} note(s) in the trash?\n\nThis action cannot be undone.\n\nPress '{}' to confirm, '{}' to cancel.",
+        app.note_manager.trash_count(),
         format_keybinding_vec(&config.keybindings.confirm_delete),
         format_keybinding_vec(&config.keybindings.cancel_delete)
     );
 
     let dialog = Paragraph::new(confirmation_text)
-        .style(Style::default().fg(config.colors.text.to_color()))
+        .style(config.colors.text.style())
         .alignment(Alignment::Center)
         .block(
             Block::default()
-                .title("Confirm Deletion")
+                .title("Empty Trash")
+                .borders(Borders::ALL)
+                .border_style(config.colors.delete_dialog_border.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.delete_dialog_border.bg_style()),
+        )
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_recovery_confirmation(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 60.min(area.width - 4);
+    let dialog_height = 10;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let (what, when) = match &app.recovered {
+        Some(entry) => {
+            let what = match (&entry.note_id, entry.title.trim()) {
+                (None, "") => "a new note".to_string(),
+                (None, title) => format!("the new note '{}'", title),
+                (Some(_), "") => "an untitled note".to_string(),
+                (Some(_), title) => format!("'{}'", title),
+            };
+            (what, entry.written_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        }
+        None => ("a note".to_string(), "the last run".to_string()),
+    };
+    let confirmation_text = format!(
+        "tui-notes didn't shut down properly last time.\n\nUnsaved changes to {} from {} were kept.\n\nPress 'Enter' or 'r' to restore them in the editor, 'd' to discard them.",
+        what, when
+    );
+
+    let dialog = Paragraph::new(confirmation_text)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Recover Unsaved Changes")
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(config.colors.delete_dialog_border.to_color()).add_modifier(Modifier::BOLD))
-                .style(Style::default().bg(config.colors.delete_dialog_border.to_bg_color())),
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.delete_dialog_border.bg_style()),
         )
         .wrap(Wrap { trim: true });
 
@@ -651,14 +1963,14 @@ fn draw_unsaved_changes_confirmation(f: &mut Frame, area: Rect, _app: &App, conf
     );
 
     let dialog = Paragraph::new(confirmation_text)
-        .style(Style::default().fg(config.colors.text.to_color()))
+        .style(config.colors.text.style())
         .alignment(Alignment::Center)
         .block(
             Block::default()
                 .title("Unsaved Changes")
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(config.colors.delete_dialog_border.to_color()).add_modifier(Modifier::BOLD))
-                .style(Style::default().bg(config.colors.delete_dialog_border.to_bg_color())),
+                .border_style(config.colors.delete_dialog_border.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.delete_dialog_border.bg_style()),
         )
         .wrap(Wrap { trim: true });
 
@@ -670,7 +1982,7 @@ fn draw_password_prompt(f: &mut Frame, area: Rect, app: &App, config: &Config) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(0),
-            Constraint::Length(8),
+            Constraint::Length(9),
             Constraint::Min(0),
         ])
         .split(area);
@@ -696,36 +2008,49 @@ fn draw_password_prompt(f: &mut Frame, area: Rect, app: &App, config: &Config) {
         Line::from("Enter your password to unlock encrypted notes:"),
         Line::from(""),
         Line::from(vec![
-            Span::styled("> ", Style::default().fg(config.colors.text.to_color())),
-            Span::styled(password_display, Style::default().fg(config.colors.text.to_color())),
+            Span::styled("> ", config.colors.text.style()),
+            Span::styled(password_display, config.colors.text.style()),
         ]),
     ];
 
+    let failed = app.note_manager.failed_unlocks();
+    if let Some(wait) = app.note_manager.unlock_wait() {
+        content.push(Line::from(Span::styled(
+            format!("{} wrong passwords, try again in {} s", failed, wait.num_seconds() + 1),
+            config.colors.delete_dialog_border.style(),
+        )).alignment(Alignment::Center));
+    } else if failed > 0 {
+        content.push(Line::from(Span::styled(
+            format!("{} wrong password{} so far", failed, if failed == 1 { "" } else { "s" }),
+            config.colors.text_secondary.style(),
+        )).alignment(Alignment::Center));
+    }
+
     if let Some(error) = &app.password_error {
         content.push(Line::from(""));
         content.push(Line::from(vec![
-            Span::styled("Error: ", Style::default().fg(config.colors.delete_dialog_border.to_color())),
-            Span::styled(error, Style::default().fg(config.colors.delete_dialog_border.to_color())),
+            Span::styled("Error: ", config.colors.delete_dialog_border.style()),
+            Span::styled(error, config.colors.delete_dialog_border.style()),
         ]).alignment(Alignment::Center));
     } else if app.password_limit_reached {
         content.push(Line::from(""));
         content.push(Line::from(vec![
             Span::styled("Maximum password length reached (256 characters)", 
-                Style::default().fg(config.colors.delete_dialog_border.to_color())),
+                config.colors.delete_dialog_border.style()),
         ]).alignment(Alignment::Center));
     }
 
     let password_block = Paragraph::new(content)
-        .style(Style::default().fg(config.colors.text.to_color()))
+        .style(config.colors.text.style())
         .alignment(Alignment::Left)
         .block(
             Block::default()
                 .title(title)
                 .borders(Borders::ALL)
                 .border_style(if app.password_error.is_some() {
-                    Style::default().fg(config.colors.delete_dialog_border.to_color())
+                    config.colors.delete_dialog_border.style()
                 } else {
-                    Style::default().fg(config.colors.border_active.to_color())
+                    config.colors.border_active.style()
                 }),
         );
 
@@ -736,12 +2061,29 @@ fn draw_password_prompt(f: &mut Frame, area: Rect, app: &App, config: &Config) {
     f.set_cursor_position((cursor_x, cursor_y));
 }
 
+// "Strength: ■■■□ Good" under a password being picked
+fn strength_meter(password: &str, config: &Config) -> Line<'static> {
+    let strength = PasswordStrength::of(password);
+    let style = match strength {
+        PasswordStrength::TooShort | PasswordStrength::Weak => config.colors.delete_dialog_border.style(),
+        PasswordStrength::Fair => config.colors.help_text.style(),
+        PasswordStrength::Good | PasswordStrength::Strong => config.colors.diff_added.style(),
+    };
+    let filled = strength.level();
+    Line::from(vec![
+        Span::styled("  Strength: ", config.colors.text_secondary.style()),
+        Span::styled("■".repeat(filled), style),
+        Span::styled("□".repeat(4 - filled), config.colors.text_secondary.style()),
+        Span::styled(format!(" {}", strength.label()), style),
+    ])
+}
+
 fn draw_password_setup(f: &mut Frame, area: Rect, app: &App, config: &Config) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(0),
-            Constraint::Length(8),
+            Constraint::Length(10),
             Constraint::Min(0),
         ])
         .split(area);
@@ -763,41 +2105,50 @@ fn draw_password_setup(f: &mut Frame, area: Rect, app: &App, config: &Config) {
         "🔐 Set Up Encryption"
     };
 
+    let (prompt, hint) = if app.password_setup_first.is_some() {
+        ("Type the password again to confirm it.", "Esc to pick a different one.")
+    } else {
+        ("Create a password for your new encrypted notes vault.", "The password must be 8-256 characters long.")
+    };
+
     let mut content = vec![
-        Line::from("Create a password for your new encrypted notes vault.").alignment(Alignment::Center),
-        Line::from("The password must be 8-256 characters long.").alignment(Alignment::Center),
+        Line::from(prompt).alignment(Alignment::Center),
+        Line::from(hint).alignment(Alignment::Center),
         Line::from(""),
         Line::from(vec![
-            Span::styled("> ", Style::default().fg(config.colors.text.to_color())),
-            Span::styled(password_display, Style::default().fg(config.colors.text.to_color())),
+            Span::styled("> ", config.colors.text.style()),
+            Span::styled(password_display, config.colors.text.style()),
         ]),
     ];
+    if app.password_setup_first.is_none() {
+        content.push(strength_meter(app.password_input.expose_secret(), config));
+    }
 
     if let Some(error) = &app.password_error {
         content.push(Line::from(""));
         content.push(Line::from(vec![
-            Span::styled("Error: ", Style::default().fg(config.colors.delete_dialog_border.to_color())),
-            Span::styled(error, Style::default().fg(config.colors.delete_dialog_border.to_color())),
+            Span::styled("Error: ", config.colors.delete_dialog_border.style()),
+            Span::styled(error, config.colors.delete_dialog_border.style()),
         ]).alignment(Alignment::Center));
     } else if app.password_limit_reached {
         content.push(Line::from(""));
         content.push(Line::from(vec![
             Span::styled("Maximum password length reached (256 characters)", 
-                Style::default().fg(config.colors.delete_dialog_border.to_color())),
+                config.colors.delete_dialog_border.style()),
         ]).alignment(Alignment::Center));
     }
 
     let password_block = Paragraph::new(content)
-        .style(Style::default().fg(config.colors.text.to_color()))
+        .style(config.colors.text.style())
         .alignment(Alignment::Left)
         .block(
             Block::default()
                 .title(title)
                 .borders(Borders::ALL)
                 .border_style(if app.password_error.is_some() {
-                    Style::default().fg(config.colors.delete_dialog_border.to_color())
+                    config.colors.delete_dialog_border.style()
                 } else {
-                    Style::default().fg(config.colors.border_active.to_color())
+                    config.colors.border_active.style()
                 }),
         );
 
@@ -835,41 +2186,46 @@ fn draw_reauthentication_prompt(f: &mut Frame, area: Rect, app: &App, config: &C
         "🔐 Re-authentication Required"
     };
 
+    let consequence = if app.export_format == ExportFormat::Encrypted {
+        "This will create an encrypted backup file."
+    } else {
+        "This will create an unencrypted backup file."
+    };
     let mut content = vec![
-        Line::from("Enter your password to authorize plaintext export:").alignment(Alignment::Center),
-        Line::from("This will create an unencrypted backup file.").alignment(Alignment::Center),
+        Line::from("Enter your password to authorize the export:").alignment(Alignment::Center),
+        Line::from(consequence).alignment(Alignment::Center),
         Line::from(""),
         Line::from(vec![
-            Span::styled("> ", Style::default().fg(config.colors.text.to_color())),
-            Span::styled(password_display, Style::default().fg(config.colors.text.to_color())),
+            Span::styled("> ", config.colors.text.style()),
+            Span::styled(password_display, config.colors.text.style()),
         ]),
     ];
 
     if let Some(error) = &app.password_error {
         content.push(Line::from(""));
         content.push(Line::from(vec![
-            Span::styled("Error: ", Style::default().fg(config.colors.delete_dialog_border.to_color())),
-            Span::styled(error, Style::default().fg(config.colors.delete_dialog_border.to_color())),
+            Span::styled("Error: ", config.colors.delete_dialog_border.style()),
+            Span::styled(error, config.colors.delete_dialog_border.style()),
         ]).alignment(Alignment::Center));
     } else if app.password_limit_reached {
         content.push(Line::from(""));
         content.push(Line::from(vec![
             Span::styled("Maximum password length reached (256 characters)", 
-                Style::default().fg(config.colors.delete_dialog_border.to_color())),
+                config.colors.delete_dialog_border.style()),
         ]).alignment(Alignment::Center));
     }
 
     let password_block = Paragraph::new(content)
-        .style(Style::default().fg(config.colors.text.to_color()))
+        .style(config.colors.text.style())
         .alignment(Alignment::Left)
         .block(
             Block::default()
                 .title(title)
                 .borders(Borders::ALL)
                 .border_style(if app.password_error.is_some() {
-                    Style::default().fg(config.colors.delete_dialog_border.to_color())
+                    config.colors.delete_dialog_border.style()
                 } else {
-                    Style::default().fg(config.colors.border_active.to_color())
+                    config.colors.border_active.style()
                 }),
         );
 
@@ -880,76 +2236,381 @@ fn draw_reauthentication_prompt(f: &mut Frame, area: Rect, app: &App, config: &C
     f.set_cursor_position((cursor_x, cursor_y));
 }
 
-fn draw_encrypted_file_warning(f: &mut Frame, area: Rect, _app: &App, config: &Config) {
-    let dialog_width = 80.min(area.width - 4);
-    let dialog_height = 12;
-    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
-    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
-    
-    let dialog_area = Rect {
-        x: dialog_x,
-        y: dialog_y,
-        width: dialog_width,
-        height: dialog_height,
-    };
-
-    f.render_widget(Clear, dialog_area);
-
-    let warning_text = "⚠️  ENCRYPTED FILE DETECTED  ⚠️\n\n\
-        Your notes file appears to be encrypted, but encryption is disabled in your configuration.\n\n\
-        To access your encrypted notes:\n\
-        1. Enable encryption in your config file (~/.config/tui-notes/config.toml)\n\
-        2. Set 'encryption_enabled = true' in the [behavior] section\n\
-        3. Restart the application\n\n\
-        Or use a different notes file by changing 'default_notes_file' in config.\n\n\
-        Press 'Esc' or 'q' to quit.";
+fn draw_export_password_prompt(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(8),
+            Constraint::Min(0),
+        ])
+        .split(area);
 
-    let dialog = Paragraph::new(warning_text)
-        .block(
-            Block::default()
-                .title("Configuration Error")
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(config.colors.delete_dialog_border.to_color()).add_modifier(Modifier::BOLD))
-                .style(Style::default().bg(config.colors.delete_dialog_border.to_bg_color())),
-        )
-        .wrap(Wrap { trim: true })
-        .style(Style::default().fg(config.colors.text.to_color()));
+    let password_area = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(70),
+            Constraint::Min(0),
+        ])
+        .split(chunks[1]);
 
-    f.render_widget(dialog, dialog_area);
-}
+    let password_display = "*".repeat(app.password_input.expose_secret().len());
 
-fn draw_export_confirmation(f: &mut Frame, area: Rect, _app: &App, config: &Config) {
-    let dialog_width = 70.min(area.width - 4);
-    let dialog_height = 9;
-    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
-    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
-    
-    let dialog_area = Rect {
-        x: dialog_x,
-        y: dialog_y,
-        width: dialog_width,
-        height: dialog_height,
+    let (prompt, hint) = if app.export_password_first.is_some() {
+        ("Type the password again to confirm it:", "")
+    } else if app.note_manager.is_encrypted() {
+        ("Password for the encrypted export:", "Leave it empty to use the vault's password.")
+    } else {
+        ("Password for the encrypted export:", "You'll need it to open the backup.")
     };
 
-    f.render_widget(Clear, dialog_area);
+    let mut content = vec![
+        Line::from(prompt).alignment(Alignment::Center),
+        Line::from(hint).alignment(Alignment::Center),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("> ", config.colors.text.style()),
+            Span::styled(password_display, config.colors.text.style()),
+        ]),
+    ];
 
-    let warning_text = "⚠️  PLAINTEXT EXPORT WARNING  ⚠️\n\n\
-        You are about to export your notes in PLAINTEXT format.\n\
-        This will create an unencrypted backup file that anyone can read.\n\n\
-        Are you sure you want to continue?\n\n\
-        Press 'Y' to open file dialog and choose location\n\
-        Press 'N' to cancel";
+    if let Some(error) = &app.password_error {
+        content.push(Line::from(""));
+        content.push(Line::from(vec![
+            Span::styled("Error: ", config.colors.delete_dialog_border.style()),
+            Span::styled(error, config.colors.delete_dialog_border.style()),
+        ]).alignment(Alignment::Center));
+    } else if app.password_limit_reached {
+        content.push(Line::from(""));
+        content.push(Line::from(vec![
+            Span::styled("Maximum password length reached (256 characters)",
+                config.colors.delete_dialog_border.style()),
+        ]).alignment(Alignment::Center));
+    }
 
-    let dialog = Paragraph::new(warning_text)
-        .style(Style::default().fg(config.colors.text.to_color()))
-        .alignment(Alignment::Center)
+    let password_block = Paragraph::new(content)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Left)
         .block(
             Block::default()
-                .title("Export Confirmation")
+                .title("🔐 Encrypted Export")
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(config.colors.delete_dialog_border.to_color()).add_modifier(Modifier::BOLD))
-                .style(Style::default().bg(config.colors.delete_dialog_border.to_bg_color())),
-        )
+                .border_style(if app.password_error.is_some() {
+                    config.colors.delete_dialog_border.style()
+                } else {
+                    config.colors.border_active.style()
+                }),
+        );
+
+    f.render_widget(password_block, password_area[1]);
+
+    let cursor_x = password_area[1].x + 3 + app.password_input.expose_secret().len() as u16;
+    let cursor_y = password_area[1].y + 4;
+    f.set_cursor_position((cursor_x, cursor_y));
+}
+
+fn draw_change_password_prompt(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(10),
+            Constraint::Min(0),
+        ])
+        .split(area);
+
+    let password_area = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(70),
+            Constraint::Min(0),
+        ])
+        .split(chunks[1]);
+
+    let password_display = "*".repeat(app.password_input.expose_secret().len());
+
+    let (prompt, hint) = match (app.vault_password_action, app.password_change_stage) {
+        (VaultPasswordAction::Decrypt, _) => (
+            "Password, to store the vault as plain text:",
+            "Anyone who can read the notes file can read the notes then.",
+        ),
+        (VaultPasswordAction::Encrypt, PasswordChangeStage::New) => (
+            "Password for the vault:",
+            "The notes file, index and attachments get encrypted with it.",
+        ),
+        (_, PasswordChangeStage::Current) => ("Current password:", ""),
+        (_, PasswordChangeStage::New) => ("New password:", "The whole vault gets encrypted again with it."),
+        (_, PasswordChangeStage::Confirm) => ("Type the new password again to confirm it:", ""),
+    };
+    let title = match app.vault_password_action {
+        VaultPasswordAction::Change => "🔐 Change Password",
+        VaultPasswordAction::Encrypt => "🔐 Encrypt Vault",
+        VaultPasswordAction::Decrypt => "🔓 Remove Encryption",
+    };
+
+    let mut content = vec![
+        Line::from(prompt).alignment(Alignment::Center),
+        Line::from(hint).alignment(Alignment::Center),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("> ", config.colors.text.style()),
+            Span::styled(password_display, config.colors.text.style()),
+        ]),
+    ];
+    if app.password_change_stage == PasswordChangeStage::New {
+        content.push(strength_meter(app.password_input.expose_secret(), config));
+    }
+
+    if let Some(error) = &app.password_error {
+        content.push(Line::from(""));
+        content.push(Line::from(vec![
+            Span::styled("Error: ", config.colors.delete_dialog_border.style()),
+            Span::styled(error, config.colors.delete_dialog_border.style()),
+        ]).alignment(Alignment::Center));
+    } else if app.password_limit_reached {
+        content.push(Line::from(""));
+        content.push(Line::from(vec![
+            Span::styled("Maximum password length reached (256 characters)",
+                config.colors.delete_dialog_border.style()),
+        ]).alignment(Alignment::Center));
+    }
+
+    let password_block = Paragraph::new(content)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(if app.password_error.is_some() {
+                    config.colors.delete_dialog_border.style()
+                } else {
+                    config.colors.border_active.style()
+                }),
+        );
+
+    f.render_widget(password_block, password_area[1]);
+
+    let cursor_x = password_area[1].x + 3 + app.password_input.expose_secret().len() as u16;
+    let cursor_y = password_area[1].y + 4;
+    f.set_cursor_position((cursor_x, cursor_y));
+}
+
+fn draw_note_password_prompt(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(10),
+            Constraint::Min(0),
+        ])
+        .split(area);
+
+    let password_area = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(70),
+            Constraint::Min(0),
+        ])
+        .split(chunks[1]);
+
+    let password_display = "*".repeat(app.password_input.expose_secret().len());
+    let title = app.note_password.as_ref()
+        .and_then(|(id, _)| app.note_manager.get_note(id))
+        .map(|note| note.title.clone())
+        .unwrap_or_default();
+    let purpose = app.note_password.as_ref().map(|(_, purpose)| *purpose);
+    let confirming = app.note_password_first.is_some();
+
+    let (prompt, hint) = match purpose {
+        Some(NotePasswordPurpose::Encrypt) if confirming => ("Type the password again to confirm it:".to_string(), ""),
+        Some(NotePasswordPurpose::Encrypt) => (
+            format!("New password for '{}':", title),
+            "Forget it and the note can't be read again.",
+        ),
+        Some(NotePasswordPurpose::Decrypt) => (
+            format!("Password of '{}':", title),
+            "The note is saved as plain text again.",
+        ),
+        _ => (format!("Password of '{}':", title), ""),
+    };
+
+    let mut content = vec![
+        Line::from(prompt).alignment(Alignment::Center),
+        Line::from(hint).alignment(Alignment::Center),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("> ", config.colors.text.style()),
+            Span::styled(password_display, config.colors.text.style()),
+        ]),
+    ];
+    if purpose == Some(NotePasswordPurpose::Encrypt) && !confirming {
+        content.push(strength_meter(app.password_input.expose_secret(), config));
+    }
+
+    if let Some(error) = &app.password_error {
+        content.push(Line::from(""));
+        content.push(Line::from(vec![
+            Span::styled("Error: ", config.colors.delete_dialog_border.style()),
+            Span::styled(error, config.colors.delete_dialog_border.style()),
+        ]).alignment(Alignment::Center));
+    } else if app.password_limit_reached {
+        content.push(Line::from(""));
+        content.push(Line::from(vec![
+            Span::styled("Maximum password length reached (256 characters)",
+                config.colors.delete_dialog_border.style()),
+        ]).alignment(Alignment::Center));
+    }
+
+    let password_block = Paragraph::new(content)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .title("🔐 Encrypted Note")
+                .borders(Borders::ALL)
+                .border_style(if app.password_error.is_some() {
+                    config.colors.delete_dialog_border.style()
+                } else {
+                    config.colors.border_active.style()
+                }),
+        );
+
+    f.render_widget(Clear, password_area[1]);
+    f.render_widget(password_block, password_area[1]);
+
+    let cursor_x = password_area[1].x + 3 + app.password_input.expose_secret().len() as u16;
+    let cursor_y = password_area[1].y + 4;
+    f.set_cursor_position((cursor_x, cursor_y));
+}
+
+fn draw_encrypted_file_warning(f: &mut Frame, area: Rect, _app: &App, config: &Config) {
+    let dialog_width = 80.min(area.width - 4);
+    let dialog_height = 12;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+    
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let warning_text = "⚠️  ENCRYPTED FILE DETECTED  ⚠️\n\n\
+        Your notes file appears to be encrypted, but encryption is disabled in your configuration.\n\n\
+        To access your encrypted notes:\n\
+        1. Enable encryption in your config file (~/.config/tui-notes/config.toml)\n\
+        2. Set 'encryption_enabled = true' in the [behavior] section\n\
+        3. Restart the application\n\n\
+        Or use a different notes file by changing 'default_notes_file' in config.\n\n\
+        Press 'Esc' or 'q' to quit.";
+
+    let dialog = Paragraph::new(warning_text)
+        .block(
+            Block::default()
+                .title("Configuration Error")
+                .borders(Borders::ALL)
+                .border_style(config.colors.delete_dialog_border.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.delete_dialog_border.bg_style()),
+        )
+        .wrap(Wrap { trim: true })
+        .style(config.colors.text.style());
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_vault_in_use(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 80.min(area.width - 4);
+    let dialog_height = 12;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let reason = app.vault_in_use.as_deref().unwrap_or("The notes file is already open in another tui-notes");
+    let warning_text = format!("⚠️  VAULT ALREADY OPEN  ⚠️\n\n\
+        {}.\n\n\
+        Two instances saving to the same notes file would overwrite each other's changes, \
+        so only one can have it open at a time.\n\n\
+        Close the other instance and start again, or point 'default_notes_file' in the config \
+        at another file.\n\n\
+        Press 'Esc' or 'q' to quit.", reason);
+
+    let dialog = Paragraph::new(warning_text)
+        .block(
+            Block::default()
+                .title("Vault In Use")
+                .borders(Borders::ALL)
+                .border_style(config.colors.delete_dialog_border.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.delete_dialog_border.bg_style()),
+        )
+        .wrap(Wrap { trim: true })
+        .style(config.colors.text.style());
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_export_confirmation(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 70.min(area.width - 4);
+    let dialog_height = 15;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+    
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let which = if let Some(note) = app.export_note_id.as_ref().and_then(|id| app.note_manager.get_note(id)) {
+        format!("'{}'", note.title)
+    } else if app.marked_notes.is_empty() {
+        "your notes".to_string()
+    } else {
+        format!("{} marked note(s)", app.marked_notes.len())
+    };
+    let choices = if app.export_note_id.is_some() {
+        "Press 'Y' to open file dialog and choose location"
+    } else {
+        "Press 'Y' to export a JSON file (can be imported again)\n\
+        Press 'M' to export a folder of Markdown files\n\
+        Press 'S' to export a notes.json for Simplenote\n\
+        Press 'C' to export a CSV file for spreadsheets\n\
+        Press 'E' to export an encrypted backup instead"
+    };
+    let warning_text = format!("⚠️  PLAINTEXT EXPORT WARNING  ⚠️\n\n\
+        You are about to export {} in PLAINTEXT format.\n\
+        This will create an unencrypted backup file that anyone can read.\n\n\
+        Are you sure you want to continue?\n\n\
+        {}\n\
+        Press 'N' to cancel", which, choices);
+
+    let dialog = Paragraph::new(warning_text)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Export Confirmation")
+                .borders(Borders::ALL)
+                .border_style(config.colors.delete_dialog_border.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.delete_dialog_border.bg_style()),
+        )
         .wrap(Wrap { trim: true });
 
     f.render_widget(dialog, dialog_area);
@@ -990,8 +2651,8 @@ fn draw_export_location_dialog(f: &mut Frame, area: Rect, app: &App, config: &Co
         Line::from(subtitle),
         Line::from(""),
         Line::from(vec![
-            Span::styled("> ", Style::default().fg(config.colors.text.to_color())),
-            Span::styled(&app.export_file_input, Style::default().fg(config.colors.text.to_color())),
+            Span::styled("> ", config.colors.text.style()),
+            Span::styled(&app.export_file_input, config.colors.text.style()),
         ]),
         Line::from(""),
         Line::from("Press Enter to export, Esc to cancel"),
@@ -999,14 +2660,14 @@ fn draw_export_location_dialog(f: &mut Frame, area: Rect, app: &App, config: &Co
     ];
 
     let dialog = Paragraph::new(content)
-        .style(Style::default().fg(config.colors.text.to_color()))
+        .style(config.colors.text.style())
         .alignment(Alignment::Left)
         .block(
             Block::default()
                 .title(title)
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(config.colors.border_active.to_color()))
-                .style(Style::default().bg(config.colors.background_selected.to_bg_color())),
+                .border_style(config.colors.border_active.style())
+                .style(config.colors.background_selected.bg_style()),
         );
 
     f.render_widget(dialog, dialog_area);
@@ -1017,3 +2678,1154 @@ fn draw_export_location_dialog(f: &mut Frame, area: Rect, app: &App, config: &Co
     let cursor_y = dialog_area.y + 4; // line with the input
     f.set_cursor_position((cursor_x, cursor_y));
 }
+
+fn draw_about(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let config_path = Config::config_path()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    let vault_encryption = if app.note_manager.is_encrypted() {
+        format!("{} with {} key derivation", CIPHER_NAME, KDF_NAME)
+    } else {
+        "disabled (plaintext JSON)".to_string()
+    };
+
+    let label = config.colors.text_secondary.style();
+    let value = config.colors.text.style();
+
+    let content = vec![
+        Line::from(Span::styled(
+            format!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            config.colors.title_bar.style().add_modifier(Modifier::BOLD),
+        )),
+        Line::from("A simple TUI-based Rust notes app."),
+        Line::from(""),
+        Line::from("Create, edit, view, search, pin and favorite notes, with optional encryption."),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Encryption:  ", label),
+            Span::styled(vault_encryption, value),
+        ]),
+        Line::from(vec![
+            Span::styled("Cipher:      ", label),
+            Span::styled(CIPHER_NAME, value),
+        ]),
+        Line::from(vec![
+            Span::styled("KDF:         ", label),
+            Span::styled(
+                format!("{} (memory {} MiB, {} iterations, parallelism {})",
+                    KDF_NAME, ARGON2_MEMORY_KIB / 1024, ARGON2_ITERATIONS, ARGON2_PARALLELISM),
+                value,
+            ),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Config file: ", label),
+            Span::styled(config_path, value),
+        ]),
+        Line::from(vec![
+            Span::styled("Notes file:  ", label),
+            Span::styled(app.note_manager.notes_file().to_string_lossy().to_string(), value),
+        ]),
+    ];
+
+    let about = Paragraph::new(content)
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .title("About")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style())
+                .padding(ratatui::widgets::Padding::horizontal(1)),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(about, area);
+}
+
+fn draw_path_prompt(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 80.min(area.width - 4);
+    let dialog_height = 8;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let (title, description) = match app.path_prompt {
+        Some(prompt) => (prompt.title(), prompt.description()),
+        None => ("Enter Path", "File path:"),
+    };
+
+    let content = vec![
+        Line::from(description),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("> ", config.colors.text.style()),
+            Span::styled(&app.path_input, config.colors.text.style()),
+        ]),
+        Line::from(""),
+        Line::from("Press Enter to confirm, Esc to cancel"),
+        Line::from("Use ←/→ to move cursor, Home/End to jump"),
+    ];
+
+    let dialog = Paragraph::new(content)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style())
+                .style(config.colors.background_selected.bg_style()),
+        );
+
+    f.render_widget(dialog, dialog_area);
+
+    let max_cursor_x = dialog_area.width.saturating_sub(4);
+    let cursor_x = (dialog_area.x + 3 + app.path_input[..app.path_cursor_position].chars().count() as u16).min(dialog_area.x + max_cursor_x);
+    let cursor_y = dialog_area.y + 3;
+    f.set_cursor_position((cursor_x, cursor_y));
+}
+
+fn draw_text_prompt(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 80.min(area.width - 4);
+    let dialog_height = 7;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let (title, description) = match app.text_prompt {
+        Some(prompt) => (prompt.title(), prompt.description()),
+        None => ("Enter Text", "Text:"),
+    };
+
+    let content = vec![
+        Line::from(description),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("> ", config.colors.text.style()),
+            Span::styled(&app.text_input, config.colors.text.style()),
+        ]),
+        Line::from(""),
+        Line::from("Press Enter to confirm, Esc to cancel"),
+    ];
+
+    let dialog = Paragraph::new(content)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Left)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style())
+                .style(config.colors.background_selected.bg_style()),
+        );
+
+    f.render_widget(dialog, dialog_area);
+
+    // the cursor goes by characters, the position is in bytes
+    let typed = app.text_input[..app.text_cursor_position].chars().count() as u16;
+    let max_cursor_x = dialog_area.width.saturating_sub(4);
+    let cursor_x = (dialog_area.x + 3 + typed).min(dialog_area.x + max_cursor_x);
+    let cursor_y = dialog_area.y + 3;
+    f.set_cursor_position((cursor_x, cursor_y));
+}
+
+fn draw_import_source_dialog(f: &mut Frame, area: Rect, config: &Config) {
+    let dialog_width = 70.min(area.width - 4);
+    let dialog_height = 12;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let text = "What do you want to import?\n\n\
+        Press 'J' for a plaintext JSON export of tui-notes\n\
+        Press 'M' for a folder of Markdown/text files (e.g. an Obsidian vault)\n\
+        Press 'X' for a Joplin export (.jex)\n\
+        Press 'S' for the notes.json of a Simplenote export\n\
+        Press 'C' for a CSV file (you pick the columns next)\n\n\
+        Press 'Esc' to cancel";
+
+    let dialog = Paragraph::new(text)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Import Notes")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.background_selected.bg_style()),
+        )
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_csv_mapping_dialog(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let Some(csv) = &app.csv_import else { return };
+
+    let dialog_width = 76.min(area.width - 4);
+    let dialog_height = (CsvField::ALL.len() as u16 + 6).min(area.height);
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Which column holds each field? Title or content is required.",
+            config.colors.text.style(),
+        )),
+        Line::from(""),
+    ];
+    for (i, field) in CsvField::ALL.iter().enumerate() {
+        let column = csv.mapping.column(*field);
+        let header = column
+            .and_then(|c| csv.preview.headers.get(c))
+            .map(|h| format!("'{}'", h))
+            .unwrap_or_else(|| "(not imported)".to_string());
+        // the first row's value, so the user can see they picked the right column
+        let sample: String = column
+            .and_then(|c| csv.preview.sample.get(c))
+            .map(|value| value.lines().next().unwrap_or("").chars().take(30).collect())
+            .unwrap_or_default();
+
+        let (marker, style) = if i == csv.selected {
+            ("▶", config.colors.border_active.style().add_modifier(Modifier::BOLD))
+        } else {
+            (" ", config.colors.text.style())
+        };
+        let mut spans = vec![Span::styled(format!("{} {:<8} ← {}", marker, field.name(), header), style)];
+        if !sample.is_empty() {
+            spans.push(Span::styled(format!("  e.g. {}", sample), config.colors.text_secondary.style()));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "←/→: change column | Enter: import | Esc: cancel",
+        config.colors.text_secondary.style(),
+    )));
+
+    let title = format!("Import CSV - {}", csv.path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default());
+    let dialog = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.background_selected.bg_style()),
+        );
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_import_policy_dialog(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 70.min(area.width - 4);
+    let dialog_height = 11;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let text = format!(
+        "Importing: {}\n\n\
+        When an imported note has the same id as an existing one:\n\n\
+        Press 'N' to keep whichever copy is newer\n\
+        Press 'B' to keep both (the imported copy gets a new id)\n\
+        Press 'S' to skip it and keep the existing note\n\n\
+        Press 'Esc' to cancel",
+        app.pending_import_path.as_deref().unwrap_or("")
+    );
+
+    let dialog = Paragraph::new(text)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Import Notes")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.background_selected.bg_style()),
+        )
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_theme_dialog(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 40.min(area.width.saturating_sub(4));
+    let dialog_height = (app.theme_choices.len() as u16 + 2).min(area.height.saturating_sub(2));
+    let dialog_area = Rect {
+        x: (area.width.saturating_sub(dialog_width)) / 2,
+        y: (area.height.saturating_sub(dialog_height)) / 2,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let items: Vec<ListItem> = app.theme_choices
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let builtin = i < theme::PRESETS.len();
+            let label = if builtin { name.clone() } else { format!("{} (custom)", name) };
+            ListItem::new(label).style(if i == app.theme_index {
+                config.colors.background_selected.bg_style().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            })
+        })
+        .collect();
+
+    let list = List::new(items)
+        .style(config.colors.text.style())
+        .block(
+            Block::default()
+                .title("Theme")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD)),
+        );
+    f.render_widget(list, dialog_area);
+}
+
+fn draw_settings(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    // the settings, then every key binding
+    let entries = config.keybindings.entries();
+    let mut rows: Vec<(&str, String)> = Setting::ALL
+        .iter()
+        .map(|setting| (setting.label(), app.setting_value(*setting, config)))
+        .collect();
+    for name in config.keybindings.names() {
+        let value = if app.rebinding == Some(name) {
+            "press a key…".to_string()
+        } else {
+            let keys: Vec<String> = entries.iter().filter(|(other, _)| *other == name).map(|(_, binding)| format_keybinding(binding)).collect();
+            keys.join("/")
+        };
+        rows.push((name, value));
+    }
+
+    let dialog_width = 60.min(area.width.saturating_sub(4));
+    let dialog_height = (rows.len() as u16 + 2).min(area.height.saturating_sub(2));
+    let dialog_area = Rect {
+        x: (area.width.saturating_sub(dialog_width)) / 2,
+        y: (area.height.saturating_sub(dialog_height)) / 2,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    // values line up after the longest label
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let items: Vec<ListItem> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, (label, value))| {
+            let line = Line::from(vec![
+                Span::raw(format!("{:<width$}  ", label, width = label_width)),
+                Span::styled(value, config.colors.text_secondary.style()),
+            ]);
+            ListItem::new(line).style(if i == app.settings_index {
+                config.colors.background_selected.bg_style().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            })
+        })
+        .collect();
+
+    let list = List::new(items)
+        .style(config.colors.text.style())
+        .block(
+            Block::default()
+                .title("Settings")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD)),
+        );
+    // scrolled so the selected row stays in view
+    let mut state = ListState::default().with_selected(Some(app.settings_index));
+    f.render_stateful_widget(list, dialog_area, &mut state);
+}
+
+fn draw_vault_picker(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 60.min(area.width.saturating_sub(4));
+    let dialog_height = (app.vault_choices.len() as u16 + 2).min(area.height.saturating_sub(2));
+    let dialog_area = Rect {
+        x: (area.width.saturating_sub(dialog_width)) / 2,
+        y: (area.height.saturating_sub(dialog_height)) / 2,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let icons = icons::icons(config.behavior.use_icons);
+    let items: Vec<ListItem> = app.vault_choices
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let vault = config.vaults.get(name).cloned().unwrap_or_default();
+            let marker = if vault.encryption_enabled { icons.encrypted } else { icons.unencrypted };
+            let open = if app.vault_name.as_ref() == Some(name) { " (open)" } else { "" };
+            let line = Line::from(vec![
+                Span::raw(format!("{} {}{}  ", marker, name, open)),
+                Span::styled(vault.notes_file.clone(), config.colors.text_secondary.style()),
+            ]);
+            ListItem::new(line).style(if i == app.vault_index {
+                config.colors.background_selected.bg_style().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            })
+        })
+        .collect();
+
+    let list = List::new(items)
+        .style(config.colors.text.style())
+        .block(
+            Block::default()
+                .title("Vaults")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD)),
+        );
+    f.render_widget(list, dialog_area);
+}
+
+fn draw_quick_switcher(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 60.min(area.width.saturating_sub(4));
+    let dialog_height = 16.min(area.height.saturating_sub(2));
+    let dialog_area = Rect {
+        x: (area.width.saturating_sub(dialog_width)) / 2,
+        y: (area.height.saturating_sub(dialog_height)) / 3,
+        width: dialog_width,
+        height: dialog_height,
+    };
+    f.render_widget(Clear, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(dialog_area);
+
+    let input = Paragraph::new(format!("{}_", app.switcher_query))
+        .style(config.colors.text.style())
+        .block(
+            Block::default()
+                .title("Go to Note")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD)),
+        );
+    f.render_widget(input, chunks[0]);
+
+    // keep the highlighted note in the visible part of the list
+    let rows = chunks[1].height.saturating_sub(2) as usize;
+    let first = app.switcher_index.saturating_sub(rows.saturating_sub(1));
+    let items: Vec<ListItem> = app.switcher_matches
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows)
+        .filter_map(|(i, id)| {
+            let note = app.note_manager.get_note(id)?;
+            let title = if note.title.is_empty() { "(untitled)" } else { note.title.as_str() };
+            let recent = app.recent_notes.contains(id);
+            let mut spans = vec![Span::raw(title.to_string())];
+            if recent {
+                spans.push(Span::styled("  recent", config.colors.text_secondary.style()));
+            }
+            Some(ListItem::new(Line::from(spans)).style(if i == app.switcher_index {
+                config.colors.background_selected.bg_style().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            }))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .style(config.colors.text.style())
+        .block(
+            Block::default()
+                .title(format!("{} notes", app.switcher_matches.len()))
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style()),
+        );
+    f.render_widget(list, chunks[1]);
+}
+
+fn draw_save_as_mode_dialog(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 70.min(area.width - 4);
+    let dialog_height = 10;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let text = format!(
+        "Saving to: {}\n\n\
+        Press 'S' to save and keep working in the new file\n\
+        Press 'C' to only write a copy and stay on the current file\n\n\
+        Press 'Esc' to cancel",
+        app.pending_save_as_path.as_deref().unwrap_or("")
+    );
+
+    let dialog = Paragraph::new(text)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Save As")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.background_selected.bg_style()),
+        )
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_conflict_dialog(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 70.min(area.width - 4);
+    let dialog_height = 13;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let text = format!(
+        "The notes file was changed by another program:\n{}\n\n\
+        You also have unsaved changes.\n\n\
+        Press 'K' to keep mine (overwrite the file on disk)\n\
+        Press 'T' to take theirs (reload, discarding my changes)\n\
+        Press 'M' to merge (newest version of each note wins)\n\
+        Press 'E' to export mine to a separate file, then reload",
+        app.note_manager.notes_file().display()
+    );
+
+    let dialog = Paragraph::new(text)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("File Changed On Disk")
+                .borders(Borders::ALL)
+                .border_style(config.colors.delete_dialog_border.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.delete_dialog_border.bg_style()),
+        )
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_history(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let Some(note) = &app.viewing_note else { return };
+    let revisions: Vec<_> = note.revisions.iter().rev().collect();
+    let Some(revision) = revisions.get(app.history_index) else { return };
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(24), Constraint::Min(0)])
+        .split(area);
+
+    let items: Vec<ListItem> = revisions
+        .iter()
+        .enumerate()
+        .map(|(i, rev)| {
+            ListItem::new(Line::from(rev.saved_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string()))
+                .style(if i == app.history_index {
+                    config.colors.background_selected.bg_style()
+                } else {
+                    Style::default()
+                })
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!("Versions ({})", revisions.len()))
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style()),
+        )
+        .style(config.colors.text.style());
+    f.render_widget(list, columns[0]);
+
+    let sides = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(columns[1]);
+
+    let lines = diff::diff_lines(&revision.content, &note.content);
+    let rows = diff::side_by_side(&lines);
+    let visible_height = sides[0].height.saturating_sub(2) as usize;
+    let start = app.scroll_offset.min(rows.len().saturating_sub(1));
+
+    let style_for = |line: &Option<DiffLine>| -> (String, Style) {
+        match line {
+            Some(DiffLine::Same(text)) => (format!("  {}", text), config.colors.text.style()),
+            Some(DiffLine::Removed(text)) => (format!("- {}", text), config.colors.diff_removed.style()),
+            Some(DiffLine::Added(text)) => (format!("+ {}", text), config.colors.diff_added.style()),
+            None => (String::new(), Style::default()),
+        }
+    };
+
+    let mut left: Vec<Line> = Vec::new();
+    let mut right: Vec<Line> = Vec::new();
+    if revision.title != note.title {
+        left.push(Line::from(Span::styled(format!("Title: {}", revision.title), config.colors.diff_removed.style().add_modifier(Modifier::BOLD))));
+        right.push(Line::from(Span::styled(format!("Title: {}", note.title), config.colors.diff_added.style().add_modifier(Modifier::BOLD))));
+    }
+    for row in rows.iter().skip(start).take(visible_height) {
+        let (text, style) = style_for(&row.left);
+        left.push(Line::from(Span::styled(text, style)));
+        let (text, style) = style_for(&row.right);
+        right.push(Line::from(Span::styled(text, style)));
+    }
+
+    let saved_at = revision.saved_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+    let old_side = Paragraph::new(left).block(
+        Block::default()
+            .title(format!("Version from {}", saved_at))
+            .borders(Borders::ALL)
+            .border_style(config.colors.border_inactive.style()),
+    );
+    let new_side = Paragraph::new(right).block(
+        Block::default()
+            .title("Current")
+            .borders(Borders::ALL)
+            .border_style(config.colors.border_inactive.style()),
+    );
+
+    f.render_widget(old_side, sides[0]);
+    f.render_widget(new_side, sides[1]);
+}
+
+// the note's changes newest first, with what the picked one did to the text before it
+fn draw_journal(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let entries: Vec<_> = app.journal_entries.iter().rev().collect();
+    let Some(entry) = entries.get(app.journal_index) else { return };
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(28), Constraint::Min(0)])
+        .split(area);
+
+    let items: Vec<ListItem> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let at = entry.at.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+            ListItem::new(Line::from(format!("{} {}", at, entry.change.label())))
+                .style(if i == app.journal_index {
+                    config.colors.background_selected.bg_style()
+                } else {
+                    Style::default()
+                })
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!("Changes ({})", entries.len()))
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style()),
+        )
+        .style(config.colors.text.style());
+    f.render_widget(list, columns[0]);
+
+    // the text as of the change before this one that has any
+    let before = entries[app.journal_index + 1..].iter().find_map(|older| older.content.as_deref());
+    let mut lines = vec![Line::from(Span::styled(format!("Title: {}", entry.title), config.colors.text_highlight.style().add_modifier(Modifier::BOLD)))];
+    if !entry.tags.is_empty() {
+        lines.push(Line::from(Span::styled(format!("Tags: {}", entry.tags.join(", ")), config.colors.text_secondary.style())));
+    }
+    lines.push(Line::from(""));
+    match &entry.content {
+        Some(content) => {
+            let changed = diff::diff_lines(before.unwrap_or(""), content);
+            for line in changed.iter().skip(app.scroll_offset) {
+                lines.push(match line {
+                    DiffLine::Same(text) => Line::from(Span::styled(format!("  {}", text), config.colors.text.style())),
+                    DiffLine::Removed(text) => Line::from(Span::styled(format!("- {}", text), config.colors.diff_removed.style())),
+                    DiffLine::Added(text) => Line::from(Span::styled(format!("+ {}", text), config.colors.diff_added.style())),
+                });
+            }
+        }
+        None if entry.change == change_journal::Change::Deleted => {
+            lines.push(Line::from(Span::styled("The note was deleted for good.", config.colors.text_secondary.style())));
+        }
+        None => {
+            lines.push(Line::from(Span::styled("The text is encrypted with the note's own password and isn't in the journal.", config.colors.text_secondary.style())));
+        }
+    }
+
+    let at = entry.at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S");
+    let detail = Paragraph::new(lines).block(
+        Block::default()
+            .title(format!("{} at {}", entry.change.label(), at))
+            .borders(Borders::ALL)
+            .border_style(config.colors.border_inactive.style()),
+    );
+    f.render_widget(detail, columns[1]);
+}
+
+fn draw_backup_list(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    if app.backups.is_empty() {
+        let empty = Paragraph::new("No backups yet. They are taken automatically while the app is open.")
+            .style(config.colors.text_secondary.style())
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .title("Backups")
+                    .borders(Borders::ALL)
+                    .border_style(config.colors.border_inactive.style()),
+            );
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = app.backups
+        .iter()
+        .enumerate()
+        .map(|(i, backup)| {
+            let created = backup.created
+                .map(|c| c.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "unknown date".to_string());
+            let line = Line::from(vec![
+                Span::styled(created, Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    format!("  {} ({:.1} KB)", backup.file_name(), backup.size as f64 / 1024.0),
+                    config.colors.text_secondary.style(),
+                ),
+            ]);
+            ListItem::new(line).style(
+                if i == app.backup_index {
+                    config.colors.background_selected.bg_style()
+                } else {
+                    Style::default()
+                }
+            )
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!("Backups ({})", app.backups.len()))
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style()),
+        )
+        .style(config.colors.text.style());
+
+    f.render_widget(list, area);
+}
+
+fn draw_restore_backup_confirmation(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 70.min(area.width - 4);
+    let dialog_height = 8;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let name = app.backups.get(app.backup_index).map(|b| b.file_name()).unwrap_or_default();
+    let confirmation_text = format!(
+        "Restore backup '{}'?\n\nYour current notes are backed up first.\n\nPress '{}' to confirm, '{}' to cancel.",
+        name,
+        format_keybinding_vec(&config.keybindings.confirm_delete),
+        format_keybinding_vec(&config.keybindings.cancel_delete)
+    );
+
+    let dialog = Paragraph::new(confirmation_text)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Restore Backup")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.background_selected.bg_style()),
+        )
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_trash_view(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let trashed = app.note_manager.trashed_notes();
+
+    if trashed.is_empty() {
+        let empty = Paragraph::new("The trash is empty.")
+            .style(config.colors.text_secondary.style())
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .title("Trash")
+                    .borders(Borders::ALL)
+                    .border_style(config.colors.border_inactive.style()),
+            );
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let retention_days = config.behavior.trash_retention_days as i64;
+    let now = chrono::Utc::now();
+
+    let items: Vec<ListItem> = trashed
+        .iter()
+        .enumerate()
+        .map(|(i, note)| {
+            let deleted_at = note.deleted_at.unwrap_or(now);
+            let mut details = format!("Deleted: {}", deleted_at.format("%Y-%m-%d %H:%M"));
+            if retention_days > 0 {
+                let days_left = (retention_days - (now - deleted_at).num_days()).max(0);
+                details.push_str(&format!(" (purged in {} day{})", days_left, if days_left == 1 { "" } else { "s" }));
+            }
+
+            let content = vec![
+                Line::from(Span::styled(&note.title, Style::default().add_modifier(Modifier::BOLD))),
+                Line::from(Span::styled(details, config.colors.text_secondary.style())),
+            ];
+
+            ListItem::new(content).style(
+                if i == app.trash_index {
+                    config.colors.background_selected.bg_style()
+                } else {
+                    Style::default()
+                }
+            )
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!("Trash ({})", trashed.len()))
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style()),
+        )
+        .style(config.colors.text.style());
+
+    f.render_widget(list, area);
+}
+
+fn draw_purge_confirmation(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let dialog_width = 60.min(area.width - 4);
+    let dialog_height = 7;
+    let dialog_x = (area.width.saturating_sub(dialog_width)) / 2;
+    let dialog_y = (area.height.saturating_sub(dialog_height)) / 2;
+
+    let dialog_area = Rect {
+        x: dialog_x,
+        y: dialog_y,
+        width: dialog_width,
+        height: dialog_height,
+    };
+
+    f.render_widget(Clear, dialog_area);
+
+    let note_title = &app.delete_note_title;
+    let truncated_title = if note_title.chars().count() > 40 {
+        let safe_title: String = note_title.chars().take(37).collect();
+        format!("{}...", safe_title)
+    } else {
+        note_title.clone()
+    };
+
+    let confirmation_text = format!(
+        "Permanently delete: '{}'\n\nThis action cannot be undone.\n\nPress '{}' to confirm, '{}' to cancel.",
+        truncated_title,
+        format_keybinding_vec(&config.keybindings.confirm_delete),
+        format_keybinding_vec(&config.keybindings.cancel_delete)
+    );
+
+    let dialog = Paragraph::new(confirmation_text)
+        .style(config.colors.text.style())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Delete Forever")
+                .borders(Borders::ALL)
+                .border_style(config.colors.delete_dialog_border.style().add_modifier(Modifier::BOLD))
+                .style(config.colors.delete_dialog_border.bg_style()),
+        )
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(dialog, dialog_area);
+}
+
+fn draw_notebook_list(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let notebooks = app.note_manager.notebooks();
+    let total: usize = notebooks.iter().map(|n| n.note_count).sum();
+
+    let mut entries = vec![("All notebooks".to_string(), total)];
+    entries.extend(notebooks.iter().map(|n| (n.display_name().to_string(), n.note_count)));
+
+    let items: Vec<ListItem> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, count))| {
+            let line = Line::from(vec![
+                Span::styled(name, Style::default().add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    format!("  ({} note{})", count, if count == 1 { "" } else { "s" }),
+                    config.colors.text_secondary.style(),
+                ),
+            ]);
+            ListItem::new(line).style(
+                if i == app.notebook_list_index {
+                    config.colors.background_selected.bg_style()
+                } else {
+                    Style::default()
+                }
+            )
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title("Notebooks")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style()),
+        )
+        .style(config.colors.text.style());
+
+    f.render_widget(list, area);
+}
+
+fn draw_import_report(f: &mut Frame, area: Rect, app: &App, config: &Config) {
+    let Some(report) = &app.import_report else { return };
+
+    let mut lines = Vec::new();
+    for (path, title) in &report.imported {
+        lines.push(Line::from(vec![
+            Span::styled("✓ ", config.colors.border_active.style()),
+            Span::styled(path.display().to_string(), config.colors.text.style()),
+            Span::styled(format!("  → {}", title), config.colors.text_secondary.style()),
+        ]));
+    }
+    for (path, reason) in &report.skipped {
+        lines.push(Line::from(vec![
+            Span::styled("✗ ", config.colors.delete_dialog_border.style()),
+            Span::styled(path.display().to_string(), config.colors.text.style()),
+            Span::styled(format!("  skipped: {}", reason), config.colors.text_secondary.style()),
+        ]));
+    }
+    if lines.is_empty() {
+        lines.push(Line::from("No files found in that folder."));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .scroll((app.scroll_offset as u16, 0))
+        .block(
+            Block::default()
+                .title(format!("Import - {} imported, {} skipped", report.imported.len(), report.skipped.len()))
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style()),
+        );
+    f.render_widget(paragraph, area);
+}
+
+fn draw_statistics(f: &mut Frame, area: Rect, app: &mut App, config: &Config) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+        ])
+        .split(area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(40),
+            Constraint::Percentage(60),
+        ])
+        .split(chunks[1]);
+
+    let vault = app.note_manager.vault_stats().clone();
+    let summary = Paragraph::new(format!(
+        "Notes: {}   Words: {}   Characters: {}   Tags: {}",
+        vault.notes, vault.words, vault.chars, vault.tags.len()
+    ))
+        .style(config.colors.text.style())
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .title("Vault")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style()),
+        );
+    f.render_widget(summary, chunks[0]);
+
+    // the tag counts share the left column with the months once there are tags
+    let left = if vault.tags.is_empty() {
+        vec![columns[0]]
+    } else {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(50),
+                Constraint::Percentage(50),
+            ])
+            .split(columns[0])
+            .to_vec()
+    };
+
+    // newest months at the bottom, cut from the top when they don't fit
+    let month_height = left[0].height.saturating_sub(2) as usize;
+    let month_max = vault.per_month.iter().map(|(_, count)| *count).max().unwrap_or(1);
+    let month_bar_width = (left[0].width as usize).saturating_sub(20).max(1);
+    let month_lines: Vec<Line> = vault.per_month
+        .iter()
+        .skip(vault.per_month.len().saturating_sub(month_height))
+        .map(|(month, count)| {
+            let bar_len = (count * month_bar_width / month_max).max(1);
+            Line::from(vec![
+                Span::styled(format!("{} ", month), config.colors.text_secondary.style()),
+                Span::styled(format!("{:>4} ", count), config.colors.text.style()),
+                Span::styled("█".repeat(bar_len), config.colors.title_bar.style()),
+            ])
+        })
+        .collect();
+    let months = Paragraph::new(month_lines)
+        .block(
+            Block::default()
+                .title("Notes per Month")
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_inactive.style()),
+        );
+    f.render_widget(months, left[0]);
+
+    if let Some(&tag_area) = left.get(1) {
+        // most used tags first, the rest are cut off at the bottom
+        let tag_max = vault.tags[0].1;
+        let tag_bar_width = (tag_area.width as usize).saturating_sub(28).max(1);
+        let tag_lines: Vec<Line> = vault.tags
+            .iter()
+            .take(tag_area.height.saturating_sub(2) as usize)
+            .map(|(tag, count)| {
+                let bar_len = (count * tag_bar_width / tag_max).max(1);
+                Line::from(vec![
+                    Span::styled(format!("#{:<20} ", tag), config.colors.text_secondary.style()),
+                    Span::styled(format!("{:>4} ", count), config.colors.text.style()),
+                    Span::styled("█".repeat(bar_len), config.colors.title_bar.style()),
+                ])
+            })
+            .collect();
+        let tags = Paragraph::new(tag_lines)
+            .block(
+                Block::default()
+                    .title(format!("Tags ({})", vault.tags.len()))
+                    .borders(Borders::ALL)
+                    .border_style(config.colors.border_inactive.style()),
+            );
+        f.render_widget(tags, tag_area);
+    }
+
+    let area = columns[1];
+    let visible_height = area.height.saturating_sub(2) as usize;
+    let frequencies = app.note_manager.word_frequencies();
+
+    if frequencies.is_empty() {
+        let empty = Paragraph::new("Not enough words yet. Write some notes first.")
+            .style(config.colors.text_secondary.style())
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .title("Most Frequent Words")
+                    .borders(Borders::ALL)
+                    .border_style(config.colors.border_inactive.style()),
+            );
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let max_count = frequencies[0].1;
+    let bar_width = (area.width as usize).saturating_sub(40).max(1);
+    let start = app.scroll_offset.min(frequencies.len().saturating_sub(1));
+
+    let lines: Vec<Line> = frequencies
+        .iter()
+        .enumerate()
+        .skip(start)
+        .take(visible_height)
+        .map(|(rank, (word, count))| {
+            let bar_len = (count * bar_width / max_count).max(1);
+            Line::from(vec![
+                Span::styled(format!("{:>3}. ", rank + 1), config.colors.text_secondary.style()),
+                Span::styled(format!("{:<20} ", word), config.colors.text.style().add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{:>6} ", count), config.colors.text_secondary.style()),
+                Span::styled("█".repeat(bar_len), config.colors.title_bar.style()),
+            ])
+        })
+        .collect();
+
+    let list = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(format!("Most Frequent Words (top {})", frequencies.len()))
+                .borders(Borders::ALL)
+                .border_style(config.colors.border_active.style()),
+        );
+
+    f.render_widget(list, area);
+}
@@ -0,0 +1,80 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+// where the lock lives, next to the vault: notes.json -> notes.json.lock
+pub fn lock_path(notes_file: &Path) -> PathBuf {
+    let mut name = notes_file.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".lock");
+    notes_file.with_file_name(name)
+}
+
+// an advisory lock on the lock file, held for as long as the vault is open. the os lets go of it
+// when the process ends, however it ends, so a lock file left behind by a crash is stale and
+// simply taken over by the next instance
+#[derive(Debug)]
+pub struct VaultLock {
+    // kept open, closing it releases the lock
+    _file: File,
+}
+
+impl VaultLock {
+    pub fn acquire(notes_file: &Path) -> io::Result<Self> {
+        let path = lock_path(notes_file);
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let owner = read_owner(&mut file);
+                let in_use = InUse { notes_file: notes_file.to_path_buf(), owner };
+                return Err(io::Error::new(io::ErrorKind::WouldBlock, in_use));
+            }
+            // some network filesystems have no locks, go without rather than not open at all
+            Err(TryLockError::Error(e)) if e.kind() == io::ErrorKind::Unsupported => {}
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+
+        // whoever held it before is gone, put our pid in for the next one to report
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+        Ok(VaultLock { _file: file })
+    }
+}
+
+// the pid the holder wrote, None while it's still writing it or the file is garbage
+fn read_owner(file: &mut File) -> Option<u32> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
+
+// the error when someone else holds the lock
+#[derive(Debug)]
+struct InUse {
+    notes_file: PathBuf,
+    owner: Option<u32>,
+}
+
+impl fmt::Display for InUse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} is already open ", self.notes_file.display())?;
+        match self.owner {
+            Some(pid) if pid == std::process::id() => write!(f, "in this window"),
+            Some(pid) => write!(f, "in another tui-notes (pid {})", pid),
+            None => write!(f, "in another tui-notes"),
+        }
+    }
+}
+
+impl std::error::Error for InUse {}
+
+// true if opening failed because another instance has the vault
+pub fn is_in_use(e: &io::Error) -> bool {
+    e.get_ref().is_some_and(|inner| inner.is::<InUse>())
+}
@@ -0,0 +1,221 @@
+use crossterm::event::KeyEvent;
+use tui_textarea::{CursorMove, Input, Key, Scrolling, TextArea};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum VimMode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    // waiting for the motion after y, d or c
+    Operator(char),
+}
+
+impl VimMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            VimMode::Normal => "NORMAL",
+            VimMode::Insert => "INSERT",
+            VimMode::Visual => "VISUAL",
+            VimMode::Operator(_) => "OPERATOR",
+        }
+    }
+}
+
+// modal editing for the content editor, keys it doesn't use fall through to the regular bindings
+#[derive(Debug, Default)]
+pub struct Vim {
+    pub mode: VimMode,
+    // first key of a two key command like gg
+    pending: Option<char>,
+}
+
+impl Vim {
+    // true when the key was used up, false to let the editor handle it as usual
+    pub fn handle(&mut self, key: KeyEvent, textarea: &mut TextArea) -> bool {
+        let input = Input::from(key);
+        let pending = self.pending.take();
+
+        if self.mode == VimMode::Insert {
+            if input.key == Key::Esc {
+                self.mode = VimMode::Normal;
+                return true;
+            }
+            return false;
+        }
+
+        match input {
+            // leaving the editor and the app's ctrl shortcuts still work from normal mode
+            Input { key: Key::Esc, .. } if self.mode == VimMode::Normal => return false,
+            Input { key: Key::Esc, .. } => {
+                textarea.cancel_selection();
+                self.mode = VimMode::Normal;
+                return true;
+            }
+            Input { key: Key::Char('r'), ctrl: true, .. } => {
+                textarea.redo();
+                self.mode = VimMode::Normal;
+                return true;
+            }
+            Input { key: Key::Char('e'), ctrl: true, .. } => textarea.scroll((1, 0)),
+            Input { key: Key::Char('y'), ctrl: true, .. } => textarea.scroll((-1, 0)),
+            Input { key: Key::Char('d'), ctrl: true, .. } => textarea.scroll(Scrolling::HalfPageDown),
+            Input { key: Key::Char('u'), ctrl: true, .. } => textarea.scroll(Scrolling::HalfPageUp),
+            Input { key: Key::Char('f'), ctrl: true, .. } => textarea.scroll(Scrolling::PageDown),
+            Input { key: Key::Char('b'), ctrl: true, .. } => textarea.scroll(Scrolling::PageUp),
+            Input { ctrl: true, .. } | Input { alt: true, .. } => return false,
+            Input { key: Key::Tab, .. } => return false,
+            Input { key: Key::Char('h'), .. } | Input { key: Key::Left, .. } | Input { key: Key::Backspace, .. } => textarea.move_cursor(CursorMove::Back),
+            Input { key: Key::Char('j'), .. } | Input { key: Key::Down, .. } | Input { key: Key::Enter, .. } => textarea.move_cursor(CursorMove::Down),
+            Input { key: Key::Char('k'), .. } | Input { key: Key::Up, .. } => textarea.move_cursor(CursorMove::Up),
+            Input { key: Key::Char('l'), .. } | Input { key: Key::Right, .. } => textarea.move_cursor(CursorMove::Forward),
+            Input { key: Key::Char('w'), .. } => textarea.move_cursor(CursorMove::WordForward),
+            Input { key: Key::Char('e'), .. } => {
+                textarea.move_cursor(CursorMove::WordEnd);
+                if matches!(self.mode, VimMode::Operator(_)) {
+                    // include the character under the cursor
+                    textarea.move_cursor(CursorMove::Forward);
+                }
+            }
+            Input { key: Key::Char('b'), .. } => textarea.move_cursor(CursorMove::WordBack),
+            Input { key: Key::Char('^' | '0'), .. } | Input { key: Key::Home, .. } => textarea.move_cursor(CursorMove::Head),
+            Input { key: Key::Char('$'), .. } | Input { key: Key::End, .. } => textarea.move_cursor(CursorMove::End),
+            Input { key: Key::Char('}'), .. } => textarea.move_cursor(CursorMove::ParagraphForward),
+            Input { key: Key::Char('{'), .. } => textarea.move_cursor(CursorMove::ParagraphBack),
+            Input { key: Key::Char('g'), .. } if pending == Some('g') => textarea.move_cursor(CursorMove::Top),
+            Input { key: Key::Char('G'), .. } => textarea.move_cursor(CursorMove::Bottom),
+            Input { key: Key::Char('D'), .. } => {
+                textarea.delete_line_by_end();
+                self.mode = VimMode::Normal;
+                return true;
+            }
+            Input { key: Key::Char('C'), .. } => {
+                textarea.delete_line_by_end();
+                textarea.cancel_selection();
+                self.mode = VimMode::Insert;
+                return true;
+            }
+            Input { key: Key::Char('p'), .. } => {
+                textarea.paste();
+                self.mode = VimMode::Normal;
+                return true;
+            }
+            Input { key: Key::Char('u'), .. } => {
+                textarea.undo();
+                self.mode = VimMode::Normal;
+                return true;
+            }
+            Input { key: Key::Char('x'), .. } => {
+                textarea.delete_next_char();
+                self.mode = VimMode::Normal;
+                return true;
+            }
+            Input { key: Key::Char('i'), .. } => {
+                textarea.cancel_selection();
+                self.mode = VimMode::Insert;
+                return true;
+            }
+            Input { key: Key::Char('a'), .. } => {
+                textarea.cancel_selection();
+                textarea.move_cursor(CursorMove::Forward);
+                self.mode = VimMode::Insert;
+                return true;
+            }
+            Input { key: Key::Char('A'), .. } => {
+                textarea.cancel_selection();
+                textarea.move_cursor(CursorMove::End);
+                self.mode = VimMode::Insert;
+                return true;
+            }
+            Input { key: Key::Char('I'), .. } => {
+                textarea.cancel_selection();
+                textarea.move_cursor(CursorMove::Head);
+                self.mode = VimMode::Insert;
+                return true;
+            }
+            Input { key: Key::Char('o'), .. } => {
+                textarea.cancel_selection();
+                textarea.move_cursor(CursorMove::End);
+                textarea.insert_newline();
+                self.mode = VimMode::Insert;
+                return true;
+            }
+            Input { key: Key::Char('O'), .. } => {
+                textarea.cancel_selection();
+                textarea.move_cursor(CursorMove::Head);
+                textarea.insert_newline();
+                textarea.move_cursor(CursorMove::Up);
+                self.mode = VimMode::Insert;
+                return true;
+            }
+            Input { key: Key::Char('v'), .. } if self.mode == VimMode::Normal => {
+                textarea.start_selection();
+                self.mode = VimMode::Visual;
+                return true;
+            }
+            Input { key: Key::Char('V'), .. } if self.mode == VimMode::Normal => {
+                textarea.move_cursor(CursorMove::Head);
+                textarea.start_selection();
+                textarea.move_cursor(CursorMove::End);
+                self.mode = VimMode::Visual;
+                return true;
+            }
+            Input { key: Key::Char('v'), .. } if self.mode == VimMode::Visual => {
+                textarea.cancel_selection();
+                self.mode = VimMode::Normal;
+                return true;
+            }
+            // yy, dd and cc work on the whole line
+            Input { key: Key::Char(c), .. } if self.mode == VimMode::Operator(c) => {
+                textarea.move_cursor(CursorMove::Head);
+                textarea.start_selection();
+                let cursor = textarea.cursor();
+                textarea.move_cursor(CursorMove::Down);
+                if cursor == textarea.cursor() {
+                    // last line, take it up to the end instead
+                    textarea.move_cursor(CursorMove::End);
+                }
+            }
+            Input { key: Key::Char(op @ ('y' | 'd' | 'c')), .. } if self.mode == VimMode::Normal => {
+                textarea.start_selection();
+                self.mode = VimMode::Operator(op);
+                return true;
+            }
+            Input { key: Key::Char(op @ ('y' | 'd' | 'c')), .. } if self.mode == VimMode::Visual => {
+                // vim's selection includes the character under the cursor
+                textarea.move_cursor(CursorMove::Forward);
+                if op == 'y' {
+                    textarea.copy();
+                } else {
+                    textarea.cut();
+                }
+                self.mode = if op == 'c' { VimMode::Insert } else { VimMode::Normal };
+                return true;
+            }
+            // anything else is swallowed so stray commands never end up in the note
+            Input { key: Key::Char(c), .. } => {
+                self.pending = Some(c);
+                return true;
+            }
+            _ => return true,
+        }
+
+        // a motion finishes a pending operator
+        self.mode = match self.mode {
+            VimMode::Operator('y') => {
+                textarea.copy();
+                VimMode::Normal
+            }
+            VimMode::Operator('d') => {
+                textarea.cut();
+                VimMode::Normal
+            }
+            VimMode::Operator(_) => {
+                textarea.cut();
+                VimMode::Insert
+            }
+            mode => mode,
+        };
+        true
+    }
+}
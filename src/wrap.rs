@@ -0,0 +1,34 @@
+// char ranges of the rows a line takes up when soft wrapped at word boundaries,
+// words longer than a row are broken up
+pub fn segments(line: &str, width: usize) -> Vec<(usize, usize)> {
+    let chars: Vec<char> = line.chars().collect();
+    if width == 0 || chars.len() <= width {
+        return vec![(0, chars.len())];
+    }
+
+    let mut rows = Vec::new();
+    let mut start = 0;
+    while chars.len() - start > width {
+        let limit = start + width;
+        // break after the last space that still fits
+        let end = (start + 1..=limit).rev().find(|&i| chars[i - 1] == ' ').unwrap_or(limit);
+        rows.push((start, end));
+        start = end;
+    }
+    rows.push((start, chars.len()));
+    rows
+}
+
+pub fn rows(line: &str, width: usize) -> usize {
+    segments(line, width).len()
+}
+
+// which row of the wrapped line a cursor column lands on, and where in that row
+pub fn locate(segments: &[(usize, usize)], column: usize) -> (usize, usize) {
+    for (row, (start, end)) in segments.iter().enumerate() {
+        if column < *end || row == segments.len() - 1 {
+            return (row, column.saturating_sub(*start));
+        }
+    }
+    (0, column)
+}